@@ -0,0 +1,44 @@
+//! Canvas fill benchmarks, run with `cargo bench --bench canvas_fill`
+//!
+//! Clears an 800x600 canvas, the size of a typical window cleared every frame, with the
+//! slice fills of Canvas::fill and fill_rect_fast and, for reference, with plot called
+//! for every pixel.
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use graphics::canvas::Canvas;
+use graphics::color::Color;
+use graphics::math::Point2D;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+
+fn fill(c: &mut Criterion) {
+    let mut group = c.benchmark_group("canvas_fill");
+    group.throughput(Throughput::Elements(WIDTH as u64 * HEIGHT as u64));
+    let canvas = Canvas::new(WIDTH, HEIGHT);
+    let color = Color::rgb(30, 60, 90);
+
+    group.bench_function("fill", |b| b.iter(|| canvas.fill(black_box(color))));
+    group.bench_function("fill_rect_fast", |b| {
+        b.iter(|| {
+            canvas.fill_rect_fast(
+                &Point2D::new(0, 0),
+                WIDTH as i32,
+                HEIGHT as i32,
+                black_box(color),
+            )
+        })
+    });
+    group.bench_function("plot", |b| {
+        b.iter(|| {
+            for y in 0..HEIGHT as i32 {
+                for x in 0..WIDTH as i32 {
+                    canvas.plot(x, y, black_box(color));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, fill);
+criterion_main!(benches);
@@ -0,0 +1,70 @@
+//! PNG decoding benchmarks, run with `cargo bench --bench png_decode`
+//!
+//! Images are generated in memory by PngWriter with every row Paeth filtered, the most
+//! expensive filter to undo, so the numbers track the filter loops as well as inflate.
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use graphics::color::Color;
+use graphics::image::png::{FilterType, PngReader, PngWriter};
+
+/// Noisy gradient, so the Paeth filter picks all three predictors
+fn gradient(width: u32, height: u32) -> Vec<Color> {
+    (0..width as usize * height as usize)
+        .map(|i| {
+            let (x, y) = (i % width as usize, i / width as usize);
+            Color::rgba(
+                (x * 7 + (i % 13) * 5) as u8,
+                (y * 3 + x) as u8,
+                (x * y % 251) as u8,
+                (255 - i % 7) as u8,
+            )
+        })
+        .collect()
+}
+
+/// Build an RGBA (4 bytes per pixel) or indexed (1 byte per pixel) PNG, Paeth filtered
+fn paeth_png(width: u32, height: u32, indexed: bool) -> Vec<u8> {
+    let pixels = gradient(width, height);
+    let indices: Vec<u8> = pixels.iter().map(|c| c.r()).collect();
+    let palette: Vec<Color> = (0..=255).map(|i| Color::rgb(i, 255 - i, i / 2)).collect();
+    let mut writer = if indexed {
+        PngWriter::new_indexed(width, height, &indices, &palette).unwrap()
+    } else {
+        PngWriter::new(width, height, &pixels).unwrap()
+    };
+    writer.set_filter(FilterType::Paeth);
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).unwrap();
+    bytes
+}
+
+fn decode(c: &mut Criterion) {
+    let mut group = c.benchmark_group("png_decode");
+    group.sample_size(10);
+    // (name, width, height, indexed)
+    let images = [
+        ("rgba_4k", 3840, 2160, false),
+        ("rgba_1080p", 1920, 1080, false),
+        ("indexed_1080p", 1920, 1080, true),
+    ];
+    for &(name, width, height, indexed) in images.iter() {
+        let bytes = paeth_png(width, height, indexed);
+        group.throughput(Throughput::Elements(width as u64 * height as u64));
+        group.bench_with_input(BenchmarkId::new("pixels", name), &bytes, |b, bytes| {
+            b.iter(|| {
+                let image = PngReader::read(&mut &bytes[..]).unwrap();
+                black_box(image.pixels().unwrap())
+            })
+        });
+        group.bench_with_input(BenchmarkId::new("rows", name), &bytes, |b, bytes| {
+            b.iter(|| {
+                for row in PngReader::rows(&bytes[..]).unwrap() {
+                    black_box(row.unwrap());
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, decode);
+criterion_main!(benches);
@@ -0,0 +1,39 @@
+use graphics::canvas::Canvas;
+use graphics::color::Color;
+use graphics::math::Point2D;
+use graphics::render::*;
+use graphics::ui::{self, ButtonStyle, Rect};
+
+fn main() {
+    let demo = ButtonDemo { lit: false };
+    demo.render().expect("Could not start rendering");
+}
+
+/// Click the button to switch the panel color
+pub struct ButtonDemo {
+    lit: bool,
+}
+
+impl Render2D for ButtonDemo {
+    fn height(&mut self) -> u32 {
+        300
+    }
+    fn width(&mut self) -> u32 {
+        400
+    }
+    fn title(&mut self) -> String {
+        "Button".into()
+    }
+
+    fn update(&mut self, canvas: &mut Canvas, input: &dyn Input, _delta_t: f32) -> bool {
+        let panel = if self.lit { Color::ORANGE } else { Color::NAVY };
+        canvas.fill_rect_fast(&Point2D::new(0, 150), 400, 150, panel);
+
+        let rect = Rect::new(140, 60, 120, 40);
+        let label = if self.lit { "Turn off" } else { "Turn on" };
+        if ui::button(canvas, rect, label, input, &ButtonStyle::default()) {
+            self.lit = !self.lit;
+        }
+        true
+    }
+}
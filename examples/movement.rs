@@ -0,0 +1,116 @@
+use graphics::canvas::Canvas;
+use graphics::color::Color;
+use graphics::math::{FVec2D, Point2D};
+use graphics::render::*;
+
+/// Movement speed in pixels per second
+const SPEED: f32 = 250.0;
+/// Longest charge in seconds
+const MAX_CHARGE: f32 = 1.0;
+
+fn main() {
+    let drawing_canvas = Movement::new(800, 600, "Movement".into());
+    drawing_canvas.render().expect("Could not start rendering");
+}
+
+/// Ball moved with held arrow keys, space charges a shot that is fired on release
+pub struct Movement {
+    width: u32,
+    height: u32,
+    title: String,
+    position: FVec2D,
+    charge: Option<f32>,
+    /// Shots flying up, position and speed
+    shots: Vec<(FVec2D, f32)>,
+}
+
+impl Movement {
+    pub fn new(width: u32, height: u32, title: String) -> Self {
+        Movement {
+            width,
+            height,
+            title,
+            position: FVec2D::new(width as f32 / 2.0, height as f32 / 2.0),
+            charge: None,
+            shots: Vec::new(),
+        }
+    }
+}
+
+impl Render2D for Movement {
+    ///  Get window properties height
+    fn height(&mut self) -> u32 {
+        self.height
+    }
+    /// Get wWindow properties width
+    fn width(&mut self) -> u32 {
+        self.width
+    }
+    /// Get wWindow properties title
+    fn title(&mut self) -> String {
+        self.title.clone()
+    }
+
+    fn update(&mut self, canvas: &mut Canvas, input: &dyn Input, delta_t: f32) -> bool {
+        // continuous motion: key_held is true every frame, scale by delta_t
+        let mut direction = FVec2D::new(0.0, 0.0);
+        if input.key_held(Key::Left) {
+            direction.x -= 1.0;
+        }
+        if input.key_held(Key::Right) {
+            direction.x += 1.0;
+        }
+        if input.key_held(Key::Up) {
+            direction.y += 1.0;
+        }
+        if input.key_held(Key::Down) {
+            direction.y -= 1.0;
+        }
+        // the left stick of a gamepad works too (with the `gamepad` feature)
+        direction.x += input.axis(GamepadAxis::LeftStickX);
+        direction.y += input.axis(GamepadAxis::LeftStickY);
+        self.position += direction * SPEED * delta_t;
+
+        // charge: start on press, grow while held, fire on release
+        if input.key_pressed(Key::Space) || input.button_pressed(GamepadButton::South) {
+            self.charge = Some(0.0);
+        }
+        if let Some(charge) = self.charge.as_mut() {
+            if input.key_held(Key::Space) || input.button_held(GamepadButton::South) {
+                *charge = (*charge + delta_t).min(MAX_CHARGE);
+            }
+        }
+        if input.key_released(Key::Space) || input.button_released(GamepadButton::South) {
+            if let Some(charge) = self.charge.take() {
+                self.shots.push((self.position, 200.0 + charge * 600.0));
+            }
+        }
+
+        let top = self.height as f32;
+        for (position, speed) in self.shots.iter_mut() {
+            position.y += *speed * delta_t;
+        }
+        self.shots.retain(|(position, _)| position.y < top);
+        for (position, _) in self.shots.iter() {
+            canvas.fill_circle(
+                Point2D::new(position.x as i32, position.y as i32),
+                4,
+                Color::YELLOW,
+            );
+        }
+
+        let radius = 10 + (self.charge.unwrap_or(0.0) * 20.0) as i32;
+        canvas.fill_circle(
+            Point2D::new(self.position.x as i32, self.position.y as i32),
+            radius,
+            Color::CYAN,
+        );
+        canvas.draw_string(
+            Point2D::new(10, 10),
+            "Hold arrows to move, hold and release space to shoot".into(),
+            0.15,
+            Color::WHITE,
+        );
+        true
+    }
+}
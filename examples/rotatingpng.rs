@@ -3,13 +3,14 @@ use graphics::canvas::Transform;
 use graphics::canvas::Transformer;
 use graphics::color;
 use graphics::image::png::PngReader;
-use graphics::image::sprite::{Sprite, SpriteExtractor, SpriteSize};
+use graphics::image::sprite::Sprite;
 use graphics::math::Point2D;
 use graphics::render::*;
+use std::convert::TryFrom;
 
 fn main() {
     let drawing_canvas = Draw2D::new(800, 600, "Tiles".into());
-    drawing_canvas.render();
+    drawing_canvas.render().expect("Could not start rendering");
 }
 
 pub struct Draw2D {
@@ -51,17 +52,14 @@ impl Render2D for Draw2D {
     fn setup(&mut self, _canvas: &mut Canvas) -> bool {
         let image =
             PngReader::read(&mut std::fs::File::open("./assets/sample.png").unwrap()).unwrap();
-        let extractor = SpriteExtractor::from_png(&image, SpriteSize::default(), 0, 0).unwrap();
-        self.tile = extractor.extract_whole();
+        self.tile = Sprite::try_from(&image).unwrap();
         true
     }
 
     /// Update method called when the canvas is to be updated
     /// This is called periodically per frame and each frame is drawn individually
     /// Must be overriden/implmented
-    fn update(&mut self, canvas: &mut Canvas, _input: &WinitInputHelper, _delta_t: f32) -> bool {
-        // clear screen
-        canvas.fill(color::Color::rgb(255, 217, 217));
+    fn update(&mut self, canvas: &mut Canvas, _input: &dyn Input, _delta_t: f32) -> bool {
         canvas.draw_string(
             Point2D::new(10, 10),
             "Rotating Sample.png".into(),
@@ -91,4 +89,9 @@ impl Render2D for Draw2D {
         canvas.transform_sprite(&self.tile, &transformer);
         true
     }
+
+    /// Background color, the canvas is cleared with it before each update
+    fn clear_color(&mut self) -> Option<color::Color> {
+        Some(color::Color::rgb(255, 217, 217))
+    }
 }
@@ -9,7 +9,7 @@ const GRID_SCALE: f32 = 20.0;
 
 fn main() {
     let game_canvas = SnakeGame::new(400, 400, "Snake Game".into());
-    game_canvas.render();
+    game_canvas.render().expect("Could not start rendering");
 }
 
 pub struct SnakeGame {
@@ -45,6 +45,10 @@ impl Render2D for SnakeGame {
     fn title(&mut self) -> String {
         self.title.clone()
     }
+    /// Update the game at a steady 60 updates per second
+    fn fixed_timestep(&self) -> Option<f32> {
+        Some(1.0 / 60.0)
+    }
     ///
     /// Setup method called when the world is first created
     /// Must be overriden.
@@ -56,22 +60,22 @@ impl Render2D for SnakeGame {
     /// Update method called when the canvas is to be updated
     /// This is called periodically per frame and each frame is drawn individually
     /// Must be overriden/implmented
-    fn update(&mut self, canvas: &mut Canvas, input: &InputHelper, delta_t: f32) -> bool {
+    fn update(&mut self, canvas: &mut Canvas, input: &dyn Input, delta_t: f32) -> bool {
         if self.snake.state == SnakeState::Alive {
             canvas.fill(Color::BLUE);
             self.snake.show(canvas);
             self.snake.update(canvas, delta_t);
             self.food.show(canvas);
-            if input.key_pressed(VirtualKeyCode::W) {
+            if input.key_pressed(Key::W) || input.button_pressed(GamepadButton::DPadUp) {
                 self.snake.dir = FVec2D::new(0.0, -1.0);
             }
-            if input.key_pressed(VirtualKeyCode::A) {
+            if input.key_pressed(Key::A) || input.button_pressed(GamepadButton::DPadLeft) {
                 self.snake.dir = FVec2D::new(-1.0, 0.0);
             }
-            if input.key_pressed(VirtualKeyCode::S) {
+            if input.key_pressed(Key::S) || input.button_pressed(GamepadButton::DPadDown) {
                 self.snake.dir = FVec2D::new(0.0, 1.0);
             }
-            if input.key_pressed(VirtualKeyCode::D) {
+            if input.key_pressed(Key::D) || input.button_pressed(GamepadButton::DPadRight) {
                 self.snake.dir = FVec2D::new(1.0, 0.0);
             }
             if self.snake.consume(&mut self.food) {
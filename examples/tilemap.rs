@@ -0,0 +1,124 @@
+use graphics::canvas::Canvas;
+use graphics::color::Color;
+use graphics::image::sprite::{Sprite, SpriteSize};
+use graphics::image::tilemap::TileMap;
+use graphics::math::Point2D;
+use graphics::render::*;
+
+const TILE_SIZE: usize = 32;
+const MAP_WIDTH: usize = 100;
+const MAP_HEIGHT: usize = 30;
+/// Scrolling speed in pixels per second
+const SCROLL_SPEED: f32 = 200.0;
+
+fn main() {
+    let drawing_canvas = Scroller::new(800, 600, "Tile Map".into());
+    drawing_canvas.render().expect("Could not start rendering");
+}
+
+pub struct Scroller {
+    width: u32,
+    height: u32,
+    title: String,
+    map: TileMap,
+    scroll_x: f32,
+    scroll_y: f32,
+}
+
+impl Scroller {
+    pub fn new(width: u32, height: u32, title: String) -> Self {
+        Scroller {
+            width,
+            height,
+            title,
+            map: TileMap::default(),
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+        }
+    }
+}
+
+/// Build a square tile with a darker border so the grid is visible
+fn make_tile(color: Color) -> Sprite {
+    let border = color * 0.6;
+    let pixels = (0..TILE_SIZE * TILE_SIZE)
+        .map(|i| {
+            let (x, y) = (i % TILE_SIZE, i / TILE_SIZE);
+            if x == 0 || y == 0 || x == TILE_SIZE - 1 || y == TILE_SIZE - 1 {
+                border
+            } else {
+                color
+            }
+        })
+        .collect();
+    Sprite {
+        width: TILE_SIZE,
+        height: TILE_SIZE,
+        pixels,
+    }
+}
+
+impl Render2D for Scroller {
+    ///  Get window properties height
+    fn height(&mut self) -> u32 {
+        self.height
+    }
+    /// Get wWindow properties width
+    fn width(&mut self) -> u32 {
+        self.width
+    }
+    /// Get wWindow properties title
+    fn title(&mut self) -> String {
+        self.title.clone()
+    }
+
+    /// Setup method called when the world is first created
+    /// Builds a tileset of plain colors and a striped map much bigger than the window
+    fn setup(&mut self, _canvas: &mut Canvas) -> bool {
+        let tileset = vec![
+            make_tile(Color::rgb(60, 160, 60)),
+            make_tile(Color::rgb(120, 90, 50)),
+            make_tile(Color::rgb(70, 110, 200)),
+        ];
+        let tiles = (0..MAP_WIDTH * MAP_HEIGHT)
+            .map(|i| (((i % MAP_WIDTH) / 3 + (i / MAP_WIDTH) / 2) % 3) as u32)
+            .collect();
+        self.map = TileMap::new(MAP_WIDTH, MAP_HEIGHT, tiles, tileset).unwrap();
+        true
+    }
+
+    /// Scroll the map with the arrow keys
+    fn update(&mut self, canvas: &mut Canvas, input: &dyn Input, delta_t: f32) -> bool {
+        let step = SCROLL_SPEED * delta_t;
+        if input.key_held(Key::Right) {
+            self.scroll_x += step;
+        }
+        if input.key_held(Key::Left) {
+            self.scroll_x -= step;
+        }
+        if input.key_held(Key::Up) {
+            self.scroll_y += step;
+        }
+        if input.key_held(Key::Down) {
+            self.scroll_y -= step;
+        }
+        let max_x = (MAP_WIDTH * TILE_SIZE) as f32 - self.width as f32;
+        let max_y = (MAP_HEIGHT * TILE_SIZE) as f32 - self.height as f32;
+        self.scroll_x = self.scroll_x.clamp(0.0, max_x.max(0.0));
+        self.scroll_y = self.scroll_y.clamp(0.0, max_y.max(0.0));
+
+        // only the tiles inside the window are drawn
+        self.map.draw(
+            canvas,
+            Point2D::new(-self.scroll_x as i32, -self.scroll_y as i32),
+            SpriteSize::new(TILE_SIZE, TILE_SIZE),
+        );
+        canvas.draw_string(
+            Point2D::new(10, 10),
+            "Arrow keys to scroll".into(),
+            0.20,
+            Color::WHITE,
+        );
+        true
+    }
+}
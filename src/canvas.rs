@@ -1,15 +1,21 @@
 use super::image::png::PngReader;
 use crate::color::Color;
+use crate::image::filter;
+use crate::image::png::PngImage;
 use crate::image::png::PngWriter;
+use crate::image::ppm::PpmWriter;
 use crate::image::sprite::Sprite;
 use crate::image::sprite::SpriteExtractor;
 use crate::image::sprite::SpriteSize;
 use crate::math;
+use crate::math::Angle;
 use crate::math::FVec2D;
 use crate::math::FVec3D;
 use crate::math::Mat3x3;
 use crate::math::Point2D;
 use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
 
 /// Font letters and symbols.
 /// const FONT_LETTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 .,;#$&()?[]}{@*!''";
@@ -18,6 +24,7 @@ pub const FONT_LETTERS: &str = "!\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNO
 /// Transform Struct
 /// Takes a number of affine transormations
 /// These transformations can be applied to a given target/point/Sprite.
+/// Rotate angles are in radians, use Transform::rotate to pass an Angle instead.
 #[derive(Debug, Copy, Clone)]
 pub enum Transform {
     Rotate(f32),
@@ -25,6 +32,13 @@ pub enum Transform {
     Translate(f32, f32),
 }
 
+impl Transform {
+    /// Create a rotate transform from an Angle in any unit
+    pub fn rotate(angle: Angle) -> Self {
+        Transform::Rotate(angle.as_radians())
+    }
+}
+
 /// A transformer used to apply several transforms  ona target
 #[allow(dead_code, unused_variables)]
 pub struct Transformer {
@@ -64,6 +78,229 @@ impl Default for Transformer {
     }
 }
 
+/// 2D camera used to convert between world and canvas (screen) coordinates
+/// `position` is the world point shown at the center of the canvas and `zoom` the number
+/// of pixels per world unit (1.0 = no zoom, 2.0 = everything twice as big).
+///
+/// Example usage:
+///    let camera = Camera2D::new(player_position, 2.0);
+///    let view = canvas.with_camera(&camera);
+///    view.fill_circle(enemy_position, 5, Color::RED);
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Camera2D {
+    pub position: FVec2D,
+    pub zoom: f32,
+}
+
+impl Camera2D {
+    /// Create a camera looking at the given world position
+    /// `zoom` must be greater than zero
+    pub fn new(position: FVec2D, zoom: f32) -> Self {
+        Self { position, zoom }
+    }
+
+    /// Convert a world position into canvas coordinates
+    pub fn world_to_screen(&self, p: Point2D, canvas: &Canvas) -> Point2D {
+        let (center_x, center_y) = screen_center(canvas);
+        Point2D::new(
+            ((p.x as f32 - self.position.x) * self.zoom + center_x).round() as i32,
+            ((p.y as f32 - self.position.y) * self.zoom + center_y).round() as i32,
+        )
+    }
+
+    /// Convert canvas coordinates (e.g. the mouse position) into a world position
+    pub fn screen_to_world(&self, p: Point2D, canvas: &Canvas) -> Point2D {
+        let (center_x, center_y) = screen_center(canvas);
+        Point2D::new(
+            ((p.x as f32 - center_x) / self.zoom + self.position.x).round() as i32,
+            ((p.y as f32 - center_y) / self.zoom + self.position.y).round() as i32,
+        )
+    }
+
+    /// Helper convert a world length (radius, width...) into pixels
+    fn scale(&self, length: i32) -> i32 {
+        (length as f32 * self.zoom).round() as i32
+    }
+}
+
+impl Default for Camera2D {
+    fn default() -> Self {
+        Self::new(FVec2D::new(0.0, 0.0), 1.0)
+    }
+}
+
+/// Helper canvas center in pixels
+fn screen_center(canvas: &Canvas) -> (f32, f32) {
+    (canvas.width() as f32 / 2.0, canvas.height() as f32 / 2.0)
+}
+
+/// A canvas seen through a camera, created with Canvas::with_camera
+/// Drawing methods take world coordinates and sizes, converted with the camera before drawing.
+pub struct CameraView<'a> {
+    canvas: &'a Canvas,
+    camera: Camera2D,
+}
+
+impl<'a> CameraView<'a> {
+    /// Convert a world position into canvas coordinates
+    pub fn to_screen(&self, p: Point2D) -> Point2D {
+        self.camera.world_to_screen(p, self.canvas)
+    }
+
+    /// Plots a single pixel at the given world position
+    pub fn plot(&self, p: Point2D, color: Color) {
+        let p = self.to_screen(p);
+        self.canvas.plot(p.x, p.y, color);
+    }
+
+    /// Draws a line between two world positions
+    pub fn line_between(&self, origin: Point2D, dest: Point2D, color: Color) {
+        self.canvas
+            .line_between(self.to_screen(origin), self.to_screen(dest), color);
+    }
+
+    /// Draws a hollow rectangle, `width` and `height` in world units
+    pub fn rectangle(&self, origin: Point2D, width: i32, height: i32, color: Color) {
+        self.canvas.rectangle(
+            self.to_screen(origin),
+            self.camera.scale(width),
+            self.camera.scale(height),
+            color,
+        );
+    }
+
+    /// Draws a filled rectangle, `width` and `height` in world units
+    pub fn fill_rectangle(&self, origin: Point2D, width: i32, height: i32, color: Color) {
+        self.canvas.fill_rectangle(
+            &self.to_screen(origin),
+            self.camera.scale(width),
+            self.camera.scale(height),
+            color,
+        );
+    }
+
+    /// Draws a hollow circle, `radius` in world units
+    pub fn circle(&self, origin: Point2D, radius: i32, color: Color) {
+        self.canvas
+            .circle(self.to_screen(origin), self.camera.scale(radius), color);
+    }
+
+    /// Draws a filled circle, `radius` in world units
+    pub fn fill_circle(&self, origin: Point2D, radius: i32, color: Color) {
+        self.canvas
+            .fill_circle(self.to_screen(origin), self.camera.scale(radius), color);
+    }
+
+    /// Draws a filled polygon from its world vertices
+    pub fn fill_polygon(&self, vertices: &[Point2D], color: Color) {
+        let vertices: Vec<Point2D> = vertices.iter().map(|p| self.to_screen(*p)).collect();
+        self.canvas.fill_polygon(&vertices, color);
+    }
+
+    /// Draws a sprite with its top left corner at a world position
+    /// Only the position follows the camera, the sprite itself is not zoomed.
+    pub fn sprite(&self, origin: Point2D, tile: &Sprite) {
+        self.canvas.sprite(self.to_screen(origin), tile);
+    }
+}
+
+/// How plot combines a color with the pixel already on the canvas
+///
+/// Which representation each mode assumes:
+/// * `Replace`: any, the color is stored as is.
+/// * `SrcOver`: straight alpha colors and straight alpha pixels (the default for PNG and sprites).
+/// * `PremultipliedSrcOver`: premultiplied colors and premultiplied pixels, convert with
+///   `Color::to_premultiplied` before drawing and `Color::from_premultiplied` before saving.
+///   Blending premultiplied colors avoids dark fringes around scaled or blurred sprites.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum BlendMode {
+    /// Overwrite the pixel, fully transparent colors are still skipped
+    Replace,
+    /// Alpha composite the color over the pixel, straight alpha
+    SrcOver,
+    /// Alpha composite the color over the pixel, premultiplied alpha
+    PremultipliedSrcOver,
+}
+
+/// Where the first row of the canvas pixel buffer is shown
+/// * `BottomLeft`: the first row of the buffer is the bottom of the picture, as glium textures
+///   expect. Default, y is stored in buffer row `height - y` as the canvas always did: y = 0
+///   is outside the canvas and the first buffer row is never drawn.
+/// * `TopLeft`: the first row of the buffer is the top of the picture, as image files and
+///   most other pixel buffers expect, `pixels` can then be handed to an encoder as is.
+///   y is stored in buffer row y.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Origin {
+    TopLeft,
+    BottomLeft,
+}
+
+/// Rectangle in canvas pixels, `x`/`y` is the top left corner
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+}
+
+impl Rect {
+    /// Create a new rectangle
+    pub fn new(x: i32, y: i32, width: i32, height: i32) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Whether `point` is inside the rectangle, the right and bottom edges excluded
+    pub fn contains(&self, point: Point2D) -> bool {
+        point.x() >= self.x
+            && point.x() < self.x + self.width
+            && point.y() >= self.y
+            && point.y() < self.y + self.height
+    }
+
+    /// Middle of the rectangle, rounded towards the top left
+    pub fn center(&self) -> Point2D {
+        Point2D::new(self.x + self.width / 2, self.y + self.height / 2)
+    }
+}
+
+/// Rectangle of the pixel buffer returned by Canvas::dirty_rect
+/// Same fields as glium::Rect, the rows are the texture ones with Origin::BottomLeft.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DirtyRect {
+    pub left: u32,
+    pub bottom: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[cfg(feature = "window")]
+impl From<DirtyRect> for glium::Rect {
+    fn from(rect: DirtyRect) -> Self {
+        glium::Rect {
+            left: rect.left,
+            bottom: rect.bottom,
+            width: rect.width,
+            height: rect.height,
+        }
+    }
+}
+
+/// A canvas used as an off-screen buffer for multi-pass effects
+/// It is never shown: draw on it, post-process it (blur, map_colors...) and then
+/// blit it, or draw it with to_sprite, on the canvas being rendered.
+/// # Example
+/// let target = RenderTarget::new(200, 200);
+/// target.fill_circle(Point2D::new(100, 100), 50, Color::WHITE);
+/// target.blur(4);
+/// canvas.blit(&target, Point2D::new(0, 0));
+pub type RenderTarget = Canvas;
+
 /// A Canvas implementation to draw pixels on a pixel bufer.
 ///
 /// Supports methods to:
@@ -71,13 +308,63 @@ impl Default for Transformer {
 /// * Draw polygons
 /// * Draw text
 /// * Draw sprite/png images
+///
+/// Every drawing method goes through plot and uses the current BlendMode,
+/// fill, blur and map_colors work on the stored pixels directly.
+#[derive(Clone)]
 #[allow(dead_code, unused_variables)]
 pub struct Canvas {
-    width: u32,
-    height: u32,
+    width: std::cell::Cell<u32>,
+    height: std::cell::Cell<u32>,
     font: Option<HashMap<char, Sprite>>,
+    blend_mode: std::cell::Cell<BlendMode>,
+    origin: std::cell::Cell<Origin>,
+    /// Region changed since the last clear_dirty: left, bottom, right, top (exclusive)
+    /// in buffer rows, the lowest row first whatever the origin.
+    dirty: std::cell::Cell<Option<[u32; 4]>>,
+    /// Color of the last clear while only `drawn` changed since, see clear
+    cleared: std::cell::Cell<Option<Color>>,
+    /// Region drawn since the last clear, same layout as dirty
+    drawn: std::cell::Cell<Option<[u32; 4]>>,
+    /// Set for canvases created with Canvas::growable
+    growth: Option<Growth>,
+    /// Drawing outside this rectangle is discarded, see set_clip
+    clip: std::cell::Cell<Option<Rect>>,
+    /// Applied to the coordinates given to drawing methods, None for the identity
+    transform: std::cell::Cell<Option<Mat3x3<f32>>>,
+    /// States pushed by save, popped by restore
+    saved: std::cell::RefCell<Vec<CanvasState>>,
     pub pixels: std::cell::RefCell<Vec<Color>>,
 }
+
+/// Drawing settings kept by Canvas::save
+#[derive(Debug, Copy, Clone, PartialEq)]
+struct CanvasState {
+    clip: Option<Rect>,
+    blend_mode: BlendMode,
+    transform: Option<Mat3x3<f32>>,
+}
+
+/// State of a growable canvas (see Canvas::growable)
+#[derive(Clone)]
+struct Growth {
+    /// Content coordinates of the buffer pixel at x = 0, y = 0
+    origin: std::cell::Cell<Point2D>,
+    /// Top left and bottom right corners of everything plotted, in content coordinates
+    bounds: std::cell::Cell<Option<(Point2D, Point2D)>>,
+    /// Largest width and height the buffer may grow to, see Canvas::set_max_size
+    max_size: std::cell::Cell<(u32, u32)>,
+}
+
+/// Extra pixels added on the side a growable canvas grows, so it is not resized on every plot
+const GROWTH_MARGIN: i32 = 64;
+/// Default largest width and height of a growable canvas (a 4096x4096 buffer is 64 MB)
+const DEFAULT_MAX_GROWTH: u32 = 4096;
+
+/// Tolerance of Mat3x3::try_inverse for the transforms of transform_sprite, the ones
+/// flattening the sprite (e.g. a zero scale) are skipped: it would cover no pixel anyway.
+const SINGULAR_EPSILON: f32 = 1e-9;
+
 impl Canvas {
     /// Create a new canvas with the given dimensions
     pub fn new(width: u32, height: u32) -> Self {
@@ -87,26 +374,469 @@ impl Canvas {
         }
         let font = read_font(); // load font into memory
         Self {
-            width,
-            height,
+            width: std::cell::Cell::new(width),
+            height: std::cell::Cell::new(height),
             font,
+            blend_mode: std::cell::Cell::new(BlendMode::Replace),
+            origin: std::cell::Cell::new(Origin::BottomLeft),
+            dirty: std::cell::Cell::new(Some([0, 0, width, height])),
+            cleared: std::cell::Cell::new(None),
+            drawn: std::cell::Cell::new(None),
+            growth: None,
+            clip: std::cell::Cell::new(None),
+            transform: std::cell::Cell::new(None),
+            saved: std::cell::RefCell::new(Vec::new()),
             pixels: std::cell::RefCell::new(pixels),
         }
     }
 
+    /// Create a canvas that grows instead of clipping when plot goes past its edges
+    /// plot and plot_points take content coordinates, which may be negative, and enlarge
+    /// the buffer as needed (see content_origin and content_bounds), up to set_max_size.
+    /// Every other method, get_pixel included, works in buffer coordinates: a content
+    /// point is at `content - content_origin()` in the buffer. They do not grow the canvas.
+    /// # Arguments
+    /// `width`     initial width of the buffer
+    /// `height`    initial height of the buffer
+    pub fn growable(width: u32, height: u32) -> Self {
+        Self {
+            growth: Some(Growth {
+                origin: std::cell::Cell::new(Point2D::new(0, 0)),
+                bounds: std::cell::Cell::new(None),
+                max_size: std::cell::Cell::new((
+                    DEFAULT_MAX_GROWTH.max(width),
+                    DEFAULT_MAX_GROWTH.max(height),
+                )),
+            }),
+            ..Self::new(width, height)
+        }
+    }
+
+    /// Set the largest width and height a growable canvas may grow to
+    /// Plots that would need a larger buffer are ignored. Defaults to 4096x4096 (or the
+    /// initial size if larger), does nothing on fixed canvases.
+    pub fn set_max_size(&self, width: u32, height: u32) {
+        if let Some(growth) = &self.growth {
+            growth.max_size.set((width, height));
+        }
+    }
+
+    /// Largest width and height the canvas may grow to, its size for fixed canvases
+    pub fn max_size(&self) -> (u32, u32) {
+        match &self.growth {
+            Some(growth) => growth.max_size.get(),
+            None => (self.width(), self.height()),
+        }
+    }
+
+    /// Whether the canvas was created with Canvas::growable
+    pub fn is_growable(&self) -> bool {
+        self.growth.is_some()
+    }
+
+    /// Content coordinates of the buffer pixel at 0, 0
+    /// Always 0, 0 for fixed canvases, negative once a growable canvas grew left or up.
+    pub fn content_origin(&self) -> Point2D {
+        self.growth
+            .as_ref()
+            .map_or(Point2D::new(0, 0), |growth| growth.origin.get())
+    }
+
+    /// Region used by the drawing, as top left and bottom right corners (inclusive)
+    /// For growable canvases this covers every point plotted, in content coordinates,
+    /// None if nothing was plotted yet. Fixed canvases return the whole canvas.
+    pub fn content_bounds(&self) -> Option<(Point2D, Point2D)> {
+        match &self.growth {
+            Some(growth) => growth.bounds.get(),
+            None => Some((
+                Point2D::new(0, 0),
+                Point2D::new(self.width() as i32 - 1, self.height() as i32 - 1),
+            )),
+        }
+    }
+
+    /// Helper: for growable canvases, enlarge the buffer to include a point in content
+    /// coordinates and return its buffer coordinates. Fixed canvases return it unchanged.
+    /// None when the canvas would have to grow past its maximum size.
+    fn grow_to(&self, x: i32, y: i32) -> Option<(i32, i32)> {
+        let growth = match &self.growth {
+            Some(growth) => growth,
+            None => return Some((x, y)),
+        };
+        let origin = growth.origin.get();
+        let (bx, by) = (x.checked_sub(origin.x)?, y.checked_sub(origin.y)?);
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let (max_width, max_height) = growth.max_size.get();
+        // pixels to add before and after `position` on an axis where 0..size is drawable,
+        // the margin is added on the growing side(s) as far as the maximum size allows
+        let grow = |position: i32, size: i32, max: i32| -> Option<(i32, i32)> {
+            let mut before = position.min(0).checked_neg()?;
+            let mut after = position.checked_sub(size)?.checked_add(1)?.max(0);
+            if before + after == 0 {
+                return Some((0, 0));
+            }
+            let mut room = max.checked_sub(size.checked_add(before)?.checked_add(after)?)?;
+            if room < 0 {
+                return None;
+            }
+            for side in [&mut before, &mut after] {
+                if *side > 0 {
+                    let margin = room.min(GROWTH_MARGIN);
+                    *side += margin;
+                    room -= margin;
+                }
+            }
+            Some((before, after))
+        };
+        let limit = |max: u32| max.min(i32::MAX as u32) as i32;
+        let (left, right) = grow(bx, width, limit(max_width))?;
+        // with Origin::BottomLeft y = 0 is not drawable, see Origin
+        let first = self.first_row();
+        let (top, bottom) = grow(
+            by.checked_sub(first)?,
+            height - first,
+            limit(max_height) - first,
+        )?;
+
+        let bounds = match growth.bounds.get() {
+            Some((min, max)) => (
+                Point2D::new(min.x.min(x), min.y.min(y)),
+                Point2D::new(max.x.max(x), max.y.max(y)),
+            ),
+            None => (Point2D::new(x, y), Point2D::new(x, y)),
+        };
+        growth.bounds.set(Some(bounds));
+        if left + top + right + bottom == 0 {
+            return Some((bx, by));
+        }
+
+        // the sizes are within max_size, only the pixel count may overflow (on 32 bits)
+        let (new_width, new_height) = (width + left + right, height + top + bottom);
+        let count = (new_width as usize).checked_mul(new_height as usize)?;
+        let mut resized = vec![Color::BLACK; count];
+        {
+            let pixels = self.pixels.borrow();
+            // rows move by the rows added before them in the buffer
+            let shift = match self.origin.get() {
+                Origin::TopLeft => top,
+                Origin::BottomLeft => bottom,
+            };
+            for (row, line) in pixels.chunks_exact(width.max(1) as usize).enumerate() {
+                let start = ((row as i32 + shift) * new_width + left) as usize;
+                resized[start..start + width as usize].copy_from_slice(line);
+            }
+        }
+        *self.pixels.borrow_mut() = resized;
+        self.width.set(new_width as u32);
+        self.height.set(new_height as u32);
+        growth
+            .origin
+            .set(Point2D::new(origin.x - left, origin.y - top));
+        self.mark_all_dirty();
+        Some((bx + left, by + top))
+    }
+
+    /// Region of the pixel buffer changed since the last clear_dirty, None if nothing changed
+    /// The rectangle is in buffer rows (`bottom` is the lowest row index, the first row
+    /// shown at the bottom or top depending on the origin), the render loop uploads only
+    /// this part of the canvas. Drawing methods update it, code writing
+    /// to `pixels` directly must call mark_all_dirty.
+    pub fn dirty_rect(&self) -> Option<DirtyRect> {
+        self.dirty
+            .get()
+            .map(|[left, bottom, right, top]| DirtyRect {
+                left,
+                bottom,
+                width: right - left,
+                height: top - bottom,
+            })
+    }
+
+    /// Flag the whole canvas as changed so it is uploaded entirely on the next frame
+    pub fn mark_all_dirty(&self) {
+        self.dirty.set(Some([0, 0, self.width(), self.height()]));
+        self.cleared.set(None);
+    }
+
+    /// Forget the changed region, done by the render loop after uploading it
+    pub fn clear_dirty(&self) {
+        self.dirty.set(None);
+    }
+
+    /// Helper: grow the dirty region to include the given buffer columns and rows (exclusive end)
+    fn mark_dirty(&self, left: u32, bottom: u32, right: u32, top: u32) {
+        let grown = |region: Option<[u32; 4]>| match region {
+            Some([l, b, r, t]) => [l.min(left), b.min(bottom), r.max(right), t.max(top)],
+            None => [left, bottom, right, top],
+        };
+        self.dirty.set(Some(grown(self.dirty.get())));
+        self.drawn.set(Some(grown(self.drawn.get())));
+    }
+
+    /// Set how colors are combined with the pixels already on the canvas
+    /// Defaults to BlendMode::Replace.
+    pub fn set_blend_mode(&self, mode: BlendMode) {
+        self.blend_mode.set(mode);
+    }
+
+    /// Retrieve the current blend mode
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode.get()
+    }
+
+    /// Only draw inside `clip` (in canvas pixels), None to draw on the whole canvas
+    /// Applies to every drawing method, fill, blur and map_colors still change every pixel.
+    /// The clip rectangle is not moved by set_transform.
+    pub fn set_clip(&self, clip: Option<Rect>) {
+        self.clip.set(clip);
+    }
+
+    /// Retrieve the current clip rectangle
+    pub fn clip(&self) -> Option<Rect> {
+        self.clip.get()
+    }
+
+    /// Transform the coordinates given to drawing methods with `m`, e.g. to scale, rotate or
+    /// move a whole drawing at once (a lower level Camera2D)
+    /// Primitives transform their points (line ends, polygon and triangle vertices, circle
+    /// centers, rectangle corners) and rasterize the result, so straight edges stay exact
+    /// under any affine transform. Circles and rings keep their round shape: their radius
+    /// is scaled by the average scale of `m`, so non uniform scales and shears only
+    /// approximate them. Sprites (and blit) only move their top left corner, use
+    /// transform_sprite (which also draws text) to scale or rotate them. fill, draw_grid
+    /// and the clip rectangle are not transformed.
+    ///
+    /// Example usage:
+    ///    canvas.set_transform(Mat3x3::compose(FVec2D::new(50.0, 50.0), angle, FVec2D::new(2.0, 2.0)));
+    ///    canvas.rectangle(Point2D::new(-10, -10), 20, 20, Color::WHITE);
+    pub fn set_transform(&self, m: Mat3x3<f32>) {
+        let transform = if m == Mat3x3::identity() {
+            None
+        } else {
+            Some(m)
+        };
+        self.transform.set(transform);
+    }
+
+    /// Retrieve the current transform, the identity unless set_transform changed it
+    pub fn transform(&self) -> Mat3x3<f32> {
+        self.transform.get().unwrap_or_else(Mat3x3::identity)
+    }
+
+    /// Back to drawing in canvas pixels, same as setting the identity transform
+    pub fn reset_transform(&self) {
+        self.transform.set(None);
+    }
+
+    /// Helper: apply the transform to a point, rounded to the nearest pixel
+    fn transformed(&self, p: Point2D) -> Point2D {
+        match self.transform.get() {
+            Some(m) => {
+                let (x, y) = transform_f(&m, p.x as f32, p.y as f32);
+                Point2D::new(x.round() as i32, y.round() as i32)
+            }
+            None => p,
+        }
+    }
+
+    /// Helper: scale a length (e.g. radius) by the average scale of the transform
+    fn transformed_length(&self, length: i32) -> i32 {
+        match self.transform.get() {
+            Some(m) => (length as f32 * m.det().abs().sqrt()).round() as i32,
+            None => length,
+        }
+    }
+
+    /// Helper: transformed rectangle from its top left corner and size
+    /// Ok with the top left corner and size of the result while the transform keeps
+    /// rectangles axis aligned (translations, scales and mirrors), otherwise Err with
+    /// its four transformed corners.
+    fn transformed_rect(
+        &self,
+        origin: Point2D,
+        width: i32,
+        height: i32,
+    ) -> Result<(Point2D, i32, i32), [Point2D; 4]> {
+        let corners = [
+            origin,
+            Point2D::new(origin.x + width, origin.y),
+            Point2D::new(origin.x + width, origin.y + height),
+            Point2D::new(origin.x, origin.y + height),
+        ];
+        let corners = corners.map(|corner| self.transformed(corner));
+        let [a, b, c, d] = corners;
+        if !(a.y == b.y && c.y == d.y && a.x == d.x && b.x == c.x) {
+            return Err(corners);
+        }
+        let top_left = Point2D::new(math::min(a.x, c.x), math::min(a.y, c.y));
+        Ok((top_left, (c.x - a.x).abs(), (c.y - a.y).abs()))
+    }
+
+    /// Push the drawing settings (clip rectangle, blend mode and transform) on a stack
+    /// Change them freely afterwards, restore brings back the pushed ones. Saves nest,
+    /// like save/restore of an HTML canvas 2D context.
+    pub fn save(&self) {
+        self.saved.borrow_mut().push(CanvasState {
+            clip: self.clip.get(),
+            blend_mode: self.blend_mode.get(),
+            transform: self.transform.get(),
+        });
+    }
+
+    /// Pop the drawing settings pushed by the last save, does nothing without one
+    pub fn restore(&self) {
+        if let Some(state) = self.saved.borrow_mut().pop() {
+            self.clip.set(state.clip);
+            self.blend_mode.set(state.blend_mode);
+            self.transform.set(state.transform);
+        }
+    }
+
+    /// Helper: region that can be drawn, the canvas intersected with the clip rectangle
+    /// Left, top, right and bottom, the last two exclusive.
+    fn drawable_bounds(&self) -> (i32, i32, i32, i32) {
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let first = self.first_row();
+        match self.clip.get() {
+            Some(clip) => (
+                clip.x.max(0),
+                clip.y.max(first),
+                (clip.x + clip.width).min(width),
+                (clip.y + clip.height).min(height),
+            ),
+            None => (0, first, width, height),
+        }
+    }
+
+    /// Helper: smallest y that can be drawn, 1 with Origin::BottomLeft (see Origin)
+    fn first_row(&self) -> i32 {
+        match self.origin.get() {
+            Origin::TopLeft => 0,
+            Origin::BottomLeft => 1,
+        }
+    }
+
+    /// Set where the first row of the pixel buffer is shown, see Origin
+    /// Defaults to Origin::BottomLeft. The pixels already drawn are moved so they keep their
+    /// coordinates (get_pixel returns the same colors).
+    pub fn set_origin(&self, origin: Origin) {
+        if origin != self.origin.get() {
+            let swapped = self.swapped_rows();
+            *self.pixels.borrow_mut() = swapped;
+            self.origin.set(origin);
+            self.mark_all_dirty();
+        }
+    }
+
+    /// Retrieve where the first row of the pixel buffer is shown
+    pub fn origin(&self) -> Origin {
+        self.origin.get()
+    }
+
+    /// Helper: index in the pixel buffer of the row showing y, y must be inside the canvas
+    fn buffer_row(&self, y: i32) -> usize {
+        match self.origin.get() {
+            Origin::TopLeft => y as usize,
+            Origin::BottomLeft => (self.height() as i32 - y) as usize,
+        }
+    }
+
     /// Clear the canvas by filling it with a given color
     pub fn fill(&self, color: Color) {
-        for pixel in &mut *self.pixels.borrow_mut() {
-            *pixel = color;
+        self.pixels.borrow_mut().fill(color);
+        self.mark_all_dirty();
+        self.cleared.set(Some(color));
+        self.drawn.set(None);
+    }
+
+    /// Clear the canvas to a color, as fill, but only touch what changed since
+    /// When the previous clear (or fill) used the same color only the region drawn since
+    /// is reset and marked dirty, so a frame redrawing a small sprite uploads only that
+    /// sprite. This is what the render loop does with Render2D::clear_color.
+    pub fn clear(&self, color: Color) {
+        match (self.cleared.get(), self.drawn.get()) {
+            (Some(cleared), Some([left, bottom, right, top])) if cleared == color => {
+                let width = self.width() as usize;
+                let mut pixels = self.pixels.borrow_mut();
+                for row in bottom as usize..top as usize {
+                    pixels[row * width + left as usize..row * width + right as usize].fill(color);
+                }
+                self.mark_dirty(left, bottom, right, top);
+            }
+            (Some(cleared), None) if cleared == color => {}
+            _ => {
+                self.pixels.borrow_mut().fill(color);
+                self.mark_all_dirty();
+            }
+        }
+        self.cleared.set(Some(color));
+        self.drawn.set(None);
+    }
+
+    /// Tile a two color grid across the whole canvas, starting with color_a at the top left
+    /// `cell`      size in pixels of every square, 0 is treated as 1
+    /// `color_a`   color of the even cells
+    /// `color_b`   color of the odd cells
+    pub fn fill_checkerboard(&self, cell: u32, color_a: Color, color_b: Color) {
+        let cell = cell.max(1);
+        let mut pixels = self.pixels.borrow_mut();
+        for y in 0..self.height() {
+            for x in 0..self.width() {
+                let color = if (x / cell + y / cell) % 2 == 1 {
+                    color_b
+                } else {
+                    color_a
+                };
+                self.plot_into(&mut pixels, x as i32, y as i32, color);
+            }
+        }
+    }
+
+    /// Tile a sprite across the whole canvas, starting at the top left corner
+    /// `tile`  sprite repeated in both directions, an empty sprite draws nothing
+    pub fn fill_pattern(&self, tile: &Sprite) {
+        if tile.width == 0 || tile.height == 0 {
+            return;
+        }
+        let mut pixels = self.pixels.borrow_mut();
+        for y in 0..self.height() as usize {
+            for x in 0..self.width() as usize {
+                if let Some(color) = tile.get_pixel(x % tile.width, y % tile.height) {
+                    self.plot_into(&mut pixels, x as i32, y as i32, color);
+                }
+            }
+        }
+    }
+
+    /// Draw through a camera, the returned view takes world coordinates
+    pub fn with_camera(&self, camera: &Camera2D) -> CameraView<'_> {
+        CameraView {
+            canvas: self,
+            camera: *camera,
         }
     }
+
     /// Retrieve canvas width
     pub fn width(&self) -> u32 {
-        self.width
+        self.width.get()
     }
     /// Retrieve canvas height
     pub fn height(&self) -> u32 {
-        self.height
+        self.height.get()
+    }
+
+    /// Retrieve the color of the pixel at the given coordinates
+    /// Returns None if the coordinates fall outside of the canvas.
+    /// Coordinates are in the buffer, as for every drawing method: on growable canvases
+    /// subtract content_origin from the content coordinates given to plot.
+    pub fn get_pixel(&self, x: i32, y: i32) -> Option<Color> {
+        let (first, width, height) = (self.first_row(), self.width() as i32, self.height() as i32);
+        if x >= 0 && x < width && y >= first && y < height {
+            let normalized_position = self.buffer_row(y) * self.width() as usize + x as usize;
+            self.pixels.borrow().get(normalized_position).copied()
+        } else {
+            None
+        }
     }
 
     ///
@@ -116,19 +846,110 @@ impl Canvas {
     /// `y`   y axis offset
     /// `color`  pixel color
     ///
+    /// Growable canvases take content coordinates and grow to include the pixel instead of
+    /// clipping it, pixels past their maximum size are ignored.
     pub fn plot(&self, x: i32, y: i32, color: Color) {
+        let p = self.transformed(Point2D::new(x, y));
+        if let Some((x, y)) = self.grow_to(p.x, p.y) {
+            self.plot_into(&mut self.pixels.borrow_mut(), x, y, color);
+        }
+    }
+
+    /// Plot many points at once, the pixel buffer is borrowed a single time
+    /// Growable canvases grow to include every point, like plot.
+    /// `points`    point and color pairs
+    pub fn plot_points(&self, points: &[(Point2D, Color)]) {
+        let transformed: Vec<(Point2D, Color)>;
+        let points = match self.transform.get() {
+            Some(_) => {
+                transformed = points
+                    .iter()
+                    .map(|(point, color)| (self.transformed(*point), *color))
+                    .collect();
+                &transformed[..]
+            }
+            None => points,
+        };
+        // points the canvas can not grow to are dropped
+        let mut skipped = vec![false; points.len()];
+        if self.is_growable() {
+            for (point, skip) in points.iter().zip(skipped.iter_mut()) {
+                *skip = self.grow_to(point.0.x, point.0.y).is_none();
+            }
+        }
+        let origin = self.content_origin();
+        let mut pixels = self.pixels.borrow_mut();
+        for ((point, color), skip) in points.iter().zip(skipped) {
+            if !skip {
+                self.plot_into(&mut pixels, point.x - origin.x, point.y - origin.y, *color);
+            }
+        }
+    }
+
+    /// Plot a pixel at fractional coordinates, spreading it over the 2 - 4 nearest pixels
+    /// Every pixel gets the color with its alpha scaled by how much of it is covered,
+    /// so this requires BlendMode::SrcOver (or PremultipliedSrcOver with a premultiplied
+    /// color) to mix with what is below; Replace overwrites the partially covered pixels.
+    /// Integer coordinates draw the same single pixel as plot.
+    /// # Arguments
+    /// `x`   X axis offset
+    /// `y`   y axis offset
+    /// `color`  pixel color
+    pub fn plot_f(&self, x: f32, y: f32, color: Color) {
+        let (x, y) = match self.transform.get() {
+            Some(m) => transform_f(&m, x, y),
+            None => (x, y),
+        };
+        let (left, top) = (x.floor(), y.floor());
+        let (fx, fy) = (x - left, y - top);
+        let premultiplied = self.blend_mode() == BlendMode::PremultipliedSrcOver;
+        let mut pixels = self.pixels.borrow_mut();
+        for (dx, dy, coverage) in [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (1, 0, fx * (1.0 - fy)),
+            (0, 1, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            let scale = |channel: u8| (channel as f32 * coverage).round() as u8;
+            let covered = if premultiplied {
+                Color::rgba(
+                    scale(color.r()),
+                    scale(color.g()),
+                    scale(color.b()),
+                    scale(color.alpha()),
+                )
+            } else {
+                Color::rgba(color.r(), color.g(), color.b(), scale(color.alpha()))
+            };
+            // pixels not covered at all end up fully transparent and are skipped
+            self.plot_into(&mut pixels, left as i32 + dx, top as i32 + dy, covered);
+        }
+    }
+
+    /// Helper: plot into an already borrowed pixel buffer
+    /// Primitives borrow the pixels once and plot every pixel through here
+    /// instead of going through plot and borrowing for each one of them.
+    fn plot_into(&self, buf: &mut [Color], x: i32, y: i32, color: Color) {
         // Don't paint transparent pixels
         if color.alpha() == 0 {
             return;
         }
-        let pixel_length = self.width() * self.height();
-        if x >= 0 && x < self.width as i32 && y >= 0 && y < self.height as i32 {
-            // let normalized_position = (y * self.width as i32 + x) as usize;
-            // reverse y location  as glium texture starts bottom left as origing
-            let normalized_position = ((self.height as i32 - y) * self.width as i32 + x) as usize;
-            if normalized_position < pixel_length as usize {
-                self.pixels.borrow_mut()[normalized_position] = color;
-            }
+        let (left, top, right, bottom) = self.drawable_bounds();
+        if x >= left && x < right && y >= top && y < bottom {
+            // rows are flipped or not depending on the origin, see Origin
+            let row = self.buffer_row(y);
+            let pixel = &mut buf[row * self.width() as usize + x as usize];
+            *pixel = self.blended(color, *pixel);
+            self.mark_dirty(x as u32, row as u32, x as u32 + 1, row as u32 + 1);
+        }
+    }
+
+    /// Helper: combine a color with the pixel below it using the current blend mode
+    fn blended(&self, color: Color, background: Color) -> Color {
+        match self.blend_mode.get() {
+            BlendMode::Replace => color,
+            BlendMode::SrcOver => color.composite_over(background),
+            BlendMode::PremultipliedSrcOver => color.composite_over_premultiplied(background),
         }
     }
 
@@ -145,62 +966,77 @@ impl Canvas {
     /// `color`: Pixel color
     ///
     /// <https://www.programmersought.com/article/60715259426/>
+    ///
+    /// The segment is first clipped against the canvas bounds so only
+    /// the visible part of the line is rasterized.
     pub fn line_between(&self, origin: Point2D, dest: Point2D, color: Color) {
-        let x0 = origin.x;
-        let y0 = origin.y;
-
-        let x1 = dest.x;
-        let y1 = dest.y;
+        let (origin, dest) = (self.transformed(origin), self.transformed(dest));
+        self.line_into(&mut self.pixels.borrow_mut(), origin, dest, color);
+    }
 
-        let dx = if x1 >= x0 { x1 - x0 } else { x0 - x1 };
-        let dy = if x1 >= x0 { y1 - y0 } else { y0 - y1 };
+    /// Draw many lines at once, the pixel buffer is borrowed a single time
+    /// `segments`  start point, end point and color of each line
+    pub fn draw_lines(&self, segments: &[(Point2D, Point2D, Color)]) {
+        let mut pixels = self.pixels.borrow_mut();
+        for (origin, dest, color) in segments {
+            let (origin, dest) = (self.transformed(*origin), self.transformed(*dest));
+            self.line_into(&mut pixels, origin, dest, *color);
+        }
+    }
 
-        // ensure we go from smaller to highest
-        let mut x = if x1 >= x0 { x0 } else { x1 };
-        let mut y = if x1 >= x0 { y0 } else { y1 };
+    /// Draw vertical and horizontal lines every spacing pixels across the whole canvas
+    /// The first lines are at x = 0 and y = 0, see draw_grid_with_origin to move them.
+    /// The right and bottom edges of the canvas are always drawn, closing the grid.
+    /// `spacing`   distance in pixels between lines, 0 draws nothing
+    /// `color`     line color
+    pub fn draw_grid(&self, spacing: u32, color: Color) {
+        self.draw_grid_with_origin(spacing, Point2D::new(0, 0), color);
+    }
 
-        // veritical line
-        if dx == 0 {
-            for i in y0.min(y1)..y0.max(y1) {
-                self.plot(x, i, color);
-            }
+    /// Draw a grid with a line going through origin, e.g. to scroll it with a camera
+    /// (origin = the screen position of the world origin, see Camera2D::world_to_screen)
+    /// `spacing`   distance in pixels between lines, 0 draws nothing
+    /// `origin`    any point the grid lines cross at, may fall outside of the canvas
+    /// `color`     line color
+    /// Lines along the right (x = width - 1) and bottom (y = height - 1) edges close the grid.
+    pub fn draw_grid_with_origin(&self, spacing: u32, origin: Point2D, color: Color) {
+        if spacing == 0 {
             return;
         }
-        // horizontal line
-        if dy == 0 {
-            for i in x0.min(x1)..x0.max(x1) {
-                self.plot(i, y, color);
-            }
-            return;
+        let step = spacing as usize;
+        let (width, height) = (self.width() as i32, self.height() as i32);
+        let (right, bottom) = (width - 1, height - 1);
+        let mut pixels = self.pixels.borrow_mut();
+        // the ranges stop before the edges, drawn once as the closing lines, and the lines
+        // end at width / height as straight lines leave out their last point
+        let columns = (origin.x.rem_euclid(spacing as i32)..right).step_by(step);
+        for x in columns.chain(std::iter::once(right)) {
+            self.line_into(
+                &mut pixels,
+                Point2D::new(x, 0),
+                Point2D::new(x, height),
+                color,
+            );
         }
-        // slope is less than 1
-        if dy.abs() <= dx {
-            let mut decision = 2 * dy.abs() - dx;
-            self.plot(x, y, color);
-            while x < x0.max(x1) {
-                x += 1;
-                if decision < 0 {
-                    decision += 2 * dy.abs();
-                } else {
-                    y += if dy >= 0 { 1 } else { -1 };
-                    decision += 2 * (dy.abs() - dx);
-                }
-                self.plot(x, y, color);
-            }
-        } else {
-            // slopw is > 1 and dy positions are swapped
-            let mut decision = 2 * dx - dy.abs();
-            self.plot(x, y, color);
-            while x < x0.max(x1) {
-                y += if dy >= 0 { 1 } else { -1 };
-                if decision < 0 {
-                    decision += 2 * dx;
-                } else {
-                    x += 1;
-                    decision += 2 * (dx - dy.abs());
-                }
-                self.plot(x, y, color);
-            }
+        let rows = (origin.y.rem_euclid(spacing as i32)..bottom).step_by(step);
+        for y in rows.chain(std::iter::once(bottom)) {
+            self.line_into(
+                &mut pixels,
+                Point2D::new(0, y),
+                Point2D::new(width, y),
+                color,
+            );
+        }
+    }
+
+    /// Helper: draw a line into an already borrowed pixel buffer (see line_between)
+    fn line_into(&self, buf: &mut [Color], origin: Point2D, dest: Point2D, color: Color) {
+        let (origin, dest) = match clip_line(origin, dest, self.width(), self.height()) {
+            Some(segment) => segment,
+            None => return, // line is completely outside of the canvas
+        };
+        for point in line_points(origin, dest) {
+            self.plot_into(buf, point.x, point.y, color);
         }
     }
 
@@ -212,28 +1048,10 @@ impl Canvas {
     /// `color`: Pixel color
     ///
     pub fn circle(&self, origin: Point2D, radius: i32, color: Color) {
-        let mut x = 0;
-        let mut y = radius;
-        let mut decision = 3 - 2 * y;
-        while y >= x {
-            self.plot(origin.x + x, origin.y + y, color);
-            self.plot(origin.x - x, origin.y + y, color);
-            self.plot(origin.x + x, origin.y - y, color);
-            self.plot(origin.x - x, origin.y - y, color);
-            self.plot(origin.x + y, origin.y + x, color);
-            self.plot(origin.x - y, origin.y + x, color);
-            self.plot(origin.x + y, origin.y - x, color);
-            self.plot(origin.x - y, origin.y - x, color);
-
-            x += 1;
-            if decision > 0 {
-                y -= 1;
-                decision = decision + 4 * (x - y) + 10;
-            } else {
-                decision = decision + 4 * x + 6;
-            }
-
-            // break;
+        let (origin, radius) = (self.transformed(origin), self.transformed_length(radius));
+        let mut pixels = self.pixels.borrow_mut();
+        for point in circle_points(origin, radius) {
+            self.plot_into(&mut pixels, point.x, point.y, color);
         }
     }
 
@@ -264,12 +1082,58 @@ impl Canvas {
     /// `Height`
     /// `Color` Pixel color
     ///
+    /// Covers `width` x `height` pixels, the right and bottom edges are excluded, rotated by
+    /// the transform or not. Same as fill_rect_fast.
     pub fn fill_rectangle(&self, origin: &Point2D, width: i32, height: i32, color: Color) {
-        for i in 0..=height {
-            let right = Point2D::new(origin.x, origin.y + i);
-            let left = Point2D::new(origin.x + width, origin.y + i);
-            self.line_between(right, left, color);
+        self.fill_rect_fast(origin, width, height, color);
+    }
+
+    /// Draws a filled rectangle writing whole rows at once
+    /// Covers exactly `width` x `height` pixels starting at `origin`, as fill_rectangle,
+    /// and is clipped to the canvas (and clip rectangle). Opaque colors (or BlendMode::Replace)
+    /// are written with a single slice fill per row, translucent colors are blended pixel by
+    /// pixel.
+    /// `origin`    top left corner
+    /// `width`     rectangle width
+    /// `height`    rectangle height
+    /// `color`     fill color
+    pub fn fill_rect_fast(&self, origin: &Point2D, width: i32, height: i32, color: Color) {
+        if color.alpha() == 0 {
+            return;
+        }
+        let (origin, width, height) = match self.transformed_rect(*origin, width, height) {
+            Ok(rect) => rect,
+            Err(corners) => return self.rasterize_polygon(&corners, color),
+        };
+        let (left, top, right, bottom) = self.drawable_bounds();
+        let x0 = origin.x.max(left);
+        let x1 = (origin.x + width).min(right);
+        let y0 = origin.y.max(top);
+        let y1 = (origin.y + height).min(bottom);
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let opaque = color.alpha() == 255 || self.blend_mode.get() == BlendMode::Replace;
+        let mut pixels = self.pixels.borrow_mut();
+        for y in y0..y1 {
+            let row = self.buffer_row(y) * self.width() as usize;
+            let span = &mut pixels[row + x0 as usize..row + x1 as usize];
+            if opaque {
+                span.fill(color);
+            } else {
+                for pixel in span {
+                    *pixel = self.blended(color, *pixel);
+                }
+            }
         }
+        let (first, last) = (self.buffer_row(y0), self.buffer_row(y1 - 1));
+        self.mark_dirty(
+            x0 as u32,
+            first.min(last) as u32,
+            x1 as u32,
+            first.max(last) as u32 + 1,
+        );
     }
 
     ///
@@ -302,12 +1166,105 @@ impl Canvas {
         }
     }
 
+    ///
+    /// Draw a filled polygon based on the given vertices/points vector
+    /// `vertices`  Points of the polygon in order, the last point connects back to the first
+    /// `color`  color to fill it with
+    ///
+    /// Uses a scanline fill with an active edge table and the even-odd rule,
+    /// so convex and concave (non self-intersecting) polygons are supported.
+    /// <https://www.cs.rit.edu/~icss571/filling/how_to.html>
+    ///
+    /// Each edge covers the scanlines from its top end (included) to its bottom end (excluded)
+    /// so vertices shared by two edges are not counted twice, likewise each span goes from
+    /// its left crossing (included) to its right one (excluded): polygons sharing an edge
+    /// don't overlap.
+    pub fn fill_polygon(&self, vertices: &[Point2D], color: Color) {
+        match self.transform.get() {
+            Some(_) => {
+                let vertices: Vec<Point2D> =
+                    vertices.iter().map(|p| self.transformed(*p)).collect();
+                self.rasterize_polygon(&vertices, color);
+            }
+            None => self.rasterize_polygon(vertices, color),
+        }
+    }
+
+    /// Helper: fill_polygon without the transform, the vertices are in canvas pixels
+    fn rasterize_polygon(&self, vertices: &[Point2D], color: Color) {
+        if vertices.len() < 3 {
+            return;
+        }
+
+        // build edge table, horizontal edges are skipped as they are covered by their neighbours
+        let mut edges: Vec<PolygonEdge> = Vec::with_capacity(vertices.len());
+        for (i, start) in vertices.iter().enumerate() {
+            let end = vertices[(i + 1) % vertices.len()];
+            if start.y == end.y {
+                continue;
+            }
+            let (top, bottom) = if start.y < end.y {
+                (*start, end)
+            } else {
+                (end, *start)
+            };
+            edges.push(PolygonEdge {
+                y_min: top.y,
+                y_max: bottom.y,
+                x: top.x as f32,
+                inverse_slope: (bottom.x - top.x) as f32 / (bottom.y - top.y) as f32,
+            });
+        }
+        if edges.is_empty() {
+            return;
+        }
+        edges.sort_by_key(|edge| edge.y_min);
+
+        // no need to go over scan lines outside of the canvas
+        let first_y = math::max(edges[0].y_min, 0);
+        let last_y = edges.iter().map(|edge| edge.y_max).max().unwrap_or(0);
+        let last_y = math::min(last_y, self.height() as i32);
+
+        let mut next_edge = 0;
+        let mut active: Vec<PolygonEdge> = Vec::new();
+        let mut crossings: Vec<f32> = Vec::new();
+        let mut pixels = self.pixels.borrow_mut();
+        for y in first_y..last_y {
+            // move edges starting at this scan line into the active edge table
+            while next_edge < edges.len() && edges[next_edge].y_min <= y {
+                let mut edge = edges[next_edge];
+                edge.x += (y - edge.y_min) as f32 * edge.inverse_slope;
+                active.push(edge);
+                next_edge += 1;
+            }
+            // drop edges that ended
+            active.retain(|edge| edge.y_max > y);
+
+            crossings.clear();
+            crossings.extend(active.iter().map(|edge| edge.x));
+            crossings.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+
+            // even-odd rule, fill between each pair of crossings, right end excluded
+            for span in crossings.chunks_exact(2) {
+                let start = math::max(span[0].round() as i32, 0);
+                let end = math::min(span[1].round() as i32, self.width() as i32);
+                for x in start..end {
+                    self.plot_into(&mut pixels, x, y, color);
+                }
+            }
+
+            for edge in active.iter_mut() {
+                edge.x += edge.inverse_slope;
+            }
+        }
+    }
+
     ///
     /// Draw a regular polygone based on the given start point, number of sides and length
     /// `origin`  First point on the polygon
     /// `sides`  Number of sides on the polygon
     /// `length`  Length of each side of the polygon
-    /// `angle`  Optional angle (in degrees) given to start position of polygon
+    /// `angle`  Optional angle (in degrees, see Angle::as_degrees) given to start position of polygon
     /// `color`  Line color
     ///
     /// Not sure if there is a more efficient way of doing this but its similar to to the process of drawing a traingle.
@@ -351,7 +1308,7 @@ impl Canvas {
     ) {
         let delta_angle = 2.0 * std::f32::consts::PI / sides as f32;
         let mut current_angle = match angle {
-            Some(value) => value * std::f32::consts::PI / 180.0,
+            Some(value) => math::to_radians(value),
             None => 2.0 * std::f32::consts::PI,
         };
         let mut start = origin;
@@ -367,51 +1324,86 @@ impl Canvas {
     }
 
     /// Draws a filled circle
-    /// #Arguments   
-    /// `origin` top left corner
-    /// `Width`   width
-    /// `Height`  height
+    /// Every row is a single span (see disk_points), so translucent colors blend each pixel once.
+    /// #Arguments
+    /// `origin`  center of circle
+    /// `radius`  radius
     /// `Color`   color for pixels
     pub fn fill_circle(&self, origin: Point2D, radius: i32, color: Color) {
-        let mut x = 0;
-        let mut y = radius;
-        let mut decision = 3 - 2 * y;
-        while y >= x {
-            self.line(
-                origin.x + x,
-                origin.y - y,
-                origin.x + x,
-                origin.y + y,
-                color,
-            );
-            self.line(
-                origin.x - x,
-                origin.y - y,
-                origin.x - x,
-                origin.y + y,
-                color,
-            );
-            self.line(
-                origin.x - y,
-                origin.y - x,
-                origin.x + y,
-                origin.y - x,
-                color,
-            );
-            self.line(
-                origin.x - y,
-                origin.y + x,
-                origin.x + y,
-                origin.y + x,
-                color,
-            );
+        let (origin, radius) = (self.transformed(origin), self.transformed_length(radius));
+        let mut pixels = self.pixels.borrow_mut();
+        for point in disk_points(origin, radius) {
+            self.plot_into(&mut pixels, point.x, point.y, color);
+        }
+    }
 
-            x += 1;
-            if decision > 0 {
-                y -= 1;
-                decision = decision + 4 * (x - y) + 10;
-            } else {
-                decision = decision + 4 * x + 6;
+    /// Draws the filled band between two circles (see ring_points)
+    /// #Arguments
+    /// `center`    center of both circles
+    /// `inner_r`   radius of the hole, pixels of fill_circle(center, inner_r) are untouched
+    /// `outer_r`   outer radius
+    /// `color`     color for pixels
+    pub fn fill_ring(&self, center: Point2D, inner_r: i32, outer_r: i32, color: Color) {
+        let center = self.transformed(center);
+        let (inner_r, outer_r) = (
+            self.transformed_length(inner_r),
+            self.transformed_length(outer_r),
+        );
+        let mut pixels = self.pixels.borrow_mut();
+        for point in ring_points(center, inner_r, outer_r) {
+            self.plot_into(&mut pixels, point.x, point.y, color);
+        }
+    }
+
+    /// Draws the part of fill_ring swept from `start_deg` to `end_deg`, e.g. a progress ring
+    /// Angles are in degrees from the positive x axis towards the positive y axis (clockwise
+    /// on screen, as y grows downwards), the sweep always goes that way: 270 to 90 covers
+    /// the right half. A sweep of 360 degrees or more draws the whole ring.
+    /// #Arguments
+    /// `center`    center of both circles
+    /// `inner_r`   radius of the hole
+    /// `outer_r`   outer radius
+    /// `start_deg` angle the sweep starts at
+    /// `end_deg`   angle the sweep ends at
+    /// `color`     color for pixels
+    pub fn fill_ring_arc(
+        &self,
+        center: Point2D,
+        inner_r: i32,
+        outer_r: i32,
+        start_deg: f32,
+        end_deg: f32,
+        color: Color,
+    ) {
+        if end_deg - start_deg >= 360.0 {
+            self.fill_ring(center, inner_r, outer_r, color);
+            return;
+        }
+        let (start_deg, end_deg) = match self.transform.get() {
+            Some(m) => {
+                // a mirror reverses the sweep, then the rotation turns it
+                let (_, rotation, scale) = m.decompose();
+                let (start, end) = if scale.y() < 0.0 {
+                    (-end_deg, -start_deg)
+                } else {
+                    (start_deg, end_deg)
+                };
+                (start + rotation.to_degrees(), end + rotation.to_degrees())
+            }
+            None => (start_deg, end_deg),
+        };
+        let center = self.transformed(center);
+        let (inner_r, outer_r) = (
+            self.transformed_length(inner_r),
+            self.transformed_length(outer_r),
+        );
+        let sweep = (end_deg - start_deg).rem_euclid(360.0);
+        let mut pixels = self.pixels.borrow_mut();
+        for point in ring_points(center, inner_r, outer_r) {
+            let (dx, dy) = (point.x - center.x, point.y - center.y);
+            let angle = (dy as f32).atan2(dx as f32).to_degrees();
+            if (dx, dy) == (0, 0) || (angle - start_deg).rem_euclid(360.0) <= sweep {
+                self.plot_into(&mut pixels, point.x, point.y, color);
             }
         }
     }
@@ -424,126 +1416,275 @@ impl Canvas {
     /// `v3` third point
     /// `color` Color for the pixels
     ///
-    /// Uses scan line algorithm: <https://www.avrfreaks.net/sites/default/files/triangles.c>
+    /// Uses a scan line algorithm: the span of every row is solved exactly from the edge
+    /// functions of the triangle and is inclusive on both ends. Pixels lying exactly on an
+    /// edge follow the top-left fill rule, so triangles sharing an edge (e.g. a mesh) cover
+    /// it exactly once, leaving no cracks and blending no pixel twice.
+    /// <https://learn.microsoft.com/en-us/windows/win32/direct3d11/d3d10-graphics-programming-guide-rasterizer-stage-rules>
     ///
     pub fn fill_triangle(&self, v1: Point2D, v2: Point2D, v3: Point2D, color: Color) {
-        let mut a: i32;
-        let mut b: i32;
-
-        let mut x0 = v1.x;
-        let mut y0 = v1.y;
+        let (v1, v2, v3) = (
+            self.transformed(v1),
+            self.transformed(v2),
+            self.transformed(v3),
+        );
+        // wind the vertices so the signed area is positive (clockwise on screen, y down)
+        let (v1, v2, v3) = match edge_function(v1, v2, v3) {
+            0 => {
+                // zero area: collinear or coincident points, draw the segment they span
+                let (start, end) = farthest_pair([v1, v2, v3]);
+                let mut pixels = self.pixels.borrow_mut();
+                if start == end {
+                    self.plot_into(&mut pixels, start.x, start.y, color);
+                } else {
+                    self.line_into(&mut pixels, start, end, color);
+                }
+                return;
+            }
+            area if area < 0 => (v1, v3, v2),
+            _ => (v1, v2, v3),
+        };
+        let edges = [(v1, v2), (v2, v3), (v3, v1)];
 
-        let mut x1 = v2.x;
-        let mut y1 = v2.y;
+        let min_y = math::max(math::min(v1.y, math::min(v2.y, v3.y)), 0);
+        let max_y = math::min(
+            math::max(v1.y, math::max(v2.y, v3.y)),
+            self.height() as i32 - 1,
+        );
+        let mut pixels = self.pixels.borrow_mut();
+        for y in min_y..=max_y {
+            let mut left = 0_i64;
+            let mut right = self.width() as i64 - 1;
+            for (a, b) in edges {
+                let (dx, dy) = (b.x as i64 - a.x as i64, b.y as i64 - a.y as i64);
+                // edge_function(a, b, (x, y)) = c - dy * (x - a.x) must reach the threshold:
+                // 0 on top and left edges, 1 (strictly inside) on the others
+                let c = dx * (y as i64 - a.y as i64);
+                let top_left = dy < 0 || (dy == 0 && dx > 0);
+                let threshold = if top_left { 0 } else { 1 };
+                match dy {
+                    0 if c < threshold => {
+                        left = 1; // the whole row is outside of this edge
+                        right = 0;
+                    }
+                    0 => (),
+                    dy if dy < 0 => {
+                        // left side edge, x >= a.x + ceil((threshold - c) / -dy)
+                        let bound = -(c - threshold).div_euclid(-dy);
+                        left = left.max(a.x as i64 + bound);
+                    }
+                    dy => {
+                        // right side edge, x <= a.x + floor((c - threshold) / dy)
+                        let bound = (c - threshold).div_euclid(dy);
+                        right = right.min(a.x as i64 + bound);
+                    }
+                }
+            }
+            for x in left..=right {
+                self.plot_into(&mut pixels, x as i32, y, color);
+            }
+        }
+    }
 
-        let mut x2 = v3.x;
-        let mut y2 = v3.y;
+    ///
+    /// Draws a filled triangle interpolating the colors of its vertices (Gouraud shading)
+    /// Takes:
+    /// `v1`, `c1` first point and its color
+    /// `v2`, `c2` second point and its color
+    /// `v3`, `c3` third point and its color
+    ///
+    /// Each scanline of the triangle bounding box is walked and the color of every pixel inside
+    /// is the blend of the vertex colors weighted by its barycentric coordinates.
+    ///
+    pub fn fill_triangle_gouraud(
+        &self,
+        v1: Point2D,
+        c1: Color,
+        v2: Point2D,
+        c2: Color,
+        v3: Point2D,
+        c3: Color,
+    ) {
+        let v = [v1, v2, v3].map(|p| self.transformed(p));
+        self.rasterize_triangle(v, |w, _| Some(gouraud_color([c1, c2, c3], w)));
+    }
 
-        // Sort coordinates by Y order (y2 >= y1 >= y0)
-        if y0 > y1 {
-            std::mem::swap(&mut y0, &mut y1);
-            std::mem::swap(&mut x0, &mut x1);
-        }
-        if y1 > y2 {
-            std::mem::swap(&mut y2, &mut y1);
-            std::mem::swap(&mut x2, &mut x1);
-        }
-        if y0 > y1 {
-            std::mem::swap(&mut y0, &mut y1);
-            std::mem::swap(&mut x0, &mut x1);
+    ///
+    /// Draws a texture mapped triangle
+    /// Takes:
+    /// `v`   the three points of the triangle
+    /// `uv`  texture coordinates for each of the points, (0,0) top left and (1,1) bottom right of the texture
+    /// `tex` sprite used as texture
+    ///
+    /// Texture coordinates are interpolated linearly in screen space (affine mapping, not perspective correct)
+    /// and clamped to [0, 1]. The texture is sampled using the nearest texel, no filtering is applied.
+    ///
+    pub fn fill_triangle_textured(&self, v: [Point2D; 3], uv: [FVec2D; 3], tex: &Sprite) {
+        if tex.width == 0 || tex.height == 0 {
+            return;
         }
+        let max_u = (tex.width - 1) as f32;
+        let max_v = (tex.height - 1) as f32;
 
-        let smallest_x = math::min(x0, math::min(x1, x2));
-        let biggest_x = math::max(x0, math::max(x1, x2));
-        let h_line_plot = |a: i32, b: i32, y: i32| {
-            for i in math::min(a, b)..math::max(a, b) {
-                if i >= smallest_x && i <= biggest_x {
-                    self.plot(i, y, color);
-                }
+        let v = v.map(|p| self.transformed(p));
+        self.rasterize_triangle(v, |w, _| {
+            let u = (uv[0].x * w[0] + uv[1].x * w[1] + uv[2].x * w[2]).clamp(0.0, 1.0);
+            let v = (uv[0].y * w[0] + uv[1].y * w[1] + uv[2].y * w[2]).clamp(0.0, 1.0);
+            tex.get_pixel((u * max_u).round() as usize, (v * max_v).round() as usize)
+        });
+    }
+
+    /// Draws a filled triangle hiding the parts behind what was already drawn (z-buffer)
+    /// The depth is interpolated across the triangle, a pixel is only painted when it is
+    /// closer (smaller) than the depth stored for it, which is then updated.
+    /// # Arguments
+    /// `v`         the three points of the triangle
+    /// `depth`     depth of each point, e.g. z after the projection
+    /// `colors`    color of each point, interpolated as with fill_triangle_gouraud
+    ///             (the same color three times for a flat triangle)
+    /// `buffer`    one depth per canvas pixel, y = 0 row first, fill it with f32::INFINITY
+    ///             before drawing a frame. Nothing is drawn when its size doesn't match.
+    pub fn fill_triangle_depth(
+        &self,
+        v: [Point2D; 3],
+        depth: [f32; 3],
+        colors: [Color; 3],
+        buffer: &mut [f32],
+    ) {
+        let width = self.width() as usize;
+        if buffer.len() != width * self.height() as usize {
+            return;
+        }
+        let v = v.map(|p| self.transformed(p));
+        self.rasterize_triangle(v, |w, point| {
+            let z = depth[0] * w[0] + depth[1] * w[1] + depth[2] * w[2];
+            let closest = &mut buffer[point.y as usize * width + point.x as usize];
+            if z < *closest {
+                *closest = z;
+                Some(gouraud_color(colors, w))
+            } else {
+                None
             }
-        };
-        if y0 == y2 {
-            // All on same line case
-            a = x0;
-            b = x0;
-            if x1 < a {
-                a = x1;
-            } else if x1 > b {
-                b = x1;
-            }
-            if x2 < a {
-                a = x2;
-            } else if x2 > b {
-                b = x2;
-            }
-            // self.line(a, y0, b, y0, color);
-            h_line_plot(a, b, y0);
+        });
+    }
+
+    /// Helper walk each scanline of the triangle bounding box (clipped to the drawable region)
+    /// calling `shade` for every pixel inside the triangle with its barycentric weights and
+    /// position, the returned color (if any) is plotted. `shade` is never called for pixels
+    /// outside of the canvas or the clip rectangle.
+    /// <https://www.scratchapixel.com/lessons/3d-basic-rendering/rasterization-practical-implementation/rasterization-stage.html>
+    fn rasterize_triangle(
+        &self,
+        v: [Point2D; 3],
+        mut shade: impl FnMut([f32; 3], Point2D) -> Option<Color>,
+    ) {
+        // twice the signed area of the triangle, zero for degenerate triangles
+        let area = edge_function(v[0], v[1], v[2]);
+        if area == 0 {
             return;
         }
-        let dx01 = x1 - x0;
-        let dy01 = y1 - y0;
-        let dx02 = x2 - x0;
-        let dy02 = y2 - y0;
-        let dx12 = x2 - x1;
-        let dy12 = y2 - y1;
-        let mut sa = 0;
-        let mut sb = 0;
-        // For upper part of triangle, find scanline crossings for segment
-        // 0-1 and 0-2.  If y1=y2 (flat-bottomed triangle), the scanline y
-        // is included here (and second loop will be skipped, avoiding a /
-        // error there), otherwise scanline y1 is skipped here and handle
-        // in the second loop...which also avoids a /0 error here if y0=y
-        // (flat-topped triangle)
-        let last = if y1 == y2 {
-            y1
-        }
-        // Include y1 scanline
-        else {
-            y1 - 1
-        };
 
-        // Skip it
-        for y in y0..=last {
-            if dy01 != 0 && dy02 != 0 {
-                a = x0 + sa / dy01;
-                b = x0 + sb / dy02;
-                sa += dx01;
-                sb += dx02;
-                // longhand a = x0 + (x1 - x0) * (y - y0) / (y1 - y0)
-                //          b = x0 + (x2 - x0) * (y - y0) / (y2 - y0)
-                // self.line(a, y, b, y, color);
-                h_line_plot(a, b, y);
+        // top-left fill rule as in fill_triangle, the edges taken with a positive winding:
+        // pixels on top and left edges are inside, those on the others are not, so
+        // triangles sharing an edge never cover (and blend) its pixels twice
+        let sign = area.signum();
+        let threshold = |a: Point2D, b: Point2D| {
+            let (dx, dy) = (
+                sign * (b.x as i64 - a.x as i64),
+                sign * (b.y as i64 - a.y as i64),
+            );
+            if dy < 0 || (dy == 0 && dx > 0) {
+                0
+            } else {
+                1
             }
-        }
+        };
+        let thresholds = [
+            threshold(v[1], v[2]),
+            threshold(v[2], v[0]),
+            threshold(v[0], v[1]),
+        ];
+
+        let (left, top, right, bottom) = self.drawable_bounds();
+        let min_x = math::max(math::min(v[0].x, math::min(v[1].x, v[2].x)), left);
+        let max_x = math::min(math::max(v[0].x, math::max(v[1].x, v[2].x)), right - 1);
+        let min_y = math::max(math::min(v[0].y, math::min(v[1].y, v[2].y)), top);
+        let max_y = math::min(math::max(v[0].y, math::max(v[1].y, v[2].y)), bottom - 1);
 
-        // pick up where we left off
-        let y = last;
-        // For lower part of triangle, find scanline crossings for segment
-        // 0-2 and 1-2.  This loop is skipped if y1=y2
-        sa = dx12 * (y - y1);
-        sb = dx02 * (y - y0);
-        for i in y..=y2 {
-            if dy12 != 0 && dy02 != 0 {
-                a = x1 + sa / dy12;
-                b = x0 + sb / dy02;
-                sa += dx12;
-                sb += dx02;
-                // longhand a = x1 + (x2 - x1) * (y - y1) / (y2 - y1)
-                //          b = x0 + (x2 - x0) * (y - y0) / (y2 - y0)
-                h_line_plot(a, b, i);
-                // self.line(a, y, b, y, color);
+        let mut pixels = self.pixels.borrow_mut();
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                let point = Point2D::new(x, y);
+                // edge functions, all of them share the sign of the area when inside
+                let e1 = edge_function(v[1], v[2], point);
+                let e2 = edge_function(v[2], v[0], point);
+                let e3 = edge_function(v[0], v[1], point);
+                if sign * e1 >= thresholds[0]
+                    && sign * e2 >= thresholds[1]
+                    && sign * e3 >= thresholds[2]
+                {
+                    // barycentric weights
+                    let w1 = e1 as f32 / area as f32;
+                    let w2 = e2 as f32 / area as f32;
+                    let w3 = e3 as f32 / area as f32;
+                    if let Some(color) = shade([w1, w2, w3], point) {
+                        self.plot_into(&mut pixels, x, y, color);
+                    }
+                }
             }
         }
     }
 
     /// Draws a sprite at given point
+    /// Older name of draw_sprite, same result: it used to plot every pixel on its own and
+    /// now goes through draw_sprite.
     /// `origin` top left corner of the sprite
     /// `tile` sprite tile to draw
     pub fn sprite(&self, origin: Point2D, tile: &Sprite) {
-        for (i, pixel) in tile.pixels.iter().enumerate() {
-            let x = origin.x() + (i % tile.width) as i32;
-            let y = origin.y() + (i / tile.width) as i32;
-            self.plot(x, y, *pixel);
+        self.draw_sprite(origin, tile);
+    }
+
+    /// Draw a sprite the way it looks saved as an image: its first row at the top
+    /// Whatever the canvas Origin, the sprite's first row lands on y = `origin.y` and rows go
+    /// down from there. Every pixel is combined with the canvas using the current BlendMode
+    /// (so SrcOver blends per pixel alpha), fully transparent pixels are skipped in every
+    /// mode, as with plot. The sprite is clipped to the canvas and the clip rectangle, the
+    /// canvas transform only moves `origin` (see transform_sprite to rotate or scale).
+    /// # Arguments
+    /// `origin`    position of the top left corner of the sprite
+    /// `sprite`    sprite to draw, its pixels top row first
+    pub fn draw_sprite(&self, origin: Point2D, sprite: &Sprite) {
+        let origin = self.transformed(origin);
+        let (left, top, right, bottom) = self.drawable_bounds();
+        let (x0, x1) = (
+            origin.x().max(left),
+            (origin.x() + sprite.width as i32).min(right),
+        );
+        let (y0, y1) = (
+            origin.y().max(top),
+            (origin.y() + sprite.height as i32).min(bottom),
+        );
+        if x0 >= x1 || y0 >= y1 {
+            return;
+        }
+
+        let width = self.width() as usize;
+        let mut pixels = self.pixels.borrow_mut();
+        for y in y0..y1 {
+            let start = (y - origin.y()) as usize * sprite.width + (x0 - origin.x()) as usize;
+            let source = match sprite.pixels.get(start..start + (x1 - x0) as usize) {
+                Some(source) => source,
+                None => break,
+            };
+            // rows are flipped or not depending on the origin, see Origin
+            let row = self.buffer_row(y);
+            let target = &mut pixels[row * width + x0 as usize..row * width + x1 as usize];
+            for (pixel, color) in target.iter_mut().zip(source) {
+                if color.alpha() > 0 {
+                    *pixel = self.blended(*color, *pixel);
+                }
+            }
+            self.mark_dirty(x0 as u32, row as u32, x1 as u32, row as u32 + 1);
         }
     }
 
@@ -573,12 +1714,20 @@ impl Canvas {
         for transform in transformer.all() {
             transformed = transformed
                 * match transform {
-                    Transform::Rotate(angle) => Mat3x3::<f32>::rotate(angle),
+                    Transform::Rotate(angle) => Mat3x3::rotation(angle),
                     Transform::Scale(cx, cy) => Mat3x3::<f32>::scale(cx, cy),
                     Transform::Translate(cx, cy) => Mat3x3::<f32>::translate(cx, cy),
                 };
         }
-        let inversed_transformed = transformed.inverse();
+        // the canvas transform is applied last, after the sprite own transforms
+        if let Some(canvas_transform) = self.transform.get() {
+            transformed = canvas_transform * transformed;
+        }
+        // a zero scale squashes the sprite to nothing (and has no inverse), skip it
+        let inversed_transformed = match transformed.try_inverse(SINGULAR_EPSILON) {
+            Some(inverse) => inverse,
+            None => return,
+        };
 
         // get corners of untransformed sprite
         // I could use Vec2D here since the z component is 1.0 but being able to not skipp a dimension
@@ -595,37 +1744,42 @@ impl Canvas {
         let br_transformed = transformed.transform_point(br_corner);
 
         // get bounding box coordinates of transformed box
+        let start = FVec3D::min_components(
+            FVec3D::min_components(tl_transformed, tr_transformed),
+            FVec3D::min_components(bl_transformed, br_transformed),
+        );
+        let end = FVec3D::max_components(
+            FVec3D::max_components(tl_transformed, tr_transformed),
+            FVec3D::max_components(bl_transformed, br_transformed),
+        );
+        // clamped to the canvas: NaN and negative values become 0, huge scales don't
+        // loop over pixels that can't be drawn
+        let (sx, sy) = (start.x().max(0.0) as usize, start.y().max(0.0) as usize);
+        let ex = (end.x().min(self.width() as f32)).max(0.0) as usize;
+        let ey = (end.y().min(self.height() as f32)).max(0.0) as usize;
 
-        let mut sx = math::min(tl_transformed.x(), br_transformed.x());
-        let mut sy = math::min(tl_transformed.y(), br_transformed.y());
-
-        let mut ex = math::max(tl_transformed.x(), br_transformed.x());
-        let mut ey = math::max(tl_transformed.y(), br_transformed.y());
-
-        ex = math::max(ex, bl_transformed.x());
-        ey = math::max(ey, bl_transformed.y());
-        sx = math::min(sx, bl_transformed.x());
-        sy = math::min(sy, bl_transformed.y());
-
-        ex = math::max(ex, tr_transformed.x());
-        ey = math::max(ey, tr_transformed.y());
-        sx = math::min(sx, tr_transformed.x());
-        sy = math::min(sy, tr_transformed.y());
-
-        for x in sx as usize..ex as usize {
-            for y in sy as usize..ey as usize {
-                let new_point =
-                    inversed_transformed.transform_point(FVec3D::new(x as f32, y as f32, 1.0));
-                if let Some(pixel) = tile.get_pixel(
-                    (new_point.x() + 0.5) as usize,
-                    (new_point.y() + 0.5) as usize,
-                ) {
+        let mut pixels = self.pixels.borrow_mut();
+        for x in sx..ex {
+            for y in sy..ey {
+                // sample the sprite under the pixel center, so mirrored (negative scale)
+                // sprites cover the same pixels as their unmirrored version
+                let new_point = inversed_transformed.transform_point(FVec3D::new(
+                    x as f32 + 0.5,
+                    y as f32 + 0.5,
+                    1.0,
+                ));
+                let (u, v) = (new_point.x().floor(), new_point.y().floor());
+                // `as usize` would turn points left of/above the sprite into column/row 0
+                if u < 0.0 || v < 0.0 {
+                    continue;
+                }
+                if let Some(pixel) = tile.get_pixel(u as usize, v as usize) {
                     if let Some(override_color) = color {
                         if pixel.alpha() != 0 {
-                            self.plot(x as i32, y as i32, override_color);
+                            self.plot_into(&mut pixels, x as i32, y as i32, override_color);
                         }
                     } else {
-                        self.plot(x as i32, y as i32, pixel);
+                        self.plot_into(&mut pixels, x as i32, y as i32, pixel);
                     }
                 }
             }
@@ -660,6 +1814,79 @@ impl Canvas {
         }
     }
 
+    /// Draw text centered on a point, see draw_string
+    /// `center`    point the middle of the text is drawn at
+    ///  `msg`      message/text to be drawn
+    /// `size`      size/scale of text being rawing
+    /// `color`     color for the text being drawn
+    pub fn draw_string_centered(&self, center: Point2D, msg: String, size: f32, color: Color) {
+        let text = self.text_size(&msg, size);
+        let origin = Point2D::new(
+            center.x() - (text.x() / 2.0) as i32,
+            center.y() - (text.y() / 2.0) as i32,
+        );
+        self.draw_string(origin, msg, size, color);
+    }
+
+    /// Size in pixels of `msg` drawn with draw_string at `size`, (0, 0) without a font
+    /// The width is how far draw_string advances, the height is the tallest glyph of the
+    /// font (the line height) so it doesn't depend on the text.
+    pub fn text_size(&self, msg: &str, size: f32) -> FVec2D {
+        let font = match &self.font {
+            Some(font) => font,
+            None => return FVec2D::new(0.0, 0.0),
+        };
+        // same advance as draw_string: unknown characters reuse the previous width
+        let mut width = 0.0;
+        let mut total = 0.0;
+        for character in msg.chars() {
+            if let Some(sprite) = font.get(&character) {
+                width = sprite.width as f32 * size;
+            }
+            total += width;
+        }
+        let height = font.values().map(|sprite| sprite.height).max().unwrap_or(0);
+        FVec2D::new(total, height as f32 * size)
+    }
+
+    /// Apply a function to every pixel of the canvas, useful to post-process a frame
+    /// # Example
+    /// canvas.map_colors(|color| color.adjust(0.1, 1.2));
+    pub fn map_colors(&self, f: impl Fn(Color) -> Color) {
+        for pixel in &mut *self.pixels.borrow_mut() {
+            *pixel = f(*pixel);
+        }
+        self.mark_all_dirty();
+    }
+
+    /// Blur the whole canvas using a box blur, useful to post-process a frame
+    /// # Arguments
+    /// `radius`    blur radius in pixels
+    pub fn blur(&self, radius: u32) {
+        let mut pixels = self.pixels.borrow_mut();
+        *pixels = filter::box_blur(
+            &pixels,
+            self.width() as usize,
+            self.height() as usize,
+            radius,
+        );
+        self.mark_all_dirty();
+    }
+
+    /// Blur the whole canvas using a gaussian blur
+    /// # Arguments
+    /// `sigma`     standard deviation of the gaussian in pixels
+    pub fn gaussian_blur(&self, sigma: f32) {
+        let mut pixels = self.pixels.borrow_mut();
+        *pixels = filter::gaussian_blur(
+            &pixels,
+            self.width() as usize,
+            self.height() as usize,
+            sigma,
+        );
+        self.mark_all_dirty();
+    }
+
     /// Take a snapshot of the current canvas and save it to a png file
     /// # Arguments
     /// `outpath`    File path/name to the resultant PNG image
@@ -673,22 +1900,266 @@ impl Canvas {
 
         // we save the image here
         let mut outfile = std::fs::File::create(filepath)?;
+        let pixels = self.screen_pixels();
+        PngWriter::new(self.width(), self.height(), &pixels)?.write(&mut outfile)?;
 
-        // Collect all pixels but mirror them because we are using Glium for rendiern,
-        //  we mirror the pixels in place now we have to revert this.
-        let pixels: Vec<Color> = self
-            .pixels
-            .borrow()
-            .chunks_exact(self.width as usize)
-            .rev()
-            .fold(Vec::<Color>::new(), |mut acc, newval| {
-                acc.extend_from_slice(newval);
-                acc
-            });
-        PngWriter::new(self.width, self.height, &pixels)?.write(&mut outfile)?;
+        Ok(())
+    }
+
+    /// Take a snapshot of the current canvas and save it to a binary PPM file
+    /// Handy to inspect or diff intermediate output while developing.
+    /// # Arguments
+    /// `outpath`    File path/name to the resultant PPM image
+    pub fn save_ppm(&self, outpath: &str) -> Result<(), Box<dyn std::error::Error>> {
+        // Add file extension if missing
+        let filepath = if outpath.to_lowercase().ends_with(".ppm") {
+            outpath.to_owned()
+        } else {
+            format!("{}.ppm", outpath)
+        };
+
+        let mut outfile = std::io::BufWriter::new(std::fs::File::create(filepath)?);
+        let pixels = self.screen_pixels();
+        PpmWriter::new(self.width(), self.height(), &pixels)?.write(&mut outfile)?;
 
         Ok(())
     }
+
+    /// Copy the canvas into a sprite, top row first whatever the origin
+    /// Together with blit this allows render to texture without the GPU: draw a scene or
+    /// an effect on an off-screen canvas (see RenderTarget), then draw it, as is or
+    /// transformed/tinted as a sprite, on another canvas.
+    pub fn to_sprite(&self) -> Sprite {
+        Sprite {
+            width: self.width() as usize,
+            height: self.height() as usize,
+            pixels: self.screen_pixels(),
+        }
+    }
+
+    /// Draw another canvas on this one, `origin` is where its top left corner goes
+    /// Pixels are blended with the current BlendMode, as for sprite.
+    pub fn blit(&self, source: &Canvas, origin: Point2D) {
+        self.sprite(origin, &source.to_sprite());
+    }
+
+    /// Helper collect all pixels row by row from y = 0, the layout of image files
+    fn screen_pixels(&self) -> Vec<Color> {
+        match self.origin.get() {
+            Origin::TopLeft => self.pixels.borrow().clone(),
+            Origin::BottomLeft => self.swapped_rows(),
+        }
+    }
+
+    /// Helper: the pixels with the rows of the other origin, row r takes the row
+    /// (height - r) % height so every y keeps its pixels (see Origin). The first
+    /// BottomLeft row, never drawn, and y = 0 of TopLeft swap places.
+    fn swapped_rows(&self) -> Vec<Color> {
+        let width = self.width().max(1) as usize;
+        let height = self.height() as usize;
+        let pixels = self.pixels.borrow();
+        (0..height)
+            .flat_map(|row| {
+                let start = (height - row) % height * width;
+                pixels[start..start + width].iter().copied()
+            })
+            .collect()
+    }
+}
+
+/// Pixels covered by a line using Bresenham Algorthm, without drawing them
+/// Yields the same pixels line_between plots (before clipping to the canvas) going from the
+/// left most end to the right most one. As in line_between straight lines leave out their last pixel.
+/// Useful for line of sight checks on grids.
+/// `origin`: start point
+/// `dest`: final point
+///
+/// Example usage:
+///    let blocked = line_points(player, enemy).any(|p| is_wall(p));
+pub fn line_points(origin: Point2D, dest: Point2D) -> impl Iterator<Item = Point2D> {
+    let x0 = origin.x;
+    let y0 = origin.y;
+
+    let x1 = dest.x;
+    let y1 = dest.y;
+
+    let dx = if x1 >= x0 { x1 - x0 } else { x0 - x1 };
+    let dy = if x1 >= x0 { y1 - y0 } else { y0 - y1 };
+
+    // ensure we go from smaller to highest
+    let mut x = if x1 >= x0 { x0 } else { x1 };
+    let mut y = if x1 >= x0 { y0 } else { y1 };
+    let last_x = x0.max(x1);
+    let step_y = if dy >= 0 { 1 } else { -1 };
+
+    let kind = if dx == 0 {
+        LineKind::Vertical
+    } else if dy == 0 {
+        LineKind::Horizontal
+    } else if dy.abs() <= dx {
+        LineKind::Shallow
+    } else {
+        LineKind::Steep
+    };
+    let mut decision = match kind {
+        LineKind::Shallow => 2 * dy.abs() - dx,
+        LineKind::Steep => 2 * dx - dy.abs(),
+        _ => 0,
+    };
+    // straight lines walk an exclusive range
+    let mut i = match kind {
+        LineKind::Vertical => y0.min(y1),
+        _ => x0.min(x1),
+    };
+    let end = match kind {
+        LineKind::Vertical => y0.max(y1),
+        _ => x0.max(x1),
+    };
+    let mut started = false;
+
+    std::iter::from_fn(move || match kind {
+        LineKind::Vertical | LineKind::Horizontal => {
+            if i >= end {
+                return None;
+            }
+            i += 1;
+            match kind {
+                LineKind::Vertical => Some(Point2D::new(x, i - 1)),
+                _ => Some(Point2D::new(i - 1, y)),
+            }
+        }
+        _ if !started => {
+            started = true;
+            Some(Point2D::new(x, y))
+        }
+        _ if x >= last_x => None,
+        // slope is less than 1
+        LineKind::Shallow => {
+            x += 1;
+            if decision < 0 {
+                decision += 2 * dy.abs();
+            } else {
+                y += step_y;
+                decision += 2 * (dy.abs() - dx);
+            }
+            Some(Point2D::new(x, y))
+        }
+        // slopw is > 1 and dy positions are swapped
+        LineKind::Steep => {
+            y += step_y;
+            if decision < 0 {
+                decision += 2 * dx;
+            } else {
+                x += 1;
+                decision += 2 * (dx - dy.abs());
+            }
+            Some(Point2D::new(x, y))
+        }
+    })
+}
+
+/// Pixels on the outline of a circle using Bresenham Algortim for circles, without drawing them
+/// Yields the same pixels circle plots, each one of them once.
+/// <https://iq.opengenus.org/bresenhams-circle-drawing-algorithm/>
+/// `center`: Center of circle
+/// `radius`: Radius
+pub fn circle_points(center: Point2D, radius: i32) -> impl Iterator<Item = Point2D> {
+    let mut x = 0;
+    let mut y = radius;
+    let mut decision = 3 - 2 * y;
+    let octants = std::iter::from_fn(move || {
+        if y < x {
+            return None;
+        }
+        let step = (x, y);
+        x += 1;
+        if decision > 0 {
+            y -= 1;
+            decision = decision + 4 * (x - y) + 10;
+        } else {
+            decision = decision + 4 * x + 6;
+        }
+        Some(step)
+    });
+
+    octants.flat_map(move |(x, y)| {
+        let mut points = vec![
+            Point2D::new(center.x + x, center.y + y),
+            Point2D::new(center.x - x, center.y + y),
+            Point2D::new(center.x + x, center.y - y),
+            Point2D::new(center.x - x, center.y - y),
+            Point2D::new(center.x + y, center.y + x),
+            Point2D::new(center.x - y, center.y + x),
+            Point2D::new(center.x + y, center.y - x),
+            Point2D::new(center.x - y, center.y - x),
+        ];
+        // on the axes and the diagonals some of the symmetric points are the same
+        points.sort_by_key(|point| (point.x, point.y));
+        points.dedup();
+        points
+    })
+}
+
+/// Pixels inside a circle (outline included) row by row, without drawing them
+/// A pixel is inside when x² + y² <= r² + r, which matches the outline yielded by circle_points.
+/// Useful for spatial queries, e.g. which tiles are within a given radius.
+/// `center`: Center of circle
+/// `radius`: Radius
+pub fn disk_points(center: Point2D, radius: i32) -> impl Iterator<Item = Point2D> {
+    (-radius..=radius).flat_map(move |dy| {
+        let half = disk_half_width(radius, dy);
+        (-half..=half).map(move |dx| Point2D::new(center.x + dx, center.y + dy))
+    })
+}
+
+/// Pixels between two circles row by row, without drawing them
+/// Yields the pixels of disk_points(center, outer_radius) that are not in
+/// disk_points(center, inner_radius), a negative inner radius leaves no hole.
+/// `center`: Center of both circles
+/// `inner_radius`: Radius of the hole
+/// `outer_radius`: Outer radius
+pub fn ring_points(
+    center: Point2D,
+    inner_radius: i32,
+    outer_radius: i32,
+) -> impl Iterator<Item = Point2D> {
+    (-outer_radius..=outer_radius).flat_map(move |dy| {
+        let outer = disk_half_width(outer_radius, dy);
+        // rows crossing the hole are split in a left and a right span
+        let (left_end, right_start) = if inner_radius >= 0 && dy.abs() <= inner_radius {
+            let inner = disk_half_width(inner_radius, dy);
+            (-inner - 1, inner + 1)
+        } else {
+            (outer, outer + 1)
+        };
+        (-outer..=left_end)
+            .chain(right_start..=outer)
+            .map(move |dx| Point2D::new(center.x + dx, center.y + dy))
+    })
+}
+
+/// Helper half the width of the row `dy` of a disk: x² + y² <= r² + r
+fn disk_half_width(radius: i32, dy: i32) -> i32 {
+    let limit = radius as i64 * radius as i64 + radius as i64;
+    let rest = limit - dy as i64 * dy as i64;
+    // integer square root, corrected for floating point rounding
+    let mut half = (rest.max(0) as f64).sqrt() as i64;
+    while half * half > rest {
+        half -= 1;
+    }
+    while (half + 1) * (half + 1) <= rest {
+        half += 1;
+    }
+    half as i32
+}
+
+/// Helper the cases handled by line_points
+#[derive(Debug, Copy, Clone)]
+enum LineKind {
+    Vertical,
+    Horizontal,
+    Shallow,
+    Steep,
 }
 
 /// Helper read all fonts into statuc FONT_SYMBOLS for later usage.
@@ -711,10 +2182,175 @@ fn read_font() -> Option<HashMap<char, Sprite>> {
     Some(font_map)
 }
 
+/// Helper: blend three colors (e.g. of a triangle's vertices) with barycentric weights
+pub(crate) fn gouraud_color(colors: [Color; 3], w: [f32; 3]) -> Color {
+    let channel = |a: u8, b: u8, c: u8| -> u8 {
+        (a as f32 * w[0] + b as f32 * w[1] + c as f32 * w[2])
+            .round()
+            .clamp(0.0, 255.0) as u8
+    };
+    let [c1, c2, c3] = colors;
+    Color::rgba(
+        channel(c1.r(), c2.r(), c3.r()),
+        channel(c1.g(), c2.g(), c3.g()),
+        channel(c1.b(), c2.b(), c3.b()),
+        channel(c1.alpha(), c2.alpha(), c3.alpha()),
+    )
+}
+
+/// Helper edge function, twice the signed area of the triangle (a, b, p)
+/// Positive on one side of the a -> b edge, negative on the other and zero on it.
+fn edge_function(a: Point2D, b: Point2D, p: Point2D) -> i64 {
+    (b.x as i64 - a.x as i64) * (p.y as i64 - a.y as i64)
+        - (b.y as i64 - a.y as i64) * (p.x as i64 - a.x as i64)
+}
+
+/// Helper: the two points furthest apart, the ends of the segment spanned by collinear points
+fn farthest_pair(points: [Point2D; 3]) -> (Point2D, Point2D) {
+    let distance = |(a, b): &(Point2D, Point2D)| {
+        (b.x as i64 - a.x as i64).pow(2) + (b.y as i64 - a.y as i64).pow(2)
+    };
+    [
+        (points[0], points[1]),
+        (points[1], points[2]),
+        (points[0], points[2]),
+    ]
+    .iter()
+    .copied()
+    .max_by_key(distance)
+    .unwrap_or((points[0], points[0]))
+}
+
+/// Helper apply an affine transform to a point given by its coordinates
+fn transform_f(m: &Mat3x3<f32>, x: f32, y: f32) -> (f32, f32) {
+    let p = m.transform_point(FVec3D::new(x, y, 1.0));
+    (p.x(), p.y())
+}
+
+/// A single polygon edge as stored in the edge tables used by fill_polygon
+#[derive(Debug, Copy, Clone)]
+struct PolygonEdge {
+    y_min: i32,
+    y_max: i32,
+    x: f32, // x coordinate where the edge crosses the current scan line
+    inverse_slope: f32,
+}
+
+/// Region codes used by the Cohen-Sutherland line clipping
+const INSIDE: u8 = 0;
+const LEFT: u8 = 1;
+const RIGHT: u8 = 2;
+const ABOVE: u8 = 4;
+const BELOW: u8 = 8;
+
+/// Helper determine in which region a point lies relative to the clipping rectangle
+fn region_code(x: f64, y: f64, x_max: f64, y_max: f64) -> u8 {
+    let mut code = INSIDE;
+    if x < 0.0 {
+        code |= LEFT;
+    } else if x > x_max {
+        code |= RIGHT;
+    }
+    if y < 0.0 {
+        code |= ABOVE;
+    } else if y > y_max {
+        code |= BELOW;
+    }
+    code
+}
+
+/// Helper clip a line segment to the canvas bounds using Cohen-Sutherland
+/// <https://en.wikipedia.org/wiki/Cohen%E2%80%93Sutherland_algorithm>
+///
+/// Returns None when no part of the segment is visible.
+/// The clipping rectangle goes one pixel past the last column and row so
+/// the exclusive ranges used for straight lines still reach the edges.
+fn clip_line(
+    origin: Point2D,
+    dest: Point2D,
+    width: u32,
+    height: u32,
+) -> Option<(Point2D, Point2D)> {
+    let x_max = width as f64;
+    let y_max = height as f64;
+
+    let (mut x0, mut y0) = (origin.x as f64, origin.y as f64);
+    let (mut x1, mut y1) = (dest.x as f64, dest.y as f64);
+    let mut code0 = region_code(x0, y0, x_max, y_max);
+    let mut code1 = region_code(x1, y1, x_max, y_max);
+
+    loop {
+        if code0 | code1 == INSIDE {
+            // both end points are visible
+            return Some((
+                Point2D::new(x0.round() as i32, y0.round() as i32),
+                Point2D::new(x1.round() as i32, y1.round() as i32),
+            ));
+        }
+        if code0 & code1 != INSIDE {
+            // both end points share an outside region, nothing to draw
+            return None;
+        }
+
+        // move the outside end point onto the boundary it crosses
+        let code_out = if code0 != INSIDE { code0 } else { code1 };
+        let (x, y) = if code_out & BELOW != 0 {
+            (x0 + (x1 - x0) * (y_max - y0) / (y1 - y0), y_max)
+        } else if code_out & ABOVE != 0 {
+            (x0 + (x1 - x0) * -y0 / (y1 - y0), 0.0)
+        } else if code_out & RIGHT != 0 {
+            (x_max, y0 + (y1 - y0) * (x_max - x0) / (x1 - x0))
+        } else {
+            (0.0, y0 + (y1 - y0) * -x0 / (x1 - x0))
+        };
+
+        if code_out == code0 {
+            x0 = x;
+            y0 = y;
+            code0 = region_code(x0, y0, x_max, y_max);
+        } else {
+            x1 = x;
+            y1 = y;
+            code1 = region_code(x1, y1, x_max, y_max);
+        }
+    }
+}
+
+/// Create a canvas the size of a PNG image showing it, with the default origin
+///
+/// Example usage:
+///    let image = PngReader::read(&mut file)?;
+///    let canvas = Canvas::try_from(&image)?;
+impl TryFrom<&PngImage> for Canvas {
+    type Error = Box<dyn Error>;
+    fn try_from(image: &PngImage) -> Result<Self, Self::Error> {
+        let pixels = image.pixels()?;
+        let canvas = Canvas::new(image.width(), image.height());
+        // PNG rows are stored by y as in a TopLeft buffer, move them to the default origin
+        canvas.set_origin(Origin::TopLeft);
+        *canvas.pixels.borrow_mut() = pixels;
+        canvas.set_origin(Origin::BottomLeft);
+        Ok(canvas)
+    }
+}
+
+#[cfg(feature = "window")]
 impl<'a> glium::texture::Texture2dDataSource<'a> for &'a Canvas {
     type Data = u8;
     fn into_raw(self) -> glium::texture::RawImage2d<'a, Self::Data> {
-        let length = (self.width * self.height) as usize;
+        let length = (self.width() * self.height()) as usize;
+
+        if self.origin() == Origin::TopLeft {
+            // textures start with the bottom row, copy the rows in reverse order
+            let data = self
+                .pixels
+                .borrow()
+                .rchunks_exact(self.width().max(1) as usize)
+                .flatten()
+                .flat_map(|pixel| pixel.as_bytes())
+                .collect();
+            return glium::texture::RawImage2d::from_raw_rgba(data, (self.width(), self.height()));
+        }
 
         // let mut data: Vec<u8> = Vec::with_capacity(length * 4);
         // for pixel in &*self.pixels.borrow() {
@@ -726,8 +2362,8 @@ impl<'a> glium::texture::Texture2dDataSource<'a> for &'a Canvas {
                 std::slice::from_raw_parts(self.pixels.borrow().as_ptr() as *const u8, length * 4)
             }),
             // data: std::borrow::Cow::Owned(data),
-            height: self.height,
-            width: self.width,
+            height: self.height(),
+            width: self.width(),
             format: glium::texture::ClientFormat::U8U8U8U8,
         }
     }
@@ -1,12 +1,17 @@
+use std::error::Error;
+use std::fmt;
 pub use std::ops::Add;
 pub use std::ops::AddAssign;
 pub use std::ops::Div;
 pub use std::ops::Mul;
 pub use std::ops::Sub;
 pub use std::ops::SubAssign;
+use std::str::FromStr;
 
 /// RGB like color structure
+/// With the "serde" feature colors are serialized as an [r, g, b, a] array of bytes.
 #[derive(Default, Debug, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Color(u8, u8, u8, u8);
 
 impl Color {
@@ -22,13 +27,25 @@ impl Color {
     pub const GRAY: Color = Color(127, 127, 127, 255);
     pub const TRANSPARENT: Color = Color(255, 255, 255, 0);
 
+    /// More named colors, values taken from the CSS color keywords
+    pub const ORANGE: Color = Color(255, 165, 0, 255);
+    pub const PURPLE: Color = Color(128, 0, 128, 255);
+    pub const BROWN: Color = Color(165, 42, 42, 255);
+    pub const PINK: Color = Color(255, 192, 203, 255);
+    pub const LIME: Color = Color(0, 255, 0, 255);
+    pub const TEAL: Color = Color(0, 128, 128, 255);
+    pub const NAVY: Color = Color(0, 0, 128, 255);
+    pub const OLIVE: Color = Color(128, 128, 0, 255);
+    pub const SILVER: Color = Color(192, 192, 192, 255);
+    pub const MAROON: Color = Color(128, 0, 0, 255);
+
     /// Create a new color from RGB values
     ///
     /// # Arguments
     /// `r`  Red component
     /// `g`  Green component
     /// `b`  Blue component
-    pub fn rgb(r: u8, g: u8, b: u8) -> Self {
+    pub const fn rgb(r: u8, g: u8, b: u8) -> Self {
         Self(r, g, b, 255)
     }
 
@@ -39,14 +56,14 @@ impl Color {
     /// `g`  Green component
     /// `b`  Blue component
     /// `a`  Alpha component 0 - 244
-    pub fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
+    pub const fn rgba(r: u8, g: u8, b: u8, a: u8) -> Self {
         Self(r, g, b, a)
     }
 
     /// Build a color from byte array
     /// # Arguments
     /// `bytes`  Byte[4] array with bytes for color
-    pub fn from_bytes(bytes: &[u8; 4]) -> Self {
+    pub const fn from_bytes(bytes: &[u8; 4]) -> Self {
         Self(bytes[0], bytes[1], bytes[2], bytes[3])
     }
 
@@ -65,6 +82,49 @@ impl Color {
         }
         Self::from_bytes(&array)
     }
+    /// Build a color from an hexadecimal string, the leading # is optional
+    /// Accepted formats: RGB, RGBA, RRGGBB and RRGGBBAA, alpha defaults to 255.
+    /// # Arguments
+    /// `hex`  string such as "#ff8800" or "f80"
+    pub fn from_hex(hex: &str) -> Result<Self, ColorError> {
+        let digits = hex.strip_prefix('#').unwrap_or(hex);
+        if !digits.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(ColorError::ParsingError(format!(
+                "Invalid hexadecimal digits in \"{}\"",
+                hex
+            )));
+        }
+        // only ASCII digits remain so slicing by byte is safe
+        let channel = |i: usize, len: usize| {
+            let value = u8::from_str_radix(&digits[i * len..(i + 1) * len], 16).unwrap();
+            if len == 1 {
+                value * 17 // 0xf -> 0xff
+            } else {
+                value
+            }
+        };
+        match digits.len() {
+            3 => Ok(Self(channel(0, 1), channel(1, 1), channel(2, 1), 255)),
+            4 => Ok(Self(
+                channel(0, 1),
+                channel(1, 1),
+                channel(2, 1),
+                channel(3, 1),
+            )),
+            6 => Ok(Self(channel(0, 2), channel(1, 2), channel(2, 2), 255)),
+            8 => Ok(Self(
+                channel(0, 2),
+                channel(1, 2),
+                channel(2, 2),
+                channel(3, 2),
+            )),
+            len => Err(ColorError::ParsingError(format!(
+                "Expected 3, 4, 6 or 8 hexadecimal digits, found {}",
+                len
+            ))),
+        }
+    }
+
     /// Retrieve red component
     pub fn r(&self) -> u8 {
         self.0
@@ -99,6 +159,277 @@ impl Color {
     pub fn as_bytes(&self) -> [u8; 4] {
         [self.0, self.1, self.2, self.3]
     }
+
+    /// Convert a straight alpha color into premultiplied alpha (RGB scaled by alpha)
+    pub fn to_premultiplied(&self) -> Self {
+        let scale = |channel: u8| ((channel as u32 * self.3 as u32 + 127) / 255) as u8;
+        Self(scale(self.0), scale(self.1), scale(self.2), self.3)
+    }
+
+    /// Convert a premultiplied alpha color back into straight alpha
+    /// Fully transparent colors become transparent black.
+    pub fn from_premultiplied(&self) -> Self {
+        if self.3 == 0 {
+            return Self(0, 0, 0, 0);
+        }
+        let alpha = self.3 as u32;
+        let scale = |channel: u8| ((channel as u32 * 255 + alpha / 2) / alpha).min(255) as u8;
+        Self(scale(self.0), scale(self.1), scale(self.2), self.3)
+    }
+
+    /// Composite this color over a background color ("source over"), straight alpha
+    /// # Arguments
+    /// `background`  color underneath, straight alpha
+    pub fn composite_over(&self, background: Color) -> Self {
+        let src_alpha = self.3 as f32 / 255.0;
+        let dst_alpha = background.3 as f32 / 255.0 * (1.0 - src_alpha);
+        let alpha = src_alpha + dst_alpha;
+        if alpha <= 0.0 {
+            return Self(0, 0, 0, 0);
+        }
+        let mix = |src: u8, dst: u8| {
+            clamp_channel((src as f32 * src_alpha + dst as f32 * dst_alpha) / alpha)
+        };
+        Self(
+            mix(self.0, background.0),
+            mix(self.1, background.1),
+            mix(self.2, background.2),
+            clamp_channel(alpha * 255.0),
+        )
+    }
+
+    /// Composite this color over a background color ("source over"), premultiplied alpha
+    /// Both colors must be premultiplied, the result is premultiplied too.
+    /// # Arguments
+    /// `background`  color underneath, premultiplied alpha
+    pub fn composite_over_premultiplied(&self, background: Color) -> Self {
+        let remaining = 255 - self.3 as u32;
+        let mix =
+            |src: u8, dst: u8| (src as u32 + (dst as u32 * remaining + 127) / 255).min(255) as u8;
+        Self(
+            mix(self.0, background.0),
+            mix(self.1, background.1),
+            mix(self.2, background.2),
+            mix(self.3, background.3),
+        )
+    }
+
+    /// Gray color with the same luminance (0.299 R + 0.587 G + 0.114 B), keeps alpha
+    pub fn grayscale(&self) -> Self {
+        let luma = 0.299 * self.0 as f32 + 0.587 * self.1 as f32 + 0.114 * self.2 as f32;
+        let luma = clamp_channel(luma);
+        Self(luma, luma, luma, self.3)
+    }
+
+    /// Sepia toned version of the color, keeps alpha
+    pub fn sepia(&self) -> Self {
+        let (r, g, b) = (self.0 as f32, self.1 as f32, self.2 as f32);
+        Self(
+            clamp_channel(0.393 * r + 0.769 * g + 0.189 * b),
+            clamp_channel(0.349 * r + 0.686 * g + 0.168 * b),
+            clamp_channel(0.272 * r + 0.534 * g + 0.131 * b),
+            self.3,
+        )
+    }
+
+    /// Negative of the color, keeps alpha
+    pub fn invert(&self) -> Self {
+        Self(255 - self.0, 255 - self.1, 255 - self.2, self.3)
+    }
+
+    /// Adjust brightness and contrast, keeps alpha
+    /// # Arguments
+    /// `brightness`  amount added to every channel, from -1.0 (black) to 1.0 (white), 0.0 = unchanged
+    /// `contrast`    scale of every channel around mid gray, 1.0 = unchanged, 0.0 = all gray
+    pub fn adjust(&self, brightness: f32, contrast: f32) -> Self {
+        let adjust = |channel: u8| {
+            clamp_channel((channel as f32 - 128.0) * contrast + 128.0 + brightness * 255.0)
+        };
+        Self(adjust(self.0), adjust(self.1), adjust(self.2), self.3)
+    }
+
+    /// Linearly interpolate two colors channel by channel in sRGB space (alpha included)
+    /// Cheap, but sRGB values are not proportional to light so midpoints come out too dark,
+    /// e.g. halfway between red and green is a muddy brown. See lerp_linear.
+    /// # Arguments
+    /// `a`   color at t = 0.0
+    /// `b`   color at t = 1.0
+    /// `t`   interpolation factor, clamped to 0.0 - 1.0
+    pub fn lerp(a: Color, b: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| clamp_channel(a as f32 + (b as f32 - a as f32) * t);
+        Self(mix(a.0, b.0), mix(a.1, b.1), mix(a.2, b.2), mix(a.3, b.3))
+    }
+
+    /// Mix this color with another at ratio t, channel by channel (alpha included)
+    /// Same as Color::lerp(self, other, t), unlike difuse which multiplies the colors.
+    /// # Arguments
+    /// `other`   color at t = 1.0
+    /// `t`       mix factor, clamped to 0.0 - 1.0
+    pub fn blend(&self, other: &Color, t: f32) -> Self {
+        Self::lerp(*self, *other, t)
+    }
+
+    /// Interpolate two colors in linear light, converting from and back to sRGB
+    /// Gradients and blends keep the expected brightness: halfway between black and white
+    /// is 188, which looks like middle gray, instead of the darker 128 given by lerp.
+    /// Alpha is not gamma encoded and is interpolated linearly.
+    /// # Arguments
+    /// `a`   color at t = 0.0
+    /// `b`   color at t = 1.0
+    /// `t`   interpolation factor, clamped to 0.0 - 1.0
+    pub fn lerp_linear(a: Color, b: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        let mix = |a: u8, b: u8| {
+            let (a, b) = (srgb_to_linear(a), srgb_to_linear(b));
+            linear_to_srgb(a + (b - a) * t)
+        };
+        Self(
+            mix(a.0, b.0),
+            mix(a.1, b.1),
+            mix(a.2, b.2),
+            clamp_channel(a.3 as f32 + (b.3 as f32 - a.3 as f32) * t),
+        )
+    }
+
+    /// Euclidean distance between two colors in RGB space, alpha is ignored
+    /// 0.0 for identical colors, about 441.7 between black and white.
+    pub fn distance(&self, other: &Color) -> f32 {
+        let dr = self.0 as f32 - other.0 as f32;
+        let dg = self.1 as f32 - other.1 as f32;
+        let db = self.2 as f32 - other.2 as f32;
+        (dr * dr + dg * dg + db * db).sqrt()
+    }
+
+    /// Perceptual distance between two colors (CIE76: euclidean distance in Lab space)
+    /// 0.0 for identical colors, 100.0 between black and white, around 2.3 is barely noticeable.
+    pub fn distance_cie76(&self, other: &Color) -> f32 {
+        let (l1, a1, b1) = self.to_lab();
+        let (l2, a2, b2) = other.to_lab();
+        ((l1 - l2).powi(2) + (a1 - a2).powi(2) + (b1 - b2).powi(2)).sqrt()
+    }
+
+    /// Convert to CIE Lab (D65 white point), alpha is ignored
+    /// Returns (L, a, b) with L from 0.0 (black) to 100.0 (white).
+    /// <https://en.wikipedia.org/wiki/CIELAB_color_space>
+    pub fn to_lab(&self) -> (f32, f32, f32) {
+        let (r, g, b) = (
+            srgb_to_linear(self.0),
+            srgb_to_linear(self.1),
+            srgb_to_linear(self.2),
+        );
+        let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / D65_WHITE.0;
+        let y = (0.2126 * r + 0.7152 * g + 0.0722 * b) / D65_WHITE.1;
+        let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / D65_WHITE.2;
+        let (fx, fy, fz) = (lab_f(x), lab_f(y), lab_f(z));
+        (116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz))
+    }
+
+    /// Build an opaque color from CIE Lab (D65 white point), out of gamut values are clamped
+    /// # Arguments
+    /// `l`  lightness 0.0 - 100.0
+    /// `a`  green (negative) to red (positive)
+    /// `b`  blue (negative) to yellow (positive)
+    pub fn from_lab(l: f32, a: f32, b: f32) -> Self {
+        let fy = (l + 16.0) / 116.0;
+        let fx = fy + a / 500.0;
+        let fz = fy - b / 200.0;
+        let x = lab_f_inverse(fx) * D65_WHITE.0;
+        let y = lab_f_inverse(fy) * D65_WHITE.1;
+        let z = lab_f_inverse(fz) * D65_WHITE.2;
+        Self(
+            linear_to_srgb(3.2406 * x - 1.5372 * y - 0.4986 * z),
+            linear_to_srgb(-0.9689 * x + 1.8758 * y + 0.0415 * z),
+            linear_to_srgb(0.0557 * x - 0.2040 * y + 1.0570 * z),
+            255,
+        )
+    }
+}
+
+/// Formats the color as "#rrggbbaa", which Color::from_hex and str::parse read back
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "#{:02x}{:02x}{:02x}{:02x}",
+            self.0, self.1, self.2, self.3
+        )
+    }
+}
+
+/// Parses the same formats as Color::from_hex
+impl FromStr for Color {
+    type Err = ColorError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_hex(s)
+    }
+}
+
+/// Error type for colors that could not be parsed
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+    ParsingError(String),
+}
+
+impl Error for ColorError {}
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColorError::ParsingError(msg) => {
+                write!(f, "Color Error: Could not parse color. {}", msg)
+            }
+        }
+    }
+}
+
+/// Helper: round and clamp a channel value to the 0 - 255 range
+fn clamp_channel(value: f32) -> u8 {
+    (value + 0.5).clamp(0.0, 255.0) as u8
+}
+
+/// Reference white (X, Y, Z) of the D65 illuminant used by sRGB
+const D65_WHITE: (f32, f32, f32) = (0.950_47, 1.0, 1.088_83);
+
+/// Helper: nonlinear compression used by the XYZ to Lab conversion
+fn lab_f(t: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    if t > DELTA * DELTA * DELTA {
+        t.cbrt()
+    } else {
+        t / (3.0 * DELTA * DELTA) + 4.0 / 29.0
+    }
+}
+
+/// Helper: inverse of lab_f
+fn lab_f_inverse(t: f32) -> f32 {
+    const DELTA: f32 = 6.0 / 29.0;
+    if t > DELTA {
+        t * t * t
+    } else {
+        3.0 * DELTA * DELTA * (t - 4.0 / 29.0)
+    }
+}
+
+/// Helper: decode an sRGB channel to linear light (0.0 - 1.0)
+/// <https://en.wikipedia.org/wiki/SRGB#Transformation>
+fn srgb_to_linear(channel: u8) -> f32 {
+    let value = channel as f32 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// Helper: encode linear light (0.0 - 1.0) back to an sRGB channel
+fn linear_to_srgb(value: f32) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let encoded = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    clamp_channel(encoded * 255.0)
 }
 
 /// Operator +
@@ -0,0 +1,228 @@
+//! DEFLATE compression (RFC 1951) and its zlib (RFC 1950) and gzip (RFC 1952) containers
+//!
+//! The same code the PNG reader and writer use, usable for any data. `level` goes from 0
+//! (no compression, only stored blocks) to 10 (smallest output), 6 is a good default.
+//!
+//! Example usage:
+//!    let packed = compress::deflate_zlib(&bytes, 6);
+//!    let bytes = compress::inflate_zlib(&packed)?;
+//!
+use crate::image::png::update_crc;
+use miniz_oxide::inflate::stream::InflateState;
+use miniz_oxide::{deflate, inflate};
+use miniz_oxide::{DataFormat, MZError, MZFlush, MZStatus};
+use std::error::Error;
+use std::fmt;
+
+/// Highest compression level accepted by miniz_oxide, higher levels are clamped to it
+const MAX_LEVEL: u8 = 10;
+
+/// First bytes of every gzip member
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+/// Compression method of gzip members, 8 is DEFLATE (the only one defined)
+const GZIP_DEFLATE: u8 = 8;
+/// gzip header flags, see RFC 1952 section 2.3.1
+const GZIP_FHCRC: u8 = 2;
+const GZIP_FEXTRA: u8 = 4;
+const GZIP_FNAME: u8 = 8;
+const GZIP_FCOMMENT: u8 = 16;
+/// Operating system byte of the gzip header, 255 is unknown
+const GZIP_OS_UNKNOWN: u8 = 255;
+
+/// Compress `data` into a raw DEFLATE stream, without header or checksum
+pub fn deflate(data: &[u8], level: u8) -> Vec<u8> {
+    deflate::compress_to_vec(data, level.min(MAX_LEVEL))
+}
+
+/// Decompress a raw DEFLATE stream, as written by deflate
+pub fn inflate(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    inflate::decompress_to_vec(data)
+        .map_err(|e| CompressError::InvalidData(format!("Bad DEFLATE stream ({:?})", e)))
+}
+
+/// Compress `data` into a zlib stream: DEFLATE with a small header and an Adler-32 checksum
+pub fn deflate_zlib(data: &[u8], level: u8) -> Vec<u8> {
+    deflate::compress_to_vec_zlib(data, level.min(MAX_LEVEL))
+}
+
+/// Decompress a zlib stream, as written by deflate_zlib, checking its checksum
+pub fn inflate_zlib(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    inflate::decompress_to_vec_zlib(data)
+        .map_err(|e| CompressError::InvalidData(format!("Bad zlib stream ({:?})", e)))
+}
+
+/// Compress `data` into a gzip member, as read by gzip/gunzip (no file name, no time stamp)
+pub fn deflate_gzip(data: &[u8], level: u8) -> Vec<u8> {
+    let compressed = deflate(data, level);
+    let mut gzip = Vec::with_capacity(compressed.len() + 18);
+    gzip.extend_from_slice(&GZIP_MAGIC);
+    gzip.extend_from_slice(&[GZIP_DEFLATE, 0, 0, 0, 0, 0, 0, GZIP_OS_UNKNOWN]);
+    gzip.extend_from_slice(&compressed);
+    gzip.extend_from_slice(&crc32(data).to_le_bytes());
+    gzip.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    gzip
+}
+
+/// Decompress a gzip member, as written by deflate_gzip or gzip, checking its CRC and size
+/// Only the first member is read, the optional header fields (name, comment...) are skipped.
+pub fn inflate_gzip(data: &[u8]) -> Result<Vec<u8>, CompressError> {
+    if data.len() < 18 || data[..2] != GZIP_MAGIC {
+        return Err(CompressError::InvalidData("Not a gzip stream".into()));
+    }
+    if data[2] != GZIP_DEFLATE {
+        return Err(CompressError::Unsupported(format!(
+            "gzip compression method {}",
+            data[2]
+        )));
+    }
+    let flags = data[3];
+    let mut start = 10;
+    if flags & GZIP_FEXTRA != 0 {
+        let length = data
+            .get(start..start + 2)
+            .map(|length| u16::from_le_bytes([length[0], length[1]]) as usize)
+            .ok_or_else(|| truncated("gzip header"))?;
+        start += 2 + length;
+    }
+    for flag in [GZIP_FNAME, GZIP_FCOMMENT] {
+        if flags & flag != 0 {
+            // zero terminated string
+            let end = data
+                .get(start..)
+                .and_then(|rest| rest.iter().position(|byte| *byte == 0))
+                .ok_or_else(|| truncated("gzip header"))?;
+            start += end + 1;
+        }
+    }
+    if flags & GZIP_FHCRC != 0 {
+        start += 2;
+    }
+
+    let compressed = data
+        .get(start..data.len() - 8)
+        .ok_or_else(|| truncated("gzip header"))?;
+    let (decompressed, consumed) = inflate_prefix(compressed)?;
+    let trailer = &data[start + consumed..];
+    if trailer.len() < 8 {
+        return Err(truncated("gzip trailer"));
+    }
+    let crc = u32::from_le_bytes([trailer[0], trailer[1], trailer[2], trailer[3]]);
+    let size = u32::from_le_bytes([trailer[4], trailer[5], trailer[6], trailer[7]]);
+    if crc != crc32(&decompressed) || size != decompressed.len() as u32 {
+        return Err(CompressError::InvalidData(
+            "gzip checksum does not match the data".into(),
+        ));
+    }
+    Ok(decompressed)
+}
+
+/// Incremental zlib decompression, compressed data can be given in pieces as it arrives
+/// and the output is written to a buffer of any size.
+pub struct ZlibStream {
+    state: Box<InflateState>,
+}
+
+impl Default for ZlibStream {
+    fn default() -> Self {
+        Self {
+            state: InflateState::new_boxed(DataFormat::Zlib),
+        }
+    }
+}
+
+impl ZlibStream {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decompress as much of `input` as fits into `output`
+    /// Returns the number of bytes consumed from `input` and written to `output`,
+    /// both are zero when more input is needed or the stream already ended.
+    pub fn decompress(
+        &mut self,
+        input: &[u8],
+        output: &mut [u8],
+    ) -> Result<(usize, usize), CompressError> {
+        let result = inflate::stream::inflate(&mut self.state, input, output, MZFlush::None);
+        match result.status {
+            Ok(_) | Err(MZError::Buf) => Ok((result.bytes_consumed, result.bytes_written)),
+            Err(e) => Err(CompressError::InvalidData(format!(
+                "Bad zlib stream ({:?})",
+                e
+            ))),
+        }
+    }
+}
+
+/// Errors returned when decompressing
+#[derive(Debug, Clone, PartialEq)]
+pub enum CompressError {
+    /// The data is corrupted, truncated or not in the expected format
+    InvalidData(String),
+    /// The data uses a feature this module doesn't implement
+    Unsupported(String),
+}
+
+impl Error for CompressError {}
+impl fmt::Display for CompressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CompressError::InvalidData(msg) => {
+                write!(f, "Compress Error: Invalid data. {}", msg)
+            }
+            CompressError::Unsupported(msg) => {
+                write!(f, "Compress Error: Unsupported. {}", msg)
+            }
+        }
+    }
+}
+
+/// Helper: CRC-32 of `data`, the same one PNG chunks use
+fn crc32(data: &[u8]) -> u32 {
+    update_crc(0xffffffff_u32, data) ^ 0xffffffff_u32
+}
+
+/// Helper: error for data ending before `what`
+fn truncated(what: &str) -> CompressError {
+    CompressError::InvalidData(format!("Data ends inside the {}", what))
+}
+
+/// Helper: decompress the raw DEFLATE stream at the start of `data`
+/// Returns the decompressed bytes and the number of bytes the stream took, the rest of
+/// `data` (e.g. a gzip trailer) is left alone.
+fn inflate_prefix(data: &[u8]) -> Result<(Vec<u8>, usize), CompressError> {
+    let mut state = InflateState::new_boxed(DataFormat::Raw);
+    let mut output = vec![0; data.len().saturating_mul(2).max(64)];
+    let (mut consumed, mut written) = (0, 0);
+    loop {
+        let result = inflate::stream::inflate(
+            &mut state,
+            &data[consumed..],
+            &mut output[written..],
+            MZFlush::None,
+        );
+        consumed += result.bytes_consumed;
+        written += result.bytes_written;
+        match result.status {
+            Ok(MZStatus::StreamEnd) => {
+                output.truncate(written);
+                return Ok((output, consumed));
+            }
+            // output buffer full, make room and carry on
+            Ok(_) | Err(MZError::Buf) if written == output.len() => {
+                output.resize(output.len() * 2, 0);
+            }
+            // no progress with room left: the input ended before the stream did
+            Ok(_) | Err(MZError::Buf) if result.bytes_consumed == 0 => {
+                return Err(truncated("DEFLATE stream"))
+            }
+            Ok(_) | Err(MZError::Buf) => (),
+            Err(e) => {
+                return Err(CompressError::InvalidData(format!(
+                    "Bad DEFLATE stream ({:?})",
+                    e
+                )))
+            }
+        }
+    }
+}
@@ -0,0 +1,254 @@
+use crate::color::Color;
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////////////////////////
+//         Decoded BMP Image                            //
+//////////////////////////////////////////////////////////
+
+/// A decoded BMP image, rows are stored top to bottom
+#[derive(Default, Debug, Clone)]
+pub struct BmpImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// Implementation of BmpImage functionalities
+///
+/// Example usage:
+///    let mut file = std::fs::File::open("image.bmp").unwrap();
+///    let image = BmpReader::read(&mut file).unwrap();
+///    let pixels : Vec<Color> = image.pixels();
+impl BmpImage {
+    /// Get BMP Image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get BMP Image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// collect all the pixels in this image as a vector of pixels
+    pub fn pixels(&self) -> Vec<Color> {
+        self.pixels.clone()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Read BMP From File                                                                  //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// BMP Reader
+/// Supports uncompressed 24 and 32 bit bitmaps with a BITMAPINFOHEADER
+/// (or any of the larger headers that extend it).
+///
+/// # Example:
+/// let mut file = std::fs::File::open("image.bmp").unwrap();
+/// let image : BmpImage = BmpReader::read(&mut file).unwrap();
+pub struct BmpReader;
+
+impl BmpReader {
+    /// Read a BMP file into a BmpImage structure
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    pub fn read(image_file: &mut impl std::io::Read) -> Result<BmpImage, Box<dyn Error>> {
+        let mut data = Vec::<u8>::new();
+        image_file.read_to_end(&mut data)?;
+
+        if data.len() < SZ_FILE_HEADER + SZ_INFO_HEADER || data[0..2] != SIGNATURE {
+            return Err(Box::new(BMPError::ParsingError(
+                "Not a valid BMP image".into(),
+            )));
+        }
+
+        let pixel_offset = read_u32(&data, 10) as usize;
+        let header_size = read_u32(&data, 14) as usize;
+        let width = read_u32(&data, 18) as i32;
+        let height = read_u32(&data, 22) as i32;
+        let bits_per_pixel = read_u16(&data, 28);
+        let compression = read_u32(&data, 30);
+
+        if header_size < SZ_INFO_HEADER || width <= 0 || height == 0 {
+            return Err(Box::new(BMPError::ParsingError(
+                "Invalid BITMAPINFOHEADER".into(),
+            )));
+        }
+        if compression != BI_RGB || (bits_per_pixel != 24 && bits_per_pixel != 32) {
+            return Err(Box::new(BMPError::Unsupported(format!(
+                "Compression {} with {} bits per pixel",
+                compression, bits_per_pixel
+            ))));
+        }
+
+        // a negative height means rows are stored top to bottom
+        let top_down = height < 0;
+        let width = width as usize;
+        let height = height.unsigned_abs() as usize;
+        let bytes_per_pixel = bits_per_pixel as usize / 8;
+        // the sizes come from the header, check them against the data before using them
+        let stride = row_stride(width, bytes_per_pixel);
+        let end = stride
+            .and_then(|stride| stride.checked_mul(height))
+            .and_then(|size| size.checked_add(pixel_offset));
+        let stride = match (stride, end) {
+            (Some(stride), Some(end)) if end <= data.len() => stride,
+            _ => {
+                return Err(Box::new(BMPError::DataError(
+                    "Not enough pixel data".into(),
+                )))
+            }
+        };
+        // rows hold at least width * bytes_per_pixel bytes, so this fits in the data
+        let row_length = width * bytes_per_pixel;
+
+        let mut pixels = Vec::with_capacity(width * height);
+        for row in 0..height {
+            let row = if top_down { row } else { height - 1 - row };
+            let start = pixel_offset + row * stride;
+            for bgra in data[start..start + row_length].chunks_exact(bytes_per_pixel) {
+                let alpha = if bytes_per_pixel == 4 { bgra[3] } else { 255 };
+                pixels.push(Color::rgba(bgra[2], bgra[1], bgra[0], alpha));
+            }
+        }
+
+        // Most 32 bit bitmaps leave the fourth byte unused (zero), treat those as opaque
+        if bytes_per_pixel == 4 && pixels.iter().all(|color| color.alpha() == 0) {
+            pixels.iter_mut().for_each(|color| color.set_alpha(255));
+        }
+
+        Ok(BmpImage {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Write BMP To File                                                                   //
+/////////////////////////////////////////////////////////////////////////////////////////
+/// A BMP Image writer
+/// Writes 32 bit bitmaps (BGRA), keeping the alpha channel of every pixel.
+///
+/// # Example
+/// ```no_run
+/// # use graphics::color::Color;
+/// # use graphics::canvas::{Canvas, Origin};
+/// # use graphics::image::bmp::BmpWriter;
+/// let canvas = Canvas::new(400, 400);
+/// canvas.set_origin(Origin::TopLeft); // image files start with the top row
+/// canvas.fill(Color::BLUE);
+///
+/// let mut file = std::fs::File::create("image.bmp").unwrap();
+/// let pixels = canvas.pixels.borrow();
+/// let writer = BmpWriter::new(canvas.width(), canvas.height(), &pixels).unwrap();
+/// writer.write(&mut file).unwrap();
+/// ```
+pub struct BmpWriter<'a> {
+    width: u32,
+    height: u32,
+    pixels: &'a [Color],
+}
+
+impl<'a> BmpWriter<'a> {
+    pub fn new(width: u32, height: u32, pixels: &'a [Color]) -> Result<Self, BMPError> {
+        // the file size is stored in 32 bits
+        let max_pixels = (u32::MAX as usize - SZ_FILE_HEADER - SZ_INFO_HEADER) / 4;
+        if pixels.len() > max_pixels {
+            Err(BMPError::DataError(
+                "Image too large for a BMP file".to_owned(),
+            ))
+        } else if (width as usize).checked_mul(height as usize) == Some(pixels.len()) {
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        } else {
+            Err(BMPError::DataError("Invalid image size".to_owned()))
+        }
+    }
+
+    /// Write the bitmap file header, info header and the pixels bottom row first
+    pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        let image_size = self.pixels.len() as u32 * 4;
+        let pixel_offset = (SZ_FILE_HEADER + SZ_INFO_HEADER) as u32;
+
+        // BITMAPFILEHEADER
+        writer.write_all(&SIGNATURE)?;
+        writer.write_all(&(pixel_offset + image_size).to_le_bytes())?;
+        writer.write_all(&[0; 4])?;
+        writer.write_all(&pixel_offset.to_le_bytes())?;
+
+        // BITMAPINFOHEADER
+        writer.write_all(&(SZ_INFO_HEADER as u32).to_le_bytes())?;
+        writer.write_all(&self.width.to_le_bytes())?;
+        writer.write_all(&self.height.to_le_bytes())?;
+        writer.write_all(&1_u16.to_le_bytes())?;
+        writer.write_all(&32_u16.to_le_bytes())?;
+        writer.write_all(&BI_RGB.to_le_bytes())?;
+        writer.write_all(&image_size.to_le_bytes())?;
+        // 72 DPI
+        writer.write_all(&2835_u32.to_le_bytes())?;
+        writer.write_all(&2835_u32.to_le_bytes())?;
+        writer.write_all(&[0; 8])?;
+
+        // 32 bit rows are always aligned to 4 bytes, no padding needed
+        if self.width > 0 {
+            for row in self.pixels.chunks_exact(self.width as usize).rev() {
+                for color in row {
+                    writer.write_all(&[color.b(), color.g(), color.r(), color.alpha()])?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Generic Error type for errors related parsing BMP images
+#[derive(Debug, Clone)]
+pub enum BMPError {
+    DataError(String),
+    ParsingError(String),
+    Unsupported(String),
+}
+
+impl Error for BMPError {}
+impl fmt::Display for BMPError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BMPError::DataError(msg) => write!(f, "BMP Error: Invalid data. {}", msg),
+            BMPError::ParsingError(msg) => write!(f, "BMP Error: Could not parse image. {}", msg),
+            BMPError::Unsupported(msg) => write!(f, "BMP Error: Unsupported image. {}", msg),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// BMP standard definitions and helpers                                                //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const SIGNATURE: [u8; 2] = *b"BM";
+const SZ_FILE_HEADER: usize = 14;
+const SZ_INFO_HEADER: usize = 40;
+/// No compression
+const BI_RGB: u32 = 0;
+
+/// Helper: Rows are padded to a multiple of 4 bytes, None if that overflows
+fn row_stride(width: usize, bytes_per_pixel: usize) -> Option<usize> {
+    Some(width.checked_mul(bytes_per_pixel)?.checked_add(3)? & !3)
+}
+
+/// Helper: read a little endian u16, caller makes sure there is enough data
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
+
+/// Helper: read a little endian u32, caller makes sure there is enough data
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
@@ -0,0 +1,63 @@
+//! Dithering
+//!
+//! Map an image to a small palette spreading the quantization error to
+//! the neighbouring pixels, so gradients don't turn into visible bands.
+//!
+//! Example usage:
+//!    let palette = quantize::median_cut(&pixels, 16);
+//!    let indices: Vec<u8> = floyd_steinberg(&pixels, width, &palette);
+//!
+use crate::color::Color;
+use crate::image::quantize::nearest_index;
+
+/// Map every pixel to a palette index using Floyd–Steinberg error diffusion
+/// The error of each pixel is spread to its right (7/16), bottom left (3/16),
+/// bottom (5/16) and bottom right (1/16) neighbours. Errors are accumulated in
+/// f32 per channel and clamped to the color range. Alpha is ignored.
+///
+/// # Arguments
+/// `pixels`    image pixels, row by row
+/// `width`     image width
+/// `palette`   target palette of up to 256 colors
+pub fn floyd_steinberg(pixels: &[Color], width: u32, palette: &[Color]) -> Vec<u8> {
+    let width = width as usize;
+    if width == 0 || palette.is_empty() {
+        return vec![0; pixels.len()];
+    }
+
+    let mut values: Vec<[f32; 3]> = pixels
+        .iter()
+        .map(|c| [c.r() as f32, c.g() as f32, c.b() as f32])
+        .collect();
+    let mut indices = Vec::with_capacity(pixels.len());
+
+    for i in 0..values.len() {
+        let [r, g, b] = values[i].map(|v| v.clamp(0.0, 255.0));
+        let index = nearest_index(palette, Color::rgb(r as u8, g as u8, b as u8));
+        indices.push(index);
+
+        let chosen = palette[index as usize];
+        let error = [
+            r - chosen.r() as f32,
+            g - chosen.g() as f32,
+            b - chosen.b() as f32,
+        ];
+
+        let x = i % width;
+        let mut spread = |offset: Option<usize>, weight: f32| {
+            if let Some(value) = offset.and_then(|o| values.get_mut(o)) {
+                for (channel, error) in value.iter_mut().zip(error.iter()) {
+                    *channel += error * weight;
+                }
+            }
+        };
+        let right = (x + 1 < width).then(|| i + 1);
+        let below = i + width;
+        spread(right, 7.0 / 16.0);
+        spread((x > 0).then(|| below - 1), 3.0 / 16.0);
+        spread(Some(below), 5.0 / 16.0);
+        spread(right.map(|_| below + 1), 1.0 / 16.0);
+    }
+
+    indices
+}
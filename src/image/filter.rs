@@ -0,0 +1,171 @@
+//! Image filters working on plain pixel buffers
+//!
+//! All filters take the pixels row by row together with the image size and
+//! return a new buffer, samples outside the image are clamped to the edge.
+//!
+//! Example usage:
+//!    let blurred: Vec<Color> = box_blur(&pixels, width, height, 3);
+//!
+use crate::color::Color;
+
+/// Box blur: every pixel becomes the average of the (2 * radius + 1)² square around it
+/// Done as a horizontal and a vertical pass using running sums, so the cost
+/// does not depend on the radius.
+///
+/// # Arguments
+/// `pixels`    image pixels, row by row
+/// `width`     image width
+/// `height`    image height
+/// `radius`    blur radius in pixels
+pub fn box_blur(pixels: &[Color], width: usize, height: usize, radius: u32) -> Vec<Color> {
+    let radius = radius as usize;
+    separable(pixels, width, height, |line, out| {
+        let last = line.len() - 1;
+        let window = (2 * radius + 1) as f32;
+        let mut sum = [0.0_f32; 4];
+        for k in 0..=2 * radius {
+            add(&mut sum, &line[k.saturating_sub(radius).min(last)], 1.0);
+        }
+        for (x, value) in out.iter_mut().enumerate() {
+            *value = sum.map(|channel| channel / window);
+            // slide the window one pixel right
+            add(&mut sum, &line[(x + radius + 1).min(last)], 1.0);
+            add(&mut sum, &line[x.saturating_sub(radius)], -1.0);
+        }
+    })
+}
+
+/// Gaussian blur with the given standard deviation (in pixels)
+/// The kernel covers three standard deviations on each side.
+///
+/// # Arguments
+/// `pixels`    image pixels, row by row
+/// `width`     image width
+/// `height`    image height
+/// `sigma`     standard deviation of the gaussian
+pub fn gaussian_blur(pixels: &[Color], width: usize, height: usize, sigma: f32) -> Vec<Color> {
+    if sigma <= 0.0 {
+        return pixels.to_vec();
+    }
+    let radius = (3.0 * sigma).ceil() as isize;
+    let mut kernel: Vec<f32> = (-radius..=radius)
+        .map(|i| (-((i * i) as f32) / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = kernel.iter().sum();
+    kernel.iter_mut().for_each(|w| *w /= total);
+
+    separable(pixels, width, height, |line, out| {
+        let last = line.len() as isize - 1;
+        for (x, value) in out.iter_mut().enumerate() {
+            let mut sum = [0.0_f32; 4];
+            for (k, weight) in kernel.iter().enumerate() {
+                let sample = (x as isize + k as isize - radius).clamp(0, last);
+                add(&mut sum, &line[sample as usize], *weight);
+            }
+            *value = sum;
+        }
+    })
+}
+
+/// Apply an arbitrary kernel to the RGB channels of an image, alpha is kept as is
+/// The kernel is given row by row and centered on each pixel, results are clamped
+/// to the 0 - 255 range (negative responses, e.g. from SOBEL_X, become 0).
+///
+/// # Arguments
+/// `pixels`    image pixels, row by row
+/// `width`     image width
+/// `height`    image height
+/// `kernel`    kernel weights, `H` rows of `W` values
+pub fn convolve<const W: usize, const H: usize>(
+    pixels: &[Color],
+    width: usize,
+    height: usize,
+    kernel: &[[f32; W]; H],
+) -> Vec<Color> {
+    if width == 0 || height == 0 || pixels.len() != width * height {
+        return pixels.to_vec();
+    }
+
+    let (cx, cy) = ((W / 2) as isize, (H / 2) as isize);
+    let mut result = Vec::with_capacity(pixels.len());
+    for y in 0..height as isize {
+        for x in 0..width as isize {
+            let mut sum = [0.0_f32; 4];
+            for (ky, row) in kernel.iter().enumerate() {
+                let sy = (y + ky as isize - cy).clamp(0, height as isize - 1);
+                for (kx, weight) in row.iter().enumerate() {
+                    let sx = (x + kx as isize - cx).clamp(0, width as isize - 1);
+                    let sample = pixels[sy as usize * width + sx as usize];
+                    add(&mut sum, &sample.as_bytes().map(|c| c as f32), *weight);
+                }
+            }
+            sum[3] = pixels[y as usize * width + x as usize].alpha() as f32;
+            result.push(to_color(&sum));
+        }
+    }
+    result
+}
+
+/// 3x3 sharpen kernel
+pub const SHARPEN: [[f32; 3]; 3] = [[0.0, -1.0, 0.0], [-1.0, 5.0, -1.0], [0.0, -1.0, 0.0]];
+/// 3x3 Sobel kernel, responds to horizontal changes (vertical edges)
+pub const SOBEL_X: [[f32; 3]; 3] = [[-1.0, 0.0, 1.0], [-2.0, 0.0, 2.0], [-1.0, 0.0, 1.0]];
+/// 3x3 Sobel kernel, responds to vertical changes (horizontal edges)
+pub const SOBEL_Y: [[f32; 3]; 3] = [[-1.0, -2.0, -1.0], [0.0, 0.0, 0.0], [1.0, 2.0, 1.0]];
+/// 3x3 emboss kernel
+pub const EMBOSS: [[f32; 3]; 3] = [[-2.0, -1.0, 0.0], [-1.0, 1.0, 1.0], [0.0, 1.0, 2.0]];
+/// 3x3 box blur kernel
+pub const BOX_BLUR_3: [[f32; 3]; 3] = [[1.0 / 9.0; 3]; 3];
+
+/// Helper: run a 1D filter over every row and then over every column
+/// The filter gets the input line and the output line to fill (same length).
+fn separable(
+    pixels: &[Color],
+    width: usize,
+    height: usize,
+    filter: impl Fn(&[[f32; 4]], &mut [[f32; 4]]),
+) -> Vec<Color> {
+    if width == 0 || height == 0 || pixels.len() != width * height {
+        return pixels.to_vec();
+    }
+
+    let mut values: Vec<[f32; 4]> = pixels
+        .iter()
+        .map(|c| c.as_bytes().map(|channel| channel as f32))
+        .collect();
+
+    // rows
+    let mut line = vec![[0.0; 4]; width];
+    for row in values.chunks_exact_mut(width) {
+        filter(row, &mut line);
+        row.copy_from_slice(&line);
+    }
+
+    // columns
+    let mut column = vec![[0.0; 4]; height];
+    let mut line = vec![[0.0; 4]; height];
+    for x in 0..width {
+        for (y, value) in column.iter_mut().enumerate() {
+            *value = values[y * width + x];
+        }
+        filter(&column, &mut line);
+        for (y, value) in line.iter().enumerate() {
+            values[y * width + x] = *value;
+        }
+    }
+
+    values.iter().map(to_color).collect()
+}
+
+/// Helper: add a weighted RGBA value to a sum
+fn add(sum: &mut [f32; 4], value: &[f32; 4], weight: f32) {
+    for (channel, value) in sum.iter_mut().zip(value.iter()) {
+        *channel += value * weight;
+    }
+}
+
+/// Helper: round and clamp RGBA values back to a color
+fn to_color(value: &[f32; 4]) -> Color {
+    let [r, g, b, a] = value.map(|channel| (channel + 0.5).clamp(0.0, 255.0) as u8);
+    Color::rgba(r, g, b, a)
+}
@@ -0,0 +1,296 @@
+use crate::color::Color;
+use crate::image::quantize::{median_cut, nearest_index};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Write animated GIF To File                                                          //
+/////////////////////////////////////////////////////////////////////////////////////////
+/// An animated GIF writer
+/// Every frame gets its own palette of up to 256 colors (see quantize::median_cut), pixels
+/// with an alpha value below 128 are written as transparent.
+/// The animation loops forever.
+///
+/// # Example
+/// ```no_run
+/// # use graphics::color::Color;
+/// # use graphics::math::Point2D;
+/// # use graphics::canvas::{Canvas, Origin};
+/// # use graphics::image::gif::GifWriter;
+/// # use std::time::Duration;
+/// let canvas = Canvas::new(200, 200);
+/// canvas.set_origin(Origin::TopLeft); // image files start with the top row
+/// let mut gif = GifWriter::new(canvas.width(), canvas.height());
+/// for i in 0..10 {
+///     canvas.fill(Color::BLACK);
+///     canvas.fill_circle(Point2D::new(20 * i, 100), 10, Color::RED);
+///     gif.add_frame(&canvas.pixels.borrow(), Duration::from_millis(100))
+///         .unwrap();
+/// }
+///
+/// let mut file = std::fs::File::create("animation.gif").unwrap();
+/// gif.write(&mut file).unwrap();
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct GifWriter {
+    width: u32,
+    height: u32,
+    frames: Vec<GifFrame>,
+}
+
+/// A single quantized frame
+#[derive(Default, Debug, Clone)]
+struct GifFrame {
+    palette: Vec<Color>,
+    transparent: Option<u8>,
+    indices: Vec<u8>,
+    /// Delay in hundredths of a second
+    delay: u16,
+}
+
+impl GifWriter {
+    /// Create a writer for an animation of the given size
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            frames: Vec::new(),
+        }
+    }
+
+    /// Number of frames added so far
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Quantize and add a frame to the animation
+    /// # Arguments
+    /// `pixels`    frame pixels, must match the size of the animation
+    /// `delay`     time the frame is shown (GIF stores it in hundredths of a second)
+    pub fn add_frame(&mut self, pixels: &[Color], delay: Duration) -> Result<(), GIFError> {
+        if self.width > u16::MAX as u32 || self.height > u16::MAX as u32 {
+            return Err(GIFError::DataError("Image too large for GIF".to_owned()));
+        }
+        if pixels.len() != (self.width * self.height) as usize {
+            return Err(GIFError::DataError("Invalid image size".to_owned()));
+        }
+
+        let is_transparent = |color: &Color| color.alpha() < 128;
+        let transparent = pixels.iter().any(is_transparent);
+        let max_colors = if transparent { 255 } else { 256 };
+        let opaque: Vec<Color> = pixels
+            .iter()
+            .filter(|c| !is_transparent(c))
+            .copied()
+            .collect();
+        let mut palette = median_cut(&opaque, max_colors);
+
+        // map every pixel to its closest palette entry, caching the lookups
+        let mut cache = HashMap::<[u8; 4], u8>::new();
+        let transparent_index = palette.len() as u8;
+        let indices = pixels
+            .iter()
+            .map(|color| {
+                if is_transparent(color) {
+                    transparent_index
+                } else {
+                    *cache
+                        .entry(color.as_bytes())
+                        .or_insert_with(|| nearest_index(&palette, *color))
+                }
+            })
+            .collect();
+
+        let transparent = if transparent {
+            palette.push(Color::TRANSPARENT);
+            Some(transparent_index)
+        } else {
+            None
+        };
+
+        let delay = (delay.as_millis() / 10).min(u16::MAX as u128) as u16;
+        self.frames.push(GifFrame {
+            palette,
+            transparent,
+            indices,
+            delay,
+        });
+        Ok(())
+    }
+
+    /// Write all the frames as an animated GIF
+    pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        let width = self.width as u16;
+        let height = self.height as u16;
+
+        // Header and logical screen descriptor, no global color table
+        writer.write_all(SIGNATURE)?;
+        writer.write_all(&width.to_le_bytes())?;
+        writer.write_all(&height.to_le_bytes())?;
+        writer.write_all(&[0, 0, 0])?;
+
+        // NETSCAPE2.0 application extension, loop forever
+        writer.write_all(&[EXTENSION, APPLICATION_LABEL, 11])?;
+        writer.write_all(b"NETSCAPE2.0")?;
+        writer.write_all(&[3, 1, 0, 0, 0])?;
+
+        for frame in &self.frames {
+            // Graphic control extension: disposal, delay and transparency
+            let (disposal, transparent_flag, transparent_index) = match frame.transparent {
+                Some(index) => (DISPOSE_BACKGROUND, 1, index),
+                None => (DISPOSE_NONE, 0, 0),
+            };
+            writer.write_all(&[EXTENSION, GRAPHIC_CONTROL_LABEL, 4])?;
+            writer.write_all(&[(disposal << 2) | transparent_flag])?;
+            writer.write_all(&frame.delay.to_le_bytes())?;
+            writer.write_all(&[transparent_index, 0])?;
+
+            // Image descriptor covering the whole screen with a local color table
+            let table_bits = color_table_bits(frame.palette.len());
+            writer.write_all(&[IMAGE_SEPARATOR, 0, 0, 0, 0])?;
+            writer.write_all(&width.to_le_bytes())?;
+            writer.write_all(&height.to_le_bytes())?;
+            writer.write_all(&[0x80 | (table_bits - 1)])?;
+            for i in 0..1 << table_bits {
+                let color = frame.palette.get(i).copied().unwrap_or(Color::BLACK);
+                writer.write_all(&[color.r(), color.g(), color.b()])?;
+            }
+
+            // LZW compressed indices split in sub blocks of up to 255 bytes
+            let min_code_size = table_bits.max(2);
+            writer.write_all(&[min_code_size])?;
+            for block in lzw_encode(&frame.indices, min_code_size).chunks(255) {
+                writer.write_all(&[block.len() as u8])?;
+                writer.write_all(block)?;
+            }
+            writer.write_all(&[0])?;
+        }
+
+        writer.write_all(&[TRAILER])?;
+        Ok(())
+    }
+}
+
+/// Generic Error type for errors related writing GIF images
+#[derive(Debug, Clone)]
+pub enum GIFError {
+    DataError(String),
+}
+
+impl Error for GIFError {}
+impl fmt::Display for GIFError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GIFError::DataError(msg) => write!(f, "GIF Error: Invalid data. {}", msg),
+        }
+    }
+}
+
+/// Helper: number of bits needed for a color table with the given number of colors (1 to 8)
+fn color_table_bits(colors: usize) -> u8 {
+    let mut bits = 1;
+    while (1 << bits) < colors {
+        bits += 1;
+    }
+    bits
+}
+
+/////////////////////////////////////////////////////////////
+//         LZW compression                                 //
+/////////////////////////////////////////////////////////////
+
+/// Compress color indices with the variable length LZW used by GIF
+/// Codes are packed least significant bit first, the table is reset
+/// with a clear code once all 4096 codes are used.
+pub(crate) fn lzw_encode(indices: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code = 1_u16 << min_code_size;
+    let end_code = clear_code + 1;
+    let mut writer = BitWriter::default();
+    let mut table = HashMap::<(u16, u8), u16>::new();
+    let mut next_code = end_code + 1;
+    let mut code_size = min_code_size + 1;
+
+    writer.write(clear_code, code_size);
+    let mut prefix = match indices.first() {
+        Some(index) => *index as u16,
+        None => {
+            writer.write(end_code, code_size);
+            return writer.finish();
+        }
+    };
+
+    for &index in &indices[1..] {
+        if let Some(code) = table.get(&(prefix, index)) {
+            prefix = *code;
+            continue;
+        }
+
+        writer.write(prefix, code_size);
+        if next_code < MAX_CODE {
+            table.insert((prefix, index), next_code);
+            next_code += 1;
+            if next_code > 1 << code_size && code_size < MAX_CODE_SIZE {
+                code_size += 1;
+            }
+        } else {
+            writer.write(clear_code, code_size);
+            table.clear();
+            next_code = end_code + 1;
+            code_size = min_code_size + 1;
+        }
+        prefix = index as u16;
+    }
+
+    writer.write(prefix, code_size);
+    writer.write(end_code, code_size);
+    writer.finish()
+}
+
+/// Packs codes of variable size into bytes, least significant bit first
+#[derive(Default, Debug)]
+struct BitWriter {
+    bytes: Vec<u8>,
+    buffer: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.buffer |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.buffer as u8);
+            self.buffer >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.buffer as u8);
+        }
+        self.bytes
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// GIF standard definitions                                                            //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const SIGNATURE: &[u8; 6] = b"GIF89a";
+const EXTENSION: u8 = 0x21;
+const APPLICATION_LABEL: u8 = 0xFF;
+const GRAPHIC_CONTROL_LABEL: u8 = 0xF9;
+const IMAGE_SEPARATOR: u8 = 0x2C;
+const TRAILER: u8 = 0x3B;
+
+/// Leave the frame in place before drawing the next one
+const DISPOSE_NONE: u8 = 1;
+/// Clear the frame before drawing the next one, so transparent pixels don't show older frames
+const DISPOSE_BACKGROUND: u8 = 2;
+
+/// Largest LZW code size and number of codes
+const MAX_CODE_SIZE: u8 = 12;
+const MAX_CODE: u16 = 1 << MAX_CODE_SIZE;
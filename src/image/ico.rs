@@ -0,0 +1,127 @@
+use crate::color::Color;
+use crate::image::png::PngWriter;
+use std::error::Error;
+use std::fmt;
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Write ICO To File                                                                   //
+/////////////////////////////////////////////////////////////////////////////////////////
+/// A multi resolution icon (.ico) writer
+/// Every image is stored PNG compressed (supported since Windows Vista), so they keep
+/// their alpha channel. Images are at most 256x256, the usual sizes are 16, 32, 48 and 256.
+///
+/// # Example
+/// ```no_run
+/// # use graphics::color::Color;
+/// # use graphics::canvas::{Canvas, Origin};
+/// # use graphics::image::ico::IcoWriter;
+/// let mut icon = IcoWriter::new();
+/// for size in [16, 32, 48, 256] {
+///     let canvas = Canvas::new(size, size);
+///     canvas.set_origin(Origin::TopLeft); // image files start with the top row
+///     canvas.fill(Color::BLUE);
+///     icon.add_image(size, size, &canvas.pixels.borrow()).unwrap();
+/// }
+///
+/// let mut file = std::fs::File::create("app.ico").unwrap();
+/// icon.write(&mut file).unwrap();
+/// ```
+#[derive(Default, Debug, Clone)]
+pub struct IcoWriter {
+    images: Vec<IcoImage>,
+}
+
+/// A single PNG encoded image
+#[derive(Default, Debug, Clone)]
+struct IcoImage {
+    width: u32,
+    height: u32,
+    png: Vec<u8>,
+}
+
+impl IcoWriter {
+    /// Create a writer without images
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of images added so far
+    pub fn image_count(&self) -> usize {
+        self.images.len()
+    }
+
+    /// Encode and add an image to the icon
+    /// # Arguments
+    /// `width`     image width, from 1 to 256
+    /// `height`    image height, from 1 to 256
+    /// `pixels`    image pixels, top row first
+    pub fn add_image(&mut self, width: u32, height: u32, pixels: &[Color]) -> Result<(), ICOError> {
+        if !(1..=MAX_SIZE).contains(&width) || !(1..=MAX_SIZE).contains(&height) {
+            return Err(ICOError::DataError(format!(
+                "Icon images go from 1x1 to 256x256, not {}x{}",
+                width, height
+            )));
+        }
+        if self.images.len() == u16::MAX as usize {
+            return Err(ICOError::DataError("Too many images".to_owned()));
+        }
+
+        let mut png = Vec::new();
+        PngWriter::new(width, height, pixels)
+            .map_err(|e| ICOError::DataError(e.to_string()))?
+            .write(&mut png)
+            .map_err(|e| ICOError::DataError(e.to_string()))?;
+        self.images.push(IcoImage { width, height, png });
+        Ok(())
+    }
+
+    /// Write the icon directory followed by the images
+    pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        // ICONDIR
+        writer.write_all(&0_u16.to_le_bytes())?;
+        writer.write_all(&ICON_TYPE.to_le_bytes())?;
+        writer.write_all(&(self.images.len() as u16).to_le_bytes())?;
+
+        // ICONDIRENTRY for every image, 256 is stored as 0
+        let mut offset = (SZ_DIR + SZ_DIR_ENTRY * self.images.len()) as u32;
+        for image in &self.images {
+            writer.write_all(&[image.width as u8, image.height as u8, 0, 0])?;
+            writer.write_all(&1_u16.to_le_bytes())?;
+            writer.write_all(&32_u16.to_le_bytes())?;
+            writer.write_all(&(image.png.len() as u32).to_le_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            offset += image.png.len() as u32;
+        }
+
+        for image in &self.images {
+            writer.write_all(&image.png)?;
+        }
+        Ok(())
+    }
+}
+
+/// Generic Error type for errors related to ICO images
+#[derive(Debug, Clone)]
+pub enum ICOError {
+    DataError(String),
+}
+
+impl Error for ICOError {}
+impl fmt::Display for ICOError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ICOError::DataError(msg) => write!(f, "ICO Error: Invalid data. {}", msg),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// ICO standard definitions                                                            //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Resource type of icons (cursors are 2)
+const ICON_TYPE: u16 = 1;
+const SZ_DIR: usize = 6;
+const SZ_DIR_ENTRY: usize = 16;
+/// Largest width and height of an icon image
+const MAX_SIZE: u32 = 256;
@@ -0,0 +1,706 @@
+use crate::color::Color;
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////////////////////////
+//         Decoded JPEG Image                           //
+//////////////////////////////////////////////////////////
+
+/// A decoded JPEG image
+/// JPEG pixels are decoded when reading the file, so this just holds the result.
+#[derive(Default, Debug, Clone)]
+pub struct JpegImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// Implementation of JpegImage functionalities
+///
+/// Example usage:
+///    let mut file = std::fs::File::open("photo.jpg").unwrap();
+///    let image = JpegReader::read(&mut file).unwrap();
+///    let pixels : Vec<Color> = image.pixels();
+impl JpegImage {
+    /// Get JPEG Image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get JPEG Image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// collect all the pixels in this image as a vector of pixels
+    pub fn pixels(&self) -> Vec<Color> {
+        self.pixels.clone()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Read JPEG From File                                                                 //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// JPEG Reader
+/// Supports baseline (sequential, Huffman coded) gray scale and YCbCr images.
+/// Progressive and arithmetic coded images are rejected.
+///
+/// # Example:
+/// let mut file = std::fs::File::open("photo.jpg").unwrap();
+/// let image : JpegImage = JpegReader::read(&mut file).unwrap();
+pub struct JpegReader;
+
+impl JpegReader {
+    /// Read a JPEG file into a JpegImage structure
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    pub fn read(image_file: &mut impl std::io::Read) -> Result<JpegImage, Box<dyn Error>> {
+        let mut data = Vec::<u8>::new();
+        image_file.read_to_end(&mut data)?;
+
+        if data.len() < 2 || data[0..2] != SOI_MARKER {
+            return Err(Box::new(JPEGError::ParsingError(
+                "Not a valid JPEG image".into(),
+            )));
+        }
+
+        let mut decoder = Decoder::default();
+        let mut pos = 2;
+        loop {
+            // skip fill bytes before the marker
+            while pos < data.len() && data[pos] == 0xFF && data.get(pos + 1) == Some(&0xFF) {
+                pos += 1;
+            }
+            if pos + 1 >= data.len() {
+                return Err(Box::new(JPEGError::DataError(
+                    "Missing EOI marker, image is truncated".into(),
+                )));
+            }
+            if data[pos] != 0xFF {
+                return Err(Box::new(JPEGError::ParsingError(format!(
+                    "Expected a marker at byte {}",
+                    pos
+                ))));
+            }
+            let marker = data[pos + 1];
+            pos += 2;
+
+            match marker {
+                EOI => break,
+                SOI | RST0..=RST7 => continue,
+                _ => {
+                    let length = read_u16(&data, pos)? as usize;
+                    if length < 2 || pos + length > data.len() {
+                        return Err(Box::new(JPEGError::DataError(
+                            "Invalid segment length".into(),
+                        )));
+                    }
+                    let segment = &data[pos + 2..pos + length];
+                    pos += length;
+                    match marker {
+                        SOF0 | SOF1 => decoder.parse_frame(segment)?,
+                        SOF2 | SOF3 | SOF5..=SOF7 | SOF9..=SOF11 | SOF13..=SOF15 => {
+                            return Err(Box::new(JPEGError::Unsupported(
+                                "Only baseline JPEG images are supported".into(),
+                            )));
+                        }
+                        DHT => decoder.parse_huffman_tables(segment)?,
+                        DQT => decoder.parse_quantization_tables(segment)?,
+                        DRI => decoder.restart_interval = read_u16(segment, 0)? as usize,
+                        SOS => {
+                            let scan = decoder.parse_scan_header(segment)?;
+                            pos = decoder.decode_scan(&scan, &data, pos)?;
+                        }
+                        // APPn, COM and friends carry nothing we need
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        decoder.image()
+    }
+}
+
+/// Generic Error type for errors related parsing JPEG images
+#[derive(Debug, Clone)]
+pub enum JPEGError {
+    DataError(String),
+    ParsingError(String),
+    Unsupported(String),
+}
+
+impl Error for JPEGError {}
+impl fmt::Display for JPEGError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JPEGError::DataError(msg) => write!(f, "JPEG Error: Invalid data. {}", msg),
+            JPEGError::ParsingError(msg) => write!(f, "JPEG Error: Could not parse image. {}", msg),
+            JPEGError::Unsupported(msg) => write!(f, "JPEG Error: Unsupported image. {}", msg),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////
+//         Decoding frames and scans                       //
+/////////////////////////////////////////////////////////////
+
+/// Image component (Y, Cb or Cr) and its decoded samples
+#[derive(Default, Debug, Clone)]
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quantization_table: usize,
+    /// Width of the sample plane, always a multiple of 8
+    stride: usize,
+    samples: Vec<u8>,
+    dc_prediction: i32,
+}
+
+/// Components taking part of a scan with their huffman table selectors
+#[derive(Default, Debug, Clone)]
+struct ScanHeader {
+    components: Vec<(usize, usize, usize)>,
+}
+
+/// Holds all the decoding state while going through the markers
+#[derive(Default, Debug, Clone)]
+struct Decoder {
+    width: usize,
+    height: usize,
+    components: Vec<Component>,
+    max_h: usize,
+    max_v: usize,
+    quantization_tables: [Option<[u16; 64]>; 4],
+    dc_tables: [Option<HuffmanTable>; 4],
+    ac_tables: [Option<HuffmanTable>; 4],
+    restart_interval: usize,
+}
+
+impl Decoder {
+    /// Parse a SOF0/SOF1 segment and allocate the sample planes
+    fn parse_frame(&mut self, segment: &[u8]) -> Result<(), JPEGError> {
+        if segment.len() < 6 {
+            return Err(JPEGError::DataError("Frame header too short".into()));
+        }
+        if segment[0] != 8 {
+            return Err(JPEGError::Unsupported(format!(
+                "Sample precision of {} bits",
+                segment[0]
+            )));
+        }
+        self.height = read_u16(segment, 1)? as usize;
+        self.width = read_u16(segment, 3)? as usize;
+        let count = segment[5] as usize;
+        if self.width == 0 || self.height == 0 {
+            return Err(JPEGError::Unsupported("Images without dimensions".into()));
+        }
+        if count != 1 && count != 3 {
+            return Err(JPEGError::Unsupported(format!(
+                "{} color components",
+                count
+            )));
+        }
+        if segment.len() < 6 + count * 3 {
+            return Err(JPEGError::DataError("Frame header too short".into()));
+        }
+
+        self.components = segment[6..6 + count * 3]
+            .chunks_exact(3)
+            .map(|c| Component {
+                id: c[0],
+                h: (c[1] >> 4) as usize,
+                v: (c[1] & 0x0F) as usize,
+                quantization_table: (c[2] & 0x03) as usize,
+                ..Component::default()
+            })
+            .collect();
+        if self
+            .components
+            .iter()
+            .any(|c| !(1..=4).contains(&c.h) || !(1..=4).contains(&c.v))
+        {
+            return Err(JPEGError::DataError("Invalid sampling factors".into()));
+        }
+
+        self.max_h = self.components.iter().map(|c| c.h).max().unwrap_or(1);
+        self.max_v = self.components.iter().map(|c| c.v).max().unwrap_or(1);
+        let (mcus_x, mcus_y) = self.mcu_count();
+        for component in self.components.iter_mut() {
+            component.stride = mcus_x * component.h * 8;
+            component.samples = vec![0; component.stride * mcus_y * component.v * 8];
+        }
+        Ok(())
+    }
+
+    /// Parse all the quantization tables in a DQT segment
+    fn parse_quantization_tables(&mut self, segment: &[u8]) -> Result<(), JPEGError> {
+        let mut pos = 0;
+        while pos < segment.len() {
+            let precision = segment[pos] >> 4;
+            let id = (segment[pos] & 0x03) as usize;
+            pos += 1;
+            let mut table = [0_u16; 64];
+            for value in table.iter_mut() {
+                *value = if precision == 0 {
+                    pos += 1;
+                    *segment.get(pos - 1).ok_or_else(table_too_short)? as u16
+                } else {
+                    pos += 2;
+                    read_u16(segment, pos - 2)?
+                };
+            }
+            self.quantization_tables[id] = Some(table);
+        }
+        Ok(())
+    }
+
+    /// Parse all the huffman tables in a DHT segment
+    fn parse_huffman_tables(&mut self, segment: &[u8]) -> Result<(), JPEGError> {
+        let mut pos = 0;
+        while pos < segment.len() {
+            let class = segment[pos] >> 4;
+            let id = (segment[pos] & 0x03) as usize;
+            let counts = segment.get(pos + 1..pos + 17).ok_or_else(table_too_short)?;
+            let total = counts.iter().map(|c| *c as usize).sum::<usize>();
+            let values = segment
+                .get(pos + 17..pos + 17 + total)
+                .ok_or_else(table_too_short)?;
+            let table = HuffmanTable::new(counts, values);
+            pos += 17 + total;
+
+            if class == 0 {
+                self.dc_tables[id] = Some(table);
+            } else {
+                self.ac_tables[id] = Some(table);
+            }
+        }
+        Ok(())
+    }
+
+    /// Parse a SOS header, mapping each component to its index in the frame
+    fn parse_scan_header(&self, segment: &[u8]) -> Result<ScanHeader, JPEGError> {
+        let count = *segment.first().ok_or_else(table_too_short)? as usize;
+        let selectors = segment.get(1..1 + count * 2).ok_or_else(table_too_short)?;
+        let mut components = Vec::with_capacity(count);
+        for selector in selectors.chunks_exact(2) {
+            let index = self
+                .components
+                .iter()
+                .position(|c| c.id == selector[0])
+                .ok_or_else(|| JPEGError::DataError("Scan references unknown component".into()))?;
+            components.push((
+                index,
+                (selector[1] >> 4) as usize & 0x03,
+                (selector[1] & 0x03) as usize,
+            ));
+        }
+        Ok(ScanHeader { components })
+    }
+
+    /// Decode the entropy coded data of a scan starting at `pos`
+    /// Returns the position of the marker that follows the scan.
+    fn decode_scan(
+        &mut self,
+        scan: &ScanHeader,
+        data: &[u8],
+        pos: usize,
+    ) -> Result<usize, JPEGError> {
+        if self.components.is_empty() {
+            return Err(JPEGError::DataError(
+                "Scan found before frame header".into(),
+            ));
+        }
+        let mut reader = BitReader::new(data, pos);
+        for component in self.components.iter_mut() {
+            component.dc_prediction = 0;
+        }
+
+        // A scan with a single component is not interleaved, its MCU is a single block
+        let (units_x, units_y) = if scan.components.len() == 1 {
+            let component = &self.components[scan.components[0].0];
+            (
+                (self.width * component.h).div_ceil(self.max_h).div_ceil(8),
+                (self.height * component.v).div_ceil(self.max_v).div_ceil(8),
+            )
+        } else {
+            self.mcu_count()
+        };
+
+        let mut units_left = self.restart_interval;
+        for unit_y in 0..units_y {
+            for unit_x in 0..units_x {
+                if self.restart_interval > 0 {
+                    if units_left == 0 {
+                        reader.restart();
+                        for component in self.components.iter_mut() {
+                            component.dc_prediction = 0;
+                        }
+                        units_left = self.restart_interval;
+                    }
+                    units_left -= 1;
+                }
+
+                for &(index, dc, ac) in &scan.components {
+                    let (h, v) = if scan.components.len() == 1 {
+                        (1, 1)
+                    } else {
+                        (self.components[index].h, self.components[index].v)
+                    };
+                    for block_y in 0..v {
+                        for block_x in 0..h {
+                            let block = self.decode_block(&mut reader, index, dc, ac)?;
+                            let component = &mut self.components[index];
+                            let x = (unit_x * h + block_x) * 8;
+                            let y = (unit_y * v + block_y) * 8;
+                            for (row, samples) in block.chunks_exact(8).enumerate() {
+                                let start = (y + row) * component.stride + x;
+                                component.samples[start..start + 8].copy_from_slice(samples);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(reader.next_marker())
+    }
+
+    /// Decode, dequantize and transform a single 8x8 block
+    fn decode_block(
+        &mut self,
+        reader: &mut BitReader,
+        index: usize,
+        dc: usize,
+        ac: usize,
+    ) -> Result<[u8; 64], JPEGError> {
+        let missing = |name: &str| JPEGError::DataError(format!("Missing {} table", name));
+        let component = &mut self.components[index];
+        let quantization = self.quantization_tables[component.quantization_table]
+            .as_ref()
+            .ok_or_else(|| missing("quantization"))?;
+        let dc_table = self.dc_tables[dc].as_ref().ok_or_else(|| missing("DC"))?;
+        let ac_table = self.ac_tables[ac].as_ref().ok_or_else(|| missing("AC"))?;
+
+        let mut coefficients = [0.0_f32; 64];
+        let size = dc_table.decode(reader)?;
+        component.dc_prediction = component
+            .dc_prediction
+            .wrapping_add(reader.receive_extend(size)?);
+        coefficients[0] = component.dc_prediction as f32 * quantization[0] as f32;
+
+        let mut k = 1;
+        while k < 64 {
+            let symbol = ac_table.decode(reader)?;
+            let run = (symbol >> 4) as usize;
+            let size = symbol & 0x0F;
+            if size == 0 {
+                if run == 15 {
+                    // sixteen zeros
+                    k += 16;
+                    continue;
+                }
+                // end of block
+                break;
+            }
+            k += run;
+            if k > 63 {
+                return Err(JPEGError::DataError("Coefficient out of block".into()));
+            }
+            coefficients[ZIGZAG[k]] = reader.receive_extend(size)? as f32 * quantization[k] as f32;
+            k += 1;
+        }
+
+        Ok(inverse_dct(&coefficients))
+    }
+
+    /// Number of MCUs in each direction for an interleaved scan
+    fn mcu_count(&self) -> (usize, usize) {
+        (
+            self.width.div_ceil(self.max_h * 8),
+            self.height.div_ceil(self.max_v * 8),
+        )
+    }
+
+    /// Upsample every component and convert them to colors
+    fn image(&self) -> Result<JpegImage, Box<dyn Error>> {
+        if self.components.is_empty() {
+            return Err(Box::new(JPEGError::DataError(
+                "Missing frame header".into(),
+            )));
+        }
+
+        let sample = |component: &Component, x: usize, y: usize| {
+            let sx = x * component.h / self.max_h;
+            let sy = y * component.v / self.max_v;
+            component.samples[sy * component.stride + sx] as f32
+        };
+
+        let mut pixels = Vec::with_capacity(self.width * self.height);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let color = match &self.components[..] {
+                    [gray] => {
+                        let gray = sample(gray, x, y) as u8;
+                        Color::rgb(gray, gray, gray)
+                    }
+                    [y_comp, cb_comp, cr_comp] => {
+                        let luma = sample(y_comp, x, y);
+                        let cb = sample(cb_comp, x, y) - 128.0;
+                        let cr = sample(cr_comp, x, y) - 128.0;
+                        Color::rgb(
+                            clamp_sample(luma + 1.402 * cr),
+                            clamp_sample(luma - 0.344_136 * cb - 0.714_136 * cr),
+                            clamp_sample(luma + 1.772 * cb),
+                        )
+                    }
+                    _ => unreachable!("frame header only allows 1 or 3 components"),
+                };
+                pixels.push(color);
+            }
+        }
+
+        Ok(JpegImage {
+            width: self.width as u32,
+            height: self.height as u32,
+            pixels,
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////
+//         Entropy decoding                                //
+/////////////////////////////////////////////////////////////
+
+/// Canonical huffman table as described in Annex C of the JPEG specification
+#[derive(Debug, Clone)]
+struct HuffmanTable {
+    /// Largest code of each length, -1 if there are no codes of that length
+    max_code: [i32; 17],
+    /// Offset from a code of a given length to the index of its value
+    value_offset: [i32; 17],
+    values: Vec<u8>,
+}
+
+impl HuffmanTable {
+    /// Build a table from the number of codes of each length (1 to 16) and their values
+    fn new(counts: &[u8], values: &[u8]) -> Self {
+        let mut max_code = [-1; 17];
+        let mut value_offset = [0; 17];
+        let mut code = 0;
+        let mut index = 0;
+        for length in 1..=16 {
+            let count = counts[length - 1] as i32;
+            if count > 0 {
+                value_offset[length] = index - code;
+                code += count;
+                index += count;
+                max_code[length] = code - 1;
+            }
+            code <<= 1;
+        }
+        Self {
+            max_code,
+            value_offset,
+            values: values.to_vec(),
+        }
+    }
+
+    /// Read bits until they form a valid code and return its value
+    fn decode(&self, reader: &mut BitReader) -> Result<u8, JPEGError> {
+        let mut code = 0;
+        for length in 1..=16 {
+            code = (code << 1) | reader.read_bit() as i32;
+            if code <= self.max_code[length] {
+                let index = (code + self.value_offset[length]) as usize;
+                return self
+                    .values
+                    .get(index)
+                    .copied()
+                    .ok_or_else(|| JPEGError::DataError("Invalid huffman code".into()));
+            }
+        }
+        Err(JPEGError::DataError("Invalid huffman code".into()))
+    }
+}
+
+/// Reads the entropy coded segment one bit at the time
+/// Takes care of removing stuffed bytes (0xFF00) and stops at markers.
+struct BitReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    buffer: u32,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self {
+            data,
+            pos,
+            buffer: 0,
+            bits: 0,
+        }
+    }
+
+    /// Read the next bit, once a marker is reached only zeros are returned
+    fn read_bit(&mut self) -> u32 {
+        if self.bits == 0 {
+            let byte = match self.data.get(self.pos) {
+                Some(0xFF) if self.data.get(self.pos + 1) == Some(&0x00) => {
+                    self.pos += 2;
+                    0xFF
+                }
+                Some(0xFF) | None => 0x00,
+                Some(byte) => {
+                    self.pos += 1;
+                    *byte
+                }
+            };
+            self.buffer = byte as u32;
+            self.bits = 8;
+        }
+        self.bits -= 1;
+        (self.buffer >> self.bits) & 1
+    }
+
+    /// Read `count` bits and extend them into a signed value (F.2.2.1)
+    /// `count` comes from the file, larger than MAX_VALUE_BITS is an error.
+    fn receive_extend(&mut self, count: u8) -> Result<i32, JPEGError> {
+        if count == 0 {
+            return Ok(0);
+        }
+        if count > MAX_VALUE_BITS {
+            return Err(JPEGError::DataError(format!(
+                "Coefficient of {} bits, at most {} are allowed",
+                count, MAX_VALUE_BITS
+            )));
+        }
+        let mut value = 0;
+        for _ in 0..count {
+            value = (value << 1) | self.read_bit() as i32;
+        }
+        if value < 1 << (count - 1) {
+            Ok(value - (1 << count) + 1)
+        } else {
+            Ok(value)
+        }
+    }
+
+    /// Drop any bits left in the current byte and skip the RSTn marker
+    fn restart(&mut self) {
+        self.bits = 0;
+        if self.data.get(self.pos) == Some(&0xFF)
+            && matches!(self.data.get(self.pos + 1), Some(RST0..=RST7))
+        {
+            self.pos += 2;
+        }
+    }
+
+    /// Position of the first marker after the entropy coded data
+    fn next_marker(&self) -> usize {
+        let mut pos = self.pos;
+        while pos + 1 < self.data.len() {
+            match (self.data[pos], self.data[pos + 1]) {
+                (0xFF, 0x00) | (0xFF, RST0..=RST7) => pos += 2,
+                (0xFF, _) => return pos,
+                _ => pos += 1,
+            }
+        }
+        self.data.len()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// JPEG markers and standard definitions                                               //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const SOI_MARKER: [u8; 2] = [0xFF, 0xD8];
+
+const SOF0: u8 = 0xC0;
+const SOF1: u8 = 0xC1;
+const SOF2: u8 = 0xC2;
+const SOF3: u8 = 0xC3;
+const DHT: u8 = 0xC4;
+const SOF5: u8 = 0xC5;
+const SOF7: u8 = 0xC7;
+const SOF9: u8 = 0xC9;
+const SOF11: u8 = 0xCB;
+const SOF13: u8 = 0xCD;
+const SOF15: u8 = 0xCF;
+const RST0: u8 = 0xD0;
+const RST7: u8 = 0xD7;
+const SOI: u8 = 0xD8;
+const EOI: u8 = 0xD9;
+const SOS: u8 = 0xDA;
+const DQT: u8 = 0xDB;
+const DRI: u8 = 0xDD;
+
+/// Largest number of bits of a coefficient value (category), 16 in lossless JPEG
+const MAX_VALUE_BITS: u8 = 16;
+
+/// Position in the 8x8 block of each coefficient in zig-zag order
+const ZIGZAG: [usize; 64] = [
+    0, 1, 8, 16, 9, 2, 3, 10, 17, 24, 32, 25, 18, 11, 4, 5, 12, 19, 26, 33, 40, 48, 41, 34, 27, 20,
+    13, 6, 7, 14, 21, 28, 35, 42, 49, 56, 57, 50, 43, 36, 29, 22, 15, 23, 30, 37, 44, 51, 58, 59,
+    52, 45, 38, 31, 39, 46, 53, 60, 61, 54, 47, 55, 62, 63,
+];
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Helper functions useful when decoding                                               //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Helper: read a big endian u16 at the given position
+fn read_u16(data: &[u8], pos: usize) -> Result<u16, JPEGError> {
+    match data.get(pos..pos + 2) {
+        Some(bytes) => Ok(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => Err(JPEGError::DataError("Unexpected end of data".into())),
+    }
+}
+
+/// Helper: error for tables and headers shorter than they claim to be
+fn table_too_short() -> JPEGError {
+    JPEGError::DataError("Segment too short".into())
+}
+
+/// Helper: round and clamp a sample to the 0 - 255 range
+fn clamp_sample(value: f32) -> u8 {
+    (value + 0.5).clamp(0.0, 255.0) as u8
+}
+
+/// Separable 8x8 inverse DCT, level shifted back to unsigned samples
+fn inverse_dct(coefficients: &[f32; 64]) -> [u8; 64] {
+    let mut cosines = [[0.0_f32; 8]; 8];
+    for (x, row) in cosines.iter_mut().enumerate() {
+        for (u, value) in row.iter_mut().enumerate() {
+            let scale = if u == 0 {
+                std::f32::consts::FRAC_1_SQRT_2
+            } else {
+                1.0
+            };
+            *value = scale * (((2 * x + 1) * u) as f32 * std::f32::consts::PI / 16.0).cos();
+        }
+    }
+
+    // rows first, then columns
+    let mut rows = [0.0_f32; 64];
+    for v in 0..8 {
+        for x in 0..8 {
+            rows[v * 8 + x] = (0..8)
+                .map(|u| cosines[x][u] * coefficients[v * 8 + u])
+                .sum::<f32>()
+                / 2.0;
+        }
+    }
+
+    let mut samples = [0_u8; 64];
+    for y in 0..8 {
+        for x in 0..8 {
+            let value = (0..8).map(|v| cosines[y][v] * rows[v * 8 + x]).sum::<f32>() / 2.0;
+            samples[y * 8 + x] = clamp_sample(value + 128.0);
+        }
+    }
+    samples
+}
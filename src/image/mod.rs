@@ -7,4 +7,18 @@
 //!
 #[macro_use]
 pub mod sprite;
+pub mod bmp;
+pub mod dither;
+pub mod filter;
+pub mod gif;
+pub mod ico;
+pub mod jpeg;
 pub mod png;
+pub mod ppm;
+pub mod quantize;
+#[cfg(test)]
+mod test;
+pub mod tga;
+pub mod tilemap;
+
+pub use filter::{convolve, BOX_BLUR_3, EMBOSS, SHARPEN, SOBEL_X, SOBEL_Y};
@@ -1,9 +1,12 @@
 use crate::color::Color;
+use crate::compress;
 use crate::utils::gz;
 use std::convert::TryFrom;
 use std::convert::TryInto;
 use std::error::Error;
 use std::fmt;
+use std::io::Read;
+use std::time::Duration;
 
 /// Image Header strtucture
 /// It contains information such as width and height and compression
@@ -12,13 +15,50 @@ use std::fmt;
 pub struct PngHeader {
     width: u32,
     height: u32,
-    bit_depth: u8,
-    color_type: u8,
+    bit_depth: BitDepth,
+    color_type: PngColorType,
     compression: u8,
     filter: u8,
     interlace: u8,
 }
 
+impl PngHeader {
+    /// How the samples of each pixel are stored
+    pub fn color_type(&self) -> PngColorType {
+        self.color_type
+    }
+
+    /// Number of bits per sample (or per palette index)
+    pub fn bit_depth(&self) -> BitDepth {
+        self.bit_depth
+    }
+
+    /// Compression method, 0 (deflate) is the only one defined
+    pub fn compression(&self) -> u8 {
+        self.compression
+    }
+
+    /// Filter method, 0 (adaptive filtering per row) is the only one defined
+    pub fn filter(&self) -> u8 {
+        self.filter
+    }
+
+    /// Interlace method, 0 for none and 1 for Adam7
+    pub fn interlace(&self) -> u8 {
+        self.interlace
+    }
+
+    /// Helper get the number of bytes per pixel (at least one, used by the filters)
+    fn bytes_per_pixel(&self) -> usize {
+        (self.color_type.channels() * self.bit_depth.bits()).div_ceil(8)
+    }
+
+    /// Helper gets the number of bytes in a row of pixels (filter type byte not included)
+    fn row_length(&self) -> usize {
+        (self.color_type.channels() * self.bit_depth.bits() * self.width as usize).div_ceil(8)
+    }
+}
+
 //////////////////////////////////////////////////////////
 //         Decoding PNG Image itself                    //
 //////////////////////////////////////////////////////////
@@ -29,8 +69,12 @@ pub struct PngHeader {
 pub struct PngImage {
     header: PngHeader,
     plte: Option<Plte>,
+    trns: Option<tRNS>,
     idat: Vec<u8>,
+    actl: Option<acTL>,
+    frames: Vec<ApngFrame>,
     other_chunks: Vec<Chunk>,
+    warnings: Vec<String>,
 }
 
 /// Implementation of PNGImage functionalities
@@ -58,51 +102,109 @@ impl PngImage {
     }
 
     /// collect all the pixels in this image as a vector of pixels
+    /// Same as collecting the rows given by pixels_with_callback.
     pub fn pixels(&self) -> Result<Vec<Color>, Box<dyn Error>> {
-        let image_data = self.image_data()?;
-        let pixels = match self.header.color_type {
-            GRAY_SCALE_CTYPE => match self.header.bit_depth {
-                1 => gray_scale_one_bit(&image_data),
-                2 => gray_scale_two_bits(&image_data),
-                4 => gray_scale_four_bits(&image_data),
-                8 => gray_scale_eight_bits(&image_data),
-
-                // TODO handle 16 bits
-                16 => Vec::new(),
-                _ => Vec::new(),
-            },
-            RGB_CTYPE => match self.header.bit_depth {
-                8 => rgb_eight_bits(&image_data),
-                16 => rgb_sixteen_bits(&image_data),
-                _ => Vec::new(),
-            },
-            PALETTE_INDEX_CTYPE => {
-                if let Some(plte) = &self.plte {
-                    match self.header.bit_depth {
-                        1 => palette_index_one_bit(&image_data, plte),
-                        2 => palette_index_two_bits(&image_data, plte),
-                        4 => palette_index_four_bits(&image_data, plte),
-                        8 => palette_index_eight_bits(&image_data, plte),
-                        _ => Vec::new(),
+        let mut pixels = Vec::with_capacity(self.width() as usize * self.height() as usize);
+        self.pixels_with_callback(|_, row| pixels.extend_from_slice(row))?;
+        Ok(pixels)
+    }
+
+    /// Decode the pixels one row at the time, from top to bottom
+    /// `on_row` is called with the index of each row and its pixels, the whole image is
+    /// never held as pixels. See PngReader::read_with_callback to decode while reading.
+    pub fn pixels_with_callback(
+        &self,
+        on_row: impl FnMut(u32, &[Color]),
+    ) -> Result<(), Box<dyn Error>> {
+        self.decode_rows(&self.header, &self.idat, on_row)
+    }
+
+    /// Frames of an animated PNG (APNG) with how long each one is shown
+    /// Each frame is the full image: the frame's pixels composed over the previous frame
+    /// following its blend and dispose operations, so they can be shown as they are.
+    /// Images without APNG chunks give the default image as a single frame shown forever
+    /// (zero delay). None when a frame can't be decoded.
+    pub fn frames(&self) -> Option<Vec<(Vec<Color>, Duration)>> {
+        if self.actl.is_none() || self.frames.is_empty() {
+            return Some(vec![(self.pixels().ok()?, Duration::ZERO)]);
+        }
+
+        let width = self.width() as usize;
+        // the output buffer starts fully transparent black
+        let mut canvas = vec![Color::rgba(0, 0, 0, 0); width * self.height() as usize];
+        let mut frames = Vec::with_capacity(self.frames.len());
+        for frame in self.frames.iter() {
+            let control = &frame.control;
+            let header = PngHeader {
+                width: control.width,
+                height: control.height,
+                ..self.header
+            };
+            // the first frame may be the default image
+            let data = frame.data.as_ref().unwrap_or(&self.idat);
+            // PREVIOUS on the first frame restores the transparent canvas, as the spec asks
+            let previous = match control.dispose_op {
+                APNG_DISPOSE_OP_PREVIOUS => Some(canvas.clone()),
+                _ => None,
+            };
+
+            let (x, y) = (control.x_offset as usize, control.y_offset as usize);
+            self.decode_rows(&header, data, |row, pixels| {
+                let start = (y + row as usize) * width + x;
+                let region = &mut canvas[start..start + pixels.len()];
+                for (destination, source) in region.iter_mut().zip(pixels) {
+                    *destination = match control.blend_op {
+                        APNG_BLEND_OP_OVER => source.composite_over(*destination),
+                        _ => *source,
+                    };
+                }
+            })
+            .ok()?;
+            frames.push((canvas.clone(), control.delay()));
+
+            match previous {
+                Some(previous) => canvas = previous,
+                None if control.dispose_op == APNG_DISPOSE_OP_BACKGROUND => {
+                    for row in y..y + control.height as usize {
+                        let start = row * width + x;
+                        canvas[start..start + control.width as usize].fill(Color::rgba(0, 0, 0, 0));
                     }
-                } else {
-                    Vec::new()
                 }
+                None => {}
             }
-            GREY_SCALE_ALPHA_CTYPE => match self.header.bit_depth {
-                8 => gray_scale_with_alpha_eight_bits(&image_data),
-                16 => gray_scale_with_alpha_sixteen_bits(&image_data),
-                _ => Vec::new(),
-            },
-            RGB_ALPHA_CTYPE => match self.header.bit_depth {
-                8 => rgba_eight_bits(&image_data),
-                16 => rgba_sixteen_bits(&image_data),
-                _ => Vec::new(),
-            },
-            _ => Vec::new(),
-        };
+        }
+        Some(frames)
+    }
 
-        Ok(pixels)
+    /// Number of times an animated PNG should be played, 0 for forever
+    /// None for images without APNG chunks.
+    pub fn plays(&self) -> Option<u32> {
+        self.actl.as_ref().map(|actl| actl.num_plays)
+    }
+
+    /// Helper: decode compressed image data (IDAT or fdAT contents) one row at the time
+    /// `header` gives the size of the image, the other fields are the same for all frames.
+    fn decode_rows(
+        &self,
+        header: &PngHeader,
+        data: &[u8],
+        mut on_row: impl FnMut(u32, &[Color]),
+    ) -> Result<(), Box<dyn Error>> {
+        let decompressed: Vec<u8> = gz::decompress_zlib(data)?;
+        let plte = self.plte.as_ref().map(|plte| match &self.trns {
+            Some(trns) => trns.apply_to_palette(plte),
+            None => plte.clone(),
+        });
+        let key = self.trns.as_ref().and_then(|t| t.color_key(header));
+
+        let rows = RowDecoder::new(&decompressed, header.row_length(), header.bytes_per_pixel());
+        for (index, row) in rows.enumerate().take(header.height as usize) {
+            let mut pixels = decode_pixels(header, plte.as_ref(), key, &row?)?;
+            // the last byte of rows with less than 8 bits per pixel may be padded
+            pixels.truncate(header.width as usize);
+            on_row(index as u32, &pixels);
+        }
+        Ok(())
     }
 
     /// Read all pixels in a picture as a continues stream of RGBA bytes.
@@ -120,46 +222,40 @@ impl PngImage {
         &self.other_chunks
     }
 
-    /// Helper get the number of bytes per pixel of this image
-    fn bytes_per_pixel(&self) -> usize {
-        let channels = match self.header.color_type {
-            GRAY_SCALE_CTYPE => 1,
-            RGB_CTYPE => 3,
-            PALETTE_INDEX_CTYPE => 1,
-            GREY_SCALE_ALPHA_CTYPE => 2,
-            RGB_ALPHA_CTYPE => 4,
-            _ => 1,
-        };
-        let bits = self.header.bit_depth * channels;
-        (bits as f32 / 8.0).ceil() as usize
+    /// Physical resolution in dots per inch (horizontal, vertical) from the pHYs chunk
+    /// None when the image has no pHYs chunk or it only gives the pixel aspect ratio.
+    pub fn dpi(&self) -> Option<(f32, f32)> {
+        let phys = self
+            .other_chunks
+            .iter()
+            .find(|chunk| chunk.c_type == *pHYs_TYPE)
+            .and_then(|chunk| pHYs::try_from(chunk).ok())?;
+        if phys.unit == PHYS_UNIT_METER {
+            Some((
+                phys.x as f32 / INCHES_PER_METER,
+                phys.y as f32 / INCHES_PER_METER,
+            ))
+        } else {
+            None
+        }
     }
 
-    /// Helper gets the number of pixels per row (consider image as matrix)
-    fn row_length(&self) -> usize {
-        let channels: usize = match self.header.color_type {
-            GRAY_SCALE_CTYPE => 1,
-            RGB_CTYPE => 3,
-            PALETTE_INDEX_CTYPE => 1,
-            GREY_SCALE_ALPHA_CTYPE => 2,
-            RGB_ALPHA_CTYPE => 4,
-            _ => 1,
-        };
-        channels * (self.header.bit_depth as usize) * (self.width() as usize) / 8
+    /// Problems found while reading the image that did not stop it from loading
+    /// Only filled when reading with PngReadOptions::collect_warnings set.
+    pub fn warnings(&self) -> &[String] {
+        &self.warnings
     }
+}
 
-    /// Collect all the image data on the image and return it as a vector
-    fn image_data(&self) -> Result<Vec<u8>, Box<dyn Error>> {
-        let decompressed: Vec<u8> = gz::decompress_zlib(&self.idat)?;
-        let bpp = self.bytes_per_pixel();
-        let row_len = self.row_length();
-
-        let mut unfiltered = Vec::<u8>::with_capacity(row_len * self.height() as usize);
-        let filter_decoder = RowDecoder::new(&decompressed, row_len, bpp);
-        for row in filter_decoder {
-            unfiltered.extend_from_slice(&row?[..]);
-        }
-
-        Ok(unfiltered)
+/// Read a PNG image, same as PngReader::read
+///
+/// Example usage:
+///    let mut file = std::fs::File::open("example.png")?;
+///    let image = PngImage::try_from(&mut file as &mut dyn std::io::Read)?;
+impl TryFrom<&mut dyn std::io::Read> for PngImage {
+    type Error = Box<dyn Error>;
+    fn try_from(mut image_file: &mut dyn std::io::Read) -> Result<Self, Self::Error> {
+        PngReader::read(&mut image_file)
     }
 }
 
@@ -167,6 +263,16 @@ impl PngImage {
 // Read PNG From File                                                                  //
 /////////////////////////////////////////////////////////////////////////////////////////
 
+/// Options used by PngReader::read_with_options
+/// The default is lenient: chunks with a bad CRC are skipped and nothing is reported.
+#[derive(Default, Debug, Copy, Clone)]
+pub struct PngReadOptions {
+    /// Fail with an error on the first chunk with a bad CRC instead of skipping it
+    pub strict_crc: bool,
+    /// Keep a message for every skipped chunk, see PngImage::warnings
+    pub collect_warnings: bool,
+}
+
 /// PNG Reader
 ///
 /// # Example:
@@ -176,15 +282,32 @@ pub struct PngReader;
 
 impl PngReader {
     /// Read a PNG file into a PNGImage structure
+    /// Chunks with a bad CRC are silently skipped, use read_with_options to change that.
     /// # Arguments
     /// `image_file`    reader containing image data
     pub fn read(image_file: &mut impl std::io::Read) -> Result<PngImage, Box<dyn Error>> {
-        let mut data = Vec::<u8>::new();
+        Self::read_with_options(image_file, PngReadOptions::default())
+    }
+
+    /// Read a PNG file into a PNGImage structure
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    /// `options`       how to deal with damaged chunks
+    pub fn read_with_options(
+        image_file: &mut impl std::io::Read,
+        options: PngReadOptions,
+    ) -> Result<PngImage, Box<dyn Error>> {
         let mut idat = Vec::<u8>::new();
         let mut signature = [0_u8; SZ_SIGNATURE];
         let mut plte: Option<Plte> = None;
+        let mut trns: Option<tRNS> = None;
+        let mut actl: Option<acTL> = None;
+        let mut frames = Vec::<ApngFrame>::new();
+        let mut iend_found = false;
         let mut header = PngHeader::default();
         let mut other_chunks = Vec::<Chunk>::new();
+        let mut warnings = Vec::<String>::new();
+        let mut order = ChunkOrder::default();
 
         image_file.read_exact(&mut signature)?;
 
@@ -194,39 +317,289 @@ impl PngReader {
             )));
         }
 
-        // collect all image dat
-        image_file.read_to_end(&mut data)?;
-
-        for chunk in ChunkDecoder::from_data(&data) {
+        // chunks are read one at the time, the file is never fully loaded in memory
+        for (index, chunk) in ChunkReader::new(image_file).enumerate() {
+            let chunk = chunk?;
             if !chunk.crc_okay() {
-                // we choose to ignore invalid chunks
+                let message = format!(
+                    "Bad CRC on chunk {} ({})",
+                    index,
+                    String::from_utf8_lossy(&chunk.c_type)
+                );
+                if options.strict_crc {
+                    return Err(Box::new(PNGError::DataError(message)));
+                }
+                if options.collect_warnings {
+                    warnings.push(message);
+                }
+                // lenient mode ignores invalid chunks
                 continue;
             }
+            order.check(&chunk.c_type)?;
 
+            #[allow(non_upper_case_globals)]
             match &chunk.c_type {
                 IDAT_TYPE => idat.extend_from_slice(&chunk.data[..]),
                 PLTE_TYPE => plte = Some(Plte::try_from(&chunk)?),
                 IHDR_TYPE => header = parse_ihdr_data(&chunk.data)?,
-                IEND_TYPE => continue,
+                tRNS_TYPE => trns = Some(tRNS::from(&chunk)),
+                acTL_TYPE => actl = Some(acTL::try_from(&chunk)?),
+                fcTL_TYPE => {
+                    let control = fcTL::try_from(&chunk)?;
+                    if control.x_offset as u64 + control.width as u64 > header.width as u64
+                        || control.y_offset as u64 + control.height as u64 > header.height as u64
+                    {
+                        return Err(Box::new(PNGError::DataError(format!(
+                            "APNG frame {} is outside of the image",
+                            frames.len()
+                        ))));
+                    }
+                    // a frame control before the IDAT chunks makes the default image a frame
+                    let data = if idat.is_empty() {
+                        None
+                    } else {
+                        Some(Vec::new())
+                    };
+                    frames.push(ApngFrame { control, data });
+                }
+                fdAT_TYPE => match frames.last_mut().map(|frame| &mut frame.data) {
+                    // skip the sequence number, the rest is the same as IDAT data
+                    Some(Some(data)) if chunk.data.len() >= 4 => {
+                        data.extend_from_slice(&chunk.data[4..])
+                    }
+                    _ => {
+                        return Err(Box::new(PNGError::ParssingError(
+                            "fdAT chunk without its fcTL chunk".into(),
+                        )))
+                    }
+                },
+                IEND_TYPE => {
+                    // anything after the IEND chunk is not part of the image
+                    iend_found = true;
+                    break;
+                }
                 // collect other chuncks, don't let them go to waste
                 _ => other_chunks.push(chunk),
             };
         }
 
-        if !valid_bit_depth(header.color_type, header.bit_depth) {
-            return Err(Box::new(PNGError::ParssingError(format!(
-                "Invalid color type bit depth combination: c: {}, bd: {}",
-                header.color_type, header.bit_depth
-            ))));
+        if !iend_found {
+            return Err(Box::new(PNGError::ParssingError(
+                "Missing IEND chunk, image is truncated".into(),
+            )));
         }
 
         Ok(PngImage {
             header,
             idat,
             plte,
+            trns,
+            actl,
+            frames,
             other_chunks,
+            warnings,
+        })
+    }
+}
+
+/// Decode a PNG one row at the time
+/// Chunks are read as they are needed and the image data is decompressed incrementally,
+/// so memory use is bounded by a couple of rows no matter how big the image is.
+/// Useful for large images, e.g. generating thumbnails on a server.
+/// Interlaced images are not supported as their rows are not stored in order.
+///
+/// # Example:
+/// let file = std::fs::File::open("mypng.png").unwrap();
+/// for row in PngReader::rows(file).unwrap() {
+///     let pixels: Vec<Color> = row.unwrap();
+/// }
+pub struct PngRowReader<R: std::io::Read> {
+    chunks: ChunkReader<R>,
+    header: PngHeader,
+    plte: Option<Plte>,
+    key: Option<Color>,
+    inflater: gz::ZlibStream,
+    idat: Vec<u8>,   // compressed data of the current IDAT chunk
+    idat_pos: usize, // bytes of the current IDAT chunk already decompressed
+    idat_done: bool, // all IDAT chunks were read
+    row: Vec<u8>,    // filter type byte followed by the filtered row
+    row_filled: usize,
+    previous_row: Vec<u8>,
+    rows_left: u32,
+}
+
+impl PngReader {
+    /// Start decoding a PNG row by row, see PngRowReader
+    /// Reads the chunks before the image data (IHDR, PLTE, tRNS), the rest is read by the iterator.
+    /// Chunks with a bad CRC are skipped, as in read.
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    pub fn rows<R: std::io::Read>(mut image_file: R) -> Result<PngRowReader<R>, Box<dyn Error>> {
+        let mut signature = [0_u8; SZ_SIGNATURE];
+        image_file.read_exact(&mut signature)?;
+        if signature != VALID_SIGNATURE {
+            return Err(Box::new(PNGError::ParssingError(
+                "Not a valid PNG image".into(),
+            )));
+        }
+
+        let mut chunks = ChunkReader::new(image_file);
+        let mut header = PngHeader::default();
+        let mut plte: Option<Plte> = None;
+        let mut trns: Option<tRNS> = None;
+        let mut order = ChunkOrder::default();
+        let idat = loop {
+            let chunk = match chunks.next() {
+                Some(chunk) => chunk?,
+                None => {
+                    return Err(Box::new(PNGError::ParssingError(
+                        "Missing image data".into(),
+                    )))
+                }
+            };
+            if !chunk.crc_okay() {
+                continue;
+            }
+            order.check(&chunk.c_type)?;
+            #[allow(non_upper_case_globals)]
+            match &chunk.c_type {
+                IDAT_TYPE => break chunk.data,
+                PLTE_TYPE => plte = Some(Plte::try_from(&chunk)?),
+                IHDR_TYPE => header = parse_ihdr_data(&chunk.data)?,
+                tRNS_TYPE => trns = Some(tRNS::from(&chunk)),
+                IEND_TYPE => {
+                    return Err(Box::new(PNGError::ParssingError(
+                        "Missing image data".into(),
+                    )))
+                }
+                _ => {}
+            }
+        };
+
+        if header.interlace != 0 {
+            return Err(Box::new(PNGError::ParssingError(
+                "Interlaced images can't be decoded row by row".into(),
+            )));
+        }
+
+        let key = trns.as_ref().and_then(|t| t.color_key(&header));
+        let plte = plte.map(|plte| match &trns {
+            Some(trns) => trns.apply_to_palette(&plte),
+            None => plte,
+        });
+        Ok(PngRowReader {
+            chunks,
+            header,
+            plte,
+            key,
+            inflater: gz::ZlibStream::new(),
+            idat,
+            idat_pos: 0,
+            idat_done: false,
+            row: vec![0; header.row_length() + 1],
+            row_filled: 0,
+            previous_row: vec![0; header.row_length()],
+            rows_left: header.height,
         })
     }
+
+    /// Decode a PNG while reading it, calling `on_row` as soon as each row is decoded
+    /// e.g. to show an image progressively while it downloads. Uses rows, so memory use is
+    /// bounded by a couple of rows and interlaced images are not supported.
+    /// Returns the image header once every row went through `on_row`.
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    /// `on_row`        called with the index of each row (top to bottom) and its pixels
+    pub fn read_with_callback<R: std::io::Read>(
+        image_file: R,
+        mut on_row: impl FnMut(u32, &[Color]),
+    ) -> Result<PngHeader, Box<dyn Error>> {
+        let rows = Self::rows(image_file)?;
+        let header = rows.header();
+        for (index, row) in rows.enumerate() {
+            on_row(index as u32, &row?);
+        }
+        Ok(header)
+    }
+}
+
+impl<R: std::io::Read> PngRowReader<R> {
+    /// Get PNG information headers
+    pub fn header(&self) -> PngHeader {
+        self.header
+    }
+
+    /// Helper decompress and unfilter the next row
+    fn next_row(&mut self) -> Result<Vec<Color>, Box<dyn Error>> {
+        while self.row_filled < self.row.len() {
+            if self.idat_pos == self.idat.len() && !self.idat_done {
+                // IDAT chunks are consecutive, the first other chunk ends the image data
+                match self.chunks.next() {
+                    Some(chunk) => {
+                        let chunk = chunk?;
+                        if &chunk.c_type != IDAT_TYPE {
+                            self.idat_done = true;
+                        } else if chunk.crc_okay() {
+                            self.idat = chunk.data;
+                            self.idat_pos = 0;
+                        }
+                        continue;
+                    }
+                    None => self.idat_done = true,
+                }
+            }
+
+            // compressed data may still be buffered in the inflater after the last chunk
+            let (consumed, written) = self.inflater.decompress(
+                &self.idat[self.idat_pos..],
+                &mut self.row[self.row_filled..],
+            )?;
+            self.idat_pos += consumed;
+            self.row_filled += written;
+            if consumed == 0 && written == 0 {
+                return Err(Box::new(PNGError::DataError(
+                    "Image data ends before the last row".into(),
+                )));
+            }
+        }
+
+        let mut current_row = self.row[1..].to_vec();
+        // rows with an unknown filter type are left as they are
+        if let Ok(filter) = FilterType::try_from(self.row[0]) {
+            remove_filter(
+                &mut current_row,
+                &self.previous_row,
+                filter,
+                self.header.bytes_per_pixel(),
+            )?;
+        }
+        self.previous_row = current_row;
+        self.row_filled = 0;
+
+        let mut pixels = decode_pixels(
+            &self.header,
+            self.plte.as_ref(),
+            self.key,
+            &self.previous_row,
+        )?;
+        // the last byte of rows with less than 8 bits per pixel may be padded
+        pixels.truncate(self.header.width as usize);
+        Ok(pixels)
+    }
+}
+
+/// Each item is a row of pixels, from top to bottom
+/// Iteration stops after the first error.
+impl<R: std::io::Read> Iterator for PngRowReader<R> {
+    type Item = Result<Vec<Color>, Box<dyn Error>>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rows_left == 0 {
+            return None;
+        }
+        let row = self.next_row();
+        self.rows_left = if row.is_ok() { self.rows_left - 1 } else { 0 };
+        Some(row)
+    }
 }
 
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -238,10 +611,11 @@ impl PngReader {
 /// ```no_run
 /// # use graphics::color::Color;
 /// # use graphics::math::Point2D;
-/// # use graphics::canvas::Canvas;
+/// # use graphics::canvas::{Canvas, Origin};
 /// # use graphics::image::png::PngWriter;
 /// # fn main() {
 ///     let canvas = Canvas::new(400, 400);
+///     canvas.set_origin(Origin::TopLeft); // image files start with the top row
 ///     let origin = Point2D::new(200,200);
 ///     canvas.fill_circle(origin, 50, Color::BLUE);
 ///     
@@ -254,17 +628,32 @@ impl PngReader {
 pub struct PngWriter<'a> {
     width: u32,
     height: u32,
-    pixels: &'a [Color],
+    data: PngData<'a>,
+    filter: FilterType,
+    phys: Option<pHYs>,
     chunks: Vec<Chunk>,
 }
 
+/// Pixels to be written by a PngWriter
+enum PngData<'a> {
+    /// True color with alpha
+    Rgba(&'a [Color]),
+    /// Palette indices and the palette they point to
+    Indexed {
+        indices: &'a [u8],
+        palette: &'a [Color],
+    },
+}
+
 impl<'a> PngWriter<'a> {
     pub fn new(width: u32, height: u32, pixels: &'a [Color]) -> Result<Self, PNGError> {
         if pixels.len() == (width * height) as usize {
             Ok(Self {
                 width,
                 height,
-                pixels,
+                data: PngData::Rgba(pixels),
+                filter: FilterType::None,
+                phys: None,
                 chunks: Vec::new(),
             })
         } else {
@@ -272,17 +661,52 @@ impl<'a> PngWriter<'a> {
         }
     }
 
+    /// Create a writer for an 8 bit palette (indexed) image
+    /// Palette alpha values are kept using a tRNS chunk.
+    /// # Arguments
+    /// `indices`   one palette index per pixel
+    /// `palette`   up to 256 colors
+    pub fn new_indexed(
+        width: u32,
+        height: u32,
+        indices: &'a [u8],
+        palette: &'a [Color],
+    ) -> Result<Self, PNGError> {
+        if indices.len() != (width * height) as usize {
+            Err(PNGError::DataError("Invalid image size".to_owned()))
+        } else if palette.is_empty() || palette.len() > 256 {
+            Err(PNGError::DataError(
+                "Palette must have between 1 and 256 colors".to_owned(),
+            ))
+        } else if indices.iter().any(|i| *i as usize >= palette.len()) {
+            Err(PNGError::DataError("Palette index out of range".to_owned()))
+        } else {
+            Ok(Self {
+                width,
+                height,
+                data: PngData::Indexed { indices, palette },
+                filter: FilterType::None,
+                phys: None,
+                chunks: Vec::new(),
+            })
+        }
+    }
+
     /// Write a
     pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
         // PNG Signature
         writer.write_all(&VALID_SIGNATURE[..])?;
 
-        // Header information 8 bit depth, RGBA, no filter
+        // Header information 8 bit depth, RGBA or palette, no filter
+        let color_type = match self.data {
+            PngData::Rgba(_) => PngColorType::Rgba,
+            PngData::Indexed { .. } => PngColorType::Indexed,
+        };
         let header = PngHeader {
             width: self.width,
             height: self.height,
-            bit_depth: 8,
-            color_type: RGB_ALPHA_CTYPE,
+            bit_depth: BitDepth::Eight,
+            color_type,
             compression: 0,
             filter: FilterType::None as u8,
             interlace: 0,
@@ -290,18 +714,45 @@ impl<'a> PngWriter<'a> {
         let header_chunk: Chunk = header.into();
         header_chunk.write_all(writer)?;
 
-        // prepare IDAT Data
-        let mut idat = Vec::<u8>::with_capacity(self.width as usize + self.pixels.len() * 4);
-        for row in self.pixels.chunks_exact(self.width as usize) {
-            // No filter
-            idat.push(FilterType::None as u8);
-            for color in row {
-                idat.extend_from_slice(&color.as_bytes()[..]);
+        // physical resolution must come before the image data
+        if let Some(phys) = &self.phys {
+            Chunk::from(phys).write_all(writer)?;
+        }
+
+        // prepare IDAT Data, unfiltered rows first
+        let (raw, bpp) = match self.data {
+            PngData::Rgba(pixels) => {
+                let mut raw = Vec::with_capacity(pixels.len() * 4);
+                for color in pixels {
+                    raw.extend_from_slice(&color.as_bytes()[..]);
+                }
+                (raw, 4)
             }
+            PngData::Indexed { indices, palette } => {
+                // palette goes before the image data, alphas only when needed
+                let plte = palette.iter().flat_map(|c| [c.r(), c.g(), c.b()]).collect();
+                Chunk::new(*PLTE_TYPE, plte).write_all(writer)?;
+                if palette.iter().any(|c| c.alpha() < 255) {
+                    let alphas = palette.iter().map(|c| c.alpha()).collect();
+                    Chunk::new(*tRNS_TYPE, alphas).write_all(writer)?;
+                }
+                (indices.to_vec(), 1)
+            }
+        };
+
+        // every row starts with its filter type byte
+        let filter = self.filter;
+        let row_len = self.width as usize * bpp;
+        let mut idat = Vec::<u8>::with_capacity(self.height as usize + raw.len());
+        let mut previous_row = vec![0; row_len];
+        for row in raw.chunks_exact(row_len.max(1)) {
+            idat.push(filter as u8);
+            idat.extend_from_slice(&apply_filter(row, &previous_row, filter, bpp));
+            previous_row.copy_from_slice(row);
         }
 
-        // compress for writing
-        let compressed_idat = gz::compress_zlib(&idat);
+        // compress for writing, at a level that lets the filters make a difference
+        let compressed_idat = compress::deflate_zlib(&idat, 6);
         let idat_chunk = Chunk::new(*IDAT_TYPE, compressed_idat);
 
         // write to file
@@ -313,11 +764,27 @@ impl<'a> PngWriter<'a> {
         }
 
         // Write ending chunk
-        writer.write_all(IEND_TYPE)?;
+        Chunk::new(*IEND_TYPE, Vec::new()).write_all(writer)?;
 
         Ok(())
     }
 
+    /// Filter applied to every row before compression, FilterType::None by default
+    /// Paeth usually gives the smallest files for photos and gradients, None for
+    /// palette images.
+    pub fn set_filter(&mut self, filter: FilterType) {
+        self.filter = filter;
+    }
+
+    /// Store the physical resolution (dots per inch) of the image in a pHYs chunk
+    pub fn set_dpi(&mut self, x: f32, y: f32) {
+        self.phys = Some(pHYs {
+            x: (x * INCHES_PER_METER).round() as u32,
+            y: (y * INCHES_PER_METER).round() as u32,
+            unit: PHYS_UNIT_METER,
+        });
+    }
+
     /// Add a chunck to t
     pub fn add_chunk(&mut self, chunk: Chunk) {
         self.chunks.push(chunk);
@@ -391,7 +858,7 @@ impl Chunk {
     }
 
     /// Writes all data in a chunk to a Writer/File
-    fn write_all(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+    pub(crate) fn write_all(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
         writer.write_all(&self.length.to_be_bytes()[..])?;
         writer.write_all(&self.c_type)?;
         writer.write_all(&self.data[..])?;
@@ -443,9 +910,10 @@ impl TryFrom<&Chunk> for Plte {
 /// or a single transparent color (for grayscale and truecolor images)
 #[derive(Debug, Clone)]
 #[doc(hidden)]
-#[allow(non_camel_case_types, dead_code)]
+#[allow(non_camel_case_types)]
 struct tRNS {
     transparency: [u8; 256],
+    length: usize,
 }
 impl From<&Chunk> for tRNS {
     fn from(chunk: &Chunk) -> tRNS {
@@ -458,6 +926,40 @@ impl From<&Chunk> for tRNS {
         }
         tRNS {
             transparency: alphas,
+            length: data.len().min(256),
+        }
+    }
+}
+
+impl tRNS {
+    /// Copy of the palette with the alpha values of each entry applied
+    fn apply_to_palette(&self, plte: &Plte) -> Plte {
+        let mut plte = plte.clone();
+        for (color, alpha) in plte.colors.iter_mut().zip(self.transparency.iter()) {
+            color.set_alpha(*alpha);
+        }
+        plte
+    }
+
+    /// The single transparent color for gray scale and RGB images
+    /// Samples are scaled to 8 bits the same way the pixels are decoded.
+    fn color_key(&self, header: &PngHeader) -> Option<Color> {
+        let sample = |i: usize| {
+            let value = u16::from_be_bytes([self.transparency[i], self.transparency[i + 1]]);
+            match header.bit_depth {
+                BitDepth::Sixteen => (255.0 * value as f32 / 65535.0) as u8,
+                bits => (value as u32 * (255 / ((1 << bits.bits()) - 1))) as u8,
+            }
+        };
+        match header.color_type {
+            PngColorType::Grayscale if self.length >= 2 => {
+                let gray = sample(0);
+                Some(Color::rgb(gray, gray, gray))
+            }
+            PngColorType::Rgb if self.length >= 6 => {
+                Some(Color::rgb(sample(0), sample(2), sample(4)))
+            }
+            _ => None,
         }
     }
 }
@@ -473,26 +975,174 @@ struct gAMA {
     gamma: u32,
 }
 
-/// Different allowed filter type
-#[derive(Debug, Copy, Clone)]
-enum FilterType {
+/// The pHYs chunk gives the intended pixel size or aspect ratio
+/// Pixels per unit on each axis, the unit is either meters or unknown (aspect ratio only).
+#[derive(Default, Debug, Clone)]
+#[allow(non_camel_case_types)]
+struct pHYs {
+    x: u32,
+    y: u32,
+    unit: u8,
+}
+
+/// The acTL chunk marks an animated PNG (APNG)
+/// <https://wiki.mozilla.org/APNG_Specification>
+#[derive(Default, Debug, Clone)]
+#[allow(non_camel_case_types, dead_code)]
+struct acTL {
+    num_frames: u32,
+    num_plays: u32,
+}
+
+/// The fcTL chunk gives the position, delay and blending of an APNG frame
+#[derive(Default, Debug, Clone)]
+#[allow(non_camel_case_types)]
+struct fcTL {
+    width: u32,
+    height: u32,
+    x_offset: u32,
+    y_offset: u32,
+    delay_num: u16,
+    delay_den: u16,
+    dispose_op: u8,
+    blend_op: u8,
+}
+
+impl fcTL {
+    /// How long the frame is shown, a zero denominator means 1/100 seconds
+    fn delay(&self) -> Duration {
+        let den = if self.delay_den == 0 {
+            100
+        } else {
+            self.delay_den
+        };
+        Duration::from_nanos(self.delay_num as u64 * 1_000_000_000 / den as u64)
+    }
+}
+
+/// An APNG frame: its fcTL chunk and compressed data
+/// `data` is None when the frame is the default image, the data is then in IDAT.
+#[derive(Default, Debug, Clone)]
+struct ApngFrame {
+    control: fcTL,
+    data: Option<Vec<u8>>,
+}
+
+/// PNG color types, how the samples of each pixel are stored
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PngColorType {
+    Grayscale = 0,
+    Rgb = 2,
+    Indexed = 3,
+    GrayscaleAlpha = 4,
+    #[default]
+    Rgba = 6,
+}
+
+impl PngColorType {
+    /// Number of samples per pixel
+    pub fn channels(&self) -> usize {
+        match self {
+            PngColorType::Grayscale => 1,
+            PngColorType::Rgb => 3,
+            PngColorType::Indexed => 1,
+            PngColorType::GrayscaleAlpha => 2,
+            PngColorType::Rgba => 4,
+        }
+    }
+
+    /// Check the bit depth is allowed for this color type
+    /// <https://www.w3.org/TR/PNG/#11IHDR>
+    pub fn allows(&self, bit_depth: BitDepth) -> bool {
+        use BitDepth::*;
+        match self {
+            PngColorType::Grayscale => true,
+            PngColorType::Indexed => bit_depth != Sixteen,
+            PngColorType::Rgb | PngColorType::GrayscaleAlpha | PngColorType::Rgba => {
+                bit_depth == Eight || bit_depth == Sixteen
+            }
+        }
+    }
+}
+
+impl TryFrom<u8> for PngColorType {
+    type Error = PNGError;
+    fn try_from(value: u8) -> Result<PngColorType, Self::Error> {
+        match value {
+            0 => Ok(PngColorType::Grayscale),
+            2 => Ok(PngColorType::Rgb),
+            3 => Ok(PngColorType::Indexed),
+            4 => Ok(PngColorType::GrayscaleAlpha),
+            6 => Ok(PngColorType::Rgba),
+            _ => Err(PNGError::ParssingError(format!(
+                "Invalid color type: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Number of bits per sample (or per palette index)
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BitDepth {
+    One = 1,
+    Two = 2,
+    Four = 4,
+    #[default]
+    Eight = 8,
+    Sixteen = 16,
+}
+
+impl BitDepth {
+    /// Number of bits as a number
+    pub fn bits(&self) -> usize {
+        *self as usize
+    }
+}
+
+impl TryFrom<u8> for BitDepth {
+    type Error = PNGError;
+    fn try_from(value: u8) -> Result<BitDepth, Self::Error> {
+        match value {
+            1 => Ok(BitDepth::One),
+            2 => Ok(BitDepth::Two),
+            4 => Ok(BitDepth::Four),
+            8 => Ok(BitDepth::Eight),
+            16 => Ok(BitDepth::Sixteen),
+            _ => Err(PNGError::ParssingError(format!(
+                "Invalid bit depth: {}",
+                value
+            ))),
+        }
+    }
+}
+
+/// Filter applied to each row before compression, see PngWriter::set_filter
+/// Each predicts a byte from its neighbours (left, above, upper left) and stores the
+/// difference, smooth images compress better with Sub, Up, Average or Paeth.
+/// <https://www.w3.org/TR/PNG/#9Filters>
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FilterType {
     None = 0,
     Sub = 1,
     Up = 2,
     Average = 3,
     Paeth = 4,
-    Unsupported,
-}
-
-impl From<u8> for FilterType {
-    fn from(val: u8) -> FilterType {
-        match val {
-            0 => FilterType::None,
-            1 => FilterType::Sub,
-            2 => FilterType::Up,
-            3 => FilterType::Average,
-            4 => FilterType::Paeth,
-            _ => FilterType::Unsupported,
+}
+
+impl TryFrom<u8> for FilterType {
+    type Error = PNGError;
+    fn try_from(value: u8) -> Result<FilterType, Self::Error> {
+        match value {
+            0 => Ok(FilterType::None),
+            1 => Ok(FilterType::Sub),
+            2 => Ok(FilterType::Up),
+            3 => Ok(FilterType::Average),
+            4 => Ok(FilterType::Paeth),
+            _ => Err(PNGError::ParssingError(format!(
+                "Invalid filter type: {}",
+                value
+            ))),
         }
     }
 }
@@ -508,14 +1158,83 @@ impl Error for PNGError {}
 impl fmt::Display for PNGError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            PNGError::ParssingError(_) => {
-                write!(f, "PNG Error: Could not parse image.")
-            }
-            PNGError::DataError(_) => {
-                write!(f, "Invalid data length extraction.")
+            PNGError::ParssingError(msg) => {
+                write!(f, "PNG Error: Could not parse image. {}", msg)
             }
-            _ => write!(f, "PNG Error: Error reading file."),
+            PNGError::DataError(msg) => write!(f, "PNG Error: Invalid data. {}", msg),
+            PNGError::FileError(msg) => write!(f, "PNG Error: Error reading file. {}", msg),
+        }
+    }
+}
+
+impl TryFrom<&Chunk> for pHYs {
+    type Error = PNGError;
+    /// Attempt parse a pHYs struct form a chunk if valid
+    fn try_from(chunk: &Chunk) -> Result<pHYs, Self::Error> {
+        if chunk.c_type != *pHYs_TYPE {
+            Err(PNGError::ParssingError("Invalid pHYs format".into()))
+        } else if chunk.data.len() != 9 {
+            Err(PNGError::ParssingError("Invalid pHYs data size".into()))
+        } else {
+            let data = &chunk.data;
+            Ok(pHYs {
+                x: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+                y: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+                unit: data[8],
+            })
+        }
+    }
+}
+
+impl TryFrom<&Chunk> for acTL {
+    type Error = PNGError;
+    /// Attempt parse an acTL struct form a chunk if valid
+    fn try_from(chunk: &Chunk) -> Result<acTL, Self::Error> {
+        if chunk.data.len() != 8 {
+            return Err(PNGError::ParssingError("Invalid acTL data size".into()));
+        }
+        let data = &chunk.data;
+        Ok(acTL {
+            num_frames: u32::from_be_bytes([data[0], data[1], data[2], data[3]]),
+            num_plays: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+        })
+    }
+}
+
+impl TryFrom<&Chunk> for fcTL {
+    type Error = PNGError;
+    /// Attempt parse a fcTL struct form a chunk if valid, the sequence number is skipped
+    fn try_from(chunk: &Chunk) -> Result<fcTL, Self::Error> {
+        if chunk.data.len() != 26 {
+            return Err(PNGError::ParssingError("Invalid fcTL data size".into()));
         }
+        let data = &chunk.data;
+        let u32_at =
+            |i: usize| u32::from_be_bytes([data[i], data[i + 1], data[i + 2], data[i + 3]]);
+        let control = fcTL {
+            width: u32_at(4),
+            height: u32_at(8),
+            x_offset: u32_at(12),
+            y_offset: u32_at(16),
+            delay_num: u16::from_be_bytes([data[20], data[21]]),
+            delay_den: u16::from_be_bytes([data[22], data[23]]),
+            dispose_op: data[24],
+            blend_op: data[25],
+        };
+        if control.width == 0 || control.height == 0 {
+            return Err(PNGError::DataError("Empty APNG frame".into()));
+        }
+        Ok(control)
+    }
+}
+
+impl From<&pHYs> for Chunk {
+    fn from(phys: &pHYs) -> Self {
+        let mut data = Vec::with_capacity(9);
+        data.extend_from_slice(&phys.x.to_be_bytes());
+        data.extend_from_slice(&phys.y.to_be_bytes());
+        data.push(phys.unit);
+        Chunk::new(*pHYs_TYPE, data)
     }
 }
 
@@ -523,53 +1242,103 @@ impl fmt::Display for PNGError {
 //         Decoding chunk                                  //
 /////////////////////////////////////////////////////////////
 
-/// Chunk decoder to ease decoding a single chunk at the time using iterators
-#[derive(Debug, Clone)]
-struct ChunkDecoder<'a> {
-    start: usize,
-    data: &'a [u8],
+/// Chunk reader to ease decoding a single chunk at the time from a reader using iterators
+/// Iteration stops at the end of the data or at a truncated chunk.
+struct ChunkReader<R: std::io::Read> {
+    reader: R,
 }
 
-impl<'a> ChunkDecoder<'a> {
-    /// Initialize the chunk decoder with a vector of bytes (usually from a file)
-    pub fn from_data(data: &'a [u8]) -> Self {
-        Self { start: 0, data }
+impl<R: std::io::Read> ChunkReader<R> {
+    /// Initialize the chunk reader with a reader positioned right after the signature
+    pub fn new(reader: R) -> Self {
+        Self { reader }
     }
 
-    /// extracts a given number of bytes from the decoder
-    /// Returns them as an optional
-    pub fn extract_bytes(&mut self, length: usize) -> Option<&[u8]> {
-        let mut value = None;
-        if length + self.start <= self.data.len() {
-            value = Some(&self.data[self.start..self.start + length]);
-            self.start += length;
+    /// Read the next chunk: length, type, data and CRC
+    fn read_chunk(&mut self) -> std::io::Result<Chunk> {
+        let mut length = [0_u8; SZ_CHUNK_LENGTH];
+        self.reader.read_exact(&mut length)?;
+        let length = u32::from_be_bytes(length);
+
+        let mut c_type = [0_u8; SZ_CHUNK_COLOR_TYPE];
+        self.reader.read_exact(&mut c_type)?;
+
+        // take() avoids allocating whatever a corrupted length asks for
+        let mut data = Vec::<u8>::new();
+        (&mut self.reader)
+            .take(length as u64)
+            .read_to_end(&mut data)?;
+        if data.len() != length as usize {
+            return Err(std::io::ErrorKind::UnexpectedEof.into());
         }
-        value
+
+        let mut crc = [0_u8; SZ_CHUNK_CRC];
+        self.reader.read_exact(&mut crc)?;
+        Ok(Chunk {
+            length,
+            c_type,
+            data,
+            crc,
+        })
     }
 }
 
-impl<'a> Iterator for ChunkDecoder<'a> {
-    type Item = Chunk;
+impl<R: std::io::Read> Iterator for ChunkReader<R> {
+    type Item = std::io::Result<Chunk>;
     fn next(&mut self) -> Option<Self::Item> {
-        if self.start < self.data.len() {
-            let length_bytes = self.extract_bytes(SZ_CHUNK_LENGTH)?;
-            let length = u32::from_be_bytes(length_bytes.try_into().ok()?);
-            let c_type: [u8; SZ_CHUNK_COLOR_TYPE] =
-                self.extract_bytes(SZ_CHUNK_COLOR_TYPE)?.try_into().ok()?;
-            let data: Vec<u8> = self.extract_bytes(length as usize)?.try_into().ok()?;
-            let crc: [u8; SZ_CHUNK_CRC] = self.extract_bytes(SZ_CHUNK_CRC)?.try_into().ok()?;
-            Some(Chunk {
-                length,
-                c_type,
-                data,
-                crc,
-            })
-        } else {
-            None
+        match self.read_chunk() {
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(e) if e.kind() == std::io::ErrorKind::UnexpectedEof => None,
+            Err(e) => Some(Err(e)),
         }
     }
 }
 
+/// Helper: checks the chunk ordering rules of the PNG spec as chunks are read
+/// <https://www.w3.org/TR/png/#5ChunkOrdering>
+#[derive(Default, Debug)]
+struct ChunkOrder {
+    count: usize,
+    plte: bool,
+    trns: bool,
+    idat: bool,
+    idat_ended: bool,
+}
+
+impl ChunkOrder {
+    /// Check that a chunk of the given type may come next
+    fn check(&mut self, c_type: &[u8; 4]) -> Result<(), PNGError> {
+        #[allow(non_upper_case_globals)]
+        let violation = match c_type {
+            IHDR_TYPE if self.count > 0 => Some("IHDR must appear only once"),
+            _ if self.count == 0 && c_type != IHDR_TYPE => Some("IHDR must be the first chunk"),
+            PLTE_TYPE if self.plte => Some("PLTE must appear only once"),
+            PLTE_TYPE if self.idat => Some("PLTE must come before IDAT"),
+            PLTE_TYPE if self.trns => Some("PLTE must come before tRNS"),
+            tRNS_TYPE if self.idat => Some("tRNS must come before IDAT"),
+            IDAT_TYPE if self.idat_ended => Some("IDAT chunks must be consecutive"),
+            _ => None,
+        };
+        if let Some(violation) = violation {
+            return Err(PNGError::ParssingError(format!(
+                "Invalid chunk order, {}",
+                violation
+            )));
+        }
+
+        self.count += 1;
+        #[allow(non_upper_case_globals)]
+        match c_type {
+            PLTE_TYPE => self.plte = true,
+            tRNS_TYPE => self.trns = true,
+            IDAT_TYPE => self.idat = true,
+            _ if self.idat => self.idat_ended = true,
+            _ => {}
+        }
+        Ok(())
+    }
+}
+
 /////////////////////////////////////////////////////////////
 //         Decoding rows                                   //
 /////////////////////////////////////////////////////////////
@@ -617,15 +1386,11 @@ impl<'a> Iterator for RowDecoder<'a> {
             let filter_type = self.extract_bytes(1)?[0];
             let mut current_row = self.extract_bytes(row_length)?.to_vec();
             let previous_row = &self.previous_row;
-            let error = remove_filter(
-                &mut current_row,
-                previous_row,
-                FilterType::from(filter_type),
-                self.bpp,
-            );
-
-            if let Err(e) = error {
-                return Some(Err(e));
+            // rows with an unknown filter type are left as they are
+            if let Ok(filter) = FilterType::try_from(filter_type) {
+                if let Err(e) = remove_filter(&mut current_row, previous_row, filter, self.bpp) {
+                    return Some(Err(e));
+                }
             }
             self.previous_row = current_row.clone();
             Some(Ok(current_row))
@@ -645,8 +1410,8 @@ impl From<PngHeader> for Chunk {
         data.extend_from_slice(&ihdr.width.to_be_bytes());
         data.extend_from_slice(&ihdr.height.to_be_bytes());
 
-        data.push(ihdr.bit_depth);
-        data.push(ihdr.color_type);
+        data.push(ihdr.bit_depth as u8);
+        data.push(ihdr.color_type as u8);
         data.push(ihdr.compression);
         data.push(ihdr.filter);
         data.push(ihdr.interlace);
@@ -785,12 +1550,6 @@ const CRC_TABLE: [u32; 256] = [
     3009837614, 3294710456, 1567103746, 711928724, 3020668471, 3272380065, 1510334235, 755167117,
 ];
 
-const GRAY_SCALE_CTYPE: u8 = 0;
-const RGB_CTYPE: u8 = 2;
-const PALETTE_INDEX_CTYPE: u8 = 3;
-const GREY_SCALE_ALPHA_CTYPE: u8 = 4;
-const RGB_ALPHA_CTYPE: u8 = 6;
-
 /// Size of a IHDR struct
 const SZ_IHDR: usize = 13;
 
@@ -805,6 +1564,28 @@ pub const tRNS_TYPE: &[u8; 4] = b"tRNS";
 #[allow(non_upper_case_globals)]
 pub const gAMA_TYPE: &[u8; 4] = b"gAMA";
 
+#[allow(non_upper_case_globals)]
+pub const pHYs_TYPE: &[u8; 4] = b"pHYs";
+
+// Animated PNG (APNG) chunk types
+#[allow(non_upper_case_globals)]
+pub const acTL_TYPE: &[u8; 4] = b"acTL";
+#[allow(non_upper_case_globals)]
+pub const fcTL_TYPE: &[u8; 4] = b"fcTL";
+#[allow(non_upper_case_globals)]
+pub const fdAT_TYPE: &[u8; 4] = b"fdAT";
+
+/// fcTL dispose operations, what happens to the frame's region before the next frame
+const APNG_DISPOSE_OP_BACKGROUND: u8 = 1;
+const APNG_DISPOSE_OP_PREVIOUS: u8 = 2;
+/// fcTL blend operation compositing the frame over the output (0 replaces it)
+const APNG_BLEND_OP_OVER: u8 = 1;
+
+/// pHYs unit specifier for pixels per meter
+const PHYS_UNIT_METER: u8 = 1;
+/// Inches per meter, to convert pixels per meter into DPI
+const INCHES_PER_METER: f32 = 39.370_08;
+
 /////////////////////////////////////////////////////////////////////////////////////////
 // Helper functions useful when decoding                                               //
 /////////////////////////////////////////////////////////////////////////////////////////
@@ -822,8 +1603,14 @@ fn parse_ihdr_data(data: &[u8]) -> Result<PngHeader, Box<dyn Error>> {
         // Parse each field for the IHDR header
         let width = u32::from_be_bytes(data[0..4].try_into()?);
         let height = u32::from_be_bytes(data[4..8].try_into()?);
-        let bit_depth = data[8];
-        let color_type = data[9];
+        let bit_depth = BitDepth::try_from(data[8])?;
+        let color_type = PngColorType::try_from(data[9])?;
+        if !color_type.allows(bit_depth) {
+            return Err(Box::new(PNGError::ParssingError(format!(
+                "Invalid color type bit depth combination: c: {:?}, bd: {:?}",
+                color_type, bit_depth
+            ))));
+        }
         let compression = data[10];
         let filter = data[11];
         let interlace = data[12];
@@ -840,7 +1627,7 @@ fn parse_ihdr_data(data: &[u8]) -> Result<PngHeader, Box<dyn Error>> {
 }
 
 #[allow(dead_code, unused_variables)]
-fn update_crc(crc: u32, data: &[u8]) -> u32 {
+pub(crate) fn update_crc(crc: u32, data: &[u8]) -> u32 {
     let mut c = crc;
 
     for n in 0..data.len() {
@@ -856,20 +1643,6 @@ fn crc(data: &[u8]) -> [u8; 4] {
     (update_crc(0xffffffff_u32, data) ^ 0xffffffff_u32).to_be_bytes()
 }
 
-/// validate the given bit depth for the IHDR header chunk
-#[doc(hidden)]
-#[allow(dead_code, unused_variables)]
-fn valid_bit_depth(color_type: u8, value: u8) -> bool {
-    match color_type {
-        GRAY_SCALE_CTYPE => vec![1, 2, 4, 8, 16].contains(&value),
-        RGB_CTYPE => vec![8, 12].contains(&value),
-        PALETTE_INDEX_CTYPE => vec![1, 2, 4, 8].contains(&value),
-        GREY_SCALE_ALPHA_CTYPE => vec![8, 16].contains(&value),
-        RGB_ALPHA_CTYPE => vec![8, 16].contains(&value),
-        _ => false,
-    }
-}
-
 /// Remove the applied filters from the picture
 /// Different filter algortims are supported.
 ///
@@ -908,50 +1681,160 @@ fn remove_filter(
             }
         }
         FilterType::Up => {
-            for j in 0..current_row.len() {
-                current_row[j] = current_row[j].wrapping_add(previous_row[j]);
+            for (current, above) in current_row.iter_mut().zip(previous_row) {
+                *current = current.wrapping_add(*above);
             }
         }
         FilterType::Average => {
             for j in 0..current_row.len() {
                 let mut a = 0;
                 let b = previous_row[j];
-                if j > bpp {
+                if j >= bpp {
                     a = current_row[j - bpp];
                 }
                 current_row[j] = current_row[j].wrapping_add(((a as u32 + b as u32) / 2) as u8);
             }
         }
         FilterType::Paeth => {
-            for j in 0..bpp {
-                current_row[j] =
-                    current_row[j].wrapping_add(paeth_predictor(0, previous_row[j], 0));
-            }
-            for j in bpp..current_row.len() {
-                let a = current_row[j - bpp];
-                let b = previous_row[j];
-                let c = previous_row[j - bpp];
-                current_row[j] = current_row[j].wrapping_add(paeth_predictor(a, b, c));
+            // one loop per pixel size, see unfilter_paeth
+            match bpp {
+                1 => unfilter_paeth::<1>(current_row, previous_row),
+                2 => unfilter_paeth::<2>(current_row, previous_row),
+                3 => unfilter_paeth::<3>(current_row, previous_row),
+                4 => unfilter_paeth::<4>(current_row, previous_row),
+                6 => unfilter_paeth::<6>(current_row, previous_row),
+                8 => unfilter_paeth::<8>(current_row, previous_row),
+                _ => {
+                    for j in 0..bpp.min(current_row.len()) {
+                        current_row[j] =
+                            current_row[j].wrapping_add(paeth_predictor(0, previous_row[j], 0));
+                    }
+                    for j in bpp..current_row.len() {
+                        let a = current_row[j - bpp];
+                        let b = previous_row[j];
+                        let c = previous_row[j - bpp];
+                        current_row[j] = current_row[j].wrapping_add(paeth_predictor(a, b, c));
+                    }
+                }
             }
         }
         // no change
         FilterType::None => {}
-
-        // Invalid filter, don't error just assume "No filter"
-        _ => {}
     }
 
     Ok(())
 }
 
+/// Apply a filter to a row of the picture, the inverse of remove_filter
+/// Each byte becomes its difference with the prediction from the unfiltered bytes on its
+/// left (`bpp` bytes before) and above (same position in `previous_row`).
+/// # Arguments
+/// `row`           unfiltered row
+/// `previous_row`  unfiltered row above, zeros for the first row
+/// `filter`        filter to apply
+/// `bpp`           bytes per pixel (at least one)
+fn apply_filter(row: &[u8], previous_row: &[u8], filter: FilterType, bpp: usize) -> Vec<u8> {
+    let left = |j: usize| if j >= bpp { row[j - bpp] } else { 0 };
+    let upper_left = |j: usize| if j >= bpp { previous_row[j - bpp] } else { 0 };
+    row.iter()
+        .zip(previous_row)
+        .enumerate()
+        .map(|(j, (&x, &above))| {
+            let prediction = match filter {
+                FilterType::Sub => left(j),
+                FilterType::Up => above,
+                FilterType::Average => ((left(j) as u16 + above as u16) / 2) as u8,
+                FilterType::Paeth => paeth_predictor(left(j), above, upper_left(j)),
+                FilterType::None => 0,
+            };
+            x.wrapping_sub(prediction)
+        })
+        .collect()
+}
+
+/// Color type and bit depth combinations decode_pixels handles, for error messages
+const SUPPORTED_FORMATS: &str = "gray scale 1, 2, 4 or 8 bits, RGB 8 or 16 bits, \
+    indexed 1, 2, 4 or 8 bits, gray scale with alpha 8 or 16 bits, RGBA 8 or 16 bits";
+
+/// Helper: convert unfiltered image data (a row or the whole image) into colors
+/// `plte` must already have the tRNS alpha values applied, `key` is the tRNS
+/// transparent color for gray scale and RGB images.
+/// Fails for formats that are not decoded (see SUPPORTED_FORMATS) and indexed images
+/// without a palette.
+fn decode_pixels(
+    header: &PngHeader,
+    plte: Option<&Plte>,
+    key: Option<Color>,
+    image_data: &[u8],
+) -> Result<Vec<Color>, PNGError> {
+    let unsupported = || {
+        PNGError::DataError(format!(
+            "unsupported bit depth {} for color type {} (supported: {})",
+            header.bit_depth.bits(),
+            header.color_type as u8,
+            SUPPORTED_FORMATS
+        ))
+    };
+    let mut pixels = match header.color_type {
+        PngColorType::Grayscale => match header.bit_depth {
+            BitDepth::One => gray_scale_one_bit(image_data),
+            BitDepth::Two => gray_scale_two_bits(image_data),
+            BitDepth::Four => gray_scale_four_bits(image_data),
+            BitDepth::Eight => gray_scale_eight_bits(image_data),
+            // TODO handle 16 bits
+            BitDepth::Sixteen => return Err(unsupported()),
+        },
+        PngColorType::Rgb => match header.bit_depth {
+            BitDepth::Eight => rgb_eight_bits(image_data),
+            BitDepth::Sixteen => rgb_sixteen_bits(image_data),
+            _ => return Err(unsupported()),
+        },
+        PngColorType::Indexed => match plte {
+            Some(plte) => match header.bit_depth {
+                BitDepth::One => palette_index_one_bit(image_data, plte),
+                BitDepth::Two => palette_index_two_bits(image_data, plte),
+                BitDepth::Four => palette_index_four_bits(image_data, plte),
+                BitDepth::Eight => palette_index_eight_bits(image_data, plte),
+                _ => return Err(unsupported()),
+            },
+            None => {
+                return Err(PNGError::DataError(
+                    "Missing PLTE chunk for an indexed color image".into(),
+                ))
+            }
+        },
+        PngColorType::GrayscaleAlpha => match header.bit_depth {
+            BitDepth::Eight => gray_scale_with_alpha_eight_bits(image_data),
+            BitDepth::Sixteen => gray_scale_with_alpha_sixteen_bits(image_data),
+            _ => return Err(unsupported()),
+        },
+        PngColorType::Rgba => match header.bit_depth {
+            BitDepth::Eight => rgba_eight_bits(image_data),
+            BitDepth::Sixteen => rgba_sixteen_bits(image_data),
+            _ => return Err(unsupported()),
+        },
+    };
+
+    // simple transparency for gray scale and RGB images
+    if let Some(key) = key {
+        pixels
+            .iter_mut()
+            .filter(|color| **color == key)
+            .for_each(|color| color.set_alpha(0));
+    }
+    Ok(pixels)
+}
+
 /// Paeth filer predictor function
 /// <https://www.w3.org/TR/REC-png-961001#R.Filtering>
+/// p - a, p - b and p - c simplify to b - c, a - c and a + b - 2c, all fit in an i16.
 #[doc(hidden)]
+#[inline(always)]
 fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
-    let p = a as i32 + b as i32 - c as i32;
-    let pa = (p - a as i32).abs();
-    let pb = (p - b as i32).abs();
-    let pc = (p - c as i32).abs();
+    let (a16, b16, c16) = (a as i16, b as i16, c as i16);
+    let pa = (b16 - c16).abs();
+    let pb = (a16 - c16).abs();
+    let pc = (a16 + b16 - 2 * c16).abs();
     if pa <= pb && pa <= pc {
         a
     } else if pb <= pc {
@@ -961,6 +1844,34 @@ fn paeth_predictor(a: u8, b: u8, c: u8) -> u8 {
     }
 }
 
+/// Helper: undo the Paeth filter of a row with `BPP` bytes per pixel
+/// Rows hold whole pixels, walking them as fixed size windows lets the compiler keep the
+/// left (a) and upper left (c) pixels in registers and drop the bounds checks.
+fn unfilter_paeth<const BPP: usize>(current_row: &mut [u8], previous_row: &[u8]) {
+    let len = current_row.len().min(previous_row.len());
+    if len < BPP {
+        return;
+    }
+    // first pixel: no left neighbours, the predictor is the byte above
+    let mut a = [0_u8; BPP];
+    let mut c = [0_u8; BPP];
+    for i in 0..BPP {
+        current_row[i] = current_row[i].wrapping_add(previous_row[i]);
+        a[i] = current_row[i];
+        c[i] = previous_row[i];
+    }
+    let pixels = current_row[BPP..len].chunks_exact_mut(BPP);
+    let above = previous_row[BPP..len].chunks_exact(BPP);
+    for (pixel, above) in pixels.zip(above) {
+        for i in 0..BPP {
+            let b = above[i];
+            pixel[i] = pixel[i].wrapping_add(paeth_predictor(a[i], b, c[i]));
+            a[i] = pixel[i];
+            c[i] = b;
+        }
+    }
+}
+
 /// convert image data into a vector of colors for RGBA with 8 bits of depth
 fn rgba_eight_bits(image_data: &[u8]) -> Vec<Color> {
     let mut pixels = Vec::new();
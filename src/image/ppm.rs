@@ -0,0 +1,225 @@
+use crate::color::Color;
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////////////////////////
+//         Decoded Netpbm Image                         //
+//////////////////////////////////////////////////////////
+
+/// A decoded PPM (P6) or PGM (P5) image
+#[derive(Default, Debug, Clone)]
+pub struct PpmImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// Implementation of PpmImage functionalities
+///
+/// Example usage:
+///    let mut file = std::fs::File::open("image.ppm").unwrap();
+///    let image = PpmReader::read(&mut file).unwrap();
+///    let pixels : Vec<Color> = image.pixels();
+impl PpmImage {
+    /// Get PPM Image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get PPM Image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// collect all the pixels in this image as a vector of pixels
+    pub fn pixels(&self) -> Vec<Color> {
+        self.pixels.clone()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Read PPM From File                                                                  //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// PPM/PGM Reader
+/// Supports the binary formats P6 (RGB) and P5 (gray scale) with 8 or 16 bit samples.
+///
+/// # Example:
+/// let mut file = std::fs::File::open("image.ppm").unwrap();
+/// let image : PpmImage = PpmReader::read(&mut file).unwrap();
+pub struct PpmReader;
+
+impl PpmReader {
+    /// Read a PPM or PGM file into a PpmImage structure
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    pub fn read(image_file: &mut impl std::io::Read) -> Result<PpmImage, Box<dyn Error>> {
+        let mut data = Vec::<u8>::new();
+        image_file.read_to_end(&mut data)?;
+
+        let channels = match data.get(0..2) {
+            Some(b"P6") => 3,
+            Some(b"P5") => 1,
+            _ => {
+                return Err(Box::new(PPMError::ParsingError(
+                    "Not a binary PPM or PGM image".into(),
+                )))
+            }
+        };
+
+        let mut header = HeaderParser {
+            data: &data,
+            pos: 2,
+        };
+        let width = header.next_value()?;
+        let height = header.next_value()?;
+        let max_value = header.next_value()?;
+        if max_value == 0 || max_value > u16::MAX as u32 {
+            return Err(Box::new(PPMError::ParsingError(format!(
+                "Invalid maximum value {}",
+                max_value
+            ))));
+        }
+        // a single whitespace separates the header from the samples
+        let start = header.pos + 1;
+
+        let sample_size = if max_value > 255 { 2 } else { 1 };
+        // the size comes from the header, it may not even fit in memory
+        let samples = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|count| count.checked_mul(channels * sample_size))
+            .and_then(|length| data.get(start..start.checked_add(length)?))
+            .ok_or_else(|| PPMError::DataError("Not enough pixel data".into()))?;
+
+        let scale = |sample: &[u8]| {
+            let value = match sample {
+                [high, low] => u16::from_be_bytes([*high, *low]) as u32,
+                _ => sample[0] as u32,
+            };
+            ((value.min(max_value) * 255 + max_value / 2) / max_value) as u8
+        };
+        let pixels = samples
+            .chunks_exact(channels * sample_size)
+            .map(|pixel| {
+                let values: Vec<u8> = pixel.chunks_exact(sample_size).map(scale).collect();
+                match values[..] {
+                    [r, g, b] => Color::rgb(r, g, b),
+                    _ => Color::rgb(values[0], values[0], values[0]),
+                }
+            })
+            .collect();
+
+        Ok(PpmImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Write PPM To File                                                                   //
+/////////////////////////////////////////////////////////////////////////////////////////
+/// A PPM/PGM Image writer
+/// Alpha is dropped since neither format supports it.
+///
+/// # Example
+/// ```no_run
+/// # use graphics::color::Color;
+/// # use graphics::image::ppm::PpmWriter;
+/// let pixels = vec![Color::RED; 100 * 100];
+/// let mut file = std::fs::File::create("image.ppm").unwrap();
+/// let writer = PpmWriter::new(100, 100, &pixels).unwrap();
+/// writer.write(&mut file).unwrap();
+/// ```
+pub struct PpmWriter<'a> {
+    width: u32,
+    height: u32,
+    pixels: &'a [Color],
+}
+
+impl<'a> PpmWriter<'a> {
+    pub fn new(width: u32, height: u32, pixels: &'a [Color]) -> Result<Self, PPMError> {
+        if (width as usize).checked_mul(height as usize) == Some(pixels.len()) {
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        } else {
+            Err(PPMError::DataError("Invalid image size".to_owned()))
+        }
+    }
+
+    /// Write the pixels as a binary RGB PPM (P6)
+    pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        let mut samples = Vec::with_capacity(self.pixels.len() * 3);
+        for color in self.pixels {
+            samples.extend_from_slice(&[color.r(), color.g(), color.b()]);
+        }
+        writer.write_all(&samples)
+    }
+
+    /// Write the pixels as a binary gray scale PGM (P5) using their luma (Rec. 601)
+    pub fn write_grayscale(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        write!(writer, "P5\n{} {}\n255\n", self.width, self.height)?;
+        let samples: Vec<u8> = self
+            .pixels
+            .iter()
+            .map(|c| {
+                (0.299 * c.r() as f32 + 0.587 * c.g() as f32 + 0.114 * c.b() as f32 + 0.5) as u8
+            })
+            .collect();
+        writer.write_all(&samples)
+    }
+}
+
+/// Generic Error type for errors related parsing PPM images
+#[derive(Debug, Clone)]
+pub enum PPMError {
+    DataError(String),
+    ParsingError(String),
+}
+
+impl Error for PPMError {}
+impl fmt::Display for PPMError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PPMError::DataError(msg) => write!(f, "PPM Error: Invalid data. {}", msg),
+            PPMError::ParsingError(msg) => write!(f, "PPM Error: Could not parse image. {}", msg),
+        }
+    }
+}
+
+/// Reads the ASCII numbers of a Netpbm header skipping whitespace and comments
+struct HeaderParser<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> HeaderParser<'a> {
+    fn next_value(&mut self) -> Result<u32, PPMError> {
+        // skip whitespace and comments (from # to the end of the line)
+        while let Some(byte) = self.data.get(self.pos) {
+            match byte {
+                b'#' => {
+                    while !matches!(self.data.get(self.pos), Some(b'\n') | None) {
+                        self.pos += 1;
+                    }
+                }
+                byte if byte.is_ascii_whitespace() => self.pos += 1,
+                _ => break,
+            }
+        }
+
+        let start = self.pos;
+        while matches!(self.data.get(self.pos), Some(byte) if byte.is_ascii_digit()) {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.data[start..self.pos])
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or_else(|| PPMError::ParsingError("Invalid header".into()))
+    }
+}
@@ -0,0 +1,124 @@
+//! Color quantization
+//!
+//! Reduce the colors of an image to a small palette, useful for indexed
+//! image formats (GIF, palette PNG) and dithering.
+//!
+//! Example usage:
+//!    let palette = median_cut(&pixels, 16);
+//!    let indices: Vec<u8> = pixels.iter().map(|c| nearest_index(&palette, *c)).collect();
+//!
+use crate::color::Color;
+use std::collections::HashMap;
+
+/// Reduce a list of colors to a palette of at most `max_colors` (up to 256) using median cut
+/// Each box of colors is split at the median of its widest channel until there are
+/// enough boxes, the palette is the average of each box weighted by how often each color
+/// appears. Alpha is ignored, all palette colors are opaque.
+/// If the pixels have no more than `max_colors` distinct colors those are returned as is.
+///
+/// # Arguments
+/// `pixels`        colors to quantize
+/// `max_colors`    maximum number of colors in the palette
+pub fn median_cut(pixels: &[Color], max_colors: usize) -> Vec<Color> {
+    let max_colors = max_colors.clamp(1, 256);
+    let mut counts = HashMap::<u32, usize>::new();
+    for color in pixels {
+        *counts.entry(rgb_key(color)).or_insert(0) += 1;
+    }
+    let mut unique: Vec<(u32, usize)> = counts.into_iter().collect();
+    unique.sort_unstable();
+
+    if unique.len() <= max_colors {
+        return unique.iter().map(|(key, _)| key_color(*key)).collect();
+    }
+
+    let mut boxes = vec![unique];
+    while boxes.len() < max_colors {
+        // widest box that can still be split
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, colors)| colors.len() > 1)
+            .map(|(i, colors)| (i, widest_channel(colors)))
+            .max_by_key(|(_, (_, range))| *range);
+        let (index, (channel, _)) = match widest {
+            Some(widest) => widest,
+            None => break,
+        };
+
+        let mut colors = boxes.swap_remove(index);
+        colors.sort_unstable_by_key(|(key, _)| channel_value(*key, channel));
+        let upper = colors.split_off(colors.len() / 2);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    boxes.iter().map(|colors| average_color(colors)).collect()
+}
+
+/// Index of the palette entry closest to a color (euclidean distance in RGB)
+/// Returns 0 for an empty palette.
+///
+/// # Arguments
+/// `palette`   palette of up to 256 colors
+/// `color`     color to look up
+pub fn nearest_index(palette: &[Color], color: Color) -> u8 {
+    let distance = |other: &Color| {
+        let dr = other.r() as i32 - color.r() as i32;
+        let dg = other.g() as i32 - color.g() as i32;
+        let db = other.b() as i32 - color.b() as i32;
+        dr * dr + dg * dg + db * db
+    };
+    palette
+        .iter()
+        .enumerate()
+        .min_by_key(|(_, other)| distance(other))
+        .map(|(i, _)| i as u8)
+        .unwrap_or(0)
+}
+
+/// Helper: the channel (0 = red, 1 = green, 2 = blue) with the largest range and that range
+fn widest_channel(colors: &[(u32, usize)]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let values = colors.iter().map(|(key, _)| channel_value(*key, channel));
+            let min = values.clone().min().unwrap_or(0);
+            let max = values.max().unwrap_or(0);
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+/// Helper: average of a box of colors weighted by how often each one appears
+fn average_color(colors: &[(u32, usize)]) -> Color {
+    let mut sums = [0_usize; 3];
+    let mut total = 0;
+    for (key, count) in colors {
+        for (channel, sum) in sums.iter_mut().enumerate() {
+            *sum += channel_value(*key, channel) as usize * count;
+        }
+        total += count;
+    }
+    let total = total.max(1);
+    Color::rgb(
+        ((sums[0] + total / 2) / total) as u8,
+        ((sums[1] + total / 2) / total) as u8,
+        ((sums[2] + total / 2) / total) as u8,
+    )
+}
+
+/// Helper: pack the RGB components of a color (alpha is ignored)
+fn rgb_key(color: &Color) -> u32 {
+    (color.r() as u32) << 16 | (color.g() as u32) << 8 | color.b() as u32
+}
+
+/// Helper: unpack a color packed with rgb_key
+fn key_color(key: u32) -> Color {
+    Color::rgb((key >> 16) as u8, (key >> 8) as u8, key as u8)
+}
+
+/// Helper: a single channel of a color packed with rgb_key
+fn channel_value(key: u32, channel: usize) -> u8 {
+    (key >> (16 - 8 * channel)) as u8
+}
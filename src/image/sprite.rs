@@ -1,17 +1,37 @@
 use crate::color::Color;
+use crate::image::filter;
 use crate::image::png::PngImage;
 use crate::math::Point2D;
+use std::convert::TryFrom;
 use std::error::Error;
+use std::ops::{Index, IndexMut};
 
 use super::png::PngReader;
 
 #[derive(Debug, Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Sprite {
     pub width: usize,
     pub height: usize,
     pub pixels: Vec<Color>,
 }
 
+/// Decode a whole PNG image into a sprite
+///
+/// Example usage:
+///    let image = PngReader::read(&mut file)?;
+///    let sprite = Sprite::try_from(&image)?;
+impl TryFrom<&PngImage> for Sprite {
+    type Error = Box<dyn Error>;
+    fn try_from(image: &PngImage) -> Result<Self, Self::Error> {
+        Ok(Sprite {
+            width: image.width() as usize,
+            height: image.height() as usize,
+            pixels: image.pixels()?,
+        })
+    }
+}
+
 impl Sprite {
     pub fn get_pixel(&self, x: usize, y: usize) -> Option<Color> {
         if x >= self.width || y >= self.height {
@@ -21,6 +41,314 @@ impl Sprite {
             self.pixels.get(normalized_position).copied()
         }
     }
+
+    /// Mutable reference to the pixel at (x, y), None when outside of the sprite
+    pub fn get_pixel_mut(&mut self, x: usize, y: usize) -> Option<&mut Color> {
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            self.pixels.get_mut(y * self.width + x)
+        }
+    }
+
+    /// Change the pixel at (x, y), returns false (and changes nothing) when outside of
+    /// the sprite
+    pub fn set_pixel(&mut self, x: usize, y: usize, color: Color) -> bool {
+        match self.get_pixel_mut(x, y) {
+            Some(pixel) => {
+                *pixel = color;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Create a sprite from raw RGBA bytes, 4 bytes per pixel row by row
+    /// # Arguments
+    /// `width`     sprite width in pixels
+    /// `height`    sprite height in pixels
+    /// `bytes`     pixel data, must be exactly width * height * 4 bytes long
+    pub fn from_rgba(width: usize, height: usize, bytes: &[u8]) -> Result<Sprite, Box<dyn Error>> {
+        let expected = width
+            .checked_mul(height)
+            .and_then(|size| size.checked_mul(4));
+        if expected != Some(bytes.len()) {
+            return Err(format!(
+                "Invalid RGBA data size for a {}x{} sprite: {} bytes",
+                width,
+                height,
+                bytes.len()
+            )
+            .into());
+        }
+        Ok(Sprite {
+            width,
+            height,
+            pixels: bytes.chunks_exact(4).map(Color::from_slice).collect(),
+        })
+    }
+
+    /// Raw RGBA bytes of the sprite, 4 bytes per pixel row by row
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        self.pixels
+            .iter()
+            .for_each(|color| bytes.extend_from_slice(&color.as_bytes()));
+        bytes
+    }
+
+    /// Create a copy of the sprite with a function applied to every pixel
+    /// # Example
+    /// let gray = sprite.map_colors(|color| color.grayscale());
+    pub fn map_colors(&self, f: impl Fn(Color) -> Color) -> Sprite {
+        Sprite {
+            width: self.width,
+            height: self.height,
+            pixels: self.pixels.iter().map(|color| f(*color)).collect(),
+        }
+    }
+
+    /// Alpha blend another sprite on top of this one ("source over")
+    /// Pixels of `top` falling outside of this sprite are ignored.
+    /// # Arguments
+    /// `top`   sprite drawn over this one
+    /// `at`    position of the top left corner of `top` within this sprite
+    pub fn compose_over(&mut self, top: &Sprite, at: Point2D) {
+        for row in 0..top.height {
+            let y = at.y() as i64 + row as i64;
+            if y < 0 || y >= self.height as i64 {
+                continue;
+            }
+            for column in 0..top.width {
+                let x = at.x() as i64 + column as i64;
+                if x < 0 || x >= self.width as i64 {
+                    continue;
+                }
+                let index = y as usize * self.width + x as usize;
+                let color = top.pixels[row * top.width + column];
+                self.pixels[index] = color.composite_over(self.pixels[index]);
+            }
+        }
+    }
+
+    /// Create a copy of the sprite multiplied channel by channel by a color, keeps alpha
+    /// White leaves the sprite unchanged, useful for damage flashes or team colors.
+    /// # Arguments
+    /// `tint`  color to multiply every pixel by
+    pub fn tinted(&self, tint: Color) -> Sprite {
+        self.map_colors(|color| {
+            let mut tinted = color.difuse(&tint);
+            tinted.set_alpha(color.alpha());
+            tinted
+        })
+    }
+
+    /// Create a copy of the sprite with every channel scaled, keeps alpha
+    /// # Arguments
+    /// `factor`    1.0 = unchanged, 0.0 = black, values above 1.0 brighten (saturating)
+    pub fn brightened(&self, factor: f32) -> Sprite {
+        self.map_colors(|color| {
+            let mut scaled = color * factor.max(0.0);
+            scaled.set_alpha(color.alpha());
+            scaled
+        })
+    }
+
+    /// Create a blurred copy of the sprite using a box blur
+    /// # Arguments
+    /// `radius`    blur radius in pixels
+    pub fn blur(&self, radius: u32) -> Sprite {
+        Sprite {
+            width: self.width,
+            height: self.height,
+            pixels: filter::box_blur(&self.pixels, self.width, self.height, radius),
+        }
+    }
+
+    /// Create a blurred copy of the sprite using a gaussian blur
+    /// # Arguments
+    /// `sigma`     standard deviation of the gaussian in pixels
+    pub fn gaussian_blur(&self, sigma: f32) -> Sprite {
+        Sprite {
+            width: self.width,
+            height: self.height,
+            pixels: filter::gaussian_blur(&self.pixels, self.width, self.height, sigma),
+        }
+    }
+
+    /// Create a resized copy of the sprite using the given filter
+    /// Resampling is done horizontally and then vertically, good for
+    /// downscaling photos (e.g. thumbnails), for pixel art prefer nearest neighbour.
+    /// # Arguments
+    /// `new_width`     width of the resulting sprite
+    /// `new_height`    height of the resulting sprite
+    /// `filter`        resampling kernel
+    pub fn resampled(&self, new_width: usize, new_height: usize, filter: ResampleFilter) -> Sprite {
+        if self.width == 0 || self.height == 0 || new_width == 0 || new_height == 0 {
+            return Sprite {
+                width: new_width,
+                height: new_height,
+                pixels: vec![Color::TRANSPARENT; new_width * new_height],
+            };
+        }
+
+        let source: Vec<[f32; 4]> = self
+            .pixels
+            .iter()
+            .map(|c| c.as_bytes().map(|channel| channel as f32))
+            .collect();
+
+        // horizontal pass: height rows of new_width
+        let columns = filter.weights(self.width, new_width);
+        let mut horizontal = Vec::with_capacity(self.height * new_width);
+        for row in source.chunks_exact(self.width) {
+            for (start, weights) in &columns {
+                horizontal.push(convolve(weights, |i| row[start + i]));
+            }
+        }
+
+        // vertical pass: new_height rows of new_width
+        let rows = filter.weights(self.height, new_height);
+        let mut pixels = Vec::with_capacity(new_width * new_height);
+        for (start, weights) in &rows {
+            for x in 0..new_width {
+                let value = convolve(weights, |i| horizontal[(start + i) * new_width + x]);
+                let [r, g, b, a] = value.map(|channel| (channel + 0.5).clamp(0.0, 255.0) as u8);
+                pixels.push(Color::rgba(r, g, b, a));
+            }
+        }
+
+        Sprite {
+            width: new_width,
+            height: new_height,
+            pixels,
+        }
+    }
+}
+
+/// Pixel at (x, y), the unchecked counterpart of get_pixel for loops that stay in bounds
+/// Panics when x or y is outside of the sprite.
+impl Index<(usize, usize)> for Sprite {
+    type Output = Color;
+    fn index(&self, (x, y): (usize, usize)) -> &Color {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) outside of a {}x{} sprite",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        &self.pixels[y * self.width + x]
+    }
+}
+
+/// Edit the pixel at (x, y) in place, panics when x or y is outside of the sprite
+impl IndexMut<(usize, usize)> for Sprite {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Color {
+        assert!(
+            x < self.width && y < self.height,
+            "pixel ({}, {}) outside of a {}x{} sprite",
+            x,
+            y,
+            self.width,
+            self.height
+        );
+        &mut self.pixels[y * self.width + x]
+    }
+}
+
+/// Kernels available to resample sprites
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ResampleFilter {
+    /// Linear interpolation (bilinear when upscaling)
+    Triangle,
+    /// Catmull-Rom cubic spline, sharper than Triangle
+    Cubic,
+    /// Windowed sinc with three lobes, sharpest but may ring around edges
+    Lanczos3,
+}
+
+impl ResampleFilter {
+    /// Radius of the kernel in source pixels (when not downscaling)
+    fn support(&self) -> f32 {
+        match self {
+            ResampleFilter::Triangle => 1.0,
+            ResampleFilter::Cubic => 2.0,
+            ResampleFilter::Lanczos3 => 3.0,
+        }
+    }
+
+    /// Kernel value at the given distance
+    fn kernel(&self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            ResampleFilter::Triangle => (1.0 - x).max(0.0),
+            ResampleFilter::Cubic => {
+                // Catmull-Rom (a = -0.5)
+                if x < 1.0 {
+                    1.5 * x * x * x - 2.5 * x * x + 1.0
+                } else if x < 2.0 {
+                    -0.5 * x * x * x + 2.5 * x * x - 4.0 * x + 2.0
+                } else {
+                    0.0
+                }
+            }
+            ResampleFilter::Lanczos3 => {
+                if x < 3.0 {
+                    sinc(x) * sinc(x / 3.0)
+                } else {
+                    0.0
+                }
+            }
+        }
+    }
+
+    /// Normalized weights of each destination pixel along one axis
+    /// Returns for each destination pixel the first source pixel and the weights from there.
+    fn weights(&self, source_len: usize, dest_len: usize) -> Vec<(usize, Vec<f32>)> {
+        let scale = source_len as f32 / dest_len as f32;
+        // widen the kernel when downscaling so every source pixel contributes
+        let filter_scale = scale.max(1.0);
+        let support = self.support() * filter_scale;
+
+        (0..dest_len)
+            .map(|i| {
+                let center = (i as f32 + 0.5) * scale;
+                let start = (center - support).floor().max(0.0) as usize;
+                let end = ((center + support).ceil() as usize).min(source_len);
+                let mut weights: Vec<f32> = (start..end)
+                    .map(|j| self.kernel((j as f32 + 0.5 - center) / filter_scale))
+                    .collect();
+                let total: f32 = weights.iter().sum();
+                if total != 0.0 {
+                    weights.iter_mut().for_each(|w| *w /= total);
+                }
+                (start, weights)
+            })
+            .collect()
+    }
+}
+
+/// Helper: normalized sinc function
+fn sinc(x: f32) -> f32 {
+    if x == 0.0 {
+        1.0
+    } else {
+        let x = x * std::f32::consts::PI;
+        x.sin() / x
+    }
+}
+
+/// Helper: weighted sum of RGBA values
+fn convolve(weights: &[f32], value: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+    for (i, weight) in weights.iter().enumerate() {
+        for (channel, value) in sum.iter_mut().zip(value(i).iter()) {
+            *channel += weight * value;
+        }
+    }
+    sum
 }
 
 #[derive(Debug, Default, Clone)]
@@ -155,3 +483,186 @@ impl Iterator for SpriteExtractor {
         sprite
     }
 }
+
+/// How an animation continues once it reaches its last frame
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AnimationMode {
+    /// Stop on the last frame
+    Once,
+    /// Start over from the first frame
+    Loop,
+    /// Play backwards to the first frame and then forward again
+    PingPong,
+}
+
+/// A sequence of sprites with a duration for each of them
+/// Purely logical, draw `current_frame()` with `Canvas::sprite`.
+///
+/// Example usage:
+///    let frames: Vec<Sprite> = SpriteExtractor::from_file("walk.png", size, 0, 0)?.collect();
+///    let mut walk = SpriteAnimation::new(frames, 0.1, AnimationMode::Loop)?;
+///    walk.advance(delta_t);
+///    canvas.sprite(&position, walk.current_frame());
+#[derive(Debug, Clone)]
+pub struct SpriteAnimation {
+    frames: Vec<Sprite>,
+    durations: Vec<f32>,
+    mode: AnimationMode,
+    current: usize,
+    elapsed: f32,
+    forward: bool,
+    finished: bool,
+}
+
+impl SpriteAnimation {
+    /// Create an animation where every frame lasts the same time
+    /// # Arguments
+    /// `frames`            animation frames, at least one
+    /// `frame_duration`    time each frame is shown, same units as `advance` (e.g. seconds)
+    /// `mode`              what happens after the last frame
+    /// Fails if there are no frames or the duration is not a positive number.
+    pub fn new(
+        frames: Vec<Sprite>,
+        frame_duration: f32,
+        mode: AnimationMode,
+    ) -> Result<Self, Box<dyn Error>> {
+        let durations = vec![frame_duration; frames.len()];
+        Self::with_durations(frames, durations, mode)
+    }
+
+    /// Create an animation with a different duration for each frame
+    /// Fails if there are no frames, the number of durations does not match the number
+    /// of frames or any duration is not a positive number.
+    pub fn with_durations(
+        frames: Vec<Sprite>,
+        durations: Vec<f32>,
+        mode: AnimationMode,
+    ) -> Result<Self, Box<dyn Error>> {
+        if frames.is_empty() {
+            return Err("An animation needs at least one frame".into());
+        }
+        if frames.len() != durations.len() {
+            return Err(format!(
+                "{} durations given for {} frames",
+                durations.len(),
+                frames.len()
+            )
+            .into());
+        }
+        if !durations
+            .iter()
+            .all(|duration| duration.is_finite() && *duration > 0.0)
+        {
+            return Err("Frame durations must be positive numbers".into());
+        }
+        Ok(Self {
+            frames,
+            durations,
+            mode,
+            current: 0,
+            elapsed: 0.0,
+            forward: true,
+            finished: false,
+        })
+    }
+
+    /// Sprite to display right now
+    pub fn current_frame(&self) -> &Sprite {
+        &self.frames[self.current]
+    }
+
+    /// Index of the sprite to display right now
+    pub fn current_index(&self) -> usize {
+        self.current
+    }
+
+    /// Number of frames in the animation
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Always false, animations have at least one frame
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// True once an `AnimationMode::Once` animation reached its last frame
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Go back to the first frame
+    pub fn reset(&mut self) {
+        self.current = 0;
+        self.elapsed = 0.0;
+        self.forward = true;
+        self.finished = false;
+    }
+
+    /// Move the animation forward in time, skipping frames if needed
+    /// Negative, infinite and NaN times are ignored.
+    /// # Arguments
+    /// `delta_t`   time since the last update
+    pub fn advance(&mut self, delta_t: f32) {
+        if self.finished || !delta_t.is_finite() {
+            return;
+        }
+        self.elapsed += delta_t.max(0.0);
+        // repeating animations are back on the same frame after a whole cycle, drop the
+        // cycles first so a huge delta_t does not step through every frame
+        let cycle = self.cycle_duration();
+        if self.elapsed >= cycle {
+            self.elapsed %= cycle;
+        }
+        while !self.finished && self.elapsed >= self.durations[self.current] {
+            self.elapsed -= self.durations[self.current];
+            self.next_frame();
+        }
+    }
+
+    /// Helper time to get back to the current frame (and direction), infinite for
+    /// AnimationMode::Once which never does
+    fn cycle_duration(&self) -> f32 {
+        let total: f32 = self.durations.iter().sum();
+        let (first, last) = (self.durations[0], self.durations[self.durations.len() - 1]);
+        match self.mode {
+            AnimationMode::Once => f32::INFINITY,
+            AnimationMode::Loop => total,
+            // every frame is shown twice per cycle except the two ends
+            AnimationMode::PingPong if self.durations.len() > 1 => 2.0 * total - first - last,
+            AnimationMode::PingPong => total,
+        }
+    }
+
+    /// Helper step to the following frame according to the mode
+    fn next_frame(&mut self) {
+        let last = self.frames.len() - 1;
+        match self.mode {
+            AnimationMode::Once => {
+                if self.current < last {
+                    self.current += 1;
+                }
+                if self.current == last {
+                    self.finished = true;
+                    self.elapsed = 0.0;
+                }
+            }
+            AnimationMode::Loop => self.current = (self.current + 1) % self.frames.len(),
+            AnimationMode::PingPong => {
+                if last == 0 {
+                    return;
+                }
+                if self.forward && self.current == last {
+                    self.forward = false;
+                } else if !self.forward && self.current == 0 {
+                    self.forward = true;
+                }
+                if self.forward {
+                    self.current += 1;
+                } else {
+                    self.current -= 1;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,1558 @@
+use crate::canvas::{Canvas, Origin};
+use crate::color::Color;
+use crate::image::bmp::{BmpReader, BmpWriter};
+use crate::image::dither::floyd_steinberg;
+use crate::image::gif::{lzw_encode, GifWriter};
+use crate::image::ico::IcoWriter;
+use crate::image::jpeg::JpegReader;
+use crate::image::png::{acTL_TYPE, fcTL_TYPE, fdAT_TYPE, tRNS_TYPE};
+use crate::image::png::{BitDepth, Chunk, FilterType, PngColorType, PngReadOptions};
+use crate::image::png::{PngImage, PngReader, PngWriter};
+use crate::image::png::{IDAT_TYPE, IEND_TYPE, IHDR_TYPE, PLTE_TYPE};
+use crate::image::ppm::{PpmReader, PpmWriter};
+use crate::image::quantize::{median_cut, nearest_index};
+use crate::image::sprite::{AnimationMode, ResampleFilter, Sprite, SpriteAnimation, SpriteSize};
+use crate::image::tga::{TgaReader, TgaWriter};
+use crate::image::tilemap::TileMap;
+use crate::image::{convolve, BOX_BLUR_3, SHARPEN, SOBEL_X, SOBEL_Y};
+use crate::math::Point2D;
+use crate::utils::gz;
+use std::convert::TryFrom;
+use std::time::Duration;
+
+const SIGNATURE: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
+
+/// Helper build a 2x1 palette image with the given extra chunks before IDAT
+fn palette_image(chunks: &[Chunk], with_iend: bool) -> Vec<u8> {
+    let mut bytes = SIGNATURE.to_vec();
+    // width 2, height 1, 8 bits, palette index
+    let ihdr = vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 3, 0, 0, 0];
+    Chunk::new(*IHDR_TYPE, ihdr).write_all(&mut bytes).unwrap();
+    for chunk in chunks {
+        chunk.write_all(&mut bytes).unwrap();
+    }
+    let idat = gz::compress_zlib(&[0, 0, 1]);
+    Chunk::new(*IDAT_TYPE, idat).write_all(&mut bytes).unwrap();
+    if with_iend {
+        Chunk::new(*IEND_TYPE, Vec::new())
+            .write_all(&mut bytes)
+            .unwrap();
+    }
+    bytes
+}
+
+#[test]
+fn png_write_read_round_trip() {
+    let pixels = vec![
+        Color::RED,
+        Color::GREEN,
+        Color::BLUE,
+        Color::rgba(10, 20, 30, 40),
+        Color::WHITE,
+        Color::BLACK,
+    ];
+    let mut bytes = Vec::new();
+    PngWriter::new(3, 2, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.width(), 3);
+    assert_eq!(image.height(), 2);
+    assert_eq!(image.pixels().unwrap(), pixels);
+
+    let header = image.header();
+    assert_eq!(header.color_type(), PngColorType::Rgba);
+    assert_eq!(header.bit_depth(), BitDepth::Eight);
+    assert_eq!(header.compression(), 0);
+    assert_eq!(header.filter(), 0);
+    assert_eq!(header.interlace(), 0);
+}
+
+#[test]
+fn png_conversions() {
+    // 2x2, top row red and green, bottom row blue and white
+    let pixels = vec![Color::RED, Color::GREEN, Color::BLUE, Color::WHITE];
+    let mut bytes = Vec::new();
+    PngWriter::new(2, 2, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+
+    let mut reader = &bytes[..];
+    let image = PngImage::try_from(&mut reader as &mut dyn std::io::Read).unwrap();
+    assert_eq!(image.pixels().unwrap(), pixels);
+    let mut truncated = &bytes[..bytes.len() - 12];
+    assert!(PngImage::try_from(&mut truncated as &mut dyn std::io::Read).is_err());
+
+    let sprite = Sprite::try_from(&image).unwrap();
+    assert_eq!((sprite.width, sprite.height), (2, 2));
+    assert_eq!(sprite.pixels, pixels);
+
+    // the canvas keeps the image coordinates with its default origin, where the y = 0 row
+    // is stored in the first buffer row but can't be drawn
+    let canvas = Canvas::try_from(&image).unwrap();
+    assert_eq!((canvas.width(), canvas.height()), (2, 2));
+    assert_eq!(canvas.origin(), Origin::BottomLeft);
+    assert_eq!(canvas.to_sprite().pixels, pixels);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::WHITE));
+    assert_eq!(canvas.pixels.borrow()[..2], [Color::RED, Color::GREEN]);
+}
+
+#[test]
+fn png_missing_iend_is_an_error() {
+    let plte = || Chunk::new(*PLTE_TYPE, vec![255, 0, 0, 0, 0, 255]);
+    let bytes = palette_image(&[plte()], true);
+    assert!(PngReader::read(&mut &bytes[..]).is_ok());
+
+    let bytes = palette_image(&[plte()], false);
+    assert!(PngReader::read(&mut &bytes[..]).is_err());
+}
+
+#[test]
+fn png_bad_crc_options() {
+    let plte = Chunk::new(*PLTE_TYPE, vec![255, 0, 0, 0, 0, 255]);
+    let text = Chunk::new(*b"tEXt", b"Comment\0damaged".to_vec());
+    let mut bytes = palette_image(&[plte, text], true);
+    // last CRC byte of the tEXt chunk: signature, IHDR (25), PLTE (18), tEXt (27)
+    bytes[8 + 25 + 18 + 26] ^= 0xff;
+
+    // lenient by default, the chunk is skipped without any notice
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    assert!(image.non_data_chunks().is_empty());
+    assert!(image.warnings().is_empty());
+
+    let options = PngReadOptions {
+        collect_warnings: true,
+        ..Default::default()
+    };
+    let image = PngReader::read_with_options(&mut &bytes[..], options).unwrap();
+    assert_eq!(image.warnings(), ["Bad CRC on chunk 2 (tEXt)"]);
+    assert_eq!(image.pixels().unwrap(), vec![Color::RED, Color::BLUE]);
+
+    let options = PngReadOptions {
+        strict_crc: true,
+        ..Default::default()
+    };
+    assert!(PngReader::read_with_options(&mut &bytes[..], options).is_err());
+}
+
+/// Helper reader handing out a few bytes per call, like a slow network stream
+struct Trickle<'a>(&'a [u8]);
+
+impl std::io::Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = buf.len().min(self.0.len()).min(3);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn png_rows_streaming() {
+    let (width, height) = (37, 23);
+    let pixels: Vec<Color> = (0..width * height)
+        .map(|i| Color::rgba((i * 7) as u8, (i / 3) as u8, (i % 251) as u8, 200))
+        .collect();
+    let mut bytes = Vec::new();
+    PngWriter::new(width, height, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+
+    let rows: Vec<Vec<Color>> = PngReader::rows(Trickle(&bytes))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(rows.len(), height as usize);
+    assert_eq!(rows.concat(), pixels);
+
+    // image data split over several IDAT chunks, 1 bit gray scale with a padded last byte
+    let data = gz::compress_zlib(&[0, 0b1010_1010, 0b1100_0000, 0, 0xff, 0xff]);
+    let split_image = |pieces: usize| {
+        let mut bytes = SIGNATURE.to_vec();
+        let ihdr = vec![0, 0, 0, 10, 0, 0, 0, 2, 1, 0, 0, 0, 0];
+        Chunk::new(*IHDR_TYPE, ihdr).write_all(&mut bytes).unwrap();
+        for piece in data.chunks(3).take(pieces) {
+            Chunk::new(*IDAT_TYPE, piece.to_vec())
+                .write_all(&mut bytes)
+                .unwrap();
+        }
+        Chunk::new(*IEND_TYPE, Vec::new())
+            .write_all(&mut bytes)
+            .unwrap();
+        bytes
+    };
+    let bytes = split_image(usize::MAX);
+    let rows: Vec<Vec<Color>> = PngReader::rows(&bytes[..])
+        .unwrap()
+        .map(|row| row.unwrap())
+        .collect();
+    let (w, b) = (Color::WHITE, Color::BLACK);
+    assert_eq!(rows[0], vec![w, b, w, b, w, b, w, b, w, w]);
+    assert_eq!(rows[1], vec![w; 10]);
+    // reading the whole image drops the padding the same way
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.pixels().unwrap(), rows.concat());
+
+    // missing image data is reported, 12 bytes hold the zlib and block headers and the first row
+    let bytes = split_image(4);
+    let mut rows = PngReader::rows(&bytes[..]).unwrap();
+    assert!(rows.next().unwrap().is_ok());
+    assert!(rows.next().unwrap().is_err());
+    assert!(rows.next().is_none());
+}
+
+#[test]
+fn png_read_with_callback() {
+    let (width, height) = (19, 11);
+    let pixels: Vec<Color> = (0..width * height)
+        .map(|i| Color::rgb((i * 5) as u8, (i / 2) as u8, 255 - i as u8))
+        .collect();
+    let mut bytes = Vec::new();
+    PngWriter::new(width, height, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+
+    // rows arrive in order while the data trickles in
+    let mut rows: Vec<(u32, Vec<Color>)> = Vec::new();
+    let header = PngReader::read_with_callback(Trickle(&bytes), |index, row| {
+        rows.push((index, row.to_vec()))
+    })
+    .unwrap();
+    assert_eq!(header.bit_depth(), BitDepth::Eight);
+    assert_eq!(rows.len(), height as usize);
+    for (expected, (index, row)) in rows.iter().enumerate() {
+        assert_eq!(*index, expected as u32);
+        assert_eq!(row.len(), width as usize);
+    }
+    let streamed: Vec<Color> = rows.into_iter().flat_map(|(_, row)| row).collect();
+    assert_eq!(streamed, pixels);
+
+    // the batch decoding collects the same rows
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    let mut count = 0;
+    image
+        .pixels_with_callback(|index, row| {
+            assert_eq!(
+                row,
+                &pixels[(index * width) as usize..((index + 1) * width) as usize]
+            );
+            count += 1;
+        })
+        .unwrap();
+    assert_eq!(count, height);
+    assert_eq!(image.pixels().unwrap(), pixels);
+
+    // errors stop the decoding
+    assert!(PngReader::read_with_callback(&bytes[..20], |_, _| ()).is_err());
+}
+
+/// Build a PNG from raw rows, every row filtered with `filter`
+/// The rows are filtered with the predictors written as in the PNG specification, not the
+/// optimized ones of the decoder and PngWriter, so that decoding checks them.
+/// # Arguments
+/// `width`, `height`       image size
+/// `depth`, `color_type`   IHDR bit depth and color type
+/// `bpp`                   bytes per pixel used by the filter (1 below 8 bits)
+/// `filter`                filter applied to every row
+/// `raw`                   unfiltered rows, `height` of them
+fn filtered_png(
+    (width, height): (u32, u32),
+    depth: u8,
+    color_type: u8,
+    bpp: usize,
+    filter: FilterType,
+    raw: &[u8],
+) -> Vec<u8> {
+    // p = a + b - c, the predictor is the neighbour closest to p, ties in order a, b, c
+    let paeth = |a: u8, b: u8, c: u8| {
+        let p = a as i16 + b as i16 - c as i16;
+        let (pa, pb, pc) = (
+            (p - a as i16).abs(),
+            (p - b as i16).abs(),
+            (p - c as i16).abs(),
+        );
+        if pa <= pb && pa <= pc {
+            a
+        } else if pb <= pc {
+            b
+        } else {
+            c
+        }
+    };
+    let row_len = raw.len() / height.max(1) as usize;
+    let mut idat = Vec::with_capacity(raw.len() + height as usize);
+    let mut previous = vec![0; row_len];
+    for row in raw.chunks_exact(row_len.max(1)) {
+        idat.push(filter as u8);
+        for (j, &x) in row.iter().enumerate() {
+            let (a, b) = (if j >= bpp { row[j - bpp] } else { 0 }, previous[j]);
+            let c = if j >= bpp { previous[j - bpp] } else { 0 };
+            let prediction = match filter {
+                FilterType::None => 0,
+                FilterType::Sub => a,
+                FilterType::Up => b,
+                FilterType::Average => ((a as u16 + b as u16) / 2) as u8,
+                FilterType::Paeth => paeth(a, b, c),
+            };
+            idat.push(x.wrapping_sub(prediction));
+        }
+        previous.copy_from_slice(row);
+    }
+
+    let mut ihdr = width.to_be_bytes().to_vec();
+    ihdr.extend_from_slice(&height.to_be_bytes());
+    ihdr.extend_from_slice(&[depth, color_type, 0, 0, 0]);
+    let mut bytes = SIGNATURE.to_vec();
+    for chunk in [
+        Chunk::new(*IHDR_TYPE, ihdr),
+        Chunk::new(*IDAT_TYPE, gz::compress_zlib(&idat)),
+        Chunk::new(*IEND_TYPE, Vec::new()),
+    ] {
+        chunk
+            .write_all(&mut bytes)
+            .expect("writing to a Vec does not fail");
+    }
+    bytes
+}
+
+#[test]
+fn png_paeth_filter_pixel_sizes() {
+    // (bit depth, color type, bytes per pixel), one for every specialized pixel size
+    let formats = [
+        (8, 0, 1),
+        (8, 4, 2),
+        (8, 2, 3),
+        (8, 6, 4),
+        (16, 2, 6),
+        (16, 6, 8),
+        (4, 0, 1),
+    ];
+    let width = 7;
+    for &(depth, color_type, bpp) in formats.iter() {
+        let row_len = if depth < 8 {
+            (width as usize * depth as usize).div_ceil(8)
+        } else {
+            width as usize * bpp
+        };
+        let raw: Vec<u8> = (0..row_len * 4)
+            .map(|i| (i * 37 % 256) as u8 ^ (i / 3) as u8)
+            .collect();
+        let decode = |filter| {
+            let bytes = filtered_png((width, 4), depth, color_type, bpp, filter, &raw);
+            PngReader::read(&mut &bytes[..]).unwrap().pixels().unwrap()
+        };
+        let expected = decode(FilterType::None);
+        assert_eq!(
+            expected.len(),
+            28,
+            "{} bits, color type {}",
+            depth,
+            color_type
+        );
+        assert_eq!(
+            decode(FilterType::Paeth),
+            expected,
+            "{} bits, color type {}",
+            depth,
+            color_type
+        );
+    }
+}
+
+#[test]
+fn png_average_filter() {
+    // 2x1 gray image: the second pixel adds half of the first one (100 / 2) to 60
+    let mut bytes = SIGNATURE.to_vec();
+    let ihdr = vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 0, 0, 0, 0];
+    Chunk::new(*IHDR_TYPE, ihdr).write_all(&mut bytes).unwrap();
+    let idat = gz::compress_zlib(&[3, 100, 60]);
+    Chunk::new(*IDAT_TYPE, idat).write_all(&mut bytes).unwrap();
+    Chunk::new(*IEND_TYPE, Vec::new())
+        .write_all(&mut bytes)
+        .unwrap();
+    let pixels = PngReader::read(&mut &bytes[..]).unwrap().pixels().unwrap();
+    assert_eq!(
+        pixels,
+        vec![Color::rgb(100, 100, 100), Color::rgb(110, 110, 110)]
+    );
+
+    // the byte right after the first pixel uses its left neighbour for every pixel size
+    let width = 5;
+    for &(color_type, bpp) in [(0, 1), (4, 2), (2, 3), (6, 4)].iter() {
+        let raw: Vec<u8> = (0..width as usize * bpp * 4)
+            .map(|i| (i * 53 % 256) as u8)
+            .collect();
+        let decode = |filter| {
+            let bytes = filtered_png((width, 4), 8, color_type, bpp, filter, &raw);
+            PngReader::read(&mut &bytes[..]).unwrap().pixels().unwrap()
+        };
+        assert_eq!(
+            decode(FilterType::Average),
+            decode(FilterType::None),
+            "color type {}",
+            color_type
+        );
+    }
+}
+
+#[test]
+fn png_write_filters() {
+    // gradient with some noise, so every predictor gets picked by Paeth
+    let (width, height) = (13, 9);
+    let pixels: Vec<Color> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            Color::rgba(
+                (x * 19) as u8,
+                (y * 27) as u8,
+                ((x * y * 7) % 256) as u8,
+                (255 - x * 3 - (i % 5) * 11) as u8,
+            )
+        })
+        .collect();
+    let indices: Vec<u8> = (0..width * height).map(|i| (i * 7 % 11) as u8).collect();
+    let palette: Vec<Color> = (0..11).map(|i| Color::rgb(i * 20, 0, 255 - i)).collect();
+
+    let mut encoded = Vec::new();
+    for &filter in [
+        FilterType::None,
+        FilterType::Sub,
+        FilterType::Up,
+        FilterType::Average,
+        FilterType::Paeth,
+    ]
+    .iter()
+    {
+        let mut bytes = Vec::new();
+        let mut writer = PngWriter::new(width, height, &pixels).unwrap();
+        writer.set_filter(filter);
+        writer.write(&mut bytes).unwrap();
+        let image = PngReader::read(&mut &bytes[..]).unwrap();
+        assert_eq!(image.pixels().unwrap(), pixels, "{:?}", filter);
+        encoded.push(bytes);
+
+        let mut bytes = Vec::new();
+        let mut writer = PngWriter::new_indexed(width, height, &indices, &palette).unwrap();
+        writer.set_filter(filter);
+        writer.write(&mut bytes).unwrap();
+        let image = PngReader::read(&mut &bytes[..]).unwrap();
+        let expected: Vec<Color> = indices.iter().map(|&i| palette[i as usize]).collect();
+        assert_eq!(image.pixels().unwrap(), expected, "indexed {:?}", filter);
+        assert_eq!(FilterType::try_from(filter as u8).unwrap(), filter);
+    }
+    assert!(FilterType::try_from(5).is_err());
+    // the filter really changed the stored data
+    for (i, bytes) in encoded.iter().enumerate() {
+        assert!(encoded[i + 1..].iter().all(|other| other != bytes));
+    }
+
+    // smooth gradients compress better once Paeth filtered
+    let (width, height) = (64, 64);
+    let gradient: Vec<Color> = (0..width * height)
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            Color::rgb((x * 3) as u8, (y * 3) as u8, ((x + y) * 2) as u8)
+        })
+        .collect();
+    let size = |filter| {
+        let mut bytes = Vec::new();
+        let mut writer = PngWriter::new(width, height, &gradient).unwrap();
+        writer.set_filter(filter);
+        writer.write(&mut bytes).unwrap();
+        bytes.len()
+    };
+    assert!(size(FilterType::Paeth) < size(FilterType::None));
+}
+
+/// Helper: fcTL chunk data, sequence number, size, offset, delay (ms), dispose and blend ops
+fn fctl(sequence: u32, size: (u32, u32), offset: (u32, u32), delay: u16, ops: (u8, u8)) -> Chunk {
+    let mut data = sequence.to_be_bytes().to_vec();
+    for value in [size.0, size.1, offset.0, offset.1] {
+        data.extend_from_slice(&value.to_be_bytes());
+    }
+    data.extend_from_slice(&delay.to_be_bytes());
+    data.extend_from_slice(&1000_u16.to_be_bytes());
+    data.extend_from_slice(&[ops.0, ops.1]);
+    Chunk::new(*fcTL_TYPE, data)
+}
+
+/// Helper: zlib compressed, unfiltered RGBA rows
+fn rgba_data(rows: &[&[Color]]) -> Vec<u8> {
+    let mut raw = Vec::new();
+    for row in rows {
+        raw.push(0);
+        row.iter()
+            .for_each(|color| raw.extend_from_slice(&color.as_bytes()));
+    }
+    gz::compress_zlib(&raw)
+}
+
+#[test]
+fn png_apng_frames() {
+    let clear = Color::rgba(0, 0, 0, 0);
+    let half_blue = Color::rgba(0, 0, 255, 128);
+    let red = [Color::RED, Color::RED];
+
+    let mut bytes = SIGNATURE.to_vec();
+    let ihdr = vec![0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0];
+    Chunk::new(*IHDR_TYPE, ihdr).write_all(&mut bytes).unwrap();
+    let actl = vec![0, 0, 0, 3, 0, 0, 0, 2];
+    let chunks = [
+        Chunk::new(*acTL_TYPE, actl),
+        // the default image is the first frame, kept under the next one
+        fctl(0, (2, 2), (0, 0), 100, (0, 0)),
+        Chunk::new(*IDAT_TYPE, rgba_data(&[&red, &red])),
+        // half transparent blue blended over the bottom right pixel, then cleared
+        fctl(1, (1, 1), (1, 1), 50, (1, 1)),
+        Chunk::new(
+            *fdAT_TYPE,
+            [&[0, 0, 0, 2][..], &rgba_data(&[&[half_blue]])].concat(),
+        ),
+        // green replacing the top left pixel
+        fctl(3, (1, 1), (0, 0), 250, (0, 0)),
+        Chunk::new(
+            *fdAT_TYPE,
+            [&[0, 0, 0, 4][..], &rgba_data(&[&[Color::GREEN]])].concat(),
+        ),
+        Chunk::new(*IEND_TYPE, Vec::new()),
+    ];
+    for chunk in chunks.iter() {
+        chunk.write_all(&mut bytes).unwrap();
+    }
+
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.plays(), Some(2));
+    assert_eq!(image.pixels().unwrap(), vec![Color::RED; 4]);
+    let frames = image.frames().unwrap();
+    let blended = half_blue.composite_over(Color::RED);
+    assert_eq!(
+        frames,
+        vec![
+            (vec![Color::RED; 4], Duration::from_millis(100)),
+            (
+                vec![Color::RED, Color::RED, Color::RED, blended],
+                Duration::from_millis(50)
+            ),
+            (
+                vec![Color::GREEN, Color::RED, Color::RED, clear],
+                Duration::from_millis(250)
+            ),
+        ]
+    );
+
+    // frames outside of the image are rejected
+    bytes.truncate(SIGNATURE.len() + 25);
+    for chunk in [
+        fctl(0, (2, 2), (1, 0), 100, (0, 0)),
+        Chunk::new(*IEND_TYPE, Vec::new()),
+    ] {
+        chunk.write_all(&mut bytes).unwrap();
+    }
+    assert!(PngReader::read(&mut &bytes[..]).is_err());
+
+    // plain PNG files give their image as the only frame
+    let pixels = [Color::RED, Color::BLUE];
+    let mut bytes = Vec::new();
+    PngWriter::new(2, 1, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.plays(), None);
+    assert_eq!(
+        image.frames(),
+        Some(vec![(pixels.to_vec(), Duration::ZERO)])
+    );
+}
+
+#[test]
+fn png_chunk_order() {
+    let plte = || Chunk::new(*PLTE_TYPE, vec![255, 0, 0, 0, 0, 255]);
+    let trns = || Chunk::new(*tRNS_TYPE, vec![0]);
+    let ihdr = || Chunk::new(*IHDR_TYPE, vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 3, 0, 0, 0]);
+    let idat = |data: &[u8]| Chunk::new(*IDAT_TYPE, data.to_vec());
+    let image = |chunks: &[Chunk]| {
+        let mut bytes = SIGNATURE.to_vec();
+        for chunk in chunks {
+            chunk.write_all(&mut bytes).unwrap();
+        }
+        Chunk::new(*IEND_TYPE, Vec::new())
+            .write_all(&mut bytes)
+            .unwrap();
+        PngReader::read(&mut &bytes[..]).map_err(|e| format!("{:?}", e))
+    };
+    let data = gz::compress_zlib(&[0, 0, 1]);
+    let (first, rest) = data.split_at(4);
+
+    assert!(image(&[ihdr(), plte(), trns(), idat(first), idat(rest)]).is_ok());
+    let error = |chunks: &[Chunk], expected: &str| {
+        let message = image(chunks).unwrap_err();
+        assert!(message.contains(expected), "{}", message);
+    };
+    error(
+        &[plte(), ihdr(), idat(&data)],
+        "IHDR must be the first chunk",
+    );
+    error(
+        &[ihdr(), ihdr(), plte(), idat(&data)],
+        "IHDR must appear only once",
+    );
+    error(&[ihdr(), idat(&data), plte()], "PLTE must come before IDAT");
+    error(
+        &[ihdr(), trns(), plte(), idat(&data)],
+        "PLTE must come before tRNS",
+    );
+    error(
+        &[ihdr(), plte(), idat(&data), trns()],
+        "tRNS must come before IDAT",
+    );
+    let text = Chunk::new(*b"tEXt", b"Comment\0between".to_vec());
+    error(
+        &[ihdr(), plte(), idat(first), text, idat(rest)],
+        "IDAT chunks must be consecutive",
+    );
+}
+
+#[test]
+fn png_dpi_round_trip() {
+    let pixels = vec![Color::RED; 4];
+    let mut writer = PngWriter::new(2, 2, &pixels).unwrap();
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).unwrap();
+    assert_eq!(PngReader::read(&mut &bytes[..]).unwrap().dpi(), None);
+
+    writer.set_dpi(300.0, 72.0);
+    let mut bytes = Vec::new();
+    writer.write(&mut bytes).unwrap();
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+    let (x, y) = image.dpi().unwrap();
+    assert!((x - 300.0).abs() < 0.01, "{}", x);
+    assert!((y - 72.0).abs() < 0.01, "{}", y);
+    assert_eq!(image.pixels().unwrap(), pixels);
+}
+
+#[test]
+fn png_color_type_bit_depth_combinations() {
+    use std::convert::TryFrom;
+    let depths = [1, 2, 4, 8, 16];
+    let valid: [(u8, &[u8]); 5] = [
+        (0, &[1, 2, 4, 8, 16]),
+        (2, &[8, 16]),
+        (3, &[1, 2, 4, 8]),
+        (4, &[8, 16]),
+        (6, &[8, 16]),
+    ];
+    for (color_type, allowed) in valid.iter() {
+        let color_type = PngColorType::try_from(*color_type).unwrap();
+        for depth in depths.iter() {
+            let bit_depth = BitDepth::try_from(*depth).unwrap();
+            assert_eq!(bit_depth.bits(), *depth as usize);
+            assert_eq!(
+                color_type.allows(bit_depth),
+                allowed.contains(depth),
+                "{:?} {:?}",
+                color_type,
+                bit_depth
+            );
+        }
+    }
+    for invalid in [1, 5, 7, 255].iter() {
+        assert!(PngColorType::try_from(*invalid).is_err());
+    }
+    for invalid in [0, 3, 12, 32].iter() {
+        assert!(BitDepth::try_from(*invalid).is_err());
+    }
+
+    // 1x1 image header with the given bit depth and color type
+    let image = |bit_depth: u8, color_type: u8| {
+        let mut bytes = SIGNATURE.to_vec();
+        let ihdr = vec![0, 0, 0, 1, 0, 0, 0, 1, bit_depth, color_type, 0, 0, 0];
+        Chunk::new(*IHDR_TYPE, ihdr).write_all(&mut bytes).unwrap();
+        let idat = gz::compress_zlib(&[0, 255, 0, 0]);
+        Chunk::new(*IDAT_TYPE, idat).write_all(&mut bytes).unwrap();
+        Chunk::new(*IEND_TYPE, Vec::new())
+            .write_all(&mut bytes)
+            .unwrap();
+        bytes
+    };
+    let header = PngReader::read(&mut &image(8, 2)[..]).unwrap().header();
+    assert_eq!(header.color_type(), PngColorType::Rgb);
+    assert_eq!(header.bit_depth(), BitDepth::Eight);
+    assert!(PngReader::read(&mut &image(12, 2)[..]).is_err());
+    assert!(PngReader::read(&mut &image(4, 2)[..]).is_err());
+    assert!(PngReader::read(&mut &image(8, 5)[..]).is_err());
+    assert!(PngReader::rows(&image(16, 3)[..]).is_err());
+
+    // valid but not decoded: an error naming the format instead of no pixels
+    let bytes = image(16, 0);
+    let gray16 = PngReader::read(&mut &bytes[..]).unwrap();
+    let error = gray16.pixels().unwrap_err().to_string();
+    assert!(
+        error.contains("unsupported bit depth 16 for color type 0"),
+        "{}",
+        error
+    );
+    let mut rows = PngReader::rows(&bytes[..]).unwrap();
+    assert!(rows.next().unwrap().is_err());
+}
+
+#[test]
+fn png_palette_transparency() {
+    let plte = Chunk::new(*PLTE_TYPE, vec![255, 0, 0, 0, 0, 255]);
+    let trns = Chunk::new(*tRNS_TYPE, vec![0]);
+    let bytes = palette_image(&[plte, trns], true);
+    let image = PngReader::read(&mut &bytes[..]).unwrap();
+
+    // only the first entry has an alpha value, the rest stay opaque
+    let pixels = image.pixels().unwrap();
+    assert_eq!(pixels[0], Color::rgba(255, 0, 0, 0));
+    assert_eq!(pixels[1], Color::BLUE);
+}
+
+/// Helper build a baseline JPEG using a single quantization table (all 8s)
+/// and huffman tables with one DC code ('0' => size 4) and one AC code ('0' => EOB)
+fn baseline_jpeg(width: u16, height: u16, components: u8, scan: &[u8]) -> Vec<u8> {
+    let mut bytes = vec![0xFF, 0xD8];
+    let mut segment = |marker: u8, data: &[u8]| {
+        bytes.extend_from_slice(&[0xFF, marker]);
+        bytes.extend_from_slice(&(data.len() as u16 + 2).to_be_bytes());
+        bytes.extend_from_slice(data);
+    };
+
+    let mut dqt = vec![0];
+    dqt.extend_from_slice(&[8; 64]);
+    segment(0xDB, &dqt);
+
+    let mut sof = vec![8];
+    sof.extend_from_slice(&height.to_be_bytes());
+    sof.extend_from_slice(&width.to_be_bytes());
+    sof.push(components);
+    let mut sos = vec![components];
+    for id in 1..=components {
+        sof.extend_from_slice(&[id, 0x11, 0]);
+        sos.extend_from_slice(&[id, 0x00]);
+    }
+    sos.extend_from_slice(&[0, 63, 0]);
+    segment(0xC0, &sof);
+
+    let mut counts = [0_u8; 16];
+    counts[0] = 1;
+    let mut dc = vec![0x00];
+    dc.extend_from_slice(&counts);
+    dc.push(4);
+    segment(0xC4, &dc);
+    let mut ac = vec![0x10];
+    ac.extend_from_slice(&counts);
+    ac.push(0);
+    segment(0xC4, &ac);
+
+    segment(0xDA, &sos);
+    bytes.extend_from_slice(scan);
+    bytes.extend_from_slice(&[0xFF, 0xD9]);
+    bytes
+}
+
+#[test]
+fn jpeg_gray_scale_dc_prediction() {
+    // first block DC +8 (0 1000 0), second block DC -8 (0 0111 0), padded with ones
+    let bytes = baseline_jpeg(16, 8, 1, &[0x40, 0xEF]);
+    let image = JpegReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.width(), 16);
+    assert_eq!(image.height(), 8);
+
+    let pixels = image.pixels();
+    assert_eq!(pixels.len(), 16 * 8);
+    for (i, pixel) in pixels.iter().enumerate() {
+        let expected = if i % 16 < 8 { 136 } else { 128 };
+        assert_eq!(*pixel, Color::rgb(expected, expected, expected));
+    }
+}
+
+#[test]
+fn jpeg_ycbcr_to_rgb() {
+    // Y, Cb and Cr blocks all with DC +8
+    let bytes = baseline_jpeg(8, 8, 3, &[0x41, 0x04, 0x3F]);
+    let image = JpegReader::read(&mut &bytes[..]).unwrap();
+    assert!(image
+        .pixels()
+        .iter()
+        .all(|p| *p == Color::rgb(147, 128, 150)));
+}
+
+#[test]
+fn jpeg_oversized_coefficient_is_an_error() {
+    // the DC code now stands for 40 bit values, more than any JPEG may use
+    let mut bytes = baseline_jpeg(8, 8, 1, &[0x40, 0xEF]);
+    let mut dc_table = vec![0x00, 1];
+    dc_table.extend_from_slice(&[0; 15]);
+    dc_table.push(4);
+    let value = bytes
+        .windows(dc_table.len())
+        .position(|w| w == &dc_table[..])
+        .unwrap()
+        + dc_table.len()
+        - 1;
+    bytes[value] = 40;
+    assert!(JpegReader::read(&mut &bytes[..]).is_err());
+}
+
+#[test]
+fn jpeg_progressive_is_unsupported() {
+    let mut bytes = baseline_jpeg(8, 8, 1, &[0x43]);
+    let sof = bytes.windows(2).position(|w| w == [0xFF, 0xC0]).unwrap();
+    bytes[sof + 1] = 0xC2;
+    assert!(JpegReader::read(&mut &bytes[..]).is_err());
+}
+
+#[test]
+fn bmp_write_read_round_trip() {
+    let pixels = vec![
+        Color::RED,
+        Color::GREEN,
+        Color::BLUE,
+        Color::rgba(10, 20, 30, 40),
+        Color::WHITE,
+        Color::BLACK,
+    ];
+    let mut bytes = Vec::new();
+    BmpWriter::new(3, 2, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+    assert_eq!(bytes.len(), 54 + 6 * 4);
+
+    let image = BmpReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.width(), 3);
+    assert_eq!(image.height(), 2);
+    assert_eq!(image.pixels(), pixels);
+}
+
+#[test]
+fn bmp_24_bits_bottom_up_with_padding() {
+    // 1x2 image: 3 bytes per row padded to 4, bottom row (blue) first
+    let mut bytes = b"BM".to_vec();
+    bytes.extend_from_slice(&(54_u32 + 8).to_le_bytes());
+    bytes.extend_from_slice(&[0; 4]);
+    bytes.extend_from_slice(&54_u32.to_le_bytes());
+    bytes.extend_from_slice(&40_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u32.to_le_bytes());
+    bytes.extend_from_slice(&2_u32.to_le_bytes());
+    bytes.extend_from_slice(&1_u16.to_le_bytes());
+    bytes.extend_from_slice(&24_u16.to_le_bytes());
+    bytes.extend_from_slice(&[0; 24]);
+    bytes.extend_from_slice(&[255, 0, 0, 0]);
+    bytes.extend_from_slice(&[0, 0, 255, 0]);
+
+    let image = BmpReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.pixels(), vec![Color::RED, Color::BLUE]);
+}
+
+#[test]
+fn bmp_huge_header_is_an_error() {
+    // headers claiming more pixels than the file holds, some overflowing the sizes
+    for &(offset, width, height) in [
+        (54_u32, 0x7fff_ffff_u32, 0x7fff_ffff_u32),
+        (54, 0x4000_0000, 1),
+        (0xffff_ffff, 1, 1),
+        (54, 2, 0x8000_0000),
+    ]
+    .iter()
+    {
+        let mut bytes = b"BM".to_vec();
+        bytes.extend_from_slice(&[0; 8]);
+        bytes.extend_from_slice(&offset.to_le_bytes());
+        bytes.extend_from_slice(&40_u32.to_le_bytes());
+        bytes.extend_from_slice(&width.to_le_bytes());
+        bytes.extend_from_slice(&height.to_le_bytes());
+        bytes.extend_from_slice(&1_u16.to_le_bytes());
+        bytes.extend_from_slice(&32_u16.to_le_bytes());
+        bytes.extend_from_slice(&[0; 32]);
+        assert!(BmpReader::read(&mut &bytes[..]).is_err());
+    }
+    assert!(BmpWriter::new(u32::MAX, 2, &[Color::RED; 2]).is_err());
+}
+
+#[test]
+fn ico_multiple_sizes() {
+    let sizes = [(16, 16), (32, 32), (48, 24), (256, 256)];
+    let mut icon = IcoWriter::new();
+    for &(width, height) in sizes.iter() {
+        let pixels = vec![Color::rgba(width as u8, 0, 0, 200); width * height];
+        icon.add_image(width as u32, height as u32, &pixels)
+            .unwrap();
+    }
+    assert_eq!(icon.image_count(), 4);
+    assert!(icon.add_image(257, 1, &[Color::RED; 257]).is_err());
+    assert!(icon.add_image(2, 2, &[Color::RED; 3]).is_err());
+
+    let mut bytes = Vec::new();
+    icon.write(&mut bytes).unwrap();
+    let read_u16 = |pos: usize| u16::from_le_bytes([bytes[pos], bytes[pos + 1]]) as usize;
+    let read_u32 = |pos: usize| read_u16(pos) + (read_u16(pos + 2) << 16);
+    assert_eq!(&bytes[..6], &[0, 0, 1, 0, 4, 0]);
+    for (i, &(width, height)) in sizes.iter().enumerate() {
+        let entry = 6 + i * 16;
+        // 256 is stored as 0
+        assert_eq!(bytes[entry] as usize, width % 256);
+        assert_eq!(bytes[entry + 1] as usize, height % 256);
+        assert_eq!(read_u16(entry + 6), 32);
+
+        let (size, offset) = (read_u32(entry + 8), read_u32(entry + 12));
+        let image = PngReader::read(&mut &bytes[offset..offset + size]).unwrap();
+        assert_eq!(
+            (image.width(), image.height()),
+            (width as u32, height as u32)
+        );
+        let expected = Color::rgba(width as u8, 0, 0, 200);
+        assert!(image
+            .pixels()
+            .unwrap()
+            .iter()
+            .all(|&color| color == expected));
+    }
+}
+
+/// Helper: TGA header for an RLE true color image with a 2 byte image id
+fn rle_tga_header(width: u16, height: u16, bits_per_pixel: u8, descriptor: u8) -> Vec<u8> {
+    let mut bytes = vec![2, 0, 10, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    bytes.extend_from_slice(&width.to_le_bytes());
+    bytes.extend_from_slice(&height.to_le_bytes());
+    bytes.extend_from_slice(&[bits_per_pixel, descriptor]);
+    bytes.extend_from_slice(b"id");
+    bytes
+}
+
+#[test]
+fn tga_rle_round_trip() {
+    // 3x2, 24 bits, bottom row first: a run of two blue pixels, then raw packets with
+    // the second one crossing into the top row
+    let mut bytes = rle_tga_header(3, 2, 24, 0);
+    bytes.extend_from_slice(&[0x81, 255, 0, 0]);
+    bytes.extend_from_slice(&[0x01, 0, 0, 255, 0, 0, 255]);
+    bytes.extend_from_slice(&[0x01, 0, 255, 0, 255, 255, 255]);
+    let image = TgaReader::read(&mut &bytes[..]).unwrap();
+    let pixels = vec![
+        Color::RED,
+        Color::GREEN,
+        Color::WHITE,
+        Color::BLUE,
+        Color::BLUE,
+        Color::RED,
+    ];
+    assert_eq!((image.width(), image.height()), (3, 2));
+    assert_eq!(image.pixels(), pixels);
+
+    let mut written = Vec::new();
+    TgaWriter::new(3, 2, &pixels)
+        .unwrap()
+        .write(&mut written)
+        .unwrap();
+    let image = TgaReader::read(&mut &written[..]).unwrap();
+    assert_eq!((image.width(), image.height()), (3, 2));
+    assert_eq!(image.pixels(), pixels);
+
+    // 32 bits stored top row first with 8 alpha bits, one run for the whole image
+    let mut bytes = rle_tga_header(2, 2, 32, 0x28);
+    bytes.extend_from_slice(&[0x83, 10, 20, 30, 40]);
+    let image = TgaReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(image.pixels(), vec![Color::rgba(30, 20, 10, 40); 4]);
+
+    // truncated RLE data
+    assert!(TgaReader::read(&mut &bytes[..bytes.len() - 1]).is_err());
+    assert!(TgaWriter::new(3, 3, &pixels).is_err());
+}
+
+#[test]
+fn tga_huge_header_small_file() {
+    // the header claims 65535x65535 pixels, the data runs out long before
+    let mut bytes = rle_tga_header(u16::MAX, u16::MAX, 32, 0);
+    bytes.extend_from_slice(&[0xff, 0, 0, 255, 255]);
+    assert!(TgaReader::read(&mut &bytes[..]).is_err());
+    bytes[2] = 2;
+    assert!(TgaReader::read(&mut &bytes[..]).is_err());
+}
+
+/// Helper decode GIF LZW data back into color indices
+fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+    let clear_code = 1_usize << min_code_size;
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut code_size = min_code_size + 1;
+    let mut previous: Option<usize> = None;
+    let mut output = Vec::new();
+    let (mut buffer, mut bits, mut pos) = (0_u32, 0, 0);
+
+    loop {
+        while bits < code_size {
+            buffer |= (data[pos] as u32) << bits;
+            bits += 8;
+            pos += 1;
+        }
+        let code = (buffer & ((1 << code_size) - 1)) as usize;
+        buffer >>= code_size;
+        bits -= code_size;
+
+        if code == clear_code {
+            table = (0..clear_code).map(|i| vec![i as u8]).collect();
+            table.push(Vec::new());
+            table.push(Vec::new());
+            code_size = min_code_size + 1;
+            previous = None;
+            continue;
+        }
+        if code == clear_code + 1 {
+            return output;
+        }
+
+        let entry = match (table.get(code), previous) {
+            (Some(entry), _) if code < table.len() => entry.clone(),
+            (_, Some(previous)) => {
+                let mut entry = table[previous].clone();
+                entry.push(table[previous][0]);
+                entry
+            }
+            _ => panic!("invalid code {}", code),
+        };
+        if let Some(previous) = previous {
+            if table.len() < 4096 {
+                let mut new_entry = table[previous].clone();
+                new_entry.push(entry[0]);
+                table.push(new_entry);
+            }
+        }
+        if table.len() == 1 << code_size && code_size < 12 {
+            code_size += 1;
+        }
+        output.extend_from_slice(&entry);
+        previous = Some(code);
+    }
+}
+
+#[test]
+fn gif_lzw_round_trip() {
+    // long enough to fill the code table and force a clear code
+    let indices: Vec<u8> = (0..40_000_u32)
+        .map(|i| ((i * i / 7 + i / 3) % 16) as u8)
+        .collect();
+    assert_eq!(lzw_decode(&lzw_encode(&indices, 4), 4), indices);
+
+    let indices = vec![1, 1, 1, 1, 0, 0, 1, 0, 1, 1, 1];
+    assert_eq!(lzw_decode(&lzw_encode(&indices, 2), 2), indices);
+}
+
+#[test]
+fn gif_animation_layout() {
+    let red = vec![Color::RED; 4 * 3];
+    let mut mixed = vec![Color::BLUE; 4 * 3];
+    mixed[0] = Color::rgba(0, 0, 0, 0);
+
+    let mut gif = GifWriter::new(4, 3);
+    gif.add_frame(&red, Duration::from_millis(100)).unwrap();
+    gif.add_frame(&mixed, Duration::from_millis(250)).unwrap();
+    assert!(gif
+        .add_frame(&red[1..], Duration::from_millis(100))
+        .is_err());
+    assert_eq!(gif.frame_count(), 2);
+
+    let mut bytes = Vec::new();
+    gif.write(&mut bytes).unwrap();
+    assert_eq!(&bytes[..6], b"GIF89a");
+    assert_eq!(&bytes[6..10], &[4, 0, 3, 0]);
+    assert_eq!(bytes.last(), Some(&0x3B));
+
+    // graphic control extensions hold the delays, the second one with transparency
+    let controls: Vec<&[u8]> = bytes
+        .windows(8)
+        .filter(|w| w[..3] == [0x21, 0xF9, 4])
+        .collect();
+    assert_eq!(controls.len(), 2);
+    assert_eq!(controls[0][3..6], [1 << 2, 10, 0]);
+    assert_eq!(controls[1][3..6], [(2 << 2) | 1, 25, 0]);
+}
+
+#[test]
+fn ppm_write_read_round_trip() {
+    let pixels = vec![Color::RED, Color::GREEN, Color::BLUE, Color::WHITE];
+    let mut bytes = Vec::new();
+    PpmWriter::new(2, 2, &pixels)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+    assert!(bytes.starts_with(b"P6\n2 2\n255\n"));
+
+    let image = PpmReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!((image.width(), image.height()), (2, 2));
+    assert_eq!(image.pixels(), pixels);
+
+    let mut bytes = Vec::new();
+    PpmWriter::new(2, 2, &pixels)
+        .unwrap()
+        .write_grayscale(&mut bytes)
+        .unwrap();
+    let gray: Vec<u8> = PpmReader::read(&mut &bytes[..])
+        .unwrap()
+        .pixels()
+        .iter()
+        .map(|c| c.r())
+        .collect();
+    assert_eq!(gray, vec![76, 150, 29, 255]);
+}
+
+#[test]
+fn pgm_header_comments_and_sixteen_bits() {
+    let mut bytes = b"P5 # comment\n2 # width\n1\n65535\n".to_vec();
+    bytes.extend_from_slice(&[0xFF, 0xFF, 0x80, 0x00]);
+    let image = PpmReader::read(&mut &bytes[..]).unwrap();
+    assert_eq!(
+        image.pixels(),
+        vec![Color::WHITE, Color::rgb(128, 128, 128)]
+    );
+}
+
+#[test]
+fn ppm_huge_header_is_an_error() {
+    // 4294967295 x 4294967295 pixels of 6 bytes overflows the sample count
+    let mut bytes = b"P6\n4294967295 4294967295\n65535\n".to_vec();
+    bytes.extend_from_slice(&[0; 12]);
+    assert!(PpmReader::read(&mut &bytes[..]).is_err());
+    assert!(PpmWriter::new(u32::MAX, 2, &[Color::RED; 2]).is_err());
+}
+
+#[test]
+fn quantize_two_colors() {
+    let mut pixels = vec![Color::RED; 10];
+    pixels.extend_from_slice(&[Color::BLUE; 5]);
+    let mut palette = median_cut(&pixels, 16);
+    palette.sort_by_key(|c| c.r());
+    assert_eq!(palette, vec![Color::BLUE, Color::RED]);
+
+    // the two colors are split apart even when they are the only ones allowed
+    assert_eq!(median_cut(&pixels, 2).len(), 2);
+    assert_eq!(nearest_index(&palette, Color::rgb(200, 10, 30)), 1);
+    assert_eq!(nearest_index(&palette, Color::rgb(10, 10, 200)), 0);
+}
+
+#[test]
+fn quantize_gradient_to_limit() {
+    let pixels: Vec<Color> = (0..=255).map(|v| Color::rgb(v, v, 255 - v)).collect();
+    let palette = median_cut(&pixels, 8);
+    assert_eq!(palette.len(), 8);
+    // every pixel is within one box (32 shades) of its palette entry
+    for pixel in &pixels {
+        let nearest = palette[nearest_index(&palette, *pixel) as usize];
+        assert!((nearest.r() as i32 - pixel.r() as i32).abs() <= 16);
+    }
+}
+
+#[test]
+fn floyd_steinberg_gradient_does_not_band() {
+    // horizontal gray gradient reduced to black and white
+    let (width, height) = (256, 32);
+    let pixels: Vec<Color> = (0..width * height)
+        .map(|i| Color::rgb(i as u8, i as u8, i as u8))
+        .collect();
+    let palette = [Color::BLACK, Color::WHITE];
+
+    // average error of each column against the original shade
+    let column_error = |indices: &[u8]| {
+        (0..width)
+            .map(|x| {
+                let sum: f32 = (0..height)
+                    .map(|y| palette[indices[y * width + x] as usize].r() as f32)
+                    .sum();
+                (sum / height as f32 - x as f32).abs()
+            })
+            .sum::<f32>()
+            / width as f32
+    };
+
+    let banded: Vec<u8> = pixels.iter().map(|c| nearest_index(&palette, *c)).collect();
+    let dithered = floyd_steinberg(&pixels, width as u32, &palette);
+    assert_eq!(dithered.len(), pixels.len());
+    assert!(column_error(&banded) > 60.0);
+    assert!(column_error(&dithered) < 15.0);
+}
+
+#[test]
+fn png_indexed_round_trip() {
+    let pixels: Vec<Color> = (0..64_u32)
+        .map(|i| match i % 3 {
+            0 => Color::RED,
+            1 => Color::rgba(0, 128, 255, 100),
+            _ => Color::rgb((i * 4) as u8, 0, 0),
+        })
+        .collect();
+    let mut palette = median_cut(&pixels, 8);
+    palette.iter_mut().for_each(|c| {
+        if c.b() == 255 {
+            c.set_alpha(100)
+        }
+    });
+    let indices: Vec<u8> = pixels.iter().map(|c| nearest_index(&palette, *c)).collect();
+
+    let mut bytes = Vec::new();
+    PngWriter::new_indexed(8, 8, &indices, &palette)
+        .unwrap()
+        .write(&mut bytes)
+        .unwrap();
+    let decoded = PngReader::read(&mut &bytes[..]).unwrap().pixels().unwrap();
+    let expected: Vec<Color> = indices.iter().map(|i| palette[*i as usize]).collect();
+    assert_eq!(decoded, expected);
+    assert_eq!(decoded[1], Color::rgba(0, 128, 255, 100));
+
+    assert!(PngWriter::new_indexed(8, 8, &indices, &[]).is_err());
+    assert!(PngWriter::new_indexed(8, 8, &indices, &palette[..1]).is_err());
+    assert!(PngWriter::new_indexed(8, 7, &indices, &palette).is_err());
+}
+
+#[test]
+fn sprite_rgba_bytes() {
+    let bytes = [255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0, 1, 2, 3, 4];
+    let sprite = Sprite::from_rgba(2, 2, &bytes).unwrap();
+    assert_eq!((sprite.width, sprite.height), (2, 2));
+    assert_eq!(sprite.get_pixel(1, 0), Some(Color::rgba(0, 255, 0, 128)));
+    assert_eq!(sprite.get_pixel(1, 1), Some(Color::rgba(1, 2, 3, 4)));
+    assert_eq!(sprite.to_rgba(), bytes);
+
+    assert!(Sprite::from_rgba(2, 2, &bytes[..15]).is_err());
+    assert!(Sprite::from_rgba(3, 2, &bytes).is_err());
+    assert!(Sprite::from_rgba(usize::MAX, 2, &bytes).is_err());
+}
+
+#[test]
+fn sprite_index() {
+    let mut sprite = Sprite {
+        width: 3,
+        height: 2,
+        pixels: vec![Color::BLACK; 6],
+    };
+    sprite[(2, 0)] = Color::RED;
+    sprite[(0, 1)] = Color::GREEN;
+    sprite[(1, 1)].set_alpha(10);
+
+    assert_eq!(sprite[(2, 0)], Color::RED);
+    assert_eq!(sprite[(0, 1)], Color::GREEN);
+    assert_eq!(sprite.get_pixel(2, 0), Some(Color::RED));
+    assert_eq!(sprite.pixels[3], Color::GREEN);
+    assert_eq!(sprite[(1, 1)], Color::rgba(0, 0, 0, 10));
+}
+
+#[test]
+fn sprite_set_pixel() {
+    let mut sprite = Sprite {
+        width: 3,
+        height: 2,
+        pixels: vec![Color::BLACK; 6],
+    };
+    assert!(sprite.set_pixel(2, 1, Color::RED));
+    assert_eq!(sprite.get_pixel(2, 1), Some(Color::RED));
+    if let Some(pixel) = sprite.get_pixel_mut(0, 1) {
+        *pixel = Color::BLUE;
+    }
+    assert_eq!(sprite.pixels[3], Color::BLUE);
+
+    // out of range, including x past the width that would land in the next row
+    assert!(!sprite.set_pixel(3, 0, Color::WHITE));
+    assert!(!sprite.set_pixel(0, 2, Color::WHITE));
+    assert!(sprite.get_pixel_mut(3, 0).is_none());
+    assert!(sprite.get_pixel_mut(usize::MAX, 0).is_none());
+    assert!(!sprite.pixels.contains(&Color::WHITE));
+
+    let mut empty = Sprite::default();
+    assert!(!empty.set_pixel(0, 0, Color::WHITE));
+}
+
+#[test]
+#[should_panic]
+fn sprite_index_out_of_range() {
+    let sprite = Sprite {
+        width: 3,
+        height: 2,
+        pixels: vec![Color::BLACK; 6],
+    };
+    // x past the width must not wrap to the next row
+    let _ = sprite[(3, 0)];
+}
+
+#[test]
+fn sprite_compose_over() {
+    let mut base = Sprite {
+        width: 3,
+        height: 2,
+        pixels: vec![Color::BLUE; 6],
+    };
+    let top = Sprite {
+        width: 2,
+        height: 2,
+        pixels: vec![Color::rgba(255, 0, 0, 128); 4],
+    };
+    // only the top right column of the red sprite lands on the base
+    base.compose_over(&top, Point2D::new(-1, -1));
+    let blended = Color::rgba(128, 0, 127, 255);
+    assert_eq!(
+        base.pixels,
+        [
+            blended,
+            Color::BLUE,
+            Color::BLUE,
+            Color::BLUE,
+            Color::BLUE,
+            Color::BLUE
+        ]
+    );
+
+    // clipped on the right and bottom too
+    base.compose_over(&top, Point2D::new(2, 1));
+    assert_eq!(base.get_pixel(2, 1), Some(blended));
+    assert_eq!(base.get_pixel(1, 1), Some(Color::BLUE));
+    assert_eq!(base.get_pixel(2, 0), Some(Color::BLUE));
+}
+
+#[test]
+fn sprite_tint_and_brightness() {
+    let sprite = Sprite {
+        width: 2,
+        height: 1,
+        pixels: vec![Color::rgba(200, 100, 50, 128), Color::WHITE],
+    };
+    let tinted = sprite.tinted(Color::rgb(255, 0, 128));
+    assert_eq!(
+        tinted.pixels,
+        [Color::rgba(200, 0, 25, 128), Color::rgb(255, 0, 128)]
+    );
+    assert_eq!(sprite.tinted(Color::WHITE).pixels, sprite.pixels);
+
+    let darker = sprite.brightened(0.5);
+    assert_eq!(
+        darker.pixels,
+        [Color::rgba(100, 50, 25, 128), Color::rgb(127, 127, 127)]
+    );
+    let brighter = sprite.brightened(2.0);
+    assert_eq!(brighter.pixels[0], Color::rgba(255, 200, 100, 128));
+}
+
+#[test]
+fn sprite_animation_modes() {
+    let frames: Vec<Sprite> = [Color::RED, Color::GREEN, Color::BLUE]
+        .iter()
+        .map(|color| Sprite {
+            width: 1,
+            height: 1,
+            pixels: vec![*color],
+        })
+        .collect();
+    let indices = |mode: AnimationMode| {
+        let mut animation = SpriteAnimation::new(frames.clone(), 0.1, mode).unwrap();
+        (0..6)
+            .map(|_| {
+                animation.advance(0.1001);
+                animation.current_index()
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(indices(AnimationMode::Loop), [1, 2, 0, 1, 2, 0]);
+    assert_eq!(indices(AnimationMode::PingPong), [1, 2, 1, 0, 1, 2]);
+    assert_eq!(indices(AnimationMode::Once), [1, 2, 2, 2, 2, 2]);
+
+    // per frame durations, big steps skip frames
+    let mut animation =
+        SpriteAnimation::with_durations(frames.clone(), vec![1.0, 0.5, 0.5], AnimationMode::Once)
+            .unwrap();
+    assert_eq!(animation.current_frame().pixels[0], Color::RED);
+    animation.advance(0.9);
+    assert_eq!(animation.current_index(), 0);
+    animation.advance(0.3);
+    assert_eq!(animation.current_frame().pixels[0], Color::GREEN);
+    assert!(!animation.is_finished());
+    animation.advance(10.0);
+    assert_eq!(animation.current_frame().pixels[0], Color::BLUE);
+    assert!(animation.is_finished());
+    animation.reset();
+    assert_eq!(animation.current_index(), 0);
+
+    // huge or invalid times do not hang repeating animations
+    for &mode in [AnimationMode::Loop, AnimationMode::PingPong].iter() {
+        let mut animation =
+            SpriteAnimation::with_durations(frames.clone(), vec![0.1, 0.2, 0.3], mode).unwrap();
+        animation.advance(f32::INFINITY);
+        animation.advance(f32::NAN);
+        assert_eq!(animation.current_index(), 0);
+        animation.advance(1e30);
+        assert!(animation.current_index() < 3);
+        // a whole cycle later the animation is back on the same frame
+        animation.reset();
+        animation.advance(0.15);
+        let cycle = if mode == AnimationMode::Loop {
+            0.6
+        } else {
+            0.8
+        };
+        animation.advance(cycle);
+        assert_eq!(animation.current_index(), 1, "{:?}", mode);
+    }
+
+    // bad arguments are errors instead of panics
+    assert!(SpriteAnimation::new(Vec::new(), 0.1, AnimationMode::Loop).is_err());
+    assert!(SpriteAnimation::new(frames.clone(), 0.0, AnimationMode::Loop).is_err());
+    assert!(SpriteAnimation::new(frames.clone(), f32::NAN, AnimationMode::Loop).is_err());
+    assert!(SpriteAnimation::with_durations(frames, vec![0.1], AnimationMode::Loop).is_err());
+}
+
+#[test]
+fn resample_solid_color() {
+    let color = Color::rgba(200, 100, 50, 255);
+    let sprite = Sprite {
+        width: 37,
+        height: 23,
+        pixels: vec![color; 37 * 23],
+    };
+    for filter in [
+        ResampleFilter::Triangle,
+        ResampleFilter::Cubic,
+        ResampleFilter::Lanczos3,
+    ] {
+        for (width, height) in [(10, 6), (80, 50), (1, 1)] {
+            let resized = sprite.resampled(width, height, filter);
+            assert_eq!((resized.width, resized.height), (width, height));
+            assert!(resized.pixels.iter().all(|c| *c == color));
+        }
+    }
+}
+
+#[test]
+fn resample_downscale_averages() {
+    // black and white stripes average to gray when downscaled (edges are a bit off
+    // since the kernel is cut at the border)
+    let pixels = (0..64 * 64)
+        .map(|i| {
+            if i % 2 == 0 {
+                Color::BLACK
+            } else {
+                Color::WHITE
+            }
+        })
+        .collect();
+    let sprite = Sprite {
+        width: 64,
+        height: 64,
+        pixels,
+    };
+    let resized = sprite.resampled(8, 8, ResampleFilter::Triangle);
+    assert!(resized
+        .pixels
+        .iter()
+        .all(|c| (c.r() as i32 - 128).abs() <= 6));
+}
+
+#[test]
+fn blur_spreads_symmetrically() {
+    let (width, height) = (9, 9);
+    let mut pixels = vec![Color::BLACK; width * height];
+    pixels[4 * width + 4] = Color::WHITE;
+    let sprite = Sprite {
+        width,
+        height,
+        pixels,
+    };
+
+    for blurred in [sprite.blur(1), sprite.gaussian_blur(1.0)] {
+        let at = |x: usize, y: usize| blurred.pixels[y * width + x].r();
+        let center = at(4, 4);
+        assert!(center > 0 && center < 255);
+        assert!(at(3, 4) > 0);
+        // the same brightness at the same distance in every direction
+        assert_eq!(at(3, 4), at(5, 4));
+        assert_eq!(at(3, 4), at(4, 3));
+        assert_eq!(at(3, 4), at(4, 5));
+        assert_eq!(at(3, 3), at(5, 5));
+        assert_eq!(at(3, 5), at(5, 3));
+        assert!(at(3, 4) <= center);
+        assert_eq!(at(0, 0), 0);
+    }
+
+    // a box blur of radius 1 averages over 9 pixels
+    assert_eq!(sprite.blur(1).pixels[4 * width + 4].r(), 28);
+}
+
+#[test]
+fn sobel_finds_step_edge() {
+    // black on the left half, white on the right half
+    let (width, height) = (8, 4);
+    let pixels: Vec<Color> = (0..width * height)
+        .map(|i| {
+            if i % width < 4 {
+                Color::BLACK
+            } else {
+                Color::WHITE
+            }
+        })
+        .collect();
+
+    let edges = convolve(&pixels, width, height, &SOBEL_X);
+    for (i, pixel) in edges.iter().enumerate() {
+        let expected = if i % width == 3 || i % width == 4 {
+            255
+        } else {
+            0
+        };
+        assert_eq!(pixel.r(), expected);
+        assert_eq!(pixel.alpha(), 255);
+    }
+
+    // no vertical changes at all
+    let edges = convolve(&pixels, width, height, &SOBEL_Y);
+    assert!(edges.iter().all(|c| c.r() == 0));
+
+    // flat images are left alone by sharpen and blur
+    let flat = vec![Color::rgb(90, 90, 90); 16];
+    assert_eq!(convolve(&flat, 4, 4, &SHARPEN), flat);
+    assert_eq!(convolve(&flat, 4, 4, &BOX_BLUR_3), flat);
+
+    // kernels need not be square, this one takes the pixel on the right
+    let shifted = convolve(&pixels, width, height, &[[0.0, 0.0, 1.0]]);
+    assert_eq!(shifted[3], Color::WHITE);
+    assert_eq!(shifted[2], Color::BLACK);
+}
+
+#[test]
+fn tilemap_draws_visible_tiles() {
+    let tile = |color| Sprite {
+        width: 2,
+        height: 2,
+        pixels: vec![color; 4],
+    };
+    let tileset = vec![tile(Color::RED), tile(Color::GREEN)];
+    // index 7 is not in the tileset and is left empty
+    let mut map = TileMap::new(3, 2, vec![0, 1, 0, 1, 7, 1], tileset).unwrap();
+    assert!(TileMap::new(3, 2, vec![0; 5], Vec::new()).is_err());
+    assert_eq!(map.tile(1, 1), Some(7));
+    assert_eq!(map.tile(3, 0), None);
+    map.set_tile(2, 1, 0);
+    assert_eq!(map.tile(2, 1), Some(0));
+
+    // scrolled one tile to the left, the first column is off the canvas
+    let canvas = Canvas::new(5, 6);
+    map.draw(&canvas, Point2D::new(-2, 1), SpriteSize::new(2, 2));
+    assert_eq!(canvas.get_pixel(0, 1), Some(Color::GREEN));
+    assert_eq!(canvas.get_pixel(3, 2), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(1, 3), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(3, 4), Some(Color::RED));
+    let painted = |canvas: &Canvas| {
+        let pixels = canvas.pixels.borrow();
+        pixels
+            .iter()
+            .filter(|pixel| **pixel != Color::BLACK)
+            .count()
+    };
+    assert_eq!(painted(&canvas), 12);
+
+    // completely off the canvas
+    let canvas = Canvas::new(5, 6);
+    map.draw(&canvas, Point2D::new(-6, 0), SpriteSize::new(2, 2));
+    map.draw(&canvas, Point2D::new(5, 0), SpriteSize::new(2, 2));
+    assert_eq!(painted(&canvas), 0);
+}
@@ -0,0 +1,273 @@
+use crate::color::Color;
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////////////////////////
+//         Decoded TGA Image                            //
+//////////////////////////////////////////////////////////
+
+/// A decoded TGA image, rows are stored top to bottom (as PNG pixels are)
+#[derive(Default, Debug, Clone)]
+pub struct TgaImage {
+    width: u32,
+    height: u32,
+    pixels: Vec<Color>,
+}
+
+/// Implementation of TgaImage functionalities
+///
+/// Example usage:
+///    let mut file = std::fs::File::open("image.tga").unwrap();
+///    let image = TgaReader::read(&mut file).unwrap();
+///    let pixels : Vec<Color> = image.pixels();
+impl TgaImage {
+    /// Get TGA Image width
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    /// Get TGA Image height
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// collect all the pixels in this image as a vector of pixels
+    pub fn pixels(&self) -> Vec<Color> {
+        self.pixels.clone()
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Read TGA From File                                                                  //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// TGA Reader
+/// Supports uncompressed and RLE compressed true color images, 24 or 32 bits per pixel,
+/// stored from any corner (image descriptor bits 4 and 5).
+///
+/// # Example:
+/// let mut file = std::fs::File::open("image.tga").unwrap();
+/// let image : TgaImage = TgaReader::read(&mut file).unwrap();
+pub struct TgaReader;
+
+impl TgaReader {
+    /// Read a TGA file into a TgaImage structure
+    /// # Arguments
+    /// `image_file`    reader containing image data
+    pub fn read(image_file: &mut impl std::io::Read) -> Result<TgaImage, Box<dyn Error>> {
+        let mut data = Vec::<u8>::new();
+        image_file.read_to_end(&mut data)?;
+
+        if data.len() < SZ_HEADER {
+            return Err(Box::new(TGAError::ParsingError(
+                "Not a valid TGA image".into(),
+            )));
+        }
+
+        let id_length = data[0] as usize;
+        let color_map_type = data[1];
+        let image_type = data[2];
+        let color_map_length = read_u16(&data, 5) as usize;
+        let color_map_entry_bits = data[7] as usize;
+        let width = read_u16(&data, 12) as usize;
+        let height = read_u16(&data, 14) as usize;
+        let bits_per_pixel = data[16];
+        let descriptor = data[17];
+
+        if image_type != TRUE_COLOR && image_type != RLE_TRUE_COLOR
+            || (bits_per_pixel != 24 && bits_per_pixel != 32)
+        {
+            return Err(Box::new(TGAError::Unsupported(format!(
+                "Image type {} with {} bits per pixel",
+                image_type, bits_per_pixel
+            ))));
+        }
+
+        // true color images may still carry a color map, it is not used
+        let mut start = SZ_HEADER + id_length;
+        if color_map_type != 0 {
+            let entry_bytes = color_map_entry_bits.div_ceil(8);
+            start += color_map_length * entry_bytes;
+        }
+        let bytes_per_pixel = bits_per_pixel as usize / 8;
+        let pixel_count = width
+            .checked_mul(height)
+            .filter(|count| count.checked_mul(bytes_per_pixel).is_some())
+            .ok_or_else(|| TGAError::DataError("Image too large".into()))?;
+        let pixel_data = data.get(start..).unwrap_or(&[]);
+        let raw = if image_type == RLE_TRUE_COLOR {
+            rle_decode(pixel_data, pixel_count, bytes_per_pixel)?
+        } else if pixel_data.len() >= pixel_count * bytes_per_pixel {
+            pixel_data[..pixel_count * bytes_per_pixel].to_vec()
+        } else {
+            return Err(Box::new(TGAError::DataError(
+                "Not enough pixel data".into(),
+            )));
+        };
+
+        // only use the alpha channel when the descriptor says it holds alpha bits
+        let has_alpha = bytes_per_pixel == 4 && descriptor & ALPHA_BITS_MASK != 0;
+        let right_to_left = descriptor & RIGHT_TO_LEFT != 0;
+        let top_down = descriptor & TOP_TO_BOTTOM != 0;
+
+        let mut pixels = Vec::with_capacity(pixel_count);
+        if width > 0 {
+            let rows: Vec<&[u8]> = raw.chunks_exact(width * bytes_per_pixel).collect();
+            for row in 0..height {
+                let row = if top_down {
+                    rows[row]
+                } else {
+                    rows[height - 1 - row]
+                };
+                let row_pixels = row.chunks_exact(bytes_per_pixel).map(|bgra| {
+                    let alpha = if has_alpha { bgra[3] } else { 255 };
+                    Color::rgba(bgra[2], bgra[1], bgra[0], alpha)
+                });
+                if right_to_left {
+                    pixels.extend(row_pixels.rev());
+                } else {
+                    pixels.extend(row_pixels);
+                }
+            }
+        }
+
+        Ok(TgaImage {
+            width: width as u32,
+            height: height as u32,
+            pixels,
+        })
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// Write TGA To File                                                                   //
+/////////////////////////////////////////////////////////////////////////////////////////
+/// A TGA Image writer
+/// Writes uncompressed 32 bit images (BGRA) stored from the top row, with a TGA 2.0 footer.
+///
+/// # Example
+/// ```no_run
+/// # use graphics::color::Color;
+/// # use graphics::canvas::{Canvas, Origin};
+/// # use graphics::image::tga::TgaWriter;
+/// let canvas = Canvas::new(400, 400);
+/// canvas.set_origin(Origin::TopLeft); // image files start with the top row
+/// canvas.fill(Color::BLUE);
+///
+/// let mut file = std::fs::File::create("image.tga").unwrap();
+/// let pixels = canvas.pixels.borrow();
+/// let writer = TgaWriter::new(canvas.width(), canvas.height(), &pixels).unwrap();
+/// writer.write(&mut file).unwrap();
+/// ```
+pub struct TgaWriter<'a> {
+    width: u32,
+    height: u32,
+    pixels: &'a [Color],
+}
+
+impl<'a> TgaWriter<'a> {
+    pub fn new(width: u32, height: u32, pixels: &'a [Color]) -> Result<Self, TGAError> {
+        if width > u16::MAX as u32 || height > u16::MAX as u32 {
+            Err(TGAError::DataError(
+                "TGA images are at most 65535 pixels wide and high".to_owned(),
+            ))
+        } else if pixels.len() == (width * height) as usize {
+            Ok(Self {
+                width,
+                height,
+                pixels,
+            })
+        } else {
+            Err(TGAError::DataError("Invalid image size".to_owned()))
+        }
+    }
+
+    /// Write the header, the pixels top row first and the footer
+    pub fn write(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        // no image id, no color map, origin at (0, 0)
+        writer.write_all(&[0, 0, TRUE_COLOR, 0, 0, 0, 0, 0, 0, 0, 0, 0])?;
+        writer.write_all(&(self.width as u16).to_le_bytes())?;
+        writer.write_all(&(self.height as u16).to_le_bytes())?;
+        writer.write_all(&[32, TOP_TO_BOTTOM | 8])?;
+
+        for color in self.pixels {
+            writer.write_all(&[color.b(), color.g(), color.r(), color.alpha()])?;
+        }
+
+        // no extension area nor developer directory
+        writer.write_all(&[0; 8])?;
+        writer.write_all(FOOTER_SIGNATURE)?;
+        Ok(())
+    }
+}
+
+/// Generic Error type for errors related parsing TGA images
+#[derive(Debug, Clone)]
+pub enum TGAError {
+    DataError(String),
+    ParsingError(String),
+    Unsupported(String),
+}
+
+impl Error for TGAError {}
+impl fmt::Display for TGAError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TGAError::DataError(msg) => write!(f, "TGA Error: Invalid data. {}", msg),
+            TGAError::ParsingError(msg) => write!(f, "TGA Error: Could not parse image. {}", msg),
+            TGAError::Unsupported(msg) => write!(f, "TGA Error: Unsupported image. {}", msg),
+        }
+    }
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// TGA standard definitions and helpers                                                //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+const SZ_HEADER: usize = 18;
+/// Image types
+const TRUE_COLOR: u8 = 2;
+const RLE_TRUE_COLOR: u8 = 10;
+/// Image descriptor bits
+const ALPHA_BITS_MASK: u8 = 0x0f;
+const RIGHT_TO_LEFT: u8 = 0x10;
+const TOP_TO_BOTTOM: u8 = 0x20;
+/// Last bytes of TGA 2.0 files
+const FOOTER_SIGNATURE: &[u8; 18] = b"TRUEVISION-XFILE.\0";
+
+/// Helper: expand RLE packets into `count` pixels of `bytes_per_pixel` bytes
+/// Each packet starts with a byte: the high bit set is a run of one pixel repeated,
+/// clear is that many raw pixels, the low 7 bits are the number of pixels minus one.
+/// Packets may cross rows.
+fn rle_decode(data: &[u8], count: usize, bytes_per_pixel: usize) -> Result<Vec<u8>, TGAError> {
+    let truncated = || TGAError::DataError("RLE data ends before the last pixel".into());
+    // the header size is not trusted: every input byte expands to less than 128 output bytes
+    let mut raw = Vec::with_capacity((count * bytes_per_pixel).min(data.len() * 128));
+    let mut pos = 0;
+    while raw.len() < count * bytes_per_pixel {
+        let packet = *data.get(pos).ok_or_else(truncated)?;
+        let length = (packet & 0x7f) as usize + 1;
+        pos += 1;
+        if packet & 0x80 != 0 {
+            let pixel = data.get(pos..pos + bytes_per_pixel).ok_or_else(truncated)?;
+            for _ in 0..length {
+                raw.extend_from_slice(pixel);
+            }
+            pos += bytes_per_pixel;
+        } else {
+            let pixels = data
+                .get(pos..pos + length * bytes_per_pixel)
+                .ok_or_else(truncated)?;
+            raw.extend_from_slice(pixels);
+            pos += length * bytes_per_pixel;
+        }
+    }
+    // a packet running past the last pixel is ignored
+    raw.truncate(count * bytes_per_pixel);
+    Ok(raw)
+}
+
+/// Helper: read a little endian u16, caller makes sure there is enough data
+fn read_u16(data: &[u8], pos: usize) -> u16 {
+    u16::from_le_bytes([data[pos], data[pos + 1]])
+}
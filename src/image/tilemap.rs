@@ -0,0 +1,115 @@
+use crate::canvas::Canvas;
+use crate::image::sprite::{Sprite, SpriteSize};
+use crate::math::Point2D;
+use std::error::Error;
+
+/// A grid of tiles, each cell is the index of a sprite in the tileset
+/// Indexes outside of the tileset are treated as empty cells.
+///
+/// Example usage:
+///    let tileset: Vec<Sprite> = SpriteExtractor::from_file("tiles.png", size.clone(), 0, 0)?.collect();
+///    let map = TileMap::new(3, 2, vec![0, 1, 0, 2, 2, 2], tileset)?;
+///    map.draw(&canvas, Point2D::new(-scroll, 0), size);
+#[derive(Debug, Default, Clone)]
+pub struct TileMap {
+    pub width: usize,
+    pub height: usize,
+    pub tiles: Vec<u32>,
+    pub tileset: Vec<Sprite>,
+}
+
+impl TileMap {
+    /// Create a tile map
+    /// # Arguments
+    /// `width`     map width in tiles
+    /// `height`    map height in tiles
+    /// `tiles`     tileset indexes row by row, must be exactly width * height long
+    /// `tileset`   sprites the indexes refer to
+    pub fn new(
+        width: usize,
+        height: usize,
+        tiles: Vec<u32>,
+        tileset: Vec<Sprite>,
+    ) -> Result<Self, Box<dyn Error>> {
+        if width.checked_mul(height) != Some(tiles.len()) {
+            return Err(format!(
+                "Invalid number of tiles for a {}x{} map: {}",
+                width,
+                height,
+                tiles.len()
+            )
+            .into());
+        }
+        Ok(Self {
+            width,
+            height,
+            tiles,
+            tileset,
+        })
+    }
+
+    /// Retrieve the tileset index at the given cell, None outside of the map
+    pub fn tile(&self, x: usize, y: usize) -> Option<u32> {
+        if x >= self.width || y >= self.height {
+            None
+        } else {
+            self.tiles.get(y * self.width + x).copied()
+        }
+    }
+
+    /// Change the tileset index at the given cell, ignored outside of the map
+    pub fn set_tile(&mut self, x: usize, y: usize, index: u32) {
+        if x < self.width && y < self.height {
+            self.tiles[y * self.width + x] = index;
+        }
+    }
+
+    /// Draws the tiles falling within the canvas, the rest are skipped
+    /// # Arguments
+    /// `canvas`        canvas to draw on
+    /// `origin`        position of the top left corner of the map, negative to scroll
+    /// `tile_size`     size of each cell in pixels
+    pub fn draw(&self, canvas: &Canvas, origin: Point2D, tile_size: SpriteSize) {
+        if tile_size.width == 0 || tile_size.height == 0 {
+            return;
+        }
+        let columns = visible_range(
+            origin.x(),
+            tile_size.width as i32,
+            canvas.width() as i32,
+            self.width,
+        );
+        let rows = visible_range(
+            origin.y(),
+            tile_size.height as i32,
+            canvas.height() as i32,
+            self.height,
+        );
+        for row in rows {
+            for column in columns.clone() {
+                let index = self.tiles[row * self.width + column] as usize;
+                if let Some(tile) = self.tileset.get(index) {
+                    let position = Point2D::new(
+                        origin.x() + (column * tile_size.width) as i32,
+                        origin.y() + (row * tile_size.height) as i32,
+                    );
+                    canvas.sprite(position, tile);
+                }
+            }
+        }
+    }
+}
+
+/// Helper range of cells along one axis that overlap the canvas (0 - canvas_size)
+fn visible_range(
+    origin: i32,
+    cell_size: i32,
+    canvas_size: i32,
+    cells: usize,
+) -> std::ops::Range<usize> {
+    let first = (-origin).div_euclid(cell_size).max(0) as usize;
+    let last = (canvas_size - origin + cell_size - 1)
+        .div_euclid(cell_size)
+        .max(0) as usize;
+    first.min(cells)..last.min(cells)
+}
@@ -0,0 +1,534 @@
+//! Input abstraction so user code doesn't depend on the windowing library
+//!
+//! Render2D::update receives a `&dyn Input`, the render backend translates its own key
+//! codes into the crate's Key using KEY_MAP (`window` feature) or WEB_KEY_MAP (`wasm`).
+//! With the `gamepad` feature the first connected gamepad is read too (through gilrs),
+//! without it gamepad buttons are never pressed and axes stay at 0.0.
+//! Mouse positions are given in canvas pixels, whatever the size of the window.
+//!
+//! Example usage:
+//!    if input.key_pressed(Key::W) {
+//!        player.jump();
+//!    }
+//!
+use crate::math::Point2D;
+#[cfg(feature = "window")]
+use glium::glutin::event::VirtualKeyCode;
+#[cfg(feature = "window")]
+use winit_input_helper::{TextChar, WinitInputHelper};
+
+/// Keyboard keys known by the crate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Key {
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Key0,
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Up,
+    Down,
+    Left,
+    Right,
+    Space,
+    Enter,
+    Escape,
+    Tab,
+    Backspace,
+    LShift,
+    RShift,
+    LControl,
+    RControl,
+    LAlt,
+    RAlt,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+}
+
+/// Gamepad buttons known by the crate, named after their position (Xbox A is South)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+    LeftBumper,
+    RightBumper,
+    Select,
+    Start,
+}
+
+/// Number of gamepad buttons known by the crate
+#[cfg(feature = "gamepad")]
+const BUTTON_COUNT: usize = 12;
+
+/// Gamepad analog stick axes, values go from -1.0 to 1.0 (up and right are positive)
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+/// Mouse buttons known by the crate
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+}
+
+impl MouseButton {
+    /// Button index used by winit_input_helper
+    #[cfg(feature = "window")]
+    fn index(self) -> usize {
+        match self {
+            MouseButton::Left => 0,
+            MouseButton::Right => 1,
+            MouseButton::Middle => 2,
+        }
+    }
+}
+
+/// Number of gamepad axes known by the crate
+#[cfg(feature = "gamepad")]
+const AXIS_COUNT: usize = 4;
+
+/// Keyboard and gamepad state for the current frame
+///
+/// Holding a key for three frames and letting it go gives:
+///
+/// | frame          | 1     | 2     | 3     | 4     |
+/// |----------------|-------|-------|-------|-------|
+/// | `key_pressed`  | true  | false | false | false |
+/// | `key_held`     | true  | true  | true  | false |
+/// | `key_released` | false | false | false | true  |
+pub trait Input {
+    /// True only on the frame the key went down (edge triggered)
+    /// Use for one shot actions: jumping, menus, turning in grid based games.
+    fn key_pressed(&self, key: Key) -> bool;
+    /// True on every frame the key is down, including the one it was pressed on
+    /// Use for continuous actions, scale the movement by delta_t.
+    fn key_held(&self, key: Key) -> bool;
+    /// True only on the frame the key went up (edge triggered)
+    /// Together with key_pressed allows charge mechanics: start on press, fire on release.
+    fn key_released(&self, key: Key) -> bool;
+
+    /// Characters typed since the last frame, in order, with the keyboard layout and shift
+    /// applied. Control characters (backspace, enter, ...) are left out, read them as keys.
+    fn typed_text(&self) -> String {
+        String::new()
+    }
+
+    /// Position of the mouse in canvas pixels, None when it is outside the window
+    fn mouse_position(&self) -> Option<Point2D> {
+        None
+    }
+    /// Same as key_pressed for a mouse button
+    fn mouse_pressed(&self, _button: MouseButton) -> bool {
+        false
+    }
+    /// Same as key_held for a mouse button
+    fn mouse_held(&self, _button: MouseButton) -> bool {
+        false
+    }
+    /// Same as key_released for a mouse button
+    fn mouse_released(&self, _button: MouseButton) -> bool {
+        false
+    }
+
+    /// Same as key_pressed for a button of the first connected gamepad
+    fn button_pressed(&self, _button: GamepadButton) -> bool {
+        false
+    }
+    /// Same as key_held for a button of the first connected gamepad
+    fn button_held(&self, _button: GamepadButton) -> bool {
+        false
+    }
+    /// Same as key_released for a button of the first connected gamepad
+    fn button_released(&self, _button: GamepadButton) -> bool {
+        false
+    }
+    /// Position of an analog stick axis of the first connected gamepad, -1.0 to 1.0
+    fn axis(&self, _axis: GamepadAxis) -> f32 {
+        0.0
+    }
+}
+
+/// Mapping between the crate keys and the winit (glium) key codes
+#[cfg(feature = "window")]
+pub const KEY_MAP: [(Key, VirtualKeyCode); 63] = [
+    (Key::A, VirtualKeyCode::A),
+    (Key::B, VirtualKeyCode::B),
+    (Key::C, VirtualKeyCode::C),
+    (Key::D, VirtualKeyCode::D),
+    (Key::E, VirtualKeyCode::E),
+    (Key::F, VirtualKeyCode::F),
+    (Key::G, VirtualKeyCode::G),
+    (Key::H, VirtualKeyCode::H),
+    (Key::I, VirtualKeyCode::I),
+    (Key::J, VirtualKeyCode::J),
+    (Key::K, VirtualKeyCode::K),
+    (Key::L, VirtualKeyCode::L),
+    (Key::M, VirtualKeyCode::M),
+    (Key::N, VirtualKeyCode::N),
+    (Key::O, VirtualKeyCode::O),
+    (Key::P, VirtualKeyCode::P),
+    (Key::Q, VirtualKeyCode::Q),
+    (Key::R, VirtualKeyCode::R),
+    (Key::S, VirtualKeyCode::S),
+    (Key::T, VirtualKeyCode::T),
+    (Key::U, VirtualKeyCode::U),
+    (Key::V, VirtualKeyCode::V),
+    (Key::W, VirtualKeyCode::W),
+    (Key::X, VirtualKeyCode::X),
+    (Key::Y, VirtualKeyCode::Y),
+    (Key::Z, VirtualKeyCode::Z),
+    (Key::Key0, VirtualKeyCode::Key0),
+    (Key::Key1, VirtualKeyCode::Key1),
+    (Key::Key2, VirtualKeyCode::Key2),
+    (Key::Key3, VirtualKeyCode::Key3),
+    (Key::Key4, VirtualKeyCode::Key4),
+    (Key::Key5, VirtualKeyCode::Key5),
+    (Key::Key6, VirtualKeyCode::Key6),
+    (Key::Key7, VirtualKeyCode::Key7),
+    (Key::Key8, VirtualKeyCode::Key8),
+    (Key::Key9, VirtualKeyCode::Key9),
+    (Key::Up, VirtualKeyCode::Up),
+    (Key::Down, VirtualKeyCode::Down),
+    (Key::Left, VirtualKeyCode::Left),
+    (Key::Right, VirtualKeyCode::Right),
+    (Key::Space, VirtualKeyCode::Space),
+    (Key::Enter, VirtualKeyCode::Return),
+    (Key::Escape, VirtualKeyCode::Escape),
+    (Key::Tab, VirtualKeyCode::Tab),
+    (Key::Backspace, VirtualKeyCode::Back),
+    (Key::LShift, VirtualKeyCode::LShift),
+    (Key::RShift, VirtualKeyCode::RShift),
+    (Key::LControl, VirtualKeyCode::LControl),
+    (Key::RControl, VirtualKeyCode::RControl),
+    (Key::LAlt, VirtualKeyCode::LAlt),
+    (Key::RAlt, VirtualKeyCode::RAlt),
+    (Key::F1, VirtualKeyCode::F1),
+    (Key::F2, VirtualKeyCode::F2),
+    (Key::F3, VirtualKeyCode::F3),
+    (Key::F4, VirtualKeyCode::F4),
+    (Key::F5, VirtualKeyCode::F5),
+    (Key::F6, VirtualKeyCode::F6),
+    (Key::F7, VirtualKeyCode::F7),
+    (Key::F8, VirtualKeyCode::F8),
+    (Key::F9, VirtualKeyCode::F9),
+    (Key::F10, VirtualKeyCode::F10),
+    (Key::F11, VirtualKeyCode::F11),
+    (Key::F12, VirtualKeyCode::F12),
+];
+
+#[cfg(feature = "window")]
+impl Key {
+    /// Translate into the winit key code
+    pub fn to_virtual_keycode(self) -> VirtualKeyCode {
+        KEY_MAP
+            .iter()
+            .find(|(key, _)| *key == self)
+            .map(|(_, code)| *code)
+            .expect("Every key is in KEY_MAP")
+    }
+
+    /// Translate from a winit key code, None for keys the crate doesn't know about
+    pub fn from_virtual_keycode(code: VirtualKeyCode) -> Option<Key> {
+        KEY_MAP
+            .iter()
+            .find(|(_, other)| *other == code)
+            .map(|(key, _)| *key)
+    }
+}
+
+/// Mapping between the crate keys and the browser `KeyboardEvent.code` values
+/// Codes name the physical key, so they don't change with the keyboard layout.
+pub const WEB_KEY_MAP: [(Key, &str); 63] = [
+    (Key::A, "KeyA"),
+    (Key::B, "KeyB"),
+    (Key::C, "KeyC"),
+    (Key::D, "KeyD"),
+    (Key::E, "KeyE"),
+    (Key::F, "KeyF"),
+    (Key::G, "KeyG"),
+    (Key::H, "KeyH"),
+    (Key::I, "KeyI"),
+    (Key::J, "KeyJ"),
+    (Key::K, "KeyK"),
+    (Key::L, "KeyL"),
+    (Key::M, "KeyM"),
+    (Key::N, "KeyN"),
+    (Key::O, "KeyO"),
+    (Key::P, "KeyP"),
+    (Key::Q, "KeyQ"),
+    (Key::R, "KeyR"),
+    (Key::S, "KeyS"),
+    (Key::T, "KeyT"),
+    (Key::U, "KeyU"),
+    (Key::V, "KeyV"),
+    (Key::W, "KeyW"),
+    (Key::X, "KeyX"),
+    (Key::Y, "KeyY"),
+    (Key::Z, "KeyZ"),
+    (Key::Key0, "Digit0"),
+    (Key::Key1, "Digit1"),
+    (Key::Key2, "Digit2"),
+    (Key::Key3, "Digit3"),
+    (Key::Key4, "Digit4"),
+    (Key::Key5, "Digit5"),
+    (Key::Key6, "Digit6"),
+    (Key::Key7, "Digit7"),
+    (Key::Key8, "Digit8"),
+    (Key::Key9, "Digit9"),
+    (Key::Up, "ArrowUp"),
+    (Key::Down, "ArrowDown"),
+    (Key::Left, "ArrowLeft"),
+    (Key::Right, "ArrowRight"),
+    (Key::Space, "Space"),
+    (Key::Enter, "Enter"),
+    (Key::Escape, "Escape"),
+    (Key::Tab, "Tab"),
+    (Key::Backspace, "Backspace"),
+    (Key::LShift, "ShiftLeft"),
+    (Key::RShift, "ShiftRight"),
+    (Key::LControl, "ControlLeft"),
+    (Key::RControl, "ControlRight"),
+    (Key::LAlt, "AltLeft"),
+    (Key::RAlt, "AltRight"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+];
+
+impl Key {
+    /// Translate from a browser `KeyboardEvent.code`, None for keys the crate doesn't know about
+    pub fn from_web_code(code: &str) -> Option<Key> {
+        WEB_KEY_MAP
+            .iter()
+            .find(|(_, other)| *other == code)
+            .map(|(key, _)| *key)
+    }
+}
+
+#[cfg(feature = "window")]
+impl Input for WinitInputHelper {
+    fn key_pressed(&self, key: Key) -> bool {
+        WinitInputHelper::key_pressed(self, key.to_virtual_keycode())
+    }
+
+    fn key_held(&self, key: Key) -> bool {
+        WinitInputHelper::key_held(self, key.to_virtual_keycode())
+    }
+
+    fn key_released(&self, key: Key) -> bool {
+        WinitInputHelper::key_released(self, key.to_virtual_keycode())
+    }
+
+    fn typed_text(&self) -> String {
+        self.text()
+            .into_iter()
+            .filter_map(|text| match text {
+                TextChar::Char(character) if !character.is_control() => Some(character),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// In window pixels, the render loop scales it when the canvas is stretched
+    fn mouse_position(&self) -> Option<Point2D> {
+        self.mouse()
+            .map(|(x, y)| Point2D::new(x.floor() as i32, y.floor() as i32))
+    }
+
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        WinitInputHelper::mouse_pressed(self, button.index())
+    }
+
+    fn mouse_held(&self, button: MouseButton) -> bool {
+        WinitInputHelper::mouse_held(self, button.index())
+    }
+
+    fn mouse_released(&self, button: MouseButton) -> bool {
+        WinitInputHelper::mouse_released(self, button.index())
+    }
+}
+
+/// Mapping between the crate gamepad buttons and the gilrs ones
+#[cfg(feature = "gamepad")]
+const BUTTON_MAP: [(GamepadButton, gilrs::Button); BUTTON_COUNT] = [
+    (GamepadButton::South, gilrs::Button::South),
+    (GamepadButton::East, gilrs::Button::East),
+    (GamepadButton::North, gilrs::Button::North),
+    (GamepadButton::West, gilrs::Button::West),
+    (GamepadButton::DPadUp, gilrs::Button::DPadUp),
+    (GamepadButton::DPadDown, gilrs::Button::DPadDown),
+    (GamepadButton::DPadLeft, gilrs::Button::DPadLeft),
+    (GamepadButton::DPadRight, gilrs::Button::DPadRight),
+    (GamepadButton::LeftBumper, gilrs::Button::LeftTrigger),
+    (GamepadButton::RightBumper, gilrs::Button::RightTrigger),
+    (GamepadButton::Select, gilrs::Button::Select),
+    (GamepadButton::Start, gilrs::Button::Start),
+];
+
+/// Mapping between the crate gamepad axes and the gilrs ones
+#[cfg(feature = "gamepad")]
+const AXIS_MAP: [(GamepadAxis, gilrs::Axis); AXIS_COUNT] = [
+    (GamepadAxis::LeftStickX, gilrs::Axis::LeftStickX),
+    (GamepadAxis::LeftStickY, gilrs::Axis::LeftStickY),
+    (GamepadAxis::RightStickX, gilrs::Axis::RightStickX),
+    (GamepadAxis::RightStickY, gilrs::Axis::RightStickY),
+];
+
+/// State of the first connected gamepad, read through gilrs
+/// The render loop updates it once per frame, other gamepads are ignored.
+#[cfg(feature = "gamepad")]
+pub struct Gamepad {
+    gilrs: gilrs::Gilrs,
+    active: Option<gilrs::GamepadId>,
+    held: [bool; BUTTON_COUNT],
+    pressed: [bool; BUTTON_COUNT],
+    released: [bool; BUTTON_COUNT],
+    axes: [f32; AXIS_COUNT],
+}
+
+#[cfg(feature = "gamepad")]
+impl Gamepad {
+    /// Start listening to gamepads, None when gamepads are not supported on this system
+    pub fn new() -> Option<Self> {
+        let gilrs = gilrs::Gilrs::new().ok()?;
+        let active = gilrs.gamepads().next().map(|(id, _)| id);
+        Some(Self {
+            gilrs,
+            active,
+            held: [false; BUTTON_COUNT],
+            pressed: [false; BUTTON_COUNT],
+            released: [false; BUTTON_COUNT],
+            axes: [0.0; AXIS_COUNT],
+        })
+    }
+
+    /// Process the pending gamepad events, called once per frame before update
+    pub fn update(&mut self) {
+        self.pressed = [false; BUTTON_COUNT];
+        self.released = [false; BUTTON_COUNT];
+        while let Some(gilrs::Event { id, event, .. }) = self.gilrs.next_event() {
+            // the first gamepad sending events is the one we listen to
+            if self.active.is_none() {
+                self.active = Some(id);
+            }
+            if self.active != Some(id) {
+                continue;
+            }
+            match event {
+                gilrs::EventType::ButtonPressed(button, _) => {
+                    if let Some(index) = button_index(button) {
+                        self.held[index] = true;
+                        self.pressed[index] = true;
+                    }
+                }
+                gilrs::EventType::ButtonReleased(button, _) => {
+                    if let Some(index) = button_index(button) {
+                        self.held[index] = false;
+                        self.released[index] = true;
+                    }
+                }
+                gilrs::EventType::AxisChanged(axis, value, _) => {
+                    if let Some(index) = AXIS_MAP.iter().position(|(_, other)| *other == axis) {
+                        self.axes[index] = value;
+                    }
+                }
+                gilrs::EventType::Disconnected => {
+                    self.active = None;
+                    self.held = [false; BUTTON_COUNT];
+                    self.axes = [0.0; AXIS_COUNT];
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// True only on the frame the button went down
+    pub fn button_pressed(&self, button: GamepadButton) -> bool {
+        self.pressed[button as usize]
+    }
+
+    /// True on every frame the button is down
+    pub fn button_held(&self, button: GamepadButton) -> bool {
+        self.held[button as usize]
+    }
+
+    /// True only on the frame the button went up
+    pub fn button_released(&self, button: GamepadButton) -> bool {
+        self.released[button as usize]
+    }
+
+    /// Position of an analog stick axis, -1.0 to 1.0
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes[axis as usize]
+    }
+}
+
+/// Helper index of a gilrs button in the state arrays, None for buttons we don't map
+#[cfg(feature = "gamepad")]
+fn button_index(button: gilrs::Button) -> Option<usize> {
+    BUTTON_MAP
+        .iter()
+        .find(|(_, other)| *other == button)
+        .map(|(button, _)| *button as usize)
+}
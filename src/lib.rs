@@ -1,6 +1,14 @@
 pub mod canvas;
 pub mod color;
+pub mod compress;
 pub mod image;
+pub mod input;
 pub mod math;
+pub mod particles;
 pub mod render;
+#[cfg(test)]
+mod test;
+pub mod ui;
 pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -5,11 +5,15 @@ use graphics::math::Point2D;
 use graphics::render::Render2D;
 fn main() {
     let graphics = Graphics;
-    graphics.render();
+    graphics.render().expect("Could not start rendering");
 }
 pub struct Graphics;
 
 impl Render2D for Graphics {
+    // everything is drawn once on setup, keep it on screen
+    fn clear_color(&mut self) -> Option<Color> {
+        None
+    }
     fn setup(&mut self, canvas: &mut Canvas) -> bool {
         // canvas.fill(Color::BLACK);
         canvas.fill_triangle(
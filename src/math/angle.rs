@@ -0,0 +1,59 @@
+use std::ops::{Add, Neg, Sub};
+
+/// An angle that knows its unit
+/// Primitives take plain f32 angles, some in radians (Transform::Rotate, Mat3x3::rotation,
+/// Mat4x4::rotate_x/y/z) and some in degrees (Canvas::polygon). Build an Angle from whatever
+/// unit you have and ask for the one the primitive expects.
+///
+/// Example usage:
+///    let angle = Angle::degrees(90.0);
+///    let rotation = Mat3x3::rotation(angle.as_radians());
+#[derive(Debug, Default, Copy, Clone, PartialEq, PartialOrd)]
+pub struct Angle {
+    radians: f32,
+}
+
+impl Angle {
+    /// Create an angle from a value in degrees
+    pub fn degrees(degrees: f32) -> Self {
+        Self {
+            radians: super::to_radians(degrees),
+        }
+    }
+
+    /// Create an angle from a value in radians
+    pub fn radians(radians: f32) -> Self {
+        Self { radians }
+    }
+
+    /// Angle value in radians
+    pub fn as_radians(&self) -> f32 {
+        self.radians
+    }
+
+    /// Angle value in degrees
+    pub fn as_degrees(&self) -> f32 {
+        super::to_degrees(self.radians)
+    }
+}
+
+impl Add for Angle {
+    type Output = Angle;
+    fn add(self, rhs: Self) -> Self::Output {
+        Angle::radians(self.radians + rhs.radians)
+    }
+}
+
+impl Sub for Angle {
+    type Output = Angle;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Angle::radians(self.radians - rhs.radians)
+    }
+}
+
+impl Neg for Angle {
+    type Output = Angle;
+    fn neg(self) -> Self::Output {
+        Angle::radians(-self.radians)
+    }
+}
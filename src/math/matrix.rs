@@ -1,8 +1,13 @@
 use crate::math::vector::Vector3D;
+use crate::math::vector::Vector4D;
 use crate::math::*;
 
 /// A simple Matrix 3 by 3 struct
+/// Used for 2D affine transforms with column vectors: points are transformed as `M * p`
+/// (see transform_point) so the translation lives in the last column and in `a * b`
+/// the transform `b` is applied first.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(unused_variables, dead_code)]
 pub struct Mat3x3<T>
 where
@@ -63,20 +68,6 @@ where
         result.inner[1][1] = cy;
         result
     }
-    /// create a rotate matrix with the given x and y transform values
-    pub fn rotate(alpha: f32) -> Mat3x3<f32>
-    where
-        T: Zero<Type = T> + Unit<Type = T> + LossyCast<f32> + Neg<Output = T>,
-        f32: LossyCast<T>,
-    {
-        let mut result = Mat3x3::<f32>::identity();
-        result.inner[0][0] = alpha.cos();
-        result.inner[0][1] = -alpha.sin();
-
-        result.inner[1][0] = alpha.sin();
-        result.inner[1][1] = alpha.cos();
-        result
-    }
 
     /// Calculate the determinant of this matrix
     pub fn det(&self) -> T
@@ -93,6 +84,7 @@ where
 
     /// Calculate the inverse of this matrix
     /// <https://mathworld.wolfram.com/MatrixInverse.html>
+    /// Singular matrices give inf/NaN elements (or panic for integers), see try_inverse.
     pub fn inverse(&self) -> Self
     where
         T: Add<Output = T>
@@ -131,6 +123,32 @@ where
         result / self.det()
     }
 
+    /// Inverse of this matrix, None when it is singular
+    /// `epsilon` is relative to the scale of the matrix: the determinant is compared with
+    /// the product of the lengths of the rows, the largest it can be for those rows
+    /// (Hadamard's inequality), so that matrices of small but valid scales still have an
+    /// inverse. A tolerance such as 1e-6 suits f32 matrices.
+    pub fn try_inverse(&self, epsilon: T) -> Option<Self>
+    where
+        T: Float,
+    {
+        if is_singular(&self.inner, self.det(), epsilon) {
+            return None;
+        }
+        Some(self.inverse())
+    }
+
+    /// Swap rows and columns
+    pub fn transpose(&self) -> Self {
+        let mut inner = self.inner;
+        for (row, values) in inner.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.inner[col][row];
+            }
+        }
+        Self { inner }
+    }
+
     /// Transform a single point given this matrix (useful on affine transforms)
     pub fn transform_point(&self, point: Vector3D<T>) -> Vector3D<T>
     where
@@ -147,6 +165,20 @@ where
             + self.inner[2][2] * point.z();
         Vector3D::<T>::new(x, y, z)
     }
+    /// Compare with another matrix allowing each element to differ by up to `epsilon`
+    /// Float matrices built by different operations (e.g. through inverse) rarely compare
+    /// exactly equal, use this instead of `==`.
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool
+    where
+        T: Sub<Output = T> + Absolute + PartialOrd,
+    {
+        approx_eq(
+            self.inner.iter().flatten().copied(),
+            other.inner.iter().flatten().copied(),
+            epsilon,
+        )
+    }
+
     /// Useful method to convert matrix to i32 matrix
     pub fn to_i32(&self) -> Mat3x3<i32>
     where
@@ -176,6 +208,43 @@ where
     }
 }
 
+/// Rotations only make sense for floating point matrices
+impl Mat3x3<f32> {
+    /// Create a rotation matrix for the given angle in radians
+    /// Rotates from the x axis towards the y axis, same as Mat4x4::rotate_z.
+    pub fn rotation(radians: f32) -> Self {
+        let mut result = Self::identity();
+        result.inner[0][0] = radians.cos();
+        result.inner[0][1] = -radians.sin();
+
+        result.inner[1][0] = radians.sin();
+        result.inner[1][1] = radians.cos();
+        result
+    }
+
+    /// Affine transform applying `scale`, then `rotation` (radians), then `translation`
+    /// Same as `translate * rotation * scale`, the inverse of decompose.
+    pub fn compose(translation: FVec2D, rotation: f32, scale: FVec2D) -> Self {
+        Self::translate(translation.x(), translation.y())
+            * Self::rotation(rotation)
+            * Self::scale(scale.x(), scale.y())
+    }
+
+    /// Split an affine transform into its translation, rotation (radians) and scale
+    /// Composing them again with compose gives back the matrix, as long as it has no shear.
+    /// A mirrored transform comes back with a negative y scale.
+    pub fn decompose(&self) -> (FVec2D, f32, FVec2D) {
+        let m = &self.inner;
+        let translation = FVec2D::new(m[0][2], m[1][2]);
+        // the first column is the x axis: rotated and scaled by the x scale
+        let scale_x = (m[0][0] * m[0][0] + m[1][0] * m[1][0]).sqrt();
+        let rotation = m[1][0].atan2(m[0][0]);
+        let det = m[0][0] * m[1][1] - m[0][1] * m[1][0];
+        let scale_y = if scale_x != 0.0 { det / scale_x } else { 0.0 };
+        (translation, rotation, FVec2D::new(scale_x, scale_y))
+    }
+}
+
 /// Make it so that we can use into() and from() to convert from 2D array
 impl<T> From<[[T; 3]; 3]> for Mat3x3<T>
 where
@@ -380,7 +449,12 @@ where
 }
 
 /// A simple Matrix 4 by 4 struct
+/// Used for 3D transforms with row vectors: points are transformed as `p * M`
+/// (see vector_multiply and transform_vec4) so the translation lives in the last row
+/// and in `a * b` the transform `a` is applied first.
+/// This is the transpose of the Mat3x3 convention, use transpose() to move between them.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 #[allow(unused_variables, dead_code)]
 pub struct Mat4x4<T>
 where
@@ -460,6 +534,17 @@ where
 
         a * a_mat.det() - b * b_mat.det() + c * c_mat.det() - d * d_mat.det()
     }
+    /// Swap rows and columns
+    pub fn transpose(&self) -> Self {
+        let mut inner = self.inner;
+        for (row, values) in inner.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.inner[col][row];
+            }
+        }
+        Self { inner }
+    }
+
     /// Multiply by a vector  3D (useful for projection)
     pub fn vector_multiply(&self, point: Vector3D<T>) -> Vector3D<T>
     where
@@ -495,6 +580,26 @@ where
         }
     }
 
+    /// Multiply by a homogeneous vector, returns the full result without the perspective divide
+    /// Useful to clip in clip space before dividing by w.
+    pub fn transform_vec4(&self, v: Vector4D<T>) -> Vector4D<T>
+    where
+        T: Add<Output = T> + Mul<Output = T>,
+    {
+        let column = |c: usize| {
+            self.inner[0][c] * v.x
+                + self.inner[1][c] * v.y
+                + self.inner[2][c] * v.z
+                + self.inner[3][c] * v.w
+        };
+        Vector4D {
+            x: column(0),
+            y: column(1),
+            z: column(2),
+            w: column(3),
+        }
+    }
+
     pub fn projected(aspect: T, fov: T, far: T, near: T) -> Self
     where
         T: Add<Output = T>
@@ -611,7 +716,8 @@ where
         }
     }
 
-    // Determine the inverse of this matrix
+    /// Determine the inverse of this matrix
+    /// Singular matrices give inf/NaN elements (or panic for integers), see try_inverse.
     pub fn inverse(&self) -> Self
     where
         T: Add<Output = T>
@@ -709,6 +815,30 @@ where
         result / det
     }
 
+    /// Inverse of this matrix, None when it is singular, see Mat3x3::try_inverse
+    pub fn try_inverse(&self, epsilon: T) -> Option<Self>
+    where
+        T: Float,
+    {
+        if is_singular(&self.inner, self.det(), epsilon) {
+            return None;
+        }
+        Some(self.inverse())
+    }
+
+    /// Compare with another matrix allowing each element to differ by up to `epsilon`
+    /// See Mat3x3::approx_eq.
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool
+    where
+        T: Sub<Output = T> + Absolute + PartialOrd,
+    {
+        approx_eq(
+            self.inner.iter().flatten().copied(),
+            other.inner.iter().flatten().copied(),
+            epsilon,
+        )
+    }
+
     /// Useful method to convert matrix to i32 matrix
     pub fn to_i32(&self) -> Mat4x4<i32>
     where
@@ -738,6 +868,56 @@ where
     }
 }
 
+/// Decomposition only makes sense for floating point matrices
+impl Mat4x4<f32> {
+    /// Transform applying `scale`, then the `rotation` angles (radians) around x, y and z
+    /// in that order, then `translation`. The inverse of decompose.
+    pub fn compose(translation: FVec3D, rotation: FVec3D, scale: FVec3D) -> Self {
+        Self::scale(scale.x(), scale.y(), scale.z())
+            * Self::rotate_x(rotation.x())
+            * Self::rotate_y(rotation.y())
+            * Self::rotate_z(rotation.z())
+            * Self::translate(translation.x(), translation.y(), translation.z())
+    }
+
+    /// Split a transform into its translation, rotation angles (radians, see compose) and
+    /// scale. Composing them again gives back the matrix, as long as it has no shear and
+    /// no projection. A mirrored transform comes back with a negative z scale.
+    pub fn decompose(&self) -> (FVec3D, FVec3D, FVec3D) {
+        let m = &self.inner;
+        let translation = FVec3D::new(m[3][0], m[3][1], m[3][2]);
+        // each of the first three rows is an axis: rotated and scaled by its scale
+        let length = |row: &[f32; 4]| (row[0] * row[0] + row[1] * row[1] + row[2] * row[2]).sqrt();
+        let mut scale = FVec3D::new(length(&m[0]), length(&m[1]), length(&m[2]));
+        let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+        if det < 0.0 {
+            scale.z = -scale.z;
+        }
+
+        // rotation rows, with the scale taken out
+        let mut r = [[0.0; 3]; 3];
+        for (row, axis_scale) in [scale.x(), scale.y(), scale.z()].iter().enumerate() {
+            if *axis_scale != 0.0 {
+                for column in 0..3 {
+                    r[row][column] = m[row][column] / axis_scale;
+                }
+            }
+        }
+        // R = Rx * Ry * Rz gives r[0][2] = sin(y), r[1][2] = sin(x)cos(y),
+        // r[2][2] = cos(x)cos(y), r[0][1] = cos(y)sin(z) and r[0][0] = cos(y)cos(z)
+        let y = r[0][2].clamp(-1.0, 1.0).asin();
+        let rotation = if y.cos() > 1e-6 {
+            FVec3D::new(r[1][2].atan2(r[2][2]), y, r[0][1].atan2(r[0][0]))
+        } else {
+            // gimbal lock: only x + z (or x - z) is known, put it all in x
+            FVec3D::new((-r[2][1]).atan2(r[1][1]), y, 0.0)
+        };
+        (translation, rotation, scale)
+    }
+}
+
 /// Make it so that we can use into() and from() to convert from 2D array
 impl<T> From<[[T; 4]; 4]> for Mat4x4<T>
 where
@@ -952,14 +1132,27 @@ where
 {
     type Output = Mat4x4<T>;
     fn mul(self, other: Mat4x4<T>) -> Self::Output {
+        // result[row][col] = sum of self[row][k] * other[k][col]
         let mut inner: [[T; 4]; 4] = [[T::zero(); 4]; 4];
-        for (i, item) in inner.iter_mut().enumerate() {
-            for (row, _) in self.inner.iter().enumerate() {
-                for (col, _) in self.inner[0].iter().enumerate() {
-                    item[row] += self.inner[i][col] * other.inner[col][row];
+        for (row, result_row) in inner.iter_mut().enumerate() {
+            for (col, value) in result_row.iter_mut().enumerate() {
+                for k in 0..4 {
+                    *value += self.inner[row][k] * other.inner[k][col];
                 }
             }
         }
         Mat4x4 { inner }
     }
 }
+
+/// Helper: whether a matrix with the rows `inner` and determinant `det` is singular
+/// |det| is at most the product of the lengths of the rows, which scales `epsilon`.
+fn is_singular<T: Float, const N: usize>(inner: &[[T; N]; N], det: T, epsilon: T) -> bool {
+    let bound = inner.iter().fold(T::one(), |product, row| {
+        let squared = row
+            .iter()
+            .fold(T::zero(), |sum, &value| sum + value * value);
+        product * squared.sqrt()
+    });
+    det.abs() <= epsilon * bound
+}
@@ -1,11 +1,14 @@
+pub mod angle;
 pub mod matrix;
 #[cfg(test)]
 mod test;
 pub mod vector;
 
+pub use crate::math::angle::Angle;
 pub use crate::math::matrix::Mat3x3;
 pub use crate::math::vector::Vector2D;
 pub use crate::math::vector::Vector3D;
+pub use crate::math::vector::Vector4D;
 pub use std::ops::Add;
 pub use std::ops::AddAssign;
 pub use std::ops::Div;
@@ -22,6 +25,9 @@ pub type IVec2D = crate::math::vector::Vector2D<i32>;
 /// Integer vector type.
 pub type Point2D = crate::math::vector::Vector2D<i32>;
 
+/// Unsigned integer vector type, see Vector2D for the operations it lacks
+pub type UVec2D = crate::math::vector::Vector2D<u32>;
+
 /// Floating point vector type
 pub type FVec2D = crate::math::vector::Vector2D<f32>;
 
@@ -31,8 +37,21 @@ pub type FVec3D = crate::math::vector::Vector3D<f32>;
 /// Integer 3D vector type
 pub type IVec3D = crate::math::vector::Vector3D<i32>;
 
+/// Floating point 4D (homogeneous) vector type
+pub type FVec4D = crate::math::vector::Vector4D<f32>;
+
 pub type FMat3 = crate::math::matrix::Mat3x3<f32>;
 
+/// Convert an angle in degrees to radians
+pub fn to_radians(degrees: f32) -> f32 {
+    degrees * std::f32::consts::PI / 180.0
+}
+
+/// Convert an angle in radians to degrees
+pub fn to_degrees(radians: f32) -> f32 {
+    radians * 180.0 / std::f32::consts::PI
+}
+
 /// Determine the minimum among two numbers a and b
 pub fn min<T>(a: T, b: T) -> T
 where
@@ -56,11 +75,24 @@ where
         b
     }
 }
+/// Helper: whether every value of `a` differs from the one at the same place in `b` by at
+/// most `epsilon`, behind the approx_eq of every vector and matrix type
+pub(crate) fn approx_eq<T>(
+    a: impl IntoIterator<Item = T>,
+    b: impl IntoIterator<Item = T>,
+    epsilon: T,
+) -> bool
+where
+    T: Sub<Output = T> + Absolute + PartialOrd + Copy,
+{
+    a.into_iter().zip(b).all(|(a, b)| (a - b).abs() <= epsilon)
+}
+
 /// Inner trait to implement all operations required for generic vector types.
 ///  Restricts operations to only implemented primitive types
 #[doc(hidden)]
 pub trait SimpleMathTrait:
-    Add + Div + Mul + Sub + Sized + Copy + MulAssign + DivAssign + SquareRoot + Neg + Zero
+    Add + Div + Mul + Sub + Sized + Copy + MulAssign + DivAssign + SquareRoot + Zero
 {
 }
 
@@ -71,6 +103,34 @@ pub trait SquareRoot<Rhs = Self> {
     fn sqrt(self) -> Self;
 }
 
+/// Absolute value for the primitives used in vectors, see SquareRoot for the reason behind it
+pub trait Absolute {
+    fn abs(self) -> Self;
+}
+
+/// Rounding towards negative/positive infinity, integers are returned unchanged
+pub trait Rounding {
+    fn floor(self) -> Self;
+    fn ceil(self) -> Self;
+}
+
+/// Floating point primitives, for the operations that make no sense on integers
+/// (e.g. Mat3x3::try_inverse, an integer inverse would be truncated)
+pub trait Float:
+    SimpleMathTrait
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<Output = Self>
+    + Div<Output = Self>
+    + Zero<Type = Self>
+    + Unit<Type = Self>
+    + Absolute
+    + PartialOrd
+{
+}
+impl Float for f32 {}
+impl Float for f64 {}
+
 // Used to be able to transfor into polar coordinates
 pub trait PolarTrait<Rhs = Self>
 where
@@ -110,6 +170,85 @@ impl SquareRoot for f32 {
     }
 }
 
+impl Absolute for i8 {
+    fn abs(self) -> Self {
+        i8::abs(self)
+    }
+}
+impl Absolute for i16 {
+    fn abs(self) -> Self {
+        i16::abs(self)
+    }
+}
+impl Absolute for i32 {
+    fn abs(self) -> Self {
+        i32::abs(self)
+    }
+}
+impl Absolute for f64 {
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+impl Absolute for f32 {
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+}
+impl Absolute for u32 {
+    fn abs(self) -> Self {
+        self
+    }
+}
+impl Rounding for i8 {
+    fn floor(self) -> Self {
+        self
+    }
+    fn ceil(self) -> Self {
+        self
+    }
+}
+impl Rounding for i16 {
+    fn floor(self) -> Self {
+        self
+    }
+    fn ceil(self) -> Self {
+        self
+    }
+}
+impl Rounding for i32 {
+    fn floor(self) -> Self {
+        self
+    }
+    fn ceil(self) -> Self {
+        self
+    }
+}
+impl Rounding for u32 {
+    fn floor(self) -> Self {
+        self
+    }
+    fn ceil(self) -> Self {
+        self
+    }
+}
+impl Rounding for f64 {
+    fn floor(self) -> Self {
+        f64::floor(self)
+    }
+    fn ceil(self) -> Self {
+        f64::ceil(self)
+    }
+}
+impl Rounding for f32 {
+    fn floor(self) -> Self {
+        f32::floor(self)
+    }
+    fn ceil(self) -> Self {
+        f32::ceil(self)
+    }
+}
+
 impl PolarTrait for i8 {
     fn polar(self, angle: f32) -> (i8, i8) {
         (
@@ -155,6 +294,7 @@ impl PolarTrait for u32 {
 
 /// Used to be able to calculate the angle/direction
 pub trait AngleTrait {
+    /// Angle in radians from the positive x axis, in the range -PI to PI
     fn angle(&self) -> f32;
 }
 
@@ -1,7 +1,13 @@
+use crate::math::angle::Angle;
 use crate::math::matrix::Mat3x3;
 use crate::math::matrix::Mat4x4;
+use crate::math::AngleTrait;
 use crate::math::FVec2D;
+use crate::math::FVec3D;
+use crate::math::FVec4D;
 use crate::math::IVec2D;
+use crate::math::IVec3D;
+use crate::math::UVec2D;
 
 #[test]
 fn vec2d_crossed() {
@@ -95,7 +101,8 @@ fn matrix_3x3_inverse() {
     ]
     .into();
 
-    assert_eq!(a.inverse(), b);
+    assert!(a.inverse().approx_eq(&b, 1e-6));
+    assert!((a * a.inverse()).approx_eq(&Mat3x3::identity(), 1e-5));
 }
 
 #[test]
@@ -189,5 +196,329 @@ fn matrix_4x4_inverse() {
         [-28.0, 66.0, -5.0, 16.0],
     ]) / 361.0;
 
-    assert_eq!(a.inverse(), ia);
+    assert!(a.inverse().approx_eq(&ia, 1e-6));
+    assert!((a * a.inverse()).approx_eq(&Mat4x4::identity(), 1e-5));
+}
+
+#[test]
+fn vec4d_operations() {
+    let a = FVec4D::new(1.0, 2.0, 3.0, 4.0);
+    let b = FVec4D::new(4.0, 3.0, 2.0, 1.0);
+    assert_eq!(a + b, FVec4D::new(5.0, 5.0, 5.0, 5.0));
+    assert_eq!(a - b, FVec4D::new(-3.0, -1.0, 1.0, 3.0));
+    assert_eq!(a * 2.0, FVec4D::new(2.0, 4.0, 6.0, 8.0));
+    assert_eq!(-a, FVec4D::new(-1.0, -2.0, -3.0, -4.0));
+    assert_eq!(FVec4D::dot(a, b), 20.0);
+    assert_eq!(a.perspective_divide(), FVec3D::new(0.25, 0.5, 0.75));
+}
+
+#[test]
+fn matrix_4x4_transform_vec4() {
+    let point = FVec3D::new(1.0, 2.0, 3.0);
+    let translate = Mat4x4::<f32>::translate(1.0, 1.0, 1.0);
+    let moved = translate.transform_vec4(FVec4D::from_point(point));
+    assert_eq!(moved, FVec4D::new(2.0, 3.0, 4.0, 1.0));
+
+    // the projection keeps w around, dividing matches vector_multiply
+    let projection = Mat4x4::<f32>::projected(1.0, 90.0, 1000.0, 0.1);
+    let clip = projection.transform_vec4(FVec4D::from_point(point));
+    assert_eq!(clip.w(), 3.0);
+    assert_eq!(clip.perspective_divide(), projection.vector_multiply(point));
+}
+
+#[test]
+fn matrix_3x3_column_vector_convention() {
+    let translate = Mat3x3::<f32>::translate(10.0, 20.0);
+    let scale = Mat3x3::<f32>::scale(2.0, 3.0);
+    let point = FVec3D::new(1.0, 1.0, 1.0);
+    assert_eq!(
+        translate.transform_point(point),
+        FVec3D::new(11.0, 21.0, 1.0)
+    );
+
+    // M * p: the right most matrix is applied first (scale, then translate)
+    let combined = translate * scale;
+    assert_eq!(
+        combined.transform_point(point),
+        FVec3D::new(12.0, 23.0, 1.0)
+    );
+    assert_eq!(
+        combined.transform_point(point),
+        translate.transform_point(scale.transform_point(point))
+    );
+}
+
+#[test]
+fn matrix_4x4_row_vector_convention() {
+    let translate = Mat4x4::<f32>::translate(10.0, 20.0, 30.0);
+    let scale = Mat4x4::<f32>::scale(2.0, 3.0, 4.0);
+    let point = FVec3D::new(1.0, 1.0, 1.0);
+    assert_eq!(
+        translate.vector_multiply(point),
+        FVec3D::new(11.0, 21.0, 31.0)
+    );
+
+    // p * M: the left most matrix is applied first (scale, then translate)
+    let combined = scale * translate;
+    assert_eq!(
+        combined.vector_multiply(point),
+        FVec3D::new(12.0, 23.0, 34.0)
+    );
+    assert_eq!(
+        combined.vector_multiply(point),
+        translate.vector_multiply(scale.vector_multiply(point))
+    );
+}
+
+#[test]
+fn matrix_transpose() {
+    let a: Mat3x3<i32> = [[10, 20, 10], [4, 5, 6], [2, 3, 5]].into();
+    let b: Mat3x3<i32> = [[3, 2, 4], [3, 3, 9], [4, 4, 2]].into();
+    let at: Mat3x3<i32> = [[10, 4, 2], [20, 5, 3], [10, 6, 5]].into();
+    assert_eq!(a.transpose(), at);
+    assert_eq!((a * b).transpose(), b.transpose() * a.transpose());
+
+    let c: Mat4x4<i32> = [[5, 7, 9, 10], [2, 3, 3, 8], [8, 10, 2, 3], [3, 3, 4, 8]].into();
+    let d: Mat4x4<i32> = [
+        [3, 10, 12, 18],
+        [12, 1, 4, 9],
+        [9, 10, 12, 2],
+        [3, 12, 4, 10],
+    ]
+    .into();
+    assert_eq!(c.transpose().transpose(), c);
+    assert_eq!((c * d).transpose(), d.transpose() * c.transpose());
+}
+
+#[test]
+fn matrix_3x3_rotation() {
+    let rotation = Mat3x3::rotation(std::f32::consts::FRAC_PI_2);
+    let rotated = rotation.transform_point(FVec3D::new(1.0, 0.0, 1.0));
+    assert!(rotated.approx_eq(&FVec3D::new(0.0, 1.0, 1.0), 1e-6));
+
+    // same direction as rotating around the z axis in 3D
+    let rotated_z = Mat4x4::<f32>::rotate_z(std::f32::consts::FRAC_PI_2)
+        .vector_multiply(FVec3D::new(1.0, 0.0, 0.0));
+    assert!(rotated_z.approx_eq(&FVec3D::new(rotated.x(), rotated.y(), 0.0), 1e-6));
+}
+
+#[test]
+fn angle_conversions() {
+    assert_eq!(crate::math::to_radians(180.0), std::f32::consts::PI);
+    assert_eq!(crate::math::to_degrees(std::f32::consts::FRAC_PI_2), 90.0);
+
+    let right = Angle::degrees(90.0);
+    assert_eq!(right.as_radians(), std::f32::consts::FRAC_PI_2);
+    assert_eq!(Angle::radians(std::f32::consts::PI).as_degrees(), 180.0);
+    assert_eq!(right + right, Angle::degrees(180.0));
+    assert_eq!(-right, Angle::degrees(-90.0));
+}
+
+#[test]
+fn vector_index_access() {
+    let mut v2 = IVec2D::new(3, -4);
+    assert_eq!((0..2).map(|i| v2[i]).sum::<i32>(), -1);
+    v2[1] = 10;
+    assert_eq!(v2, IVec2D::new(3, 10));
+
+    let mut v3 = FVec3D::new(1.0, 2.0, 3.5);
+    assert_eq!((0..3).map(|i| v3[i]).sum::<f32>(), 6.5);
+    for i in 0..3 {
+        v3[i] *= 2.0;
+    }
+    assert_eq!(v3, FVec3D::new(2.0, 4.0, 7.0));
+
+    let v4 = FVec4D::new(1.0, 2.0, 3.0, 4.0);
+    assert_eq!((0..4).map(|i| v4[i]).sum::<f32>(), 10.0);
+}
+
+#[test]
+#[should_panic]
+fn vector_index_out_of_range() {
+    let v3 = FVec3D::new(1.0, 2.0, 3.0);
+    let _ = v3[3];
+}
+
+#[test]
+fn vector_component_mul_div() {
+    let scale = FVec2D::new(2.0, 0.5);
+    assert_eq!(
+        FVec2D::new(3.0, 8.0).component_mul(scale),
+        FVec2D::new(6.0, 4.0)
+    );
+    assert_eq!(
+        FVec2D::new(6.0, 4.0).component_div(scale),
+        FVec2D::new(3.0, 8.0)
+    );
+
+    let v = IVec3D::new(2, -3, 4);
+    assert_eq!(
+        v.component_mul(IVec3D::new(5, 2, -1)),
+        IVec3D::new(10, -6, -4)
+    );
+    assert_eq!(
+        IVec3D::new(10, -6, -4).component_div(IVec3D::new(5, 2, -1)),
+        v
+    );
+    // Differs from scalar multiplication unless all components are equal
+    assert_eq!(v.component_mul(IVec3D::new(3, 3, 3)), v * 3);
+}
+
+#[test]
+fn vector_component_min_max_abs_rounding() {
+    let a = FVec2D::new(-1.5, 2.25);
+    let b = FVec2D::new(0.5, -3.75);
+    assert_eq!(FVec2D::min_components(a, b), FVec2D::new(-1.5, -3.75));
+    assert_eq!(FVec2D::max_components(a, b), FVec2D::new(0.5, 2.25));
+    assert_eq!(b.abs(), FVec2D::new(0.5, 3.75));
+    assert_eq!(a.floor(), FVec2D::new(-2.0, 2.0));
+    assert_eq!(a.ceil(), FVec2D::new(-1.0, 3.0));
+
+    let c = FVec3D::new(-0.25, 4.5, -7.0);
+    let d = FVec3D::new(1.0, -4.5, -6.5);
+    assert_eq!(FVec3D::min_components(c, d), FVec3D::new(-0.25, -4.5, -7.0));
+    assert_eq!(FVec3D::max_components(c, d), FVec3D::new(1.0, 4.5, -6.5));
+    assert_eq!(c.abs(), FVec3D::new(0.25, 4.5, 7.0));
+    assert_eq!(c.floor(), FVec3D::new(-1.0, 4.0, -7.0));
+    assert_eq!(c.ceil(), FVec3D::new(-0.0, 5.0, -7.0));
+
+    // integers are already whole
+    let i = IVec2D::new(-3, 4);
+    assert_eq!(i.abs(), IVec2D::new(3, 4));
+    assert_eq!(i.floor(), i);
+    assert_eq!(i.ceil(), i);
+}
+
+#[test]
+fn vector2d_angle_quadrants_and_axes() {
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+    let close = |a: f32, b: f32| (a - b).abs() < 1e-6;
+
+    // axes
+    assert!(close(FVec2D::new(1.0, 0.0).angle(), 0.0));
+    assert!(close(FVec2D::new(0.0, 1.0).angle(), FRAC_PI_2));
+    assert!(close(FVec2D::new(-1.0, 0.0).angle(), PI));
+    assert!(close(FVec2D::new(0.0, -1.0).angle(), -FRAC_PI_2));
+
+    // one diagonal per quadrant
+    assert!(close(FVec2D::new(2.0, 2.0).angle(), FRAC_PI_4));
+    assert!(close(FVec2D::new(-2.0, 2.0).angle(), 3.0 * FRAC_PI_4));
+    assert!(close(FVec2D::new(-2.0, -2.0).angle(), -3.0 * FRAC_PI_4));
+    assert!(close(FVec2D::new(2.0, -2.0).angle(), -FRAC_PI_4));
+
+    // integer vectors agree with float ones
+    assert!(close(IVec2D::new(0, 5).angle(), FRAC_PI_2));
+    assert!(close(IVec2D::new(-3, -3).angle(), -3.0 * FRAC_PI_4));
+}
+
+#[test]
+fn unsigned_vector_operations() {
+    let a = UVec2D::new(3, 4);
+    let b = UVec2D::new(1, 2);
+    assert_eq!(a + b, UVec2D::new(4, 6));
+    assert_eq!(a - b, UVec2D::new(2, 2));
+    assert_eq!(a * 2, UVec2D::new(6, 8));
+    assert_eq!(a / 2, UVec2D::new(1, 2));
+    assert_eq!(UVec2D::dot(a, b), 11);
+    assert_eq!(a.length(), 5);
+    assert_eq!(a.component_mul(b), UVec2D::new(3, 8));
+    assert_eq!(
+        UVec2D::max_components(a, UVec2D::new(5, 0)),
+        UVec2D::new(5, 4)
+    );
+
+    let mut c = a;
+    c += b;
+    c -= UVec2D::new(4, 6);
+    assert_eq!(c, UVec2D::default());
+}
+
+#[test]
+fn matrix_3x3_decompose() {
+    for &(rotation, scale) in [
+        (0.7, FVec2D::new(2.0, 0.5)),
+        (-2.5, FVec2D::new(1.0, 1.0)),
+        // mirrored: the reflection comes back on the y scale
+        (1.2, FVec2D::new(3.0, -2.0)),
+    ]
+    .iter()
+    {
+        let translation = FVec2D::new(5.0, -3.0);
+        let matrix = Mat3x3::compose(translation, rotation, scale);
+        let (t, r, s) = matrix.decompose();
+        assert!(t.approx_eq(&translation, 1e-5));
+        assert!((r - rotation).abs() < 1e-5, "{} != {}", r, rotation);
+        assert!(s.approx_eq(&scale, 1e-5), "{:?} != {:?}", s, scale);
+        assert!(Mat3x3::compose(t, r, s).approx_eq(&matrix, 1e-5));
+    }
+
+    // same as building it by hand
+    let matrix = Mat3x3::translate(1.0, 2.0) * Mat3x3::rotation(0.5) * Mat3x3::scale(4.0, 4.0);
+    let (t, r, s) = matrix.decompose();
+    assert!(t.approx_eq(&FVec2D::new(1.0, 2.0), 1e-5));
+    assert!((r - 0.5).abs() < 1e-5);
+    assert!(s.approx_eq(&FVec2D::new(4.0, 4.0), 1e-5));
+}
+
+#[test]
+fn matrix_4x4_decompose() {
+    let translation = FVec3D::new(1.0, -2.0, 3.0);
+    let rotation = FVec3D::new(0.3, -0.5, 1.2);
+    let scale = FVec3D::new(2.0, 3.0, 4.0);
+    let (t, r, s) = Mat4x4::compose(translation, rotation, scale).decompose();
+    for (found, expected) in [t, r, s].iter().zip([translation, rotation, scale].iter()) {
+        assert!(
+            found.approx_eq(expected, 1e-4),
+            "{:?} != {:?}",
+            found,
+            expected
+        );
+    }
+
+    // gimbal lock (y rotation of 90 degrees): the angles differ but the transform is the same
+    let rotation = FVec3D::new(0.4, std::f32::consts::FRAC_PI_2, 0.2);
+    let matrix = Mat4x4::compose(translation, rotation, scale);
+    let (t, r, s) = matrix.decompose();
+    assert!(Mat4x4::compose(t, r, s).approx_eq(&matrix, 1e-4));
+}
+
+#[test]
+fn approx_eq_tolerance() {
+    let a = FVec2D::new(1.0, 2.0);
+    assert!(a.approx_eq(&FVec2D::new(1.05, 1.95), 0.1));
+    assert!(!a.approx_eq(&FVec2D::new(1.2, 2.0), 0.1));
+    assert!(FVec4D::new(1.0, 2.0, 3.0, 4.0).approx_eq(&FVec4D::new(1.0, 2.0, 3.0, 4.0), 0.0));
+    assert!(IVec3D::new(1, 2, 3).approx_eq(&IVec3D::new(2, 2, 2), 1));
+
+    // 0.1 + 0.2 != 0.3 in floating point
+    let sum = Mat3x3::<f32>::scale(0.1, 1.0) + Mat3x3::<f32>::scale(0.2, 1.0);
+    let expected: Mat3x3<f32> = [[0.3, 0.0, 0.0], [0.0, 2.0, 0.0], [0.0, 0.0, 2.0]].into();
+    assert!(sum.approx_eq(&expected, 1e-6));
+    assert!(!sum.approx_eq(&(expected * 1.5), 1e-6));
+}
+
+#[test]
+fn matrix_try_inverse_singular() {
+    // a zero scale flattens everything on one axis, there is no way back
+    assert!(Mat3x3::<f32>::scale(0.0, 2.0).try_inverse(1e-6).is_none());
+    assert!(Mat4x4::<f32>::scale(1.0, 1.0, 0.0)
+        .try_inverse(1e-6)
+        .is_none());
+    let singular: Mat3x3<f32> = [[1.0, 2.0, 3.0], [2.0, 4.0, 6.0], [0.0, 0.0, 1.0]].into();
+    assert!(singular.try_inverse(1e-6).is_none());
+    assert!(Mat4x4::<f32>::default().try_inverse(1e-6).is_none());
+
+    // the tolerance follows the scale of the matrix
+    let tiny = Mat4x4::<f32>::scale(1e-3, 1e-3, 1e-3);
+    let inverse = tiny.try_inverse(1e-6).unwrap();
+    assert!(inverse.approx_eq(&Mat4x4::scale(1e3, 1e3, 1e3), 1e-1));
+    let almost: Mat3x3<f32> = [[1e3, 2e3, 0.0], [1e3, 2e3 + 1e-3, 0.0], [0.0, 0.0, 1e3]].into();
+    assert!(almost.try_inverse(1e-6).is_none());
+
+    let scale = Mat3x3::<f32>::scale(2.0, 4.0);
+    let inverse = scale.try_inverse(1e-6).unwrap();
+    assert!(inverse.approx_eq(&Mat3x3::scale(0.5, 0.25), 1e-6));
+    let translate = Mat4x4::<f32>::translate(1.0, 2.0, 3.0);
+    let inverse = translate.try_inverse(1e-6).unwrap();
+    assert!(inverse.approx_eq(&Mat4x4::translate(-1.0, -2.0, -3.0), 1e-6));
 }
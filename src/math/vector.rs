@@ -1,4 +1,5 @@
 use crate::math::*;
+use std::ops::{Index, IndexMut};
 
 // types that are allowed to implement vector
 impl SimpleMathTrait for i8 {}
@@ -6,14 +7,17 @@ impl SimpleMathTrait for i16 {}
 impl SimpleMathTrait for i32 {}
 impl SimpleMathTrait for f64 {}
 impl SimpleMathTrait for f32 {}
+impl SimpleMathTrait for u32 {}
 
 /// A generic vector type that offers vector operations such as
 ///     Dot product
 ///     Cross produt
 ///     Addition
 ///     Subtraction
-/// Vector of unsigned types are not allowed as we implement Neg Trait
+/// Unsigned (u32) vectors are supported, except for the operations that negate
+/// (Neg, perpendicular, 3D cross product) and subtraction must not underflow.
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector2D<T>
 where
     T: SimpleMathTrait,
@@ -29,8 +33,7 @@ where
         + Mul<Output = T>
         + Add<Output = T>
         + Sub<Output = T>
-        + PolarTrait<T>
-        + Neg<Output = T>,
+        + PolarTrait<T>,
     <T as Mul>::Output: Add<Output = T>,
     <T as Mul>::Output: Sub<Output = T>,
 {
@@ -92,13 +95,96 @@ where
         left.x * right.y - right.x * left.y
     }
 
-    pub fn perpendicular(&self) -> Self {
+    pub fn perpendicular(&self) -> Self
+    where
+        T: Neg<Output = T>,
+    {
         Self {
             x: -self.y,
             y: self.x,
         }
     }
 
+    /// Multiply component by component (x * x, y * y), e.g. for non-uniform scaling
+    pub fn component_mul(&self, other: Self) -> Self {
+        Self {
+            x: self.x * other.x,
+            y: self.y * other.y,
+        }
+    }
+
+    /// Divide component by component (x / x, y / y)
+    /// Integer vectors panic on a zero component, like integer division does.
+    pub fn component_div(&self, other: Self) -> Self {
+        Self {
+            x: self.x / other.x,
+            y: self.y / other.y,
+        }
+    }
+
+    /// Smallest of each component of a and b, e.g. the top left corner of a bounding box
+    pub fn min_components(a: Self, b: Self) -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            x: super::min(a.x, b.x),
+            y: super::min(a.y, b.y),
+        }
+    }
+
+    /// Largest of each component of a and b, e.g. the bottom right corner of a bounding box
+    pub fn max_components(a: Self, b: Self) -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            x: super::max(a.x, b.x),
+            y: super::max(a.y, b.y),
+        }
+    }
+
+    /// Absolute value of every component
+    pub fn abs(&self) -> Self
+    where
+        T: Absolute,
+    {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+        }
+    }
+
+    /// Round every component down
+    pub fn floor(&self) -> Self
+    where
+        T: Rounding,
+    {
+        Self {
+            x: self.x.floor(),
+            y: self.y.floor(),
+        }
+    }
+
+    /// Round every component up
+    pub fn ceil(&self) -> Self
+    where
+        T: Rounding,
+    {
+        Self {
+            x: self.x.ceil(),
+            y: self.y.ceil(),
+        }
+    }
+
+    /// Compare with another vector allowing each component to differ by up to `epsilon`
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool
+    where
+        T: Sub<Output = T> + Absolute + PartialOrd,
+    {
+        approx_eq([self.x, self.y], [other.x, other.y], epsilon)
+    }
+
     pub fn to_f32(self) -> Vector2D<f32>
     where
         T: LossyCast<f32>,
@@ -236,6 +322,35 @@ where
     }
 }
 
+/// Operator [] (vec[0] is x, vec[1] is y...), panics if the index is out of range
+impl<T> Index<usize> for Vector2D<T>
+where
+    T: SimpleMathTrait,
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            _ => panic!("Vector2D index out of range: {} (len 2)", index),
+        }
+    }
+}
+
+/// Operator [] for assignment, panics if the index is out of range
+impl<T> IndexMut<usize> for Vector2D<T>
+where
+    T: SimpleMathTrait,
+{
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            _ => panic!("Vector2D index out of range: {} (len 2)", index),
+        }
+    }
+}
+
 impl<T> From<(T, T)> for Vector2D<T>
 where
     T: SimpleMathTrait,
@@ -260,35 +375,35 @@ impl AngleTrait for Vector2D<i32> {
     fn angle(&self) -> f32 {
         let x = self.x as f32;
         let y = self.y as f32;
-        (x / y).atan()
+        y.atan2(x)
     }
 }
 impl AngleTrait for Vector2D<f32> {
     fn angle(&self) -> f32 {
         let x = self.x;
         let y = self.y;
-        (x / y).atan()
+        y.atan2(x)
     }
 }
 impl AngleTrait for Vector2D<i8> {
     fn angle(&self) -> f32 {
         let x = self.x as f32;
         let y = self.y as f32;
-        (x / y).atan()
+        y.atan2(x)
     }
 }
 impl AngleTrait for Vector2D<i16> {
     fn angle(&self) -> f32 {
         let x = self.x as f32;
         let y = self.y as f32;
-        (x / y).atan()
+        y.atan2(x)
     }
 }
 impl AngleTrait for Vector2D<f64> {
     fn angle(&self) -> f32 {
         let x = self.x as f32;
         let y = self.y as f32;
-        (x / y).atan()
+        y.atan2(x)
     }
 }
 
@@ -297,8 +412,10 @@ impl AngleTrait for Vector2D<f64> {
 ///     Cross produt
 ///     Addition
 ///     Subtraction
-/// Vector of unsigned types are not allowed as we implement Neg Trait
+/// Unsigned (u32) vectors are supported, except for the operations that negate
+/// (Neg, perpendicular, 3D cross product) and subtraction must not underflow.
 #[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Vector3D<T>
 where
     T: SimpleMathTrait,
@@ -310,12 +427,7 @@ where
 
 impl<T> Vector3D<T>
 where
-    T: SimpleMathTrait
-        + Div<Output = T>
-        + Mul<Output = T>
-        + Add<Output = T>
-        + Sub<Output = T>
-        + Neg<Output = T>,
+    T: SimpleMathTrait + Div<Output = T> + Mul<Output = T> + Add<Output = T> + Sub<Output = T>,
     <T as Mul>::Output: Add<Output = T>,
     <T as Mul>::Output: Sub<Output = T>,
 {
@@ -372,7 +484,10 @@ where
         left.x * right.x + left.y * right.y + left.z * right.z
     }
     /// Calculate cross product
-    pub fn cross(left: Self, right: Self) -> Self {
+    pub fn cross(left: Self, right: Self) -> Self
+    where
+        T: Neg<Output = T>,
+    {
         Self {
             x: left.y * right.z - left.z * right.y,
             y: -(left.x * right.z - left.z * right.x),
@@ -380,6 +495,97 @@ where
         }
     }
 
+    /// Multiply component by component (x * x, y * y, z * z), e.g. for non-uniform scaling
+    pub fn component_mul(&self, other: Self) -> Self {
+        Self {
+            x: self.x * other.x,
+            y: self.y * other.y,
+            z: self.z * other.z,
+        }
+    }
+
+    /// Divide component by component (x / x, y / y, z / z)
+    /// Integer vectors panic on a zero component, like integer division does.
+    pub fn component_div(&self, other: Self) -> Self {
+        Self {
+            x: self.x / other.x,
+            y: self.y / other.y,
+            z: self.z / other.z,
+        }
+    }
+
+    /// Smallest of each component of a and b, e.g. the top left corner of a bounding box
+    pub fn min_components(a: Self, b: Self) -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            x: super::min(a.x, b.x),
+            y: super::min(a.y, b.y),
+            z: super::min(a.z, b.z),
+        }
+    }
+
+    /// Largest of each component of a and b, e.g. the bottom right corner of a bounding box
+    pub fn max_components(a: Self, b: Self) -> Self
+    where
+        T: PartialOrd,
+    {
+        Self {
+            x: super::max(a.x, b.x),
+            y: super::max(a.y, b.y),
+            z: super::max(a.z, b.z),
+        }
+    }
+
+    /// Absolute value of every component
+    pub fn abs(&self) -> Self
+    where
+        T: Absolute,
+    {
+        Self {
+            x: self.x.abs(),
+            y: self.y.abs(),
+            z: self.z.abs(),
+        }
+    }
+
+    /// Round every component down
+    pub fn floor(&self) -> Self
+    where
+        T: Rounding,
+    {
+        Self {
+            x: self.x.floor(),
+            y: self.y.floor(),
+            z: self.z.floor(),
+        }
+    }
+
+    /// Round every component up
+    pub fn ceil(&self) -> Self
+    where
+        T: Rounding,
+    {
+        Self {
+            x: self.x.ceil(),
+            y: self.y.ceil(),
+            z: self.z.ceil(),
+        }
+    }
+
+    /// Compare with another vector allowing each component to differ by up to `epsilon`
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool
+    where
+        T: Sub<Output = T> + Absolute + PartialOrd,
+    {
+        approx_eq(
+            [self.x, self.y, self.z],
+            [other.x, other.y, other.z],
+            epsilon,
+        )
+    }
+
     pub fn angle(a: Self, b: Self) -> f32
     where
         T: LossyCast<f32>,
@@ -521,6 +727,37 @@ where
     }
 }
 
+/// Operator [] (vec[0] is x, vec[1] is y...), panics if the index is out of range
+impl<T> Index<usize> for Vector3D<T>
+where
+    T: SimpleMathTrait,
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            _ => panic!("Vector3D index out of range: {} (len 3)", index),
+        }
+    }
+}
+
+/// Operator [] for assignment, panics if the index is out of range
+impl<T> IndexMut<usize> for Vector3D<T>
+where
+    T: SimpleMathTrait,
+{
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            _ => panic!("Vector3D index out of range: {} (len 3)", index),
+        }
+    }
+}
+
 impl<T> From<(T, T, T)> for Vector3D<T>
 where
     T: SimpleMathTrait,
@@ -533,3 +770,275 @@ where
         }
     }
 }
+
+/// A generic 4D vector type used for homogeneous coordinates (x, y, z, w)
+/// Points usually have w = 1 and directions w = 0, after a projection w holds
+/// the depth used for the perspective divide.
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Vector4D<T>
+where
+    T: SimpleMathTrait,
+{
+    pub x: T,
+    pub y: T,
+    pub z: T,
+    pub w: T,
+}
+
+impl<T> Vector4D<T>
+where
+    T: SimpleMathTrait + Div<Output = T> + Mul<Output = T> + Add<Output = T> + Sub<Output = T>,
+{
+    pub fn new(x: T, y: T, z: T, w: T) -> Self {
+        Self { x, y, z, w }
+    }
+
+    /// Create a homogeneous point (w = 1) from a 3D vector
+    pub fn from_point(point: Vector3D<T>) -> Self
+    where
+        T: Unit<Type = T>,
+    {
+        Self::new(point.x, point.y, point.z, T::one())
+    }
+
+    /// Retrieve x component
+    pub fn x(&self) -> T {
+        self.x
+    }
+
+    /// Retrieve y component
+    pub fn y(&self) -> T {
+        self.y
+    }
+
+    /// Retrieve z component
+    pub fn z(&self) -> T {
+        self.z
+    }
+
+    /// Retrieve w component
+    pub fn w(&self) -> T {
+        self.w
+    }
+
+    /// Calculate the squared length/magnitude of the Vector4D
+    pub fn squared_length(&self) -> T {
+        self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w
+    }
+
+    /// Compare with another vector allowing each component to differ by up to `epsilon`
+    pub fn approx_eq(&self, other: &Self, epsilon: T) -> bool
+    where
+        T: Sub<Output = T> + Absolute + PartialOrd,
+    {
+        approx_eq(
+            [self.x, self.y, self.z, self.w],
+            [other.x, other.y, other.z, other.w],
+            epsilon,
+        )
+    }
+
+    /// Calculate the lenght/magnitude of the Vector4D
+    pub fn length(&self) -> T {
+        self.squared_length().sqrt()
+    }
+
+    /// Calculate dot product
+    pub fn dot(left: Self, right: Self) -> T {
+        left.x * right.x + left.y * right.y + left.z * right.z + left.w * right.w
+    }
+
+    /// Drop the w component
+    pub fn xyz(&self) -> Vector3D<T> {
+        Vector3D::new(self.x, self.y, self.z)
+    }
+
+    /// Divide x, y and z by w (perspective divide)
+    /// Returns x, y and z as they are when w is zero.
+    pub fn perspective_divide(&self) -> Vector3D<T>
+    where
+        T: PartialEq + Zero<Type = T>,
+    {
+        if self.w != T::zero() {
+            self.xyz() / self.w
+        } else {
+            self.xyz()
+        }
+    }
+
+    pub fn to_f32(self) -> Vector4D<f32>
+    where
+        T: LossyCast<f32>,
+    {
+        Vector4D::<f32>::new(self.x.cast(), self.y.cast(), self.z.cast(), self.w.cast())
+    }
+
+    pub fn to_i32(self) -> Vector4D<i32>
+    where
+        T: LossyCast<i32>,
+    {
+        Vector4D::<i32>::new(self.x.cast(), self.y.cast(), self.z.cast(), self.w.cast())
+    }
+}
+
+// Operator overloading so that +, -, *, /, -=, +=, *=, /= can be used
+impl<T> Add for Vector4D<T>
+where
+    T: SimpleMathTrait + Add<Output = T>,
+{
+    type Output = Vector4D<T>;
+    fn add(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x + rhs.x,
+            y: self.y + rhs.y,
+            z: self.z + rhs.z,
+            w: self.w + rhs.w,
+        }
+    }
+}
+impl<T> Sub for Vector4D<T>
+where
+    T: SimpleMathTrait + Sub<Output = T>,
+{
+    type Output = Vector4D<T>;
+    fn sub(self, rhs: Self) -> Self::Output {
+        Self {
+            x: self.x - rhs.x,
+            y: self.y - rhs.y,
+            z: self.z - rhs.z,
+            w: self.w - rhs.w,
+        }
+    }
+}
+impl<T> Mul<T> for Vector4D<T>
+where
+    T: SimpleMathTrait + Mul<Output = T>,
+{
+    type Output = Vector4D<T>;
+    fn mul(self, rhs: T) -> Self::Output {
+        Self {
+            x: self.x * rhs,
+            y: self.y * rhs,
+            z: self.z * rhs,
+            w: self.w * rhs,
+        }
+    }
+}
+
+impl<T> Div<T> for Vector4D<T>
+where
+    T: SimpleMathTrait + Div<Output = T>,
+{
+    type Output = Vector4D<T>;
+    fn div(self, rhs: T) -> Self::Output {
+        Self {
+            x: self.x / rhs,
+            y: self.y / rhs,
+            z: self.z / rhs,
+            w: self.w / rhs,
+        }
+    }
+}
+
+impl<T> MulAssign<T> for Vector4D<T>
+where
+    T: SimpleMathTrait + Mul<Output = T> + MulAssign,
+{
+    fn mul_assign(&mut self, rhs: T) {
+        self.x *= rhs;
+        self.y *= rhs;
+        self.z *= rhs;
+        self.w *= rhs;
+    }
+}
+
+impl<T> DivAssign<T> for Vector4D<T>
+where
+    T: SimpleMathTrait + Div<Output = T> + DivAssign,
+{
+    fn div_assign(&mut self, rhs: T) {
+        self.x /= rhs;
+        self.y /= rhs;
+        self.z /= rhs;
+        self.w /= rhs;
+    }
+}
+impl<T> AddAssign<Vector4D<T>> for Vector4D<T>
+where
+    T: SimpleMathTrait + Add<Output = T>,
+{
+    fn add_assign(&mut self, rhs: Vector4D<T>) {
+        *self = *self + rhs;
+    }
+}
+impl<T> SubAssign<Vector4D<T>> for Vector4D<T>
+where
+    T: SimpleMathTrait + Sub<Output = T>,
+{
+    fn sub_assign(&mut self, rhs: Vector4D<T>) {
+        *self = *self - rhs;
+    }
+}
+
+impl<T> Neg for Vector4D<T>
+where
+    T: SimpleMathTrait + Neg<Output = T>,
+{
+    type Output = Self;
+    fn neg(self) -> Self::Output {
+        Self {
+            x: -self.x,
+            y: -self.y,
+            z: -self.z,
+            w: -self.w,
+        }
+    }
+}
+
+/// Operator [] (vec[0] is x, vec[1] is y...), panics if the index is out of range
+impl<T> Index<usize> for Vector4D<T>
+where
+    T: SimpleMathTrait,
+{
+    type Output = T;
+    fn index(&self, index: usize) -> &T {
+        match index {
+            0 => &self.x,
+            1 => &self.y,
+            2 => &self.z,
+            3 => &self.w,
+            _ => panic!("Vector4D index out of range: {} (len 4)", index),
+        }
+    }
+}
+
+/// Operator [] for assignment, panics if the index is out of range
+impl<T> IndexMut<usize> for Vector4D<T>
+where
+    T: SimpleMathTrait,
+{
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        match index {
+            0 => &mut self.x,
+            1 => &mut self.y,
+            2 => &mut self.z,
+            3 => &mut self.w,
+            _ => panic!("Vector4D index out of range: {} (len 4)", index),
+        }
+    }
+}
+
+impl<T> From<(T, T, T, T)> for Vector4D<T>
+where
+    T: SimpleMathTrait,
+{
+    fn from(tupple: (T, T, T, T)) -> Self {
+        Self {
+            x: tupple.0,
+            y: tupple.1,
+            z: tupple.2,
+            w: tupple.3,
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! A small particle system for effects like sparks, smoke or explosions
+//!
+//! Example usage:
+//!    let mut sparks = ParticleSystem::new();
+//!    sparks.emit(Particle::new(position, velocity, 1.5, Color::YELLOW));
+//!    sparks.update(delta_t);
+//!    sparks.draw(&canvas);
+//!
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::math::FVec2D;
+
+/// A single particle, `life` is the time left before it is removed
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct Particle {
+    pub pos: FVec2D,
+    pub vel: FVec2D,
+    pub life: f32,
+    pub color: Color,
+}
+
+impl Particle {
+    /// Create a new particle
+    /// # Arguments
+    /// `pos`   starting position
+    /// `vel`   velocity in pixels per second
+    /// `life`  time to live in seconds
+    /// `color` particle color
+    pub fn new(pos: FVec2D, vel: FVec2D, life: f32, color: Color) -> Self {
+        Self {
+            pos,
+            vel,
+            life,
+            color,
+        }
+    }
+}
+
+/// A set of particles moved and drawn together
+/// Dead particles are removed in place so the same buffer is reused frame after frame.
+#[derive(Debug, Clone)]
+pub struct ParticleSystem {
+    particles: Vec<Particle>,
+    /// Acceleration applied to every particle, pixels per second squared
+    pub gravity: FVec2D,
+    /// Radius of the drawn particles, 0 plots a single pixel
+    pub radius: i32,
+}
+
+impl ParticleSystem {
+    /// Create an empty particle system without gravity
+    pub fn new() -> Self {
+        Self::with_capacity(0)
+    }
+
+    /// Create an empty particle system with room for `capacity` particles
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            particles: Vec::with_capacity(capacity),
+            gravity: FVec2D::new(0.0, 0.0),
+            radius: 0,
+        }
+    }
+
+    /// Add a particle to the system
+    pub fn emit(&mut self, particle: Particle) {
+        self.particles.push(particle);
+    }
+
+    /// Move every particle, age them and remove the dead ones
+    /// # Arguments
+    /// `delta_t`   time elapsed since the last update in seconds
+    pub fn update(&mut self, delta_t: f32) {
+        let gravity = self.gravity * delta_t;
+        for particle in self.particles.iter_mut() {
+            particle.vel += gravity;
+            particle.pos += particle.vel * delta_t;
+            particle.life -= delta_t;
+        }
+        self.particles.retain(|particle| particle.life > 0.0);
+    }
+
+    /// Draws the particles alive
+    pub fn draw(&self, canvas: &Canvas) {
+        for particle in &self.particles {
+            let (x, y) = (particle.pos.x.round() as i32, particle.pos.y.round() as i32);
+            if self.radius > 0 {
+                canvas.fill_circle((x, y).into(), self.radius, particle.color);
+            } else {
+                canvas.plot(x, y, particle.color);
+            }
+        }
+    }
+
+    /// Particles currently alive
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+
+    /// Number of particles alive
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+
+    /// True when there are no particles alive
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Remove every particle, keeps the allocated buffer
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+}
+
+impl Default for ParticleSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
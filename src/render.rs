@@ -1,14 +1,83 @@
 use crate::canvas::Canvas;
+#[cfg(feature = "window")]
+use crate::canvas::{DirtyRect, Origin};
+use crate::color::Color;
+use crate::image::sprite::Sprite;
+#[cfg(all(feature = "window", feature = "gamepad"))]
+use crate::input::Gamepad;
+pub use crate::input::{GamepadAxis, GamepadButton, Input, Key, MouseButton};
+#[cfg(feature = "window")]
+use crate::math::Point2D;
+#[cfg(feature = "window")]
 pub use glium::glutin::event::VirtualKeyCode;
-use glium::glutin::event::{Event, StartCause};
+#[cfg(feature = "window")]
+use glium::glutin::event::{Event, StartCause, WindowEvent};
+#[cfg(feature = "window")]
 use glium::glutin::event_loop::ControlFlow;
+#[cfg(feature = "window")]
+use glium::glutin::window::Fullscreen;
+#[cfg(feature = "window")]
 use glium::Surface;
+use std::error::Error;
+use std::fmt;
 use std::time::Duration;
+#[cfg(feature = "window")]
 use std::time::Instant;
+#[cfg(feature = "window")]
 pub use winit_input_helper::WinitInputHelper;
 
+#[cfg(feature = "window")]
 pub type InputHelper = WinitInputHelper;
 
+/// Longest frame time (in seconds) fed into the fixed timestep accumulator
+/// Avoids running an ever growing number of updates after a long stall.
+#[cfg(any(feature = "window", feature = "wasm"))]
+pub(crate) const MAX_FRAME_TIME: f32 = 0.25;
+
+/// Delta time (in seconds) of each headless frame when there is no fixed timestep
+const HEADLESS_FRAME_TIME: f32 = 1.0 / 60.0;
+
+/// Frame statistics given to Render2D::update_with_stats
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct FrameStats {
+    /// Frames drawn per second, measured every second (0.0 during the first second)
+    pub fps: f32,
+    /// Number of updates before this one, with a fixed timestep several updates may
+    /// happen in the same drawn frame
+    pub frame_index: u64,
+    /// Time since rendering started
+    pub elapsed: Duration,
+}
+
+/// Errors that stop Render2D::render from opening its window, or passed to
+/// Render2D::on_error once it is open
+#[derive(Debug, Clone)]
+pub enum RenderError {
+    /// The window or its OpenGL context could not be built (e.g. no display available)
+    WindowCreation(String),
+    /// The OpenGL context is not usable for drawing (missing GPU support or version too old)
+    DisplayCreation(String),
+    /// The texture holding the canvas pixels could not be allocated
+    BufferCreation(String),
+    /// Render2D::setup returned false
+    SetupFailed,
+    /// A frame could not be shown (e.g. the buffer swap failed after losing the context)
+    FrameDisplay(String),
+}
+
+impl Error for RenderError {}
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::WindowCreation(e) => write!(f, "Could not create window: {}", e),
+            RenderError::DisplayCreation(e) => write!(f, "Could not create display: {}", e),
+            RenderError::BufferCreation(e) => write!(f, "Could not create frame buffer: {}", e),
+            RenderError::SetupFailed => write!(f, "Could not start rendering, setup failed"),
+            RenderError::FrameDisplay(e) => write!(f, "Could not display frame: {}", e),
+        }
+    }
+}
+
 /// Render2D Trait which contains all the functions to:
 /// 1. Draw to the screen
 /// 2. Update objects on the screen
@@ -38,6 +107,55 @@ pub trait Render2D {
     fn title(&mut self) -> String {
         "Render2D Canvas".into()
     }
+    /// Window icon, the sprite is converted to RGBA (32x32 or 64x64 work best)
+    /// Defaults to None, the system default icon.
+    fn window_icon(&self) -> Option<Sprite> {
+        None
+    }
+    /// Initial position of the top left corner of the window, in screen pixels
+    /// Defaults to None, the system decides where the window goes.
+    fn initial_position(&self) -> Option<(i32, i32)> {
+        None
+    }
+    /// Start in borderless fullscreen on the current monitor
+    /// Defaults to false, a window of width() x height().
+    fn fullscreen(&self) -> bool {
+        false
+    }
+    /// Key switching between windowed and borderless fullscreen at any time
+    /// Defaults to F11, return None to disable it.
+    fn fullscreen_key(&self) -> Option<Key> {
+        Some(Key::F11)
+    }
+    /// Whether the canvas follows the window size
+    /// Defaults to false: the canvas keeps width() x height() pixels and is stretched to fill
+    /// the window (e.g. when fullscreen), keeping what was drawn and chunky pixel art.
+    /// When true a new, empty canvas of the window size is created every time the window
+    /// changes size (e.g. going fullscreen), read canvas.width()/height() in update instead
+    /// of assuming width() x height().
+    fn resizable_canvas(&self) -> bool {
+        false
+    }
+    /// Color used to clear the canvas before each call to update()
+    /// Defaults to black, return None to keep the previous frame and draw on top of it
+    /// (useful for trails and particle effects).
+    fn clear_color(&mut self) -> Option<Color> {
+        Some(Color::BLACK)
+    }
+    /// Fixed time step (in seconds) used when calling update()
+    /// Defaults to None, update() is called once per frame with the real elapsed time.
+    /// When Some(dt) the elapsed time is accumulated and update() is called zero or more
+    /// times per frame always with the same dt, giving deterministic game logic/physics.
+    fn fixed_timestep(&self) -> Option<f32> {
+        None
+    }
+    /// Frames drawn per second
+    /// Defaults to None, frames are synchronized with the monitor refresh rate (vsync).
+    /// When Some(fps) vsync is disabled and the loop waits between frames to draw at most
+    /// fps frames per second, lower values save battery.
+    fn target_fps(&self) -> Option<u32> {
+        None
+    }
     ///
     /// Setup method called when the world is first created
     /// Must be overriden.
@@ -49,95 +167,435 @@ pub trait Render2D {
     /// Update method called when the canvas is to be updated
     /// This is called periodically per frame and each frame is drawn individually
     /// Must be overriden/implmented
-    fn update(&mut self, canvas: &mut Canvas, events: &InputHelper, delta_t: f32) -> bool {
+    fn update(&mut self, canvas: &mut Canvas, input: &dyn Input, delta_t: f32) -> bool {
         true
     }
 
-    fn render(mut self)
+    /// Called when something fails once the window is open (e.g. the canvas texture can't
+    /// be resized or a frame can't be shown), return true to keep running as before.
+    /// Defaults to stopping the loop, render can no longer return the error then.
+    fn on_error(&mut self, error: RenderError) -> bool {
+        false
+    }
+
+    /// Same as update with access to the frame statistics (FPS, frame index, elapsed time)
+    /// This is the method the render loop calls, by default it just calls update.
+    /// Override it instead of update to display or react to performance.
+    fn update_with_stats(
+        &mut self,
+        canvas: &mut Canvas,
+        input: &dyn Input,
+        delta_t: f32,
+        stats: &FrameStats,
+    ) -> bool {
+        self.update(canvas, input, delta_t)
+    }
+
+    /// Run setup and the update loop without opening a window, capturing every frame
+    /// Useful for tests and generating images on servers without a display.
+    /// Updates use fixed_timestep (or 1/60 of a second) as delta time and an input
+    /// where no key or button is ever pressed. Stops early when update returns false,
+    /// returns no frames when setup does.
+    /// # Arguments
+    /// `frames`    number of frames to render
+    fn render_headless(&mut self, frames: usize) -> Vec<Canvas>
+    where
+        Self: Sized,
+    {
+        let mut canvas = Canvas::new(self.width(), self.height());
+        if !self.setup(&mut canvas) {
+            return Vec::new();
+        }
+        let delta_t = match self.fixed_timestep() {
+            Some(step) if step > 0.0 => step,
+            _ => HEADLESS_FRAME_TIME,
+        };
+        let input = NoInput;
+        let mut stats = FrameStats {
+            fps: 1.0 / delta_t,
+            ..FrameStats::default()
+        };
+        let mut captured = Vec::with_capacity(frames);
+        for _ in 0..frames {
+            stats.elapsed = Duration::from_secs_f32(stats.frame_index as f32 * delta_t);
+            let keep_running = next_frame(self, &mut canvas, &input, delta_t, &mut stats);
+            captured.push(canvas.clone());
+            if !keep_running {
+                break;
+            }
+        }
+        captured
+    }
+
+    /// Open the window and run the update loop until it is closed
+    /// Only returns when the window can't be opened, otherwise the process exits with it.
+    /// Requires the `window` feature (enabled by default).
+    #[cfg(feature = "window")]
+    fn render(mut self) -> Result<(), RenderError>
     where
         Self: Sized + 'static,
     {
-        let width = self.width();
-        let height = self.height();
+        let mut width = self.width();
+        let mut height = self.height();
         let title = self.title();
         let mut canvas = Canvas::new(width, height);
         let event_loop = glium::glutin::event_loop::EventLoop::new();
         let inner_size = glium::glutin::dpi::LogicalSize::new(width, height);
-        let frames_per_sec = ((1.0 / 60.0) * 1000000000.0) as u64; // 60 frames per second
+        let frame_time = match self.target_fps() {
+            Some(fps) if fps > 0 => Some(Duration::from_secs_f64(1.0 / fps as f64)),
+            _ => None,
+        };
 
-        let wb = glium::glutin::window::WindowBuilder::new()
+        let mut wb = glium::glutin::window::WindowBuilder::new()
             .with_inner_size(inner_size)
             .with_title(&title[..])
             .with_resizable(false);
+        if self.fullscreen() {
+            wb = wb.with_fullscreen(Some(Fullscreen::Borderless(None)));
+        }
+        if let Some(icon) = self.window_icon() {
+            let icon = glium::glutin::window::Icon::from_rgba(
+                icon.to_rgba(),
+                icon.width as u32,
+                icon.height as u32,
+            )
+            .map_err(|e| RenderError::WindowCreation(e.to_string()))?;
+            wb = wb.with_window_icon(Some(icon));
+        }
+        if let Some((x, y)) = self.initial_position() {
+            wb = wb.with_position(glium::glutin::dpi::PhysicalPosition::new(x, y));
+        }
+
+        let gl_window = glium::glutin::ContextBuilder::new()
+            .with_vsync(frame_time.is_none())
+            .build_windowed(wb, &event_loop)
+            .map_err(|e| RenderError::WindowCreation(e.to_string()))?;
+        let display = glium::Display::from_gl_window(gl_window)
+            .map_err(|e| RenderError::DisplayCreation(e.to_string()))?;
+        let mut texture = canvas_texture(&display, width, height)
+            .map_err(|e| RenderError::BufferCreation(e.to_string()))?;
+        let fullscreen_key = self.fullscreen_key();
+        let resizable_canvas = self.resizable_canvas();
 
-        let cb = glium::glutin::ContextBuilder::new();
-        let display = glium::Display::new(wb, cb, &event_loop).unwrap();
-        let texture = glium::Texture2d::empty_with_format(
-            &display,
-            glium::texture::UncompressedFloatFormat::U8U8U8U8,
-            glium::texture::MipmapsOption::EmptyMipmaps,
-            width,
-            height,
-        )
-        .unwrap();
-
-        let mut input = InputHelper::new();
+        let mut input = WindowInput::new();
         let mut last_frame_time = Instant::now();
         let mut next_frame_time = Instant::now();
         let mut frame_counter = 0.0;
         let mut last_draw = Instant::now();
-        if self.setup(&mut canvas) {
-            event_loop.run(move |event, _, control_flow| {
-                match event {
-                    Event::NewEvents(StartCause::Init)
-                    | Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
-                        let elapsed = Instant::now().duration_since(last_draw);
-                        if !self.update(&mut canvas, &input, elapsed.as_secs_f32()) {
-                            *control_flow = ControlFlow::Exit;
+        let mut accumulator = 0.0;
+        let render_start = Instant::now();
+        let mut stats = FrameStats::default();
+        if !self.setup(&mut canvas) {
+            return Err(RenderError::SetupFailed);
+        }
+        event_loop.run(move |event, _, control_flow| {
+            match event {
+                Event::NewEvents(StartCause::Init)
+                | Event::NewEvents(StartCause::Poll)
+                | Event::NewEvents(StartCause::ResumeTimeReached { .. }) => {
+                    let elapsed = Instant::now().duration_since(last_draw).as_secs_f32();
+                    last_draw = Instant::now();
+                    input.update_gamepad();
+                    let window_size = display.gl_window().window().inner_size();
+                    input.set_canvas_scale(&canvas, window_size.width, window_size.height);
+                    stats.elapsed = render_start.elapsed();
+                    if fullscreen_key.is_some_and(|key| Input::key_pressed(&input, key)) {
+                        let gl_window = display.gl_window();
+                        let window = gl_window.window();
+                        window.set_fullscreen(match window.fullscreen() {
+                            Some(_) => None,
+                            None => Some(Fullscreen::Borderless(window.current_monitor())),
+                        });
+                    }
+                    let keep_running = match self.fixed_timestep() {
+                        Some(step) if step > 0.0 => {
+                            let mut keep_running = true;
+                            accumulator += elapsed.min(MAX_FRAME_TIME);
+                            while keep_running && accumulator >= step {
+                                keep_running =
+                                    next_frame(&mut self, &mut canvas, &input, step, &mut stats);
+                                accumulator -= step;
+                            }
+                            keep_running
                         }
-                        last_draw = Instant::now();
-                        frame_counter += 1.0;
+                        _ => next_frame(&mut self, &mut canvas, &input, elapsed, &mut stats),
+                    };
+                    if !keep_running {
+                        *control_flow = ControlFlow::Exit;
+                    }
+                    frame_counter += 1.0;
 
-                        let target = display.draw();
-                        texture.write(
-                            glium::Rect {
+                    let target = display.draw();
+                    // upload only the part of the canvas drawn since the last frame
+                    if let Some(rect) = canvas.dirty_rect() {
+                        if (rect.width, rect.height) == (width, height) {
+                            let full = glium::Rect {
                                 left: 0,
                                 bottom: 0,
                                 width,
                                 height,
-                            },
-                            &canvas,
-                        );
-                        texture
-                            .as_surface()
-                            .fill(&target, glium::uniforms::MagnifySamplerFilter::Nearest);
-                        target.finish().unwrap();
-                        next_frame_time += Duration::from_nanos(frames_per_sec);
-                        *control_flow = ControlFlow::WaitUntil(next_frame_time);
+                            };
+                            texture.write(full, &canvas);
+                        } else {
+                            let rect = texture_rect(&canvas, rect);
+                            texture.write(rect, dirty_region(&canvas, &rect));
+                        }
+                        canvas.clear_dirty();
                     }
-                    Event::WindowEvent { ref event, .. } => {
-                        if event == &glium::glutin::event::WindowEvent::CloseRequested {
-                            *control_flow = ControlFlow::Exit
+                    texture
+                        .as_surface()
+                        .fill(&target, glium::uniforms::MagnifySamplerFilter::Nearest);
+                    let keep_running = match target.finish() {
+                        Ok(()) => keep_running,
+                        Err(e) => {
+                            let error = RenderError::FrameDisplay(e.to_string());
+                            let resume = self.on_error(error);
+                            if !resume {
+                                *control_flow = ControlFlow::Exit;
+                            }
+                            keep_running && resume
+                        }
+                    };
+                    if keep_running {
+                        match frame_time {
+                            Some(frame_time) => {
+                                next_frame_time += frame_time;
+                                *control_flow = ControlFlow::WaitUntil(next_frame_time);
+                            }
+                            // vsync makes finish() wait for the next refresh
+                            None => *control_flow = ControlFlow::Poll,
                         }
                     }
-
-                    _ => (),
                 }
-                input.update(&event);
-
-                let passed_time = Instant::now() - last_frame_time;
-                if passed_time > Duration::from_secs(1) {
-                    display.gl_window().window().set_title(&format!(
-                        "{} - {} FPS",
-                        &title,
-                        (frame_counter / passed_time.as_secs_f32()) as u32
-                    ));
-                    frame_counter = 0.0;
-                    last_frame_time = Instant::now();
-                }
-            });
-        } else {
-            println!("Could not start rendering...");
+                Event::WindowEvent { ref event, .. } => match event {
+                    WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
+                    WindowEvent::Resized(size)
+                        if resizable_canvas
+                            && size.width > 0
+                            && size.height > 0
+                            && (size.width, size.height) != (width, height) =>
+                    {
+                        match canvas_texture(&display, size.width, size.height) {
+                            Ok(resized) => {
+                                texture = resized;
+                                width = size.width;
+                                height = size.height;
+                                canvas = Canvas::new(width, height);
+                            }
+                            Err(e) => {
+                                let error = RenderError::BufferCreation(e.to_string());
+                                if !self.on_error(error) {
+                                    *control_flow = ControlFlow::Exit;
+                                }
+                            }
+                        }
+                    }
+                    _ => (),
+                },
+
+                _ => (),
+            }
+            input.events.update(&event);
+
+            let passed_time = Instant::now() - last_frame_time;
+            if passed_time > Duration::from_secs(1) {
+                stats.fps = frame_counter / passed_time.as_secs_f32();
+                display
+                    .gl_window()
+                    .window()
+                    .set_title(&format!("{} - {} FPS", &title, stats.fps as u32));
+                frame_counter = 0.0;
+                last_frame_time = Instant::now();
+            }
+        });
+    }
+
+    /// Run the update loop on the HTML `<canvas>` element with id `canvas_id`
+    /// Returns once the first frame is scheduled, the browser then drives update through
+    /// requestAnimationFrame until it returns false. The element is resized to
+    /// width() x height() and the page title set to title().
+    /// Requires the `wasm` feature.
+    #[cfg(feature = "wasm")]
+    fn render_web(self, canvas_id: &str) -> Result<(), RenderError>
+    where
+        Self: Sized + 'static,
+    {
+        crate::wasm::run(self, canvas_id)
+    }
+}
+
+/// Helper create the texture the canvas is copied to before drawing it on the window
+#[cfg(feature = "window")]
+fn canvas_texture(
+    display: &glium::Display,
+    width: u32,
+    height: u32,
+) -> Result<glium::Texture2d, glium::texture::TextureCreationError> {
+    glium::Texture2d::empty_with_format(
+        display,
+        glium::texture::UncompressedFloatFormat::U8U8U8U8,
+        glium::texture::MipmapsOption::EmptyMipmaps,
+        width,
+        height,
+    )
+}
+
+/// Helper: texture region of a dirty rect, the buffer rows of Origin::TopLeft are the
+/// texture ones upside down
+#[cfg(feature = "window")]
+fn texture_rect(canvas: &Canvas, rect: DirtyRect) -> glium::Rect {
+    let bottom = match canvas.origin() {
+        Origin::BottomLeft => rect.bottom,
+        Origin::TopLeft => canvas.height() - rect.bottom - rect.height,
+    };
+    glium::Rect {
+        bottom,
+        ..glium::Rect::from(rect)
+    }
+}
+
+/// Helper: copy a region of the canvas, in texture coordinates, to upload it on its own
+#[cfg(feature = "window")]
+fn dirty_region(canvas: &Canvas, rect: &glium::Rect) -> glium::texture::RawImage2d<'static, u8> {
+    let pixels = canvas.pixels.borrow();
+    let mut data = Vec::with_capacity((rect.width * rect.height * 4) as usize);
+    for row in rect.bottom..rect.bottom + rect.height {
+        let row = match canvas.origin() {
+            Origin::BottomLeft => row,
+            Origin::TopLeft => canvas.height() - 1 - row,
+        };
+        let start = (row * canvas.width() + rect.left) as usize;
+        for pixel in &pixels[start..start + rect.width as usize] {
+            data.extend_from_slice(&pixel.as_bytes());
         }
     }
+    glium::texture::RawImage2d::from_raw_rgba(data, (rect.width, rect.height))
+}
+
+/// Input given to update by the render loop: winit keyboard and mouse events and,
+/// with the `gamepad` feature, the first connected gamepad
+#[cfg(feature = "window")]
+struct WindowInput {
+    events: InputHelper,
+    /// Canvas pixels per window pixel, horizontally and vertically
+    canvas_scale: (f32, f32),
+    #[cfg(feature = "gamepad")]
+    gamepad: Option<Gamepad>,
+}
+
+#[cfg(feature = "window")]
+impl WindowInput {
+    fn new() -> Self {
+        Self {
+            events: InputHelper::new(),
+            canvas_scale: (1.0, 1.0),
+            #[cfg(feature = "gamepad")]
+            gamepad: Gamepad::new(),
+        }
+    }
+
+    /// Map mouse positions from a window of `width` x `height` onto the canvas stretched
+    /// over it
+    fn set_canvas_scale(&mut self, canvas: &Canvas, width: u32, height: u32) {
+        self.canvas_scale = (
+            canvas.width() as f32 / width.max(1) as f32,
+            canvas.height() as f32 / height.max(1) as f32,
+        );
+    }
+
+    /// Read the gamepad events received since the last frame
+    fn update_gamepad(&mut self) {
+        #[cfg(feature = "gamepad")]
+        if let Some(gamepad) = self.gamepad.as_mut() {
+            gamepad.update();
+        }
+    }
+}
+
+#[cfg(feature = "window")]
+impl Input for WindowInput {
+    fn key_pressed(&self, key: Key) -> bool {
+        Input::key_pressed(&self.events, key)
+    }
+    fn key_held(&self, key: Key) -> bool {
+        Input::key_held(&self.events, key)
+    }
+    fn key_released(&self, key: Key) -> bool {
+        Input::key_released(&self.events, key)
+    }
+    fn typed_text(&self) -> String {
+        Input::typed_text(&self.events)
+    }
+    fn mouse_position(&self) -> Option<Point2D> {
+        let (scale_x, scale_y) = self.canvas_scale;
+        self.events
+            .mouse()
+            .map(|(x, y)| Point2D::new((x * scale_x).floor() as i32, (y * scale_y).floor() as i32))
+    }
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        Input::mouse_pressed(&self.events, button)
+    }
+    fn mouse_held(&self, button: MouseButton) -> bool {
+        Input::mouse_held(&self.events, button)
+    }
+    fn mouse_released(&self, button: MouseButton) -> bool {
+        Input::mouse_released(&self.events, button)
+    }
+
+    #[cfg(feature = "gamepad")]
+    fn button_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepad
+            .as_ref()
+            .is_some_and(|gamepad| gamepad.button_pressed(button))
+    }
+    #[cfg(feature = "gamepad")]
+    fn button_held(&self, button: GamepadButton) -> bool {
+        self.gamepad
+            .as_ref()
+            .is_some_and(|gamepad| gamepad.button_held(button))
+    }
+    #[cfg(feature = "gamepad")]
+    fn button_released(&self, button: GamepadButton) -> bool {
+        self.gamepad
+            .as_ref()
+            .is_some_and(|gamepad| gamepad.button_released(button))
+    }
+    #[cfg(feature = "gamepad")]
+    fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepad
+            .as_ref()
+            .map_or(0.0, |gamepad| gamepad.axis(axis))
+    }
+}
+
+/// Input given to update by render_headless, nothing is ever pressed
+struct NoInput;
+
+impl Input for NoInput {
+    fn key_pressed(&self, _key: Key) -> bool {
+        false
+    }
+    fn key_held(&self, _key: Key) -> bool {
+        false
+    }
+    fn key_released(&self, _key: Key) -> bool {
+        false
+    }
+}
+
+/// Helper to clear the canvas if requested and update it for a single frame
+/// Counts the update in `stats`, returns false when the renderer asks to stop.
+pub(crate) fn next_frame<R: Render2D>(
+    renderer: &mut R,
+    canvas: &mut Canvas,
+    input: &dyn Input,
+    delta_t: f32,
+    stats: &mut FrameStats,
+) -> bool {
+    if let Some(color) = renderer.clear_color() {
+        canvas.clear(color);
+    }
+    let keep_running = renderer.update_with_stats(canvas, input, delta_t, stats);
+    stats.frame_index += 1;
+    keep_running
 }
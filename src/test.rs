@@ -0,0 +1,1744 @@
+use crate::canvas::{
+    circle_points, disk_points, line_points, ring_points, BlendMode, Camera2D, Canvas, Origin,
+    RenderTarget, Transform, Transformer,
+};
+use crate::color::Color;
+use crate::compress;
+use crate::image::sprite::Sprite;
+#[cfg(feature = "window")]
+use crate::input::{GamepadAxis, GamepadButton, KEY_MAP};
+use crate::input::{Input, Key, MouseButton, WEB_KEY_MAP};
+use crate::math::matrix::Mat4x4;
+use crate::math::FVec2D;
+use crate::math::FVec3D;
+use crate::math::Mat3x3;
+use crate::math::Point2D;
+use crate::particles::{Particle, ParticleSystem};
+use crate::render::{next_frame, FrameStats, Render2D};
+#[cfg(feature = "window")]
+use crate::render::{VirtualKeyCode, WinitInputHelper};
+use crate::ui::{button, ButtonStyle, Rect, TextField};
+use crate::utils::d3::{shade, HiddenSurface, Light, Mesh3D, Object3D, Renderer3D, Triangle3D};
+use crate::utils::stl;
+use std::f32::consts::FRAC_PI_2;
+
+/// Helper count the pixels on the canvas that are not of the given color
+fn count_painted(canvas: &Canvas, background: Color) -> usize {
+    canvas
+        .pixels
+        .borrow()
+        .iter()
+        .filter(|pixel| **pixel != background)
+        .count()
+}
+
+#[test]
+fn line_clipped_to_canvas() {
+    let canvas = Canvas::new(100, 50);
+    canvas.line_between(
+        Point2D::new(-1_000_000, 10),
+        Point2D::new(1_000_000, 10),
+        Color::WHITE,
+    );
+    assert_eq!(count_painted(&canvas, Color::BLACK), 100);
+    assert_eq!(canvas.get_pixel(0, 10), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(99, 10), Some(Color::WHITE));
+}
+
+#[test]
+fn line_clipped_diagonal() {
+    let canvas = Canvas::new(100, 100);
+    canvas.line_between(
+        Point2D::new(-1_000_000, -1_000_000),
+        Point2D::new(1_000_000, 1_000_000),
+        Color::WHITE,
+    );
+    assert_eq!(canvas.get_pixel(50, 50), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(99, 99), Some(Color::WHITE));
+    assert!(count_painted(&canvas, Color::BLACK) <= 100);
+}
+
+#[test]
+fn line_outside_canvas() {
+    let canvas = Canvas::new(100, 50);
+    canvas.line_between(
+        Point2D::new(-1_000_000, -10),
+        Point2D::new(1_000_000, -10),
+        Color::WHITE,
+    );
+    assert_eq!(count_painted(&canvas, Color::BLACK), 0);
+}
+
+#[test]
+fn fill_polygon_hexagon() {
+    let canvas = Canvas::new(100, 100);
+    let center = Point2D::new(50, 50);
+    let hexagon: Vec<Point2D> = (0..6)
+        .map(|i| {
+            let angle = i as f32 * std::f32::consts::PI / 3.0;
+            center + Point2D::new((30.0 * angle.cos()) as i32, (30.0 * angle.sin()) as i32)
+        })
+        .collect();
+    canvas.fill_polygon(&hexagon, Color::WHITE);
+
+    assert_eq!(canvas.get_pixel(50, 50), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(30, 40), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(10, 50), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(90, 50), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(25, 25), Some(Color::BLACK));
+}
+
+#[test]
+fn fill_polygon_concave() {
+    let canvas = Canvas::new(100, 100);
+    // U shaped polygon, the notch must stay empty
+    let shape = [
+        Point2D::new(10, 10),
+        Point2D::new(30, 10),
+        Point2D::new(30, 60),
+        Point2D::new(70, 60),
+        Point2D::new(70, 10),
+        Point2D::new(90, 10),
+        Point2D::new(90, 90),
+        Point2D::new(10, 90),
+    ];
+    canvas.fill_polygon(&shape, Color::WHITE);
+
+    assert_eq!(canvas.get_pixel(20, 20), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(80, 20), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(50, 80), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(50, 30), Some(Color::BLACK));
+}
+
+#[test]
+fn fill_triangle_gouraud_vertices() {
+    let canvas = Canvas::new(100, 100);
+    let (v1, v2, v3) = (
+        Point2D::new(10, 10),
+        Point2D::new(90, 20),
+        Point2D::new(40, 90),
+    );
+    canvas.fill_triangle_gouraud(v1, Color::RED, v2, Color::GREEN, v3, Color::BLUE);
+
+    // the fill rule may leave a vertex out, just inside of it its color still dominates
+    let near_v1 = canvas.get_pixel(12, 12).unwrap();
+    assert!(near_v1.r() > 240 && near_v1.g() < 15 && near_v1.b() < 15);
+    let near_v2 = canvas.get_pixel(87, 21).unwrap();
+    assert!(near_v2.g() > 240 && near_v2.r() < 15 && near_v2.b() < 15);
+    let near_v3 = canvas.get_pixel(40, 87).unwrap();
+    assert!(near_v3.b() > 240 && near_v3.r() < 15 && near_v3.g() < 15);
+
+    // somewhere in the middle all three colors contribute
+    let middle = canvas.get_pixel(46, 40).unwrap();
+    assert!(middle.r() > 0 && middle.g() > 0 && middle.b() > 0);
+    assert_eq!(canvas.get_pixel(90, 90), Some(Color::BLACK));
+}
+
+#[test]
+fn fill_triangle_textured_samples() {
+    let canvas = Canvas::new(100, 100);
+    // 2x2 texture, each quadrant with its own color
+    let texture = Sprite {
+        width: 2,
+        height: 2,
+        pixels: vec![Color::RED, Color::GREEN, Color::BLUE, Color::WHITE],
+    };
+    canvas.fill_triangle_textured(
+        [
+            Point2D::new(10, 10),
+            Point2D::new(90, 10),
+            Point2D::new(10, 90),
+        ],
+        [
+            FVec2D::new(0.0, 0.0),
+            FVec2D::new(1.0, 0.0),
+            FVec2D::new(0.0, 1.0),
+        ],
+        &texture,
+    );
+
+    assert_eq!(canvas.get_pixel(12, 12), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(70, 12), Some(Color::GREEN));
+    assert_eq!(canvas.get_pixel(12, 70), Some(Color::BLUE));
+    assert_eq!(canvas.get_pixel(80, 80), Some(Color::BLACK));
+}
+
+#[test]
+fn color_tone_adjustments() {
+    assert_eq!(Color::BLACK.invert(), Color::WHITE);
+    assert_eq!(
+        Color::rgba(10, 20, 30, 40).invert(),
+        Color::rgba(245, 235, 225, 40)
+    );
+    assert_eq!(Color::RED.grayscale(), Color::rgb(76, 76, 76));
+    assert_eq!(Color::WHITE.grayscale(), Color::WHITE);
+    assert_eq!(Color::WHITE.sepia(), Color::rgb(255, 255, 239));
+    assert_eq!(Color::GRAY.adjust(0.0, 1.0), Color::GRAY);
+    assert_eq!(Color::GRAY.adjust(1.0, 1.0), Color::WHITE);
+    assert_eq!(Color::RED.adjust(0.0, 0.0), Color::rgb(128, 128, 128));
+    assert_eq!(
+        Color::rgb(138, 118, 128).adjust(0.0, 2.0),
+        Color::rgb(148, 108, 128)
+    );
+}
+
+#[test]
+fn canvas_map_colors() {
+    let canvas = Canvas::new(4, 4);
+    canvas.map_colors(|color| color.invert());
+    assert_eq!(count_painted(&canvas, Color::WHITE), 0);
+}
+
+#[test]
+fn color_premultiplied_conversion() {
+    let color = Color::rgba(200, 100, 50, 128);
+    let premultiplied = color.to_premultiplied();
+    assert_eq!(premultiplied, Color::rgba(100, 50, 25, 128));
+    // converting back loses a bit of precision
+    let restored = premultiplied.from_premultiplied();
+    assert!((restored.r() as i32 - 200).abs() <= 1);
+    assert!((restored.g() as i32 - 100).abs() <= 1);
+    assert_eq!(restored.alpha(), 128);
+    assert_eq!(Color::RED.to_premultiplied(), Color::RED);
+    assert_eq!(
+        Color::rgba(9, 9, 9, 0).from_premultiplied(),
+        Color::rgba(0, 0, 0, 0)
+    );
+}
+
+#[test]
+fn canvas_blend_modes() {
+    let canvas = Canvas::new(4, 4);
+    let half_red = Color::rgba(255, 0, 0, 128);
+    canvas.fill(Color::BLUE);
+
+    assert_eq!(canvas.blend_mode(), BlendMode::Replace);
+    canvas.plot(1, 1, half_red);
+    assert_eq!(canvas.get_pixel(1, 1), Some(half_red));
+
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    canvas.plot(2, 2, half_red);
+    assert_eq!(canvas.get_pixel(2, 2), Some(Color::rgba(128, 0, 127, 255)));
+
+    // premultiplied over premultiplied gives the same result
+    canvas.set_blend_mode(BlendMode::PremultipliedSrcOver);
+    canvas.plot(3, 3, half_red.to_premultiplied());
+    assert_eq!(canvas.get_pixel(3, 3), Some(Color::rgba(128, 0, 127, 255)));
+}
+
+#[test]
+fn draw_sprite_orientation_and_alpha() {
+    // 2x2 sprite, top row opaque red and half transparent red, bottom row green and empty
+    let half_red = Color::rgba(255, 0, 0, 128);
+    let clear = Color::rgba(0, 0, 0, 0);
+    let sprite = Sprite {
+        width: 2,
+        height: 2,
+        pixels: vec![Color::RED, half_red, Color::GREEN, clear],
+    };
+    let blended = Color::rgba(128, 0, 127, 255);
+    let b = Color::BLUE;
+
+    // 3x3 canvas, the sprite at (1, 1) (y = 0 is not drawable): the buffer of a BottomLeft
+    // canvas starts with the bottom row, the sprite's top row is the last one
+    let canvas = Canvas::new(3, 3);
+    canvas.fill(Color::BLUE);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    canvas.draw_sprite(Point2D::new(1, 1), &sprite);
+    assert_eq!(
+        *canvas.pixels.borrow(),
+        vec![b, b, b, b, Color::GREEN, b, b, Color::RED, blended]
+    );
+
+    // same picture with a TopLeft origin, the buffer then starts with the top row
+    let top_left = Canvas::new(3, 3);
+    top_left.set_origin(Origin::TopLeft);
+    top_left.fill(Color::BLUE);
+    top_left.set_blend_mode(BlendMode::SrcOver);
+    top_left.draw_sprite(Point2D::new(1, 0), &sprite);
+    assert_eq!(
+        *top_left.pixels.borrow(),
+        vec![b, Color::RED, blended, b, Color::GREEN, b, b, b, b]
+    );
+
+    // Replace stores the half transparent pixel as is, clipped at the canvas edges
+    let replaced = Canvas::new(3, 3);
+    replaced.fill(Color::BLUE);
+    replaced.draw_sprite(Point2D::new(2, 0), &sprite);
+    assert_eq!(replaced.get_pixel(2, 1), Some(Color::GREEN));
+    replaced.draw_sprite(Point2D::new(1, 2), &sprite);
+    assert_eq!(replaced.get_pixel(2, 2), Some(half_red));
+    assert_eq!(count_painted(&replaced, Color::BLUE), 3);
+
+    // the older sprite method gives the same result
+    let legacy = Canvas::new(3, 3);
+    legacy.fill(Color::BLUE);
+    legacy.set_blend_mode(BlendMode::SrcOver);
+    legacy.sprite(Point2D::new(1, 1), &sprite);
+    assert_eq!(*legacy.pixels.borrow(), *canvas.pixels.borrow());
+}
+
+#[test]
+fn fill_rect_fast_matches_plot() {
+    let fast = Canvas::new(10, 10);
+    let plotted = Canvas::new(10, 10);
+    fast.fill_rect_fast(&Point2D::new(-2, 3), 6, 20, Color::RED);
+    for y in 3..23 {
+        for x in -2..4 {
+            plotted.plot(x, y, Color::RED);
+        }
+    }
+    assert_eq!(*fast.pixels.borrow(), *plotted.pixels.borrow());
+    assert_eq!(count_painted(&fast, Color::BLACK), 4 * 7);
+
+    // translucent colors still go through the blend mode
+    let half_red = Color::rgba(255, 0, 0, 128);
+    fast.fill(Color::BLUE);
+    fast.set_blend_mode(BlendMode::SrcOver);
+    fast.fill_rect_fast(&Point2D::new(1, 1), 2, 2, half_red);
+    assert_eq!(fast.get_pixel(2, 2), Some(Color::rgba(128, 0, 127, 255)));
+}
+
+#[test]
+fn filled_shapes_draw_in_sequence() {
+    // every primitive holds the pixel buffer borrowed while drawing, drawing them one
+    // after the other must release it each time and paint each shape
+    let canvas = Canvas::new(20, 20);
+    canvas.fill_circle(Point2D::new(10, 10), 4, Color::RED);
+    assert_eq!(canvas.get_pixel(10, 10), Some(Color::RED));
+    canvas.fill_triangle(
+        Point2D::new(1, 1),
+        Point2D::new(8, 1),
+        Point2D::new(1, 8),
+        Color::GREEN,
+    );
+    assert_eq!(canvas.get_pixel(2, 2), Some(Color::GREEN));
+    canvas.fill_rectangle(&Point2D::new(15, 15), 3, 3, Color::BLUE);
+    assert_eq!(canvas.get_pixel(16, 16), Some(Color::BLUE));
+    canvas.polygon(Point2D::new(10, 2), 6, 3, Color::WHITE, None);
+    assert!(count_painted(&canvas, Color::BLACK) > 0);
+}
+
+#[test]
+fn batch_points_and_lines() {
+    let batched = Canvas::new(10, 10);
+    let single = Canvas::new(10, 10);
+    let points = [
+        (Point2D::new(1, 1), Color::RED),
+        (Point2D::new(5, 6), Color::GREEN),
+        (Point2D::new(20, 20), Color::BLUE),
+    ];
+    let segments = [
+        (Point2D::new(0, 2), Point2D::new(9, 8), Color::WHITE),
+        (Point2D::new(3, 9), Point2D::new(3, 1), Color::BLUE),
+    ];
+    batched.plot_points(&points);
+    batched.draw_lines(&segments);
+    for (point, color) in points {
+        single.plot(point.x, point.y, color);
+    }
+    for (origin, dest, color) in segments {
+        single.line_between(origin, dest, color);
+    }
+    assert_eq!(*batched.pixels.borrow(), *single.pixels.borrow());
+}
+
+#[test]
+fn line_points_match_drawn_line() {
+    let canvas = Canvas::new(20, 20);
+    for (origin, dest) in [
+        (Point2D::new(2, 3), Point2D::new(15, 12)),
+        (Point2D::new(15, 2), Point2D::new(3, 18)),
+        (Point2D::new(4, 4), Point2D::new(4, 10)),
+        (Point2D::new(12, 7), Point2D::new(1, 7)),
+    ] {
+        canvas.fill(Color::BLACK);
+        canvas.line_between(origin, dest, Color::WHITE);
+        let points: Vec<Point2D> = line_points(origin, dest).collect();
+        assert_eq!(points.len(), count_painted(&canvas, Color::BLACK));
+        for point in points {
+            assert_eq!(canvas.get_pixel(point.x, point.y), Some(Color::WHITE));
+        }
+    }
+
+    let diagonal: Vec<Point2D> = line_points(Point2D::new(5, 5), Point2D::new(1, 1)).collect();
+    assert_eq!(
+        diagonal,
+        (1..=5).map(|i| Point2D::new(i, i)).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn circle_and_disk_points() {
+    let canvas = Canvas::new(40, 40);
+    let center = Point2D::new(20, 20);
+    canvas.circle(center, 12, Color::WHITE);
+    let outline: Vec<Point2D> = circle_points(center, 12).collect();
+    assert_eq!(outline.len(), count_painted(&canvas, Color::BLACK));
+    for point in &outline {
+        assert_eq!(canvas.get_pixel(point.x, point.y), Some(Color::WHITE));
+        // every outline pixel lies inside the disk
+        let (dx, dy) = (point.x - center.x, point.y - center.y);
+        assert!(dx * dx + dy * dy <= 12 * 12 + 12);
+    }
+
+    let radius = 50;
+    let count = disk_points(Point2D::new(0, 0), radius).count() as f32;
+    let area = std::f32::consts::PI * (radius * radius) as f32;
+    assert!((count - area).abs() / area < 0.05, "{} vs {}", count, area);
+    assert_eq!(disk_points(center, 0).collect::<Vec<_>>(), vec![center]);
+    assert_eq!(disk_points(center, -1).count(), 0);
+}
+
+#[test]
+fn color_lerp_srgb_and_linear() {
+    assert_eq!(
+        Color::lerp(Color::BLACK, Color::WHITE, 0.5),
+        Color::rgb(128, 128, 128)
+    );
+    assert_eq!(
+        Color::lerp_linear(Color::BLACK, Color::WHITE, 0.5),
+        Color::rgb(188, 188, 188)
+    );
+    // end points are kept as is
+    let (a, b) = (Color::rgba(10, 200, 30, 0), Color::rgb(250, 40, 90));
+    assert_eq!(Color::lerp_linear(a, b, 0.0), a);
+    assert_eq!(Color::lerp_linear(a, b, 1.0), b);
+    assert_eq!(Color::lerp_linear(a, b, 0.5).alpha(), 128);
+}
+
+#[test]
+fn color_hex_display_and_parse() {
+    let orange = Color::rgb(255, 136, 0);
+    assert_eq!(Color::from_hex("#ff8800").unwrap(), orange);
+    assert_eq!(Color::from_hex("FF8800").unwrap(), orange);
+    assert_eq!(Color::from_hex("#f80").unwrap(), orange);
+    assert_eq!(
+        "#f808".parse::<Color>().unwrap(),
+        Color::rgba(255, 136, 0, 136)
+    );
+    assert_eq!(
+        "#ff880080".parse::<Color>().unwrap(),
+        Color::rgba(255, 136, 0, 128)
+    );
+
+    assert_eq!(format!("{}", orange), "#ff8800ff");
+    let translucent = Color::rgba(1, 2, 254, 127);
+    assert_eq!(
+        translucent.to_string().parse::<Color>().unwrap(),
+        translucent
+    );
+
+    for bad in [
+        "", "#", "#ff88f", "#ff8800f", "#gg8800", "#ff 800", "#ffé00",
+    ] {
+        assert!(bad.parse::<Color>().is_err(), "{:?} should not parse", bad);
+    }
+}
+
+#[test]
+fn color_const_constructors() {
+    const SKY: Color = Color::rgb(135, 206, 235);
+    const GLASS: Color = Color::rgba(200, 220, 255, 64);
+    const EMBER: Color = Color::from_bytes(&[255, 80, 0, 255]);
+    assert_eq!(SKY.as_bytes(), [135, 206, 235, 255]);
+    assert_eq!(GLASS.alpha(), 64);
+    assert_eq!(EMBER, Color::rgb(255, 80, 0));
+    assert_eq!(Color::ORANGE.to_string(), "#ffa500ff");
+    assert_eq!(Color::NAVY, Color::rgb(0, 0, 128));
+}
+
+#[test]
+fn color_distances_and_lab() {
+    let orange = Color::rgb(255, 136, 0);
+    assert_eq!(orange.distance(&orange), 0.0);
+    assert_eq!(orange.distance_cie76(&orange), 0.0);
+
+    let black_white = Color::BLACK.distance(&Color::WHITE);
+    assert!((black_white - 255.0 * 3.0_f32.sqrt()).abs() < 1e-3);
+    assert!((Color::BLACK.distance_cie76(&Color::WHITE) - 100.0).abs() < 0.1);
+
+    let (l, a, b) = Color::WHITE.to_lab();
+    assert!((l - 100.0).abs() < 0.1 && a.abs() < 0.1 && b.abs() < 0.1);
+    assert!(Color::BLACK.to_lab().0.abs() < 1e-3);
+
+    // red to Lab and back
+    let (l, a, b) = Color::RED.to_lab();
+    assert!((l - 53.24).abs() < 0.1 && (a - 80.09).abs() < 0.2 && (b - 67.20).abs() < 0.2);
+    for color in [Color::RED, orange, Color::TEAL, Color::GRAY] {
+        let (l, a, b) = color.to_lab();
+        assert_eq!(Color::from_lab(l, a, b), color);
+    }
+}
+
+#[test]
+fn color_blend() {
+    let gray = Color::BLACK.blend(&Color::WHITE, 0.5);
+    assert_eq!(gray, Color::rgb(128, 128, 128));
+    assert_eq!(Color::RED.blend(&Color::BLUE, 0.0), Color::RED);
+    assert_eq!(Color::RED.blend(&Color::BLUE, 3.0), Color::BLUE);
+    // alpha is mixed too
+    let faded = Color::RED.blend(&Color::rgba(255, 0, 0, 0), 0.25);
+    assert_eq!(faded.alpha(), 191);
+}
+
+#[test]
+fn camera_world_to_screen() {
+    let canvas = Canvas::new(100, 80);
+    // looking at the canvas center without zoom leaves coordinates unchanged
+    let identity = Camera2D::new(FVec2D::new(50.0, 40.0), 1.0);
+    for p in [
+        Point2D::new(0, 0),
+        Point2D::new(13, 77),
+        Point2D::new(-5, 200),
+    ] {
+        assert_eq!(identity.world_to_screen(p, &canvas), p);
+        assert_eq!(identity.screen_to_world(p, &canvas), p);
+    }
+
+    // panned to (10, 20) and zoomed in twice
+    let camera = Camera2D::new(FVec2D::new(10.0, 20.0), 2.0);
+    assert_eq!(
+        camera.world_to_screen(Point2D::new(10, 20), &canvas),
+        Point2D::new(50, 40)
+    );
+    assert_eq!(
+        camera.world_to_screen(Point2D::new(15, 10), &canvas),
+        Point2D::new(60, 20)
+    );
+    assert_eq!(
+        camera.screen_to_world(Point2D::new(60, 20), &canvas),
+        Point2D::new(15, 10)
+    );
+    assert_eq!(
+        camera.screen_to_world(Point2D::new(0, 0), &canvas),
+        Point2D::new(-15, 0)
+    );
+
+    // drawing through the camera applies the same conversion
+    let view = canvas.with_camera(&camera);
+    view.plot(Point2D::new(15, 10), Color::RED);
+    assert_eq!(canvas.get_pixel(60, 20), Some(Color::RED));
+    view.fill_circle(Point2D::new(10, 20), 3, Color::GREEN);
+    assert_eq!(canvas.get_pixel(55, 40), Some(Color::GREEN));
+    assert_eq!(canvas.get_pixel(57, 40), Some(Color::BLACK));
+}
+
+#[test]
+fn particle_system_update_and_draw() {
+    let mut system = ParticleSystem::with_capacity(4);
+    system.gravity = FVec2D::new(0.0, -10.0);
+    system.emit(Particle::new(
+        FVec2D::new(5.0, 5.0),
+        FVec2D::new(10.0, 0.0),
+        1.0,
+        Color::RED,
+    ));
+    system.emit(Particle::new(
+        FVec2D::new(1.0, 1.0),
+        FVec2D::new(0.0, 0.0),
+        0.3,
+        Color::GREEN,
+    ));
+
+    system.update(0.5);
+    assert_eq!(system.len(), 1);
+    let particle = system.particles()[0];
+    assert_eq!(particle.vel, FVec2D::new(10.0, -5.0));
+    assert_eq!(particle.pos, FVec2D::new(10.0, 2.5));
+    assert!((particle.life - 0.5).abs() < 1e-6);
+
+    let canvas = Canvas::new(20, 20);
+    system.draw(&canvas);
+    assert_eq!(canvas.get_pixel(10, 3), Some(Color::RED));
+    assert_eq!(count_painted(&canvas, Color::BLACK), 1);
+
+    system.update(0.5);
+    assert!(system.is_empty());
+}
+
+/// Helper renderer moving a pixel one step to the right every frame
+struct MovingPixel {
+    x: i32,
+    stop_at: i32,
+}
+
+impl Render2D for MovingPixel {
+    fn width(&mut self) -> u32 {
+        10
+    }
+    fn height(&mut self) -> u32 {
+        5
+    }
+    fn setup(&mut self, canvas: &mut Canvas) -> bool {
+        canvas.plot(0, 1, Color::GREEN);
+        true
+    }
+    fn update(&mut self, canvas: &mut Canvas, _input: &dyn Input, delta_t: f32) -> bool {
+        assert!((delta_t - 1.0 / 60.0).abs() < 1e-6);
+        self.x += 1;
+        canvas.plot(self.x, 1, Color::RED);
+        self.x < self.stop_at
+    }
+}
+
+#[test]
+fn render_headless_captures_frames() {
+    let frames = MovingPixel { x: 0, stop_at: 100 }.render_headless(3);
+    assert_eq!(frames.len(), 3);
+    for (i, frame) in frames.iter().enumerate() {
+        assert_eq!((frame.width(), frame.height()), (10, 5));
+        // cleared to black before every update
+        assert_eq!(frame.get_pixel(i as i32 + 1, 1), Some(Color::RED));
+        assert_eq!(count_painted(frame, Color::BLACK), 1);
+    }
+
+    // stops as soon as update returns false
+    let frames = MovingPixel { x: 0, stop_at: 2 }.render_headless(10);
+    assert_eq!(frames.len(), 2);
+}
+
+#[test]
+fn clear_color_keeps_dirty_rect_small() {
+    let mut renderer = MovingPixel { x: 0, stop_at: 100 };
+    let mut canvas = Canvas::new(10, 5);
+    let mut stats = FrameStats::default();
+    let mut frame = |renderer: &mut MovingPixel, canvas: &mut Canvas| {
+        canvas.clear_dirty();
+        let input = TypedInput {
+            text: "",
+            keys: &[],
+        };
+        next_frame(renderer, canvas, &input, 1.0 / 60.0, &mut stats);
+        let rect = canvas.dirty_rect().unwrap();
+        (rect.left, rect.bottom, rect.width, rect.height)
+    };
+    // the first clear covers the whole canvas
+    assert_eq!(frame(&mut renderer, &mut canvas), (0, 0, 10, 5));
+
+    // a single pixel drawn in the same place again
+    renderer.x = 0;
+    assert_eq!(frame(&mut renderer, &mut canvas), (1, 4, 1, 1));
+
+    // a moving pixel: the old one is cleared, the new one drawn
+    assert_eq!(frame(&mut renderer, &mut canvas), (1, 4, 2, 1));
+    assert_eq!(count_painted(&canvas, Color::BLACK), 1);
+    assert_eq!(canvas.get_pixel(2, 1), Some(Color::RED));
+
+    // another color clears everything
+    canvas.fill(Color::BLUE);
+    assert_eq!(frame(&mut renderer, &mut canvas), (0, 0, 10, 5));
+}
+
+#[test]
+fn web_key_map_round_trip() {
+    for (key, code) in WEB_KEY_MAP.iter() {
+        assert_eq!(Key::from_web_code(code), Some(*key));
+    }
+    // codes are physical keys, case sensitive
+    assert_eq!(Key::from_web_code("KeyQ"), Some(Key::Q));
+    assert_eq!(Key::from_web_code("ArrowLeft"), Some(Key::Left));
+    assert_eq!(Key::from_web_code("keyq"), None);
+    assert_eq!(Key::from_web_code("NumLock"), None);
+}
+
+#[test]
+#[cfg(feature = "window")]
+fn key_map_round_trip() {
+    for (key, code) in KEY_MAP.iter() {
+        assert_eq!(key.to_virtual_keycode(), *code);
+        assert_eq!(Key::from_virtual_keycode(*code), Some(*key));
+    }
+    assert_eq!(Key::from_virtual_keycode(VirtualKeyCode::Numlock), None);
+
+    // the winit helper is an Input, nothing is pressed before any event
+    let helper = WinitInputHelper::new();
+    let input: &dyn Input = &helper;
+    assert!(!input.key_pressed(Key::A));
+    assert!(!input.key_held(Key::Space));
+    assert!(!input.key_released(Key::Enter));
+    // keyboard only inputs have no gamepad
+    assert!(!input.button_pressed(GamepadButton::South));
+    assert!(!input.button_held(GamepadButton::DPadUp));
+    assert_eq!(input.axis(GamepadAxis::LeftStickX), 0.0);
+}
+
+/// Helper renderer keeping the frame statistics it was given
+#[derive(Default)]
+struct StatsRecorder {
+    stats: Vec<FrameStats>,
+}
+
+impl Render2D for StatsRecorder {
+    fn fixed_timestep(&self) -> Option<f32> {
+        Some(0.5)
+    }
+    fn update_with_stats(
+        &mut self,
+        _canvas: &mut Canvas,
+        _input: &dyn Input,
+        _delta_t: f32,
+        stats: &FrameStats,
+    ) -> bool {
+        self.stats.push(*stats);
+        true
+    }
+}
+
+#[test]
+fn frame_stats_given_to_update() {
+    let mut recorder = StatsRecorder::default();
+    assert_eq!(recorder.render_headless(3).len(), 3);
+    let indexes: Vec<u64> = recorder.stats.iter().map(|s| s.frame_index).collect();
+    assert_eq!(indexes, [0, 1, 2]);
+    let elapsed: Vec<f32> = recorder
+        .stats
+        .iter()
+        .map(|s| s.elapsed.as_secs_f32())
+        .collect();
+    assert_eq!(elapsed, [0.0, 0.5, 1.0]);
+    assert!(recorder.stats.iter().all(|s| s.fps == 2.0));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_round_trip() {
+    use crate::math::matrix::Mat4x4;
+    use crate::math::{FMat3, FVec3D};
+
+    let color = Color::rgba(255, 128, 0, 200);
+    let json = serde_json::to_string(&color).unwrap();
+    assert_eq!(json, "[255,128,0,200]");
+    assert_eq!(serde_json::from_str::<Color>(&json).unwrap(), color);
+
+    let position = FVec2D::new(1.5, -2.0);
+    let json = serde_json::to_string(&position).unwrap();
+    assert_eq!(serde_json::from_str::<FVec2D>(&json).unwrap(), position);
+
+    let normal = FVec3D::new(0.0, 1.0, 0.0);
+    let json = serde_json::to_string(&normal).unwrap();
+    assert_eq!(serde_json::from_str::<FVec3D>(&json).unwrap(), normal);
+
+    let transform = FMat3::rotation(0.5) * FMat3::translate(3.0, 4.0);
+    let json = serde_json::to_string(&transform).unwrap();
+    assert_eq!(serde_json::from_str::<FMat3>(&json).unwrap(), transform);
+
+    let projection = Mat4x4::<f32>::identity();
+    let json = serde_json::to_string(&projection).unwrap();
+    assert_eq!(
+        serde_json::from_str::<Mat4x4<f32>>(&json).unwrap(),
+        projection
+    );
+
+    let sprite = Sprite::from_rgba(2, 1, &[1, 2, 3, 4, 5, 6, 7, 8]).unwrap();
+    let json = serde_json::to_string(&sprite).unwrap();
+    let restored: Sprite = serde_json::from_str(&json).unwrap();
+    assert_eq!((restored.width, restored.height), (2, 1));
+    assert_eq!(restored.pixels, sprite.pixels);
+}
+
+#[test]
+fn canvas_checkerboard_and_pattern() {
+    let canvas = Canvas::new(8, 8);
+    canvas.fill_checkerboard(2, Color::WHITE, Color::GRAY);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(2, 1), Some(Color::GRAY));
+    assert_eq!(canvas.get_pixel(1, 3), Some(Color::GRAY));
+    assert_eq!(canvas.get_pixel(3, 3), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(7, 5), Some(Color::GRAY));
+
+    let tile = Sprite::from_rgba(3, 1, &[255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255]).unwrap();
+    canvas.fill_pattern(&tile);
+    assert_eq!(canvas.get_pixel(0, 4), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(4, 4), Some(Color::GREEN));
+    assert_eq!(canvas.get_pixel(5, 7), Some(Color::BLUE));
+}
+
+#[test]
+fn canvas_grid_lines() {
+    let canvas = Canvas::new(10, 10);
+    canvas.draw_grid(4, Color::GRAY);
+    assert_eq!(canvas.get_pixel(4, 5), Some(Color::GRAY));
+    assert_eq!(canvas.get_pixel(8, 1), Some(Color::GRAY));
+    assert_eq!(canvas.get_pixel(5, 4), Some(Color::GRAY));
+    assert_eq!(canvas.get_pixel(5, 5), Some(Color::BLACK));
+    // the right and bottom edges close the grid
+    for i in 1..10 {
+        assert_eq!(
+            canvas.get_pixel(9, i),
+            Some(Color::GRAY),
+            "right edge, y = {}",
+            i
+        );
+        assert_eq!(
+            canvas.get_pixel(i, 9),
+            Some(Color::GRAY),
+            "bottom edge, x = {}",
+            i
+        );
+    }
+    assert_eq!(canvas.get_pixel(7, 5), Some(Color::BLACK));
+
+    let canvas = Canvas::new(10, 10);
+    canvas.draw_grid_with_origin(4, Point2D::new(-3, 6), Color::GRAY);
+    // vertical lines at x = 1, 5, 9 and horizontal lines at y = 2, 6 and the bottom edge
+    for x in [1, 5, 9] {
+        assert_eq!(canvas.get_pixel(x, 3), Some(Color::GRAY));
+    }
+    for y in [2, 6, 9] {
+        assert_eq!(canvas.get_pixel(3, y), Some(Color::GRAY));
+    }
+    assert_eq!(canvas.get_pixel(4, 4), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(3, 8), Some(Color::BLACK));
+
+    let canvas = Canvas::new(10, 10);
+    canvas.draw_grid(0, Color::GRAY);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 0);
+}
+
+#[test]
+fn canvas_dirty_rect_tracking() {
+    let canvas = Canvas::new(200, 200);
+    let full = canvas.dirty_rect().unwrap();
+    assert_eq!(
+        (full.left, full.bottom, full.width, full.height),
+        (0, 0, 200, 200)
+    );
+    canvas.clear_dirty();
+    assert!(canvas.dirty_rect().is_none());
+
+    // a scene that only updates its top left corner
+    canvas.fill_rect_fast(&Point2D::new(0, 1), 16, 16, Color::RED);
+    let rect = canvas.dirty_rect().unwrap();
+    assert_eq!(
+        (rect.left, rect.bottom, rect.width, rect.height),
+        (0, 184, 16, 16)
+    );
+    // 1 KiB uploaded instead of the 160 KB of the whole canvas
+    assert_eq!(rect.width * rect.height * 4, 1024);
+
+    canvas.plot(20, 10, Color::RED);
+    let rect = canvas.dirty_rect().unwrap();
+    assert_eq!(
+        (rect.left, rect.bottom, rect.width, rect.height),
+        (0, 184, 21, 16)
+    );
+
+    canvas.clear_dirty();
+    canvas.plot(-5, 10, Color::RED);
+    canvas.plot(10, 10, Color::TRANSPARENT);
+    assert!(canvas.dirty_rect().is_none());
+
+    canvas.map_colors(|color| color.invert());
+    assert_eq!(canvas.dirty_rect().unwrap().width, 200);
+}
+
+#[test]
+fn growable_canvas_expands_on_plot() {
+    let canvas = Canvas::growable(4, 4);
+    assert!(canvas.is_growable());
+    assert!(canvas.content_bounds().is_none());
+
+    canvas.plot(1, 1, Color::RED);
+    assert_eq!((canvas.width(), canvas.height()), (4, 4));
+
+    // past the right/bottom edges and into negative coordinates
+    canvas.plot(10, 12, Color::GREEN);
+    canvas.plot(-5, -7, Color::BLUE);
+    assert!(canvas.width() as i32 > 15 && canvas.height() as i32 > 19);
+    let origin = canvas.content_origin();
+    assert!(origin.x <= -5 && origin.y <= -7);
+
+    // earlier pixels keep their content coordinates
+    let content_pixel = |x: i32, y: i32| {
+        let origin = canvas.content_origin();
+        canvas.get_pixel(x - origin.x, y - origin.y)
+    };
+    assert_eq!(content_pixel(1, 1), Some(Color::RED));
+    assert_eq!(content_pixel(10, 12), Some(Color::GREEN));
+    assert_eq!(content_pixel(-5, -7), Some(Color::BLUE));
+    assert_eq!(
+        canvas.content_bounds(),
+        Some((Point2D::new(-5, -7), Point2D::new(10, 12)))
+    );
+
+    canvas.plot_points(&[
+        (Point2D::new(-200, 3), Color::WHITE),
+        (Point2D::new(2, 150), Color::YELLOW),
+    ]);
+    assert_eq!(content_pixel(-200, 3), Some(Color::WHITE));
+    assert_eq!(content_pixel(2, 150), Some(Color::YELLOW));
+    assert_eq!(content_pixel(-5, -7), Some(Color::BLUE));
+
+    // fixed canvases still clip
+    let fixed = Canvas::new(4, 4);
+    fixed.plot(10, 12, Color::GREEN);
+    assert_eq!((fixed.width(), fixed.height()), (4, 4));
+    assert_eq!(count_painted(&fixed, Color::BLACK), 0);
+    assert_eq!(fixed.content_origin(), Point2D::new(0, 0));
+
+    let empty = Canvas::growable(0, 0);
+    empty.plot(3, -2, Color::RED);
+    let origin = empty.content_origin();
+    assert_eq!(
+        empty.get_pixel(3 - origin.x, -2 - origin.y),
+        Some(Color::RED)
+    );
+}
+
+#[test]
+fn growable_canvas_max_size() {
+    let canvas = Canvas::growable(4, 4);
+    assert_eq!(canvas.max_size(), (4096, 4096));
+    canvas.set_max_size(100, 50);
+    canvas.plot(i32::MAX, 0, Color::RED);
+    canvas.plot(0, i32::MIN, Color::RED);
+    canvas.plot_points(&[(Point2D::new(-97, 0), Color::RED)]);
+    assert_eq!((canvas.width(), canvas.height()), (4, 4));
+    assert!(canvas.content_bounds().is_none());
+
+    // growing up to the limit works, the margin is trimmed to fit
+    canvas.plot(99, 49, Color::RED);
+    assert_eq!((canvas.width(), canvas.height()), (100, 50));
+    canvas.plot(-1, 0, Color::RED);
+    assert_eq!((canvas.width(), canvas.height()), (100, 50));
+    assert_eq!(count_painted(&canvas, Color::BLACK), 1);
+}
+
+#[test]
+fn plot_fractional_coordinates() {
+    let exact = Canvas::new(8, 8);
+    exact.plot_f(5.0, 3.0, Color::RED);
+    let reference = Canvas::new(8, 8);
+    reference.plot(5, 3, Color::RED);
+    assert_eq!(*exact.pixels.borrow(), *reference.pixels.borrow());
+
+    let canvas = Canvas::new(8, 8);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    canvas.plot_f(2.5, 3.0, Color::WHITE);
+    assert_eq!(canvas.get_pixel(2, 3), Some(Color::rgb(128, 128, 128)));
+    assert_eq!(canvas.get_pixel(3, 3), Some(Color::rgb(128, 128, 128)));
+    assert_eq!(count_painted(&canvas, Color::BLACK), 2);
+
+    let canvas = Canvas::new(8, 8);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    canvas.plot_f(4.25, 4.75, Color::WHITE);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 4);
+    // the nearest pixel gets most of the coverage
+    let brightest = canvas.get_pixel(4, 5).unwrap();
+    for (x, y) in [(4, 4), (5, 4), (5, 5)] {
+        assert!(canvas.get_pixel(x, y).unwrap().r() < brightest.r());
+    }
+}
+
+#[test]
+fn fill_triangle_shared_edge_covered_once() {
+    // a square split along its diagonal, drawn translucent so overlaps would show
+    let canvas = Canvas::new(16, 16);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    let half_white = Color::rgba(255, 255, 255, 128);
+    canvas.fill_triangle(
+        Point2D::new(2, 2),
+        Point2D::new(12, 2),
+        Point2D::new(12, 12),
+        half_white,
+    );
+    canvas.fill_triangle(
+        Point2D::new(2, 12),
+        Point2D::new(12, 12),
+        Point2D::new(2, 2),
+        half_white,
+    );
+    let once = half_white.composite_over(Color::BLACK);
+    for i in 2..12 {
+        assert_eq!(canvas.get_pixel(i, i), Some(once), "diagonal at {}", i);
+    }
+    // the 10x10 square (top-left rule leaves out the right and bottom edges)
+    for y in 2..12 {
+        for x in 2..12 {
+            assert_eq!(canvas.get_pixel(x, y), Some(once), "pixel {},{}", x, y);
+        }
+    }
+    assert_eq!(count_painted(&canvas, Color::BLACK), 100);
+}
+
+#[test]
+fn fill_triangle_gouraud_shared_edge_covered_once() {
+    // same split square as above, through the rasterizer of the Gouraud, textured and
+    // depth fills
+    let canvas = Canvas::new(16, 16);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    let half_white = Color::rgba(255, 255, 255, 128);
+    let (a, b, c, d) = (
+        Point2D::new(2, 2),
+        Point2D::new(12, 2),
+        Point2D::new(12, 12),
+        Point2D::new(2, 12),
+    );
+    canvas.fill_triangle_gouraud(a, half_white, b, half_white, c, half_white);
+    canvas.fill_triangle_gouraud(d, half_white, c, half_white, a, half_white);
+    let once = half_white.composite_over(Color::BLACK);
+    for i in 2..12 {
+        assert_eq!(canvas.get_pixel(i, i), Some(once), "diagonal at {}", i);
+    }
+    for y in 2..12 {
+        for x in 2..12 {
+            assert_eq!(canvas.get_pixel(x, y), Some(once), "pixel {},{}", x, y);
+        }
+    }
+    assert_eq!(count_painted(&canvas, Color::BLACK), 100);
+}
+
+#[test]
+fn fill_triangle_degenerate() {
+    // collinear points draw the segment they span
+    let canvas = Canvas::new(16, 16);
+    canvas.fill_triangle(
+        Point2D::new(6, 6),
+        Point2D::new(2, 2),
+        Point2D::new(10, 10),
+        Color::RED,
+    );
+    let line = Canvas::new(16, 16);
+    line.line_between(Point2D::new(2, 2), Point2D::new(10, 10), Color::RED);
+    assert_eq!(*canvas.pixels.borrow(), *line.pixels.borrow());
+
+    // a single point draws a single pixel
+    let canvas = Canvas::new(16, 16);
+    canvas.fill_triangle(
+        Point2D::new(5, 7),
+        Point2D::new(5, 7),
+        Point2D::new(5, 7),
+        Color::RED,
+    );
+    assert_eq!(canvas.get_pixel(5, 7), Some(Color::RED));
+    assert_eq!(count_painted(&canvas, Color::BLACK), 1);
+
+    // degenerate triangles partly or fully outside of the canvas must not panic
+    let far = Point2D::new(-40_000, 90_000);
+    canvas.fill_triangle(far, far, far, Color::GREEN);
+    canvas.fill_triangle(
+        Point2D::new(-20, 8),
+        Point2D::new(40, 8),
+        Point2D::new(100, 8),
+        Color::GREEN,
+    );
+    assert_eq!(canvas.get_pixel(0, 8), Some(Color::GREEN));
+    assert_eq!(canvas.get_pixel(15, 8), Some(Color::GREEN));
+}
+
+#[test]
+fn fill_circle_blends_each_pixel_once() {
+    let canvas = Canvas::new(32, 32);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    let half_white = Color::rgba(255, 255, 255, 128);
+    canvas.fill_circle(Point2D::new(16, 16), 10, half_white);
+
+    let once = half_white.composite_over(Color::BLACK);
+    let covered = disk_points(Point2D::new(16, 16), 10).count();
+    assert_eq!(count_painted(&canvas, Color::BLACK), covered);
+    for pixel in canvas.pixels.borrow().iter() {
+        assert!(*pixel == Color::BLACK || *pixel == once, "{:?}", pixel);
+    }
+}
+
+#[test]
+fn canvas_origin_controls_row_order() {
+    let canvas = Canvas::new(4, 3);
+    assert_eq!(canvas.origin(), Origin::BottomLeft);
+    // bottom left keeps the canvas' original layout: y goes to buffer row height - y, so
+    // y = 0 is dropped and the first buffer row is never drawn
+    canvas.plot(1, 0, Color::WHITE);
+    canvas.plot(1, 1, Color::RED);
+    canvas.plot(2, 2, Color::GREEN);
+    assert_eq!(canvas.get_pixel(1, 0), None);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::RED));
+    let height = canvas.height() as usize;
+    assert_eq!(canvas.pixels.borrow()[(height - 1) * 4 + 1], Color::RED);
+    assert_eq!(canvas.pixels.borrow()[(height - 2) * 4 + 2], Color::GREEN);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 2);
+
+    // switching keeps the coordinates and moves the rows
+    canvas.set_origin(Origin::TopLeft);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(2, 2), Some(Color::GREEN));
+    assert_eq!(canvas.pixels.borrow()[4 + 1], Color::RED);
+    assert_eq!(canvas.pixels.borrow()[2 * 4 + 2], Color::GREEN);
+    // top left draws y = 0 too
+    canvas.plot(1, 0, Color::WHITE);
+    assert_eq!(canvas.pixels.borrow()[1], Color::WHITE);
+    canvas.set_origin(Origin::BottomLeft);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::RED));
+    assert_eq!(canvas.pixels.borrow()[1], Color::WHITE);
+
+    // drawing gives the same picture with either origin
+    let top_left = Canvas::new(6, 6);
+    top_left.set_origin(Origin::TopLeft);
+    let bottom_left = Canvas::new(6, 6);
+    for canvas in [&top_left, &bottom_left] {
+        canvas.fill_rect_fast(&Point2D::new(0, 1), 3, 2, Color::BLUE);
+        canvas.line_between(Point2D::new(0, 5), Point2D::new(5, 1), Color::WHITE);
+    }
+    bottom_left.set_origin(Origin::TopLeft);
+    assert_eq!(*top_left.pixels.borrow(), *bottom_left.pixels.borrow());
+
+    // growable canvases keep their pixels in place with either origin
+    let growable = Canvas::growable(4, 4);
+    growable.set_origin(Origin::TopLeft);
+    growable.plot(1, 1, Color::RED);
+    growable.plot(-3, -9, Color::GREEN);
+    growable.plot(9, 12, Color::BLUE);
+    let origin = growable.content_origin();
+    assert_eq!(
+        growable.get_pixel(1 - origin.x, 1 - origin.y),
+        Some(Color::RED)
+    );
+    assert_eq!(
+        growable.get_pixel(-3 - origin.x, -9 - origin.y),
+        Some(Color::GREEN)
+    );
+    assert_eq!(
+        growable.get_pixel(9 - origin.x, 12 - origin.y),
+        Some(Color::BLUE)
+    );
+}
+
+/// Helper input for a single frame: `text` typed and `keys` pressed
+struct TypedInput {
+    text: &'static str,
+    keys: &'static [Key],
+}
+
+impl Input for TypedInput {
+    fn key_pressed(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+    fn key_held(&self, key: Key) -> bool {
+        self.keys.contains(&key)
+    }
+    fn key_released(&self, _key: Key) -> bool {
+        false
+    }
+    fn typed_text(&self) -> String {
+        self.text.to_string()
+    }
+}
+
+#[test]
+fn text_field_editing() {
+    let frame = |text, keys| TypedInput { text, keys };
+    let mut field = TextField::new();
+    assert!(field.handle_input(&frame("helo", &[])));
+    assert_eq!((field.text(), field.cursor()), ("helo", 4));
+
+    // caret moves don't change the text, insertion happens at the caret
+    assert!(!field.handle_input(&frame("", &[Key::Left])));
+    assert!(field.handle_input(&frame("l", &[])));
+    assert_eq!((field.text(), field.cursor()), ("hello", 4));
+    field.handle_input(&frame("", &[Key::Right, Key::Right]));
+    assert_eq!(field.cursor(), 5);
+
+    // backspace removes the character before the caret, multi byte ones included
+    field.handle_input(&frame("é", &[]));
+    assert!(field.handle_input(&frame("", &[Key::Backspace])));
+    assert_eq!((field.text(), field.cursor()), ("hello", 5));
+    let mut field = TextField::with_text("ab");
+    field.handle_input(&frame("", &[Key::Left, Key::Left]));
+    field.handle_input(&frame("", &[Key::Left]));
+    assert!(!field.handle_input(&frame("", &[Key::Backspace])));
+    assert_eq!((field.text(), field.cursor()), ("ab", 0));
+}
+
+#[test]
+fn text_field_caret_blinks_when_idle() {
+    let idle = TypedInput {
+        text: "",
+        keys: &[],
+    };
+    let mut field = TextField::with_text("hi");
+    let visible: Vec<bool> = (0..70)
+        .map(|_| {
+            field.handle_input(&idle);
+            field.caret_visible()
+        })
+        .collect();
+    assert!(visible[..29].iter().all(|&shown| shown));
+    assert!(visible[29..59].iter().all(|&shown| !shown));
+    assert!(visible[59]);
+
+    // typing shows the caret again right away
+    field.handle_input(&TypedInput {
+        text: "!",
+        keys: &[],
+    });
+    assert!(field.caret_visible());
+    let canvas = Canvas::new(50, 50);
+    field.draw(&canvas, Point2D::new(5, 5), 0.2, Color::WHITE);
+}
+
+/// Helper input with only a mouse at `position`, the left button held and/or just pressed
+struct MouseInput {
+    position: Option<Point2D>,
+    held: bool,
+    pressed: bool,
+}
+
+impl Input for MouseInput {
+    fn key_pressed(&self, _key: Key) -> bool {
+        false
+    }
+    fn key_held(&self, _key: Key) -> bool {
+        false
+    }
+    fn key_released(&self, _key: Key) -> bool {
+        false
+    }
+    fn mouse_position(&self) -> Option<Point2D> {
+        self.position
+    }
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.pressed && button == MouseButton::Left
+    }
+    fn mouse_held(&self, button: MouseButton) -> bool {
+        self.held && button == MouseButton::Left
+    }
+}
+
+#[test]
+fn button_hover_and_click() {
+    let canvas = Canvas::new(100, 100);
+    let rect = Rect::new(10, 10, 40, 20);
+    let style = ButtonStyle {
+        border: None,
+        ..ButtonStyle::default()
+    };
+    let mouse = |position, held, pressed| MouseInput {
+        position,
+        held,
+        pressed,
+    };
+
+    // clicks outside the button are ignored
+    let outside = mouse(Some(Point2D::new(50, 10)), true, true);
+    assert!(!button(&canvas, rect, "ok", &outside, &style));
+    assert_eq!(canvas.get_pixel(12, 12), Some(style.background));
+    assert_eq!(canvas.get_pixel(50, 10), Some(Color::BLACK));
+
+    let hover = mouse(Some(Point2D::new(49, 29)), false, false);
+    assert!(!button(&canvas, rect, "ok", &hover, &style));
+    assert_eq!(canvas.get_pixel(12, 12), Some(style.hover));
+
+    let click = mouse(Some(Point2D::new(10, 10)), true, true);
+    assert!(button(&canvas, rect, "ok", &click, &style));
+    assert_eq!(canvas.get_pixel(12, 12), Some(style.pressed));
+
+    // held from an earlier frame is not a new click
+    let held = mouse(Some(Point2D::new(10, 10)), true, false);
+    assert!(!button(&canvas, rect, "ok", &held, &style));
+    assert!(!button(
+        &canvas,
+        rect,
+        "ok",
+        &mouse(None, true, true),
+        &style
+    ));
+}
+
+#[test]
+fn transform_sprite_degenerate_scales() {
+    let canvas = Canvas::new(100, 100);
+    let tile = Sprite {
+        width: 4,
+        height: 4,
+        pixels: vec![Color::RED; 16],
+    };
+
+    // zero scale: nothing to draw, no NaN bounding box
+    for &(cx, cy) in [(0.0, 0.0), (0.0, 3.0), (1e-6, 1e-6)].iter() {
+        let mut transformer = Transformer::new();
+        transformer.add(Transform::Scale(cx, cy));
+        transformer.add(Transform::Translate(10.0, 10.0));
+        canvas.transform_sprite(&tile, &transformer);
+    }
+    assert_eq!(count_painted(&canvas, Color::BLACK), 0);
+
+    // mirrored: exactly the 8x8 scaled sprite, left of the translation
+    let mut transformer = Transformer::new();
+    transformer.add(Transform::Scale(-2.0, 2.0));
+    transformer.add(Transform::Translate(50.0, 10.0));
+    canvas.transform_sprite(&tile, &transformer);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 64);
+    assert_eq!(canvas.get_pixel(45, 12), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(50, 12), Some(Color::BLACK));
+
+    // mostly off canvas and huge: clipped without looping over the whole bounding box
+    let mut transformer = Transformer::new();
+    transformer.add(Transform::Scale(1e6, 1e6));
+    transformer.add(Transform::Translate(-10.0, -10.0));
+    canvas.transform_sprite(&tile, &transformer);
+    // every drawable row, y = 0 is not
+    assert_eq!(count_painted(&canvas, Color::BLACK), 100 * 99);
+}
+
+#[test]
+fn render_target_to_sprite_and_blit() {
+    // both row orders give the same sprite, top row first
+    for &origin in [Origin::BottomLeft, Origin::TopLeft].iter() {
+        let target = RenderTarget::new(4, 3);
+        target.set_origin(origin);
+        target.plot(1, 1, Color::RED);
+        target.plot(2, 2, Color::GREEN);
+        let sprite = target.to_sprite();
+        assert_eq!((sprite.width, sprite.height), (4, 3));
+        assert_eq!(sprite.get_pixel(1, 1), Some(Color::RED));
+        assert_eq!(sprite.get_pixel(2, 2), Some(Color::GREEN));
+        assert_eq!(sprite.get_pixel(0, 0), Some(Color::BLACK));
+    }
+
+    // render a shape off-screen, then draw it at an offset on the visible canvas
+    let target = RenderTarget::new(20, 20);
+    target.fill_circle(Point2D::new(10, 10), 5, Color::WHITE);
+    let canvas = Canvas::new(100, 100);
+    canvas.fill(Color::BLUE);
+    canvas.blit(&target, Point2D::new(40, 30));
+    assert_eq!(canvas.get_pixel(50, 40), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(40, 30), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(39, 30), Some(Color::BLUE));
+    assert_eq!(
+        count_painted(&canvas, Color::BLUE),
+        20 * 20,
+        "the whole target is copied"
+    );
+    assert_eq!(
+        count_painted(&canvas, Color::BLACK),
+        count_painted(&target, Color::BLACK) + 100 * 100 - 20 * 20
+    );
+}
+
+#[test]
+fn fill_ring_and_arc() {
+    let center = Point2D::new(50, 50);
+    let canvas = Canvas::new(100, 100);
+    canvas.fill_ring(center, 10, 20, Color::WHITE);
+    // the hole is untouched, the band is filled
+    for point in disk_points(center, 10) {
+        assert_eq!(canvas.get_pixel(point.x, point.y), Some(Color::BLACK));
+    }
+    for &(x, y) in [(61, 50), (70, 50), (50, 31), (36, 36)].iter() {
+        assert_eq!(canvas.get_pixel(x, y), Some(Color::WHITE), "({}, {})", x, y);
+    }
+    assert_eq!(canvas.get_pixel(71, 50), Some(Color::BLACK));
+    let expected = disk_points(center, 20).count() - disk_points(center, 10).count();
+    assert_eq!(count_painted(&canvas, Color::BLACK), expected);
+    assert_eq!(ring_points(center, 10, 20).count(), expected);
+    // no hole and an empty band
+    assert_eq!(
+        ring_points(center, -1, 5).count(),
+        disk_points(center, 5).count()
+    );
+    assert_eq!(ring_points(center, 5, 5).count(), 0);
+
+    // quarter from the positive x axis towards the positive y axis (down on screen)
+    let canvas = Canvas::new(100, 100);
+    canvas.fill_ring_arc(center, 10, 20, 0.0, 90.0, Color::WHITE);
+    assert_eq!(canvas.get_pixel(64, 64), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(36, 64), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(64, 36), Some(Color::BLACK));
+    let quarter = count_painted(&canvas, Color::BLACK);
+    assert!((quarter as i32 - expected as i32 / 4).abs() < 20);
+
+    // the sweep wraps around 0 degrees
+    let canvas = Canvas::new(100, 100);
+    canvas.fill_ring_arc(center, 10, 20, 270.0, 90.0, Color::WHITE);
+    assert_eq!(canvas.get_pixel(65, 50), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(35, 50), Some(Color::BLACK));
+    let canvas = Canvas::new(100, 100);
+    canvas.fill_ring_arc(center, 10, 20, -90.0, 270.0, Color::WHITE);
+    assert_eq!(count_painted(&canvas, Color::BLACK), expected);
+}
+
+#[test]
+fn save_restore_clip() {
+    let canvas = Canvas::new(40, 40);
+    canvas.set_blend_mode(BlendMode::SrcOver);
+    canvas.save();
+    canvas.set_clip(Some(Rect::new(10, 10, 10, 10)));
+    canvas.set_blend_mode(BlendMode::Replace);
+    canvas.fill_rect_fast(&Point2D::new(0, 0), 40, 40, Color::WHITE);
+    canvas.line(0, 15, 39, 15, Color::RED);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 100);
+    assert_eq!(canvas.get_pixel(9, 15), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(15, 15), Some(Color::RED));
+    canvas.restore();
+
+    // the clip does not leak past restore
+    assert_eq!(canvas.clip(), None);
+    assert_eq!(canvas.blend_mode(), BlendMode::SrcOver);
+    canvas.line(0, 30, 39, 30, Color::RED);
+    assert_eq!(canvas.get_pixel(0, 30), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(30, 30), Some(Color::RED));
+
+    // nested saves, and restore without a save is ignored
+    canvas.set_clip(Some(Rect::new(0, 0, 5, 5)));
+    canvas.save();
+    canvas.set_clip(None);
+    canvas.restore();
+    assert_eq!(canvas.clip(), Some(Rect::new(0, 0, 5, 5)));
+    canvas.restore();
+    assert_eq!(canvas.clip(), Some(Rect::new(0, 0, 5, 5)));
+}
+
+#[test]
+fn transform_applies_to_drawing() {
+    let canvas = Canvas::new(40, 40);
+    canvas.set_transform(Mat3x3::translate(10.0, 5.0));
+    canvas.rectangle(Point2D::new(0, 0), 10, 10, Color::WHITE);
+    canvas.fill_rect_fast(&Point2D::new(2, 2), 6, 6, Color::RED);
+    // same pixels as drawing the translated shapes without a transform
+    let reference = Canvas::new(40, 40);
+    reference.rectangle(Point2D::new(10, 5), 10, 10, Color::WHITE);
+    reference.fill_rect_fast(&Point2D::new(12, 7), 6, 6, Color::RED);
+    assert!(canvas.pixels == reference.pixels);
+    assert_eq!(canvas.get_pixel(10, 5), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(0, 1), Some(Color::BLACK));
+
+    // saved with the other settings, reset goes back to canvas pixels
+    canvas.save();
+    canvas.set_transform(Mat3x3::scale(2.0, 2.0));
+    assert_eq!(canvas.transform(), Mat3x3::scale(2.0, 2.0));
+    canvas.restore();
+    assert_eq!(canvas.transform(), Mat3x3::translate(10.0, 5.0));
+    canvas.reset_transform();
+    canvas.plot(1, 1, Color::BLUE);
+    assert_eq!(canvas.get_pixel(1, 1), Some(Color::BLUE));
+
+    // filled rectangles cover width x height pixels, rotated or not
+    let canvas = Canvas::new(40, 40);
+    canvas.set_transform(Mat3x3::translate(5.0, 5.0));
+    canvas.fill_rectangle(&Point2D::new(0, 0), 4, 3, Color::RED);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 12);
+    assert_eq!(canvas.get_pixel(8, 7), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(9, 7), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(8, 8), Some(Color::BLACK));
+    let canvas = Canvas::new(40, 40);
+    canvas.set_transform(Mat3x3::translate(20.0, 20.0) * Mat3x3::rotation(FRAC_PI_2));
+    canvas.fill_rectangle(&Point2D::new(0, 0), 4, 3, Color::RED);
+    assert_eq!(count_painted(&canvas, Color::BLACK), 12);
+    assert_eq!(canvas.get_pixel(17, 20), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(19, 23), Some(Color::RED));
+    assert_eq!(canvas.get_pixel(20, 20), Some(Color::BLACK));
+    assert_eq!(canvas.get_pixel(17, 24), Some(Color::BLACK));
+
+    // a quarter turn around the origin maps +x onto +y
+    let canvas = Canvas::new(40, 40);
+    canvas.set_transform(Mat3x3::translate(20.0, 20.0) * Mat3x3::rotation(FRAC_PI_2));
+    canvas.line(0, 0, 10, 0, Color::WHITE);
+    assert_eq!(canvas.get_pixel(20, 29), Some(Color::WHITE));
+    assert_eq!(canvas.get_pixel(29, 20), Some(Color::BLACK));
+}
+
+#[test]
+fn compress_round_trips() {
+    let data: Vec<u8> = (0..5000_u32)
+        .map(|i| (i % 251) as u8 ^ (i / 97) as u8)
+        .collect();
+    for &level in [0, 1, 6, 10, 200].iter() {
+        assert_eq!(
+            compress::inflate(&compress::deflate(&data, level)),
+            Ok(data.clone())
+        );
+        assert_eq!(
+            compress::inflate_zlib(&compress::deflate_zlib(&data, level)),
+            Ok(data.clone())
+        );
+        assert_eq!(
+            compress::inflate_gzip(&compress::deflate_gzip(&data, level)),
+            Ok(data.clone())
+        );
+    }
+    assert_eq!(
+        compress::inflate_gzip(&compress::deflate_gzip(&[], 6)),
+        Ok(vec![])
+    );
+    // repetitive data does shrink
+    assert!(compress::deflate_zlib(&[7; 1000], 6).len() < 100);
+
+    // written by the gzip tool, with a file name in the header
+    let gzip = [
+        31, 139, 8, 8, 247, 255, 209, 106, 0, 3, 104, 101, 108, 108, 111, 46, 116, 120, 116, 0,
+        203, 72, 205, 201, 201, 215, 81, 200, 64, 162, 0, 159, 161, 202, 9, 19, 0, 0, 0,
+    ];
+    assert_eq!(
+        compress::inflate_gzip(&gzip),
+        Ok(b"hello, hello, hello".to_vec())
+    );
+
+    // corrupted or truncated data is an error, not a panic
+    let mut corrupted = compress::deflate_gzip(&data, 6);
+    let last = corrupted.len() - 5;
+    corrupted[last] ^= 0xff;
+    assert!(compress::inflate_gzip(&corrupted).is_err());
+    assert!(compress::inflate_gzip(&gzip[..30]).is_err());
+    assert!(compress::inflate_zlib(&[1, 2, 3]).is_err());
+    assert!(compress::inflate(&compress::deflate(&data, 6)[..100]).is_err());
+}
+
+#[test]
+fn lights_sum_and_clamp() {
+    let normal = FVec3D::new(0.0, 0.0, -1.0);
+    let point = FVec3D::new(0.0, 0.0, 0.0);
+
+    // directional lights follow the cosine of the angle, nothing from behind
+    let facing = Light::directional(FVec3D::new(0.0, 0.0, -2.0));
+    let tilted = Light::directional(FVec3D::new(1.0, 0.0, -1.0));
+    let behind = Light::directional(FVec3D::new(0.0, 0.0, 1.0));
+    assert_eq!(facing.intensity_at(normal, point), 1.0);
+    assert!((tilted.intensity_at(normal, point) - 0.5_f32.sqrt()).abs() < 1e-6);
+    assert_eq!(behind.intensity_at(normal, point), 0.0);
+
+    // point lights fade with the distance
+    let lamp = Light::point(FVec3D::new(0.0, 0.0, -2.0), 0.25);
+    assert_eq!(lamp.intensity_at(normal, point), 0.5);
+    assert_eq!(Light::ambient(0.2).intensity_at(normal, point), 0.2);
+
+    let color = Color::rgb(200, 100, 50);
+    let dim = [Light::ambient(0.25), lamp];
+    assert_eq!(shade(color, &dim, normal, point), Color::rgb(150, 75, 37));
+    let bright = [Light::ambient(0.5), facing, tilted];
+    assert_eq!(shade(color, &bright, normal, point), color);
+    assert_eq!(shade(color, &[behind], normal, point), Color::BLACK);
+    assert_eq!(shade(color, &[], normal, point), Color::BLACK);
+}
+
+#[test]
+fn backface_culling() {
+    // two triangles in the z = 0 plane, wound in opposite directions
+    let front = Triangle3D::new(
+        [
+            FVec3D::new(0.0, 0.0, 0.0),
+            FVec3D::new(0.0, 1.0, 0.0),
+            FVec3D::new(1.0, 0.0, 0.0),
+        ],
+        Color::RED,
+    );
+    let back = Triangle3D::new(
+        [front.vertices[0], front.vertices[2], front.vertices[1]],
+        Color::BLUE,
+    );
+    assert_eq!(front.normal(), FVec3D::new(0.0, 0.0, -1.0));
+    assert_eq!(back.normal(), FVec3D::new(0.0, 0.0, 1.0));
+
+    let camera = FVec3D::new(0.2, 0.2, -5.0);
+    assert!(front.is_facing(camera));
+    assert!(!back.is_facing(camera));
+    // from the other side it is the other way around
+    assert!(back.is_facing(FVec3D::new(0.0, 0.0, 5.0)));
+
+    let mesh = Mesh3D {
+        tris: vec![front, back],
+        vertices: Vec::new(),
+        colors: Vec::new(),
+    };
+    let visible = mesh.cull_backfaces(camera);
+    assert_eq!(visible.len(), 1);
+    assert_eq!(visible[0].color, Color::RED);
+}
+
+/// Helper: square facing a camera on the z axis, at depth `z`
+fn facing_square(half: f32, z: f32, color: Color) -> [Triangle3D; 2] {
+    let corner = |x: f32, y: f32| FVec3D::new(x * half, y * half, z);
+    [
+        Triangle3D::new(
+            [corner(-1.0, -1.0), corner(-1.0, 1.0), corner(1.0, -1.0)],
+            color,
+        ),
+        Triangle3D::new(
+            [corner(1.0, -1.0), corner(-1.0, 1.0), corner(1.0, 1.0)],
+            color,
+        ),
+    ]
+}
+
+#[test]
+fn renderer3d_hides_far_triangles() {
+    // a small red square in front of a bigger blue one, listed first so that drawing in
+    // mesh order would cover it
+    let mut tris = facing_square(0.5, 2.0, Color::RED).to_vec();
+    tris.extend_from_slice(&facing_square(2.0, 4.0, Color::BLUE));
+    // a square lying in the plane of the camera, nothing of it is in front: culled
+    tris.extend_from_slice(&facing_square(0.2, 0.0, Color::GREEN));
+    let mesh = Mesh3D {
+        tris,
+        vertices: Vec::new(),
+        colors: Vec::new(),
+    };
+    let world = Mat4x4::<f32>::translate(0.0, 0.0, 0.0);
+    let projection = Mat4x4::projected(1.0, 1.0, 100.0, 0.1);
+
+    for &method in [HiddenSurface::PainterSort, HiddenSurface::DepthBuffer].iter() {
+        let canvas = Canvas::new(40, 40);
+        canvas.fill(Color::BLACK);
+        let mut renderer = Renderer3D::new(projection, FVec3D::new(0.0, 0.0, 0.0));
+        renderer.hidden_surface = method;
+        // only the red and blue squares are drawn
+        assert_eq!(renderer.render(&mesh, &world, &canvas), 4, "{:?}", method);
+
+        // red in the middle, blue around it, then the background
+        assert_eq!(canvas.get_pixel(20, 20), Some(Color::RED), "{:?}", method);
+        assert_eq!(canvas.get_pixel(20, 12), Some(Color::BLUE), "{:?}", method);
+        assert_eq!(canvas.get_pixel(2, 2), Some(Color::BLACK), "{:?}", method);
+    }
+
+    // triangles going behind the camera are clipped at the near plane
+    let crossing = Triangle3D::new(
+        [
+            FVec3D::new(-1.0, -1.0, -2.0),
+            FVec3D::new(-1.0, 1.0, 3.0),
+            FVec3D::new(1.0, -1.0, 3.0),
+        ],
+        Color::GREEN,
+    );
+    let mesh = Mesh3D {
+        tris: vec![crossing],
+        vertices: Vec::new(),
+        colors: Vec::new(),
+    };
+    let canvas = Canvas::new(40, 40);
+    canvas.fill(Color::BLACK);
+    let mut renderer = Renderer3D::new(projection, FVec3D::new(0.0, 0.0, 0.0));
+    renderer.lights = vec![Light::ambient(1.0)];
+    // one vertex behind: the part in front is a quad, two triangles
+    assert_eq!(renderer.render(&mesh, &world, &canvas), 2);
+    assert!(count_painted(&canvas, Color::BLACK) > 0);
+}
+
+#[test]
+fn fill_triangle_depth_respects_clip() {
+    let canvas = Canvas::new(20, 20);
+    canvas.set_origin(Origin::TopLeft);
+    let mut depth = vec![f32::INFINITY; 20 * 20];
+    let square = |a, b, c, d| [[a, b, c], [a, c, d]];
+    let corners = [
+        Point2D::new(0, 0),
+        Point2D::new(19, 0),
+        Point2D::new(19, 19),
+        Point2D::new(0, 19),
+    ];
+    // a far triangle clipped to the left half does not hide the right half
+    canvas.set_clip(Some(Rect::new(0, 0, 10, 20)));
+    for v in square(corners[0], corners[1], corners[2], corners[3]).iter() {
+        canvas.fill_triangle_depth(*v, [5.0; 3], [Color::BLUE; 3], &mut depth);
+    }
+    canvas.set_clip(None);
+    for v in square(corners[0], corners[1], corners[2], corners[3]).iter() {
+        canvas.fill_triangle_depth(*v, [9.0; 3], [Color::RED; 3], &mut depth);
+    }
+    assert_eq!(canvas.get_pixel(5, 10), Some(Color::BLUE));
+    assert_eq!(canvas.get_pixel(15, 10), Some(Color::RED));
+    assert!(depth[10 * 20 + 15] == 9.0);
+
+    // both HiddenSurface methods rasterize the same pixels
+    let flat = Canvas::new(20, 20);
+    flat.set_origin(Origin::TopLeft);
+    flat.fill(Color::BLACK);
+    let v = [Point2D::new(1, 1), Point2D::new(17, 4), Point2D::new(6, 18)];
+    flat.fill_triangle_gouraud(v[0], Color::RED, v[1], Color::RED, v[2], Color::RED);
+    let tested = Canvas::new(20, 20);
+    tested.set_origin(Origin::TopLeft);
+    tested.fill(Color::BLACK);
+    let mut depth = vec![f32::INFINITY; 20 * 20];
+    tested.fill_triangle_depth(v, [1.0; 3], [Color::RED; 3], &mut depth);
+    assert_eq!(flat.pixels.borrow()[..], tested.pixels.borrow()[..]);
+    assert!(count_painted(&flat, Color::BLACK) > 0);
+}
+
+#[test]
+fn obj_vertex_colors() {
+    let obj = "v 0 0 2 1 0 0\nv 0 1 2 0 1 0.5\nv 1 0 2 0 0 1\nv 1 1 2\nf 1 2 3\nf 3 2 4\n";
+    let mesh = Object3D::from_reader(obj.as_bytes()).unwrap().mesh;
+    let default = Color::rgb(170, 248, 11);
+    let green = Color::rgb(0, 255, 128);
+    assert_eq!(mesh.colors, vec![Color::RED, green, Color::BLUE, default]);
+    assert_eq!(
+        mesh.tris[0].vertex_colors,
+        Some([Color::RED, green, Color::BLUE])
+    );
+    assert_eq!(
+        mesh.tris[1].vertex_colors,
+        Some([Color::BLUE, green, default])
+    );
+
+    // the colors are blended across the triangles
+    let canvas = Canvas::new(40, 40);
+    canvas.fill(Color::BLACK);
+    let mut renderer = Renderer3D::new(
+        Mat4x4::projected(1.0, 1.0, 100.0, 0.1),
+        FVec3D::new(0.5, 0.5, 0.0),
+    );
+    renderer.lights = vec![Light::ambient(1.0)];
+    let world = Mat4x4::<f32>::translate(0.0, 0.0, 0.0);
+    for &method in [HiddenSurface::PainterSort, HiddenSurface::DepthBuffer].iter() {
+        renderer.hidden_surface = method;
+        renderer.render(&mesh, &world, &canvas);
+        let mut painted: Vec<Color> = canvas.pixels.borrow().clone();
+        painted.sort_by_key(|color| color.as_bytes());
+        painted.dedup();
+        assert!(painted.len() > 10, "{:?}", method);
+    }
+
+    // without colors the mesh keeps the default color
+    let mesh = Object3D::from_reader("v 0 0 0\nv 0 1 0\nv 1 0 0\nf 1 2 3\n".as_bytes())
+        .unwrap()
+        .mesh;
+    assert!(mesh.colors.is_empty());
+    assert_eq!(mesh.tris[0].vertex_colors, None);
+    assert_eq!(mesh.tris[0].color, default);
+}
+
+#[test]
+fn stl_ascii_and_binary() {
+    let ascii = stl::load("./assets/tetrahedron.stl").unwrap();
+    // the binary header starts with "solid" too, the file size tells them apart
+    let binary = stl::load("./assets/tetrahedron_binary.stl").unwrap();
+    assert_eq!(ascii.tris.len(), 4);
+    assert_eq!(binary.tris.len(), ascii.tris.len());
+    assert_eq!(ascii.vertices.len(), 12);
+    for (a, b) in ascii.tris.iter().zip(binary.tris.iter()) {
+        assert_eq!(a.vertices, b.vertices);
+        assert_eq!(a.face_normal, b.face_normal);
+    }
+
+    // the stored normals are kept, not recomputed
+    assert_eq!(ascii.tris[0].face_normal, Some(FVec3D::new(0.0, 0.0, -1.0)));
+    assert_eq!(
+        ascii.tris[3].face_normal,
+        Some(FVec3D::new(0.57735, 0.57735, 0.57735))
+    );
+    assert_eq!(
+        ascii.tris[0].vertices,
+        [
+            FVec3D::new(0.0, 0.0, 0.0),
+            FVec3D::new(0.0, 1.0, 0.0),
+            FVec3D::new(1.0, 0.0, 0.0)
+        ]
+    );
+    assert!(ascii.tris[0].normal().z < 0.0);
+
+    assert!(stl::from_bytes(b"solid broken\nfacet normal 0 0\nendfacet").is_err());
+    assert!(stl::from_bytes(&[0; 90]).is_err());
+
+    // a facet without a normal does not get the one of the facet before
+    let facet = "outer loop vertex 0 0 0 vertex 0 1 0 vertex 1 0 0 endloop";
+    let text = format!(
+        "solid s facet normal 0 0 -1 {} endfacet facet {} endfacet endsolid s",
+        facet, facet
+    );
+    let mesh = stl::from_bytes(text.as_bytes()).unwrap();
+    assert_eq!(mesh.tris[0].face_normal, Some(FVec3D::new(0.0, 0.0, -1.0)));
+    assert_eq!(mesh.tris[1].face_normal, None);
+    // vertices left after the last endfacet
+    let text = format!("solid s facet {} endsolid s", facet);
+    assert!(stl::from_bytes(text.as_bytes()).is_err());
+    let text = format!("solid s facet {}", facet);
+    assert!(stl::from_bytes(text.as_bytes()).is_err());
+}
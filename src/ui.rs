@@ -0,0 +1,204 @@
+//! Immediate mode helpers to build simple tools on top of the canvas
+//!
+//! They only read the Input abstraction, so they work with every render backend.
+//!
+//! Example usage:
+//!    // in Render2D::update
+//!    if name.handle_input(input) {
+//!        println!("name: {}", name.text());
+//!    }
+//!    name.draw(canvas, Point2D::new(10, 10), 0.25, Color::WHITE);
+//!    if ui::button(canvas, Rect::new(10, 40, 80, 24), "OK", input, &ButtonStyle::default()) {
+//!        submit(name.text());
+//!    }
+//!
+pub use crate::canvas::Rect;
+
+use crate::canvas::Canvas;
+use crate::color::Color;
+use crate::input::{Input, Key, MouseButton};
+use crate::math::Point2D;
+
+/// Frames the caret stays visible, then hidden, while blinking (half a second at 60 FPS)
+const CARET_BLINK_FRAMES: u32 = 30;
+
+/// Colors and text size used by button
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ButtonStyle {
+    /// Background when the mouse is elsewhere
+    pub background: Color,
+    /// Background while the mouse is over the button
+    pub hover: Color,
+    /// Background while the left button is held over the button
+    pub pressed: Color,
+    /// Outline color, None for no outline
+    pub border: Option<Color>,
+    /// Label color
+    pub text: Color,
+    /// Label size/scale, as for draw_string
+    pub text_size: f32,
+}
+
+impl Default for ButtonStyle {
+    /// Gray button with a white outline and label
+    fn default() -> Self {
+        Self {
+            background: Color::rgb(70, 70, 70),
+            hover: Color::rgb(100, 100, 100),
+            pressed: Color::rgb(45, 45, 45),
+            border: Some(Color::WHITE),
+            text: Color::WHITE,
+            text_size: 0.25,
+        }
+    }
+}
+
+/// Draw a button with a centered label, true on the frame it is clicked
+/// Stateless: call it on every frame from update, nothing is kept between calls.
+/// The background follows the mouse (see ButtonStyle), a click is the left mouse button
+/// going down over the button.
+/// # Arguments
+/// `canvas`    canvas to draw on
+/// `rect`      position and size of the button
+/// `label`     text centered on the button
+/// `input`     input given to update
+/// `style`     colors and text size
+pub fn button(
+    canvas: &Canvas,
+    rect: Rect,
+    label: &str,
+    input: &dyn Input,
+    style: &ButtonStyle,
+) -> bool {
+    let hovered = input
+        .mouse_position()
+        .is_some_and(|position| rect.contains(position));
+    let background = if hovered && input.mouse_held(MouseButton::Left) {
+        style.pressed
+    } else if hovered {
+        style.hover
+    } else {
+        style.background
+    };
+
+    let origin = Point2D::new(rect.x, rect.y);
+    canvas.fill_rect_fast(&origin, rect.width, rect.height, background);
+    if let Some(border) = style.border {
+        canvas.rectangle(origin, rect.width - 1, rect.height - 1, border);
+    }
+    canvas.draw_string_centered(
+        rect.center(),
+        label.to_string(),
+        style.text_size,
+        style.text,
+    );
+
+    hovered && input.mouse_pressed(MouseButton::Left)
+}
+
+/// Single line of editable text with a blinking caret
+/// Typing inserts at the caret, Backspace deletes before it and Left/Right move it.
+/// handle_input must be called once per frame, it also drives the blinking.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct TextField {
+    text: String,
+    /// Caret position in characters (not bytes), 0 is before the first one
+    cursor: usize,
+    /// Frames since the last edit or caret move, the caret is shown right after one
+    frames: u32,
+}
+
+impl TextField {
+    /// Create an empty text field
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a text field holding `text`, with the caret at the end
+    pub fn with_text(text: &str) -> Self {
+        let mut field = Self::new();
+        field.set_text(text);
+        field
+    }
+
+    /// Current text
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Replace the text, the caret moves to the end
+    pub fn set_text(&mut self, text: &str) {
+        self.text = text.to_string();
+        self.cursor = self.text.chars().count();
+        self.frames = 0;
+    }
+
+    /// Caret position in characters, 0 is before the first one
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Whether the caret is in the visible half of its blink
+    pub fn caret_visible(&self) -> bool {
+        self.frames % (2 * CARET_BLINK_FRAMES) < CARET_BLINK_FRAMES
+    }
+
+    /// Apply the characters typed and the editing keys pressed this frame
+    /// Returns true when the text changed.
+    pub fn handle_input(&mut self, input: &dyn Input) -> bool {
+        let mut changed = false;
+        let mut moved = false;
+
+        for character in input.typed_text().chars() {
+            let index = self.byte_index(self.cursor);
+            self.text.insert(index, character);
+            self.cursor += 1;
+            changed = true;
+        }
+        if input.key_pressed(Key::Backspace) && self.cursor > 0 {
+            self.cursor -= 1;
+            let index = self.byte_index(self.cursor);
+            self.text.remove(index);
+            changed = true;
+        }
+        if input.key_pressed(Key::Left) && self.cursor > 0 {
+            self.cursor -= 1;
+            moved = true;
+        }
+        if input.key_pressed(Key::Right) && self.cursor < self.text.chars().count() {
+            self.cursor += 1;
+            moved = true;
+        }
+
+        // keep the caret steady while editing, blinking resumes once idle
+        if changed || moved {
+            self.frames = 0;
+        } else {
+            self.frames = self.frames.wrapping_add(1);
+        }
+        changed
+    }
+
+    /// Draw the text with draw_string and the caret as a vertical line after its character
+    /// # Arguments
+    /// `origin`    top left position of the text
+    /// `size`      size/scale of the text, as for draw_string
+    /// `color`     color of the text and the caret
+    pub fn draw(&self, canvas: &Canvas, origin: Point2D, size: f32, color: Color) {
+        canvas.draw_string(origin, self.text.clone(), size, color);
+        if self.caret_visible() {
+            let before: String = self.text.chars().take(self.cursor).collect();
+            let x = origin.x() + canvas.text_size(&before, size).x() as i32;
+            let height = canvas.text_size(&self.text, size).y() as i32;
+            canvas.line(x, origin.y(), x, origin.y() + height, color);
+        }
+    }
+
+    /// Helper: byte offset in `text` of the character at `cursor`
+    fn byte_index(&self, cursor: usize) -> usize {
+        self.text
+            .char_indices()
+            .nth(cursor)
+            .map_or(self.text.len(), |(index, _)| index)
+    }
+}
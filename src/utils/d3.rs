@@ -1,4 +1,6 @@
+use crate::canvas::{gouraud_color, Canvas};
 use crate::color::Color;
+use crate::math::matrix::Mat4x4;
 use crate::math::*;
 use std::error::Error;
 use std::fs::File;
@@ -18,9 +20,18 @@ impl Object3D {
     /// `filename`  file containing the triangle mesh for the object
     pub fn from_file(filename: &str) -> Result<Object3D, Box<dyn Error>> {
         let file = File::open(filename)?;
-        let reader = BufReader::new(file);
+        Self::from_reader(BufReader::new(file))
+    }
+
+    /// Create a 3D Object from obj data with triangles, see from_file
+    /// Vertices may have a color after their position (`v x y z r g b`, from 0 to 1), the
+    /// mesh then keeps one color per vertex and its triangles are Gouraud shaded.
+    /// `reader`    obj file contents
+    pub fn from_reader(reader: impl BufRead) -> Result<Object3D, Box<dyn Error>> {
         let mut tris = Vec::<Triangle3D>::new();
         let mut vertices = Vec::<FVec3D>::new();
+        let mut colors = Vec::<Option<Color>>::new();
+        let mut indices = Vec::<[usize; 3]>::new();
 
         for line in reader.lines().flatten() {
             // reading vertices from obj file
@@ -34,6 +45,23 @@ impl Object3D {
                 let y: f32 = tokens[2].parse()?;
                 let z: f32 = tokens[3].parse()?;
                 vertices.push(FVec3D::new(x, y, z));
+                // optional vertex color
+                // formated as: v 0.00 1.00 2.00 1.0 0.5 0.0
+                let color = if tokens.len() >= 7 {
+                    let channel = |token: &String| -> Result<u8, Box<dyn Error>> {
+                        let value: f32 = token.parse()?;
+                        Ok((value.clamp(0.0, 1.0) * 255.0).round() as u8)
+                    };
+                    let (r, g, b) = (
+                        channel(&tokens[4])?,
+                        channel(&tokens[5])?,
+                        channel(&tokens[6])?,
+                    );
+                    Some(Color::rgb(r, g, b))
+                } else {
+                    None
+                };
+                colors.push(color);
             }
             // reading triangles from obj file
             // formated as: f 1 2 3 where each number is an index
@@ -46,24 +74,78 @@ impl Object3D {
                 let x: usize = tokens[1].parse()?;
                 let y: usize = tokens[2].parse()?;
                 let z: usize = tokens[3].parse()?;
-                let triangle = Triangle3D {
-                    vertices: [vertices[x - 1], vertices[y - 1], vertices[z - 1]],
-                    color: Color::rgb(170, 248, 11),
-                };
+                let triangle = Triangle3D::new(
+                    [vertices[x - 1], vertices[y - 1], vertices[z - 1]],
+                    DEFAULT_COLOR,
+                );
                 tris.push(triangle);
+                indices.push([x - 1, y - 1, z - 1]);
+            }
+        }
+
+        // vertices without a color keep the default one when others have it
+        let colors: Vec<Color> = if colors.iter().any(Option::is_some) {
+            colors
+                .iter()
+                .map(|color| color.unwrap_or(DEFAULT_COLOR))
+                .collect()
+        } else {
+            Vec::new()
+        };
+        if !colors.is_empty() {
+            for (triangle, index) in tris.iter_mut().zip(indices) {
+                triangle.vertex_colors = Some(index.map(|i| colors[i]));
             }
         }
         Ok(Object3D {
-            mesh: Mesh3D { tris, vertices },
+            mesh: Mesh3D {
+                tris,
+                vertices,
+                colors,
+            },
         })
     }
 }
 
+/// Color of the triangles read by Object3D
+pub(crate) const DEFAULT_COLOR: Color = Color::rgb(170, 248, 11);
+
 /// A triangle implementation in 3 dimensions
 #[derive(Default, Clone, Copy)]
 pub struct Triangle3D {
     pub vertices: [FVec3D; 3],
     pub color: Color,
+    /// Color of each vertex, interpolated across the triangle (Gouraud), instead of `color`
+    pub vertex_colors: Option<[Color; 3]>,
+    /// Face normal given by the file (e.g. STL), None when there is none
+    /// normal() computes it from the vertices.
+    pub face_normal: Option<FVec3D>,
+}
+
+impl Triangle3D {
+    /// Create a triangle of a single color, without vertex colors or face normal
+    pub fn new(vertices: [FVec3D; 3], color: Color) -> Self {
+        Self {
+            vertices,
+            color,
+            ..Self::default()
+        }
+    }
+
+    /// Unit normal of the face, the cross product of its edges (v1 - v0) x (v2 - v0)
+    pub fn normal(&self) -> FVec3D {
+        let line_one = self.vertices[1] - self.vertices[0];
+        let line_two = self.vertices[2] - self.vertices[0];
+        FVec3D::cross(line_one, line_two).unit_vector()
+    }
+
+    /// Whether the face is turned towards the camera (the side its normal points to)
+    /// `camera`    position of the camera, in the same space as the vertices
+    pub fn is_facing(&self, camera: FVec3D) -> bool {
+        // any of the points in the triangle is on its plane
+        let point_of_view = (self.vertices[0] - camera).unit_vector();
+        FVec3D::dot(point_of_view, self.normal()) < 0.0
+    }
 }
 
 /// A mesh of triangles
@@ -71,4 +153,271 @@ pub struct Triangle3D {
 pub struct Mesh3D {
     pub tris: Vec<Triangle3D>,
     pub vertices: Vec<FVec3D>,
+    /// Color of each vertex, empty when the vertices have no color
+    pub colors: Vec<Color>,
+}
+
+impl Mesh3D {
+    /// Triangles facing the camera, the others are hidden on closed meshes (backface culling)
+    /// `camera`    position of the camera, in the same space as the vertices
+    pub fn cull_backfaces(&self, camera: FVec3D) -> Vec<&Triangle3D> {
+        self.tris
+            .iter()
+            .filter(|triangle| triangle.is_facing(camera))
+            .collect()
+    }
+}
+
+/// A light source used to shade triangles, see shade
+/// Several lights are summed, the result is clamped between 0 (black) and 1 (full color).
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Light {
+    /// Light reaching every surface the same, whatever its orientation
+    Ambient { intensity: f32 },
+    /// Light from very far away (e.g. the sun), the same at every point
+    /// `direction` goes from the surfaces towards the light.
+    Directional { direction: FVec3D, intensity: f32 },
+    /// Light spreading from a position (e.g. a lamp)
+    /// It fades with the distance: intensity / (1 + attenuation * distance²).
+    Point {
+        position: FVec3D,
+        intensity: f32,
+        attenuation: f32,
+    },
+}
+
+impl Light {
+    /// Ambient light of the given intensity
+    pub fn ambient(intensity: f32) -> Self {
+        Light::Ambient { intensity }
+    }
+
+    /// Directional light with an intensity of 1, `direction` goes towards the light
+    pub fn directional(direction: FVec3D) -> Self {
+        Light::Directional {
+            direction,
+            intensity: 1.0,
+        }
+    }
+
+    /// Point light with an intensity of 1
+    /// # Arguments
+    /// `position`      where the light is
+    /// `attenuation`   how fast the light fades with the distance, 0 for not at all
+    pub fn point(position: FVec3D, attenuation: f32) -> Self {
+        Light::Point {
+            position,
+            intensity: 1.0,
+            attenuation,
+        }
+    }
+
+    /// Light received by a surface (Lambert's cosine law), 0 when it faces away
+    /// # Arguments
+    /// `normal`    unit normal of the surface
+    /// `point`     position of the surface, only used by point lights
+    pub fn intensity_at(&self, normal: FVec3D, point: FVec3D) -> f32 {
+        match *self {
+            Light::Ambient { intensity } => intensity,
+            Light::Directional {
+                direction,
+                intensity,
+            } => FVec3D::dot(normal, direction.unit_vector()).max(0.0) * intensity,
+            Light::Point {
+                position,
+                intensity,
+                attenuation,
+            } => {
+                let to_light = position - point;
+                let squared_distance = to_light.squared_length();
+                if squared_distance == 0.0 {
+                    return intensity;
+                }
+                let facing = FVec3D::dot(normal, to_light.unit_vector()).max(0.0);
+                facing * intensity / (1.0 + attenuation * squared_distance)
+            }
+        }
+    }
+}
+
+/// Color of a surface lit by `lights`: the sum of their intensities, clamped to [0, 1],
+/// times `color`
+/// # Arguments
+/// `color`     color of the surface under full light
+/// `lights`    lights shining on the surface, no lights gives black
+/// `normal`    unit normal of the surface
+/// `point`     position of the surface
+pub fn shade(color: Color, lights: &[Light], normal: FVec3D, point: FVec3D) -> Color {
+    let intensity: f32 = lights
+        .iter()
+        .map(|light| light.intensity_at(normal, point))
+        .sum();
+    color * intensity.clamp(0.0, 1.0)
+}
+
+/// How Renderer3D hides the faces behind other faces
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum HiddenSurface {
+    /// Painter's algorithm: draw the triangles from the farthest to the closest
+    /// Cheap, but intersecting or overlapping long triangles may be drawn in the wrong order.
+    PainterSort,
+    /// Depth buffer (z-buffer): keep the closest triangle for every pixel
+    DepthBuffer,
+}
+
+/// Draws meshes on a canvas: transform, cull the back faces, clip against the near plane,
+/// shade, project and rasterize
+///
+/// Example usage:
+///    let projection = Mat4x4::projected(aspect_ratio, fov_radians, 1000.0, 0.1);
+///    let mut renderer = Renderer3D::new(projection, FVec3D::new(0.0, 0.0, -3.0));
+///    // in Render2D::update
+///    renderer.render(&mesh, &world_matrix, canvas);
+#[derive(Debug, Clone)]
+pub struct Renderer3D {
+    /// Projection matrix, see Mat4x4::projected
+    pub projection: Mat4x4<f32>,
+    /// Position of the camera
+    pub camera: FVec3D,
+    /// Direction the camera looks at
+    pub look_dir: FVec3D,
+    /// Triangles (or the parts of them) closer to the camera than this are not drawn
+    pub near: f32,
+    /// Lights shading the triangles, see shade
+    pub lights: Vec<Light>,
+    pub hidden_surface: HiddenSurface,
+    /// Reused between frames by HiddenSurface::DepthBuffer
+    depth: Vec<f32>,
+}
+
+impl Renderer3D {
+    /// Renderer looking along z with a light coming from the camera side, painter sorted
+    /// # Arguments
+    /// `projection`    projection matrix, see Mat4x4::projected
+    /// `camera`        position of the camera
+    pub fn new(projection: Mat4x4<f32>, camera: FVec3D) -> Self {
+        Self {
+            projection,
+            camera,
+            look_dir: FVec3D::new(0.0, 0.0, 1.0),
+            near: 0.1,
+            lights: vec![Light::directional(FVec3D::new(0.0, 0.0, -1.0))],
+            hidden_surface: HiddenSurface::PainterSort,
+            depth: Vec::new(),
+        }
+    }
+
+    /// Draw a mesh, returns the number of triangles rasterized
+    /// Every call starts a new depth buffer: meshes drawn by separate calls are layered in
+    /// call order, put them in a single mesh to have them hide each other properly.
+    /// # Arguments
+    /// `mesh`      triangles to draw, their color is lit by `lights`
+    /// `world`     transform from the mesh coordinates to the world
+    /// `canvas`    canvas to draw on, the projection covers all of it
+    pub fn render(&mut self, mesh: &Mesh3D, world: &Mat4x4<f32>, canvas: &Canvas) -> usize {
+        let up = FVec3D::new(0.0, 1.0, 0.0);
+        let target = self.camera + self.look_dir;
+        let view = Mat4x4::<f32>::point_at(self.camera, target, up).inverse();
+        let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+        let to_screen = |point: FVec3D| {
+            let projected = self.projection.vector_multiply(point);
+            FVec3D::new(
+                (projected.x + 1.0) * 0.5 * width,
+                (projected.y + 1.0) * 0.5 * height,
+                projected.z,
+            )
+        };
+
+        let mut to_raster = Vec::<Triangle3D>::with_capacity(mesh.tris.len());
+        for triangle in &mesh.tris {
+            let transformed = Triangle3D {
+                vertices: triangle.vertices.map(|v| world.vector_multiply(v)),
+                face_normal: None,
+                ..*triangle
+            };
+            if !transformed.is_facing(self.camera) {
+                continue;
+            }
+            let normal = transformed.normal();
+            let lit = |color: Color| shade(color, &self.lights, normal, transformed.vertices[0]);
+            let color = lit(triangle.color);
+            let vertex_colors = triangle.vertex_colors.map(|colors| colors.map(lit));
+            let viewed = transformed.vertices.map(|v| view.vector_multiply(v));
+            for (clipped, weights) in clip_near(viewed, self.near) {
+                to_raster.push(Triangle3D {
+                    vertices: clipped.map(to_screen),
+                    color,
+                    // colors at the new vertices are interpolated as the positions are
+                    vertex_colors: vertex_colors.map(|c| weights.map(|w| gouraud_color(c, w))),
+                    face_normal: None,
+                });
+            }
+        }
+
+        let point = |v: FVec3D| Point2D::new(v.x.round() as i32, v.y.round() as i32);
+        match self.hidden_surface {
+            HiddenSurface::PainterSort => {
+                // farthest first, by the z of the middle of the triangle
+                let mid_z = |t: &Triangle3D| t.vertices.iter().map(|v| v.z).sum::<f32>();
+                to_raster.sort_by(|a, b| {
+                    mid_z(b)
+                        .partial_cmp(&mid_z(a))
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                });
+                // flat triangles go through fill_triangle_gouraud too, both methods cover the
+                // same pixels
+                for t in &to_raster {
+                    let [a, b, c] = t.vertices;
+                    let [ca, cb, cc] = t.vertex_colors.unwrap_or([t.color; 3]);
+                    canvas.fill_triangle_gouraud(point(a), ca, point(b), cb, point(c), cc);
+                }
+            }
+            HiddenSurface::DepthBuffer => {
+                self.depth.clear();
+                self.depth.resize(
+                    canvas.width() as usize * canvas.height() as usize,
+                    f32::INFINITY,
+                );
+                for t in &to_raster {
+                    canvas.fill_triangle_depth(
+                        t.vertices.map(point),
+                        t.vertices.map(|v| v.z),
+                        t.vertex_colors.unwrap_or([t.color; 3]),
+                        &mut self.depth,
+                    );
+                }
+            }
+        }
+        to_raster.len()
+    }
+}
+
+/// Helper: clip a triangle in view space (z going away from the camera) against the plane
+/// z = near, gives zero, one or two triangles with the same winding
+/// Each new vertex comes with its barycentric weights in the original triangle, to
+/// interpolate what the vertices carry (e.g. colors).
+fn clip_near(vertices: [FVec3D; 3], near: f32) -> Vec<([FVec3D; 3], [[f32; 3]; 3])> {
+    const CORNERS: [[f32; 3]; 3] = [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]];
+    // Sutherland-Hodgman against a single plane, then a fan of the polygon left
+    let mut polygon = Vec::with_capacity(4);
+    for i in 0..3 {
+        let j = (i + 1) % 3;
+        let (p, q) = (vertices[i], vertices[j]);
+        if p.z >= near {
+            polygon.push((p, CORNERS[i]));
+        }
+        if (p.z >= near) != (q.z >= near) {
+            let t = (near - p.z) / (q.z - p.z);
+            let mut weights = [0.0; 3];
+            weights[i] = 1.0 - t;
+            weights[j] = t;
+            polygon.push((p + (q - p) * t, weights));
+        }
+    }
+    (1..polygon.len().saturating_sub(1))
+        .map(|i| {
+            let (a, b, c) = (polygon[0], polygon[i], polygon[i + 1]);
+            ([a.0, b.0, c.0], [a.1, b.1, c.1])
+        })
+        .collect()
 }
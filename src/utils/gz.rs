@@ -1,14 +1,14 @@
+//! zlib helpers used by the PNG reader and writer, see the compress module for general use
+use crate::compress;
 use crate::image::png::PNGError;
-use miniz_oxide::deflate;
-use miniz_oxide::inflate;
 
-pub fn decompress_zlib(idat: &[u8]) -> Result<Vec<u8>, PNGError> {
-    let decompressed = inflate::decompress_to_vec_zlib(idat)
-        .map_err(|_| PNGError::ParssingError("Error decompressing image data".into()))?;
+pub use crate::compress::ZlibStream;
 
-    Ok(decompressed)
+pub fn decompress_zlib(idat: &[u8]) -> Result<Vec<u8>, PNGError> {
+    compress::inflate_zlib(idat)
+        .map_err(|_| PNGError::ParssingError("Error decompressing image data".into()))
 }
 
 pub fn compress_zlib(idat: &[u8]) -> Vec<u8> {
-    deflate::compress_to_vec_zlib(idat, 0)
+    compress::deflate_zlib(idat, 0)
 }
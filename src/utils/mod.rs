@@ -1,2 +1,3 @@
 pub mod d3;
 pub mod gz;
+pub mod stl;
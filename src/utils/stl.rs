@@ -0,0 +1,164 @@
+//! A reader for STL files, the triangle meshes of 3D printing and CAD tools
+//!
+//! Both the ASCII (`solid` / `facet normal` / `vertex`) and the binary (80 byte header,
+//! triangle count, 50 bytes per triangle) variants are read.
+//!
+//! Example usage:
+//!    let mesh = stl::load("./assets/tetrahedron.stl")?;
+//!    renderer.render(&mesh, &world_matrix, canvas);
+//!
+use crate::math::FVec3D;
+use crate::utils::d3::{Mesh3D, Triangle3D, DEFAULT_COLOR};
+use std::error::Error;
+use std::fmt;
+
+/// Load an STL file, ASCII or binary, into a mesh
+/// The face normals stored in the file are kept in Triangle3D::face_normal, triangles get
+/// the same color as the ones read by Object3D.
+/// `path`  STL file to read
+pub fn load(path: impl AsRef<std::path::Path>) -> Result<Mesh3D, Box<dyn Error>> {
+    let data = std::fs::read(path)?;
+    Ok(from_bytes(&data)?)
+}
+
+/// Read an STL file already in memory, see load
+pub fn from_bytes(data: &[u8]) -> Result<Mesh3D, STLError> {
+    // binary files may start with "solid" as well, their size is what tells them apart
+    let binary_size = data
+        .get(SZ_HEADER..SZ_HEADER + 4)
+        .map(|count| SZ_HEADER + 4 + read_u32(count, 0) as usize * SZ_TRIANGLE);
+    if binary_size == Some(data.len()) {
+        from_binary(data)
+    } else if data.starts_with(b"solid") {
+        from_ascii(data)
+    } else {
+        Err(STLError::ParsingError(
+            "Neither an ASCII STL file nor a binary one of the right size".into(),
+        ))
+    }
+}
+
+/// Generic Error type for errors related parsing STL files
+#[derive(Debug, Clone)]
+pub enum STLError {
+    DataError(String),
+    ParsingError(String),
+}
+
+impl Error for STLError {}
+impl fmt::Display for STLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            STLError::DataError(msg) => write!(f, "STL Error: Invalid data. {}", msg),
+            STLError::ParsingError(msg) => write!(f, "STL Error: Could not parse file. {}", msg),
+        }
+    }
+}
+
+/// Helper: read the 50 byte triangles of a binary file, the size is already checked
+/// Each one is a normal and three vertices (12 little endian f32) then 2 unused bytes.
+fn from_binary(data: &[u8]) -> Result<Mesh3D, STLError> {
+    let mut mesh = Mesh3D::default();
+    for triangle in data[SZ_HEADER + 4..].chunks_exact(SZ_TRIANGLE) {
+        let vector = |i: usize| {
+            FVec3D::new(
+                read_f32(triangle, i * 12),
+                read_f32(triangle, i * 12 + 4),
+                read_f32(triangle, i * 12 + 8),
+            )
+        };
+        push_triangle(
+            &mut mesh,
+            Some(vector(0)),
+            [vector(1), vector(2), vector(3)],
+        );
+    }
+    Ok(mesh)
+}
+
+/// Helper: read the facets of an ASCII file
+/// formated as:
+///     facet normal 0 0 -1
+///       outer loop
+///         vertex 0 0 0
+///         vertex 0 1 0
+///         vertex 1 0 0
+///       endloop
+///     endfacet
+fn from_ascii(data: &[u8]) -> Result<Mesh3D, STLError> {
+    let text = std::str::from_utf8(data)
+        .map_err(|_| STLError::ParsingError("ASCII STL file is not valid text".into()))?;
+    let mut tokens = text.split_ascii_whitespace();
+    let mut mesh = Mesh3D::default();
+    let mut normal = None;
+    let mut vertices = Vec::with_capacity(3);
+    let unclosed =
+        |mesh: &Mesh3D| STLError::DataError(format!("Facet {} has no endfacet", mesh.tris.len()));
+    // the solid name and the loop keywords are skipped
+    while let Some(token) = tokens.next() {
+        match token {
+            "facet" => normal = None,
+            "normal" => normal = Some(read_vector(&mut tokens, token)?),
+            "vertex" => vertices.push(read_vector(&mut tokens, token)?),
+            "endsolid" if !vertices.is_empty() => return Err(unclosed(&mesh)),
+            "endfacet" => {
+                if vertices.len() != 3 {
+                    return Err(STLError::DataError(format!(
+                        "Facet {} has {} vertices instead of 3",
+                        mesh.tris.len(),
+                        vertices.len()
+                    )));
+                }
+                push_triangle(&mut mesh, normal, [vertices[0], vertices[1], vertices[2]]);
+                vertices.clear();
+            }
+            _ => {}
+        }
+    }
+    if !vertices.is_empty() {
+        return Err(unclosed(&mesh));
+    }
+    Ok(mesh)
+}
+
+/// Helper: read the 3 numbers following the `keyword` token
+fn read_vector<'a>(
+    tokens: &mut impl Iterator<Item = &'a str>,
+    keyword: &str,
+) -> Result<FVec3D, STLError> {
+    let mut coordinate = || {
+        tokens
+            .next()
+            .and_then(|token| token.parse::<f32>().ok())
+            .ok_or_else(|| STLError::DataError(format!("Expected 3 numbers after {}", keyword)))
+    };
+    Ok(FVec3D::new(coordinate()?, coordinate()?, coordinate()?))
+}
+
+/// Helper: add a triangle and its normal to the mesh
+fn push_triangle(mesh: &mut Mesh3D, normal: Option<FVec3D>, vertices: [FVec3D; 3]) {
+    mesh.tris.push(Triangle3D {
+        face_normal: normal,
+        ..Triangle3D::new(vertices, DEFAULT_COLOR)
+    });
+    mesh.vertices.extend_from_slice(&vertices);
+}
+
+/////////////////////////////////////////////////////////////////////////////////////////
+// STL standard definitions and helpers                                                //
+/////////////////////////////////////////////////////////////////////////////////////////
+
+/// Bytes before the triangle count of binary files, usually a description
+const SZ_HEADER: usize = 80;
+/// Bytes per triangle of binary files
+const SZ_TRIANGLE: usize = 50;
+
+/// Helper: read a little endian u32, caller makes sure there is enough data
+fn read_u32(data: &[u8], pos: usize) -> u32 {
+    u32::from_le_bytes([data[pos], data[pos + 1], data[pos + 2], data[pos + 3]])
+}
+
+/// Helper: read a little endian f32, caller makes sure there is enough data
+fn read_f32(data: &[u8], pos: usize) -> f32 {
+    f32::from_bits(read_u32(data, pos))
+}
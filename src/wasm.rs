@@ -0,0 +1,330 @@
+//! Browser backend (feature `wasm`): runs a Render2D on an HTML `<canvas>` element
+//!
+//! The Canvas is drawn exactly as on the native backend, each frame its changed pixels are
+//! copied into the element through a 2D context and update is driven by
+//! requestAnimationFrame.
+//! Keyboard events of the page are translated into the crate keys using WEB_KEY_MAP, mouse
+//! events over the element into canvas pixels.
+//!
+//! Example usage (built with `--no-default-features --features wasm`):
+//!    #[wasm_bindgen(start)]
+//!    pub fn start() {
+//!        Game::new().render_web("screen").expect("Could not start rendering");
+//!    }
+//!
+use crate::canvas::{Canvas, Origin};
+use crate::input::{Input, Key, MouseButton};
+use crate::math::Point2D;
+use crate::render::{next_frame, FrameStats, Render2D, RenderError, MAX_FRAME_TIME};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use std::time::Duration;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{Clamped, JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement, ImageData, KeyboardEvent, MouseEvent};
+
+/// requestAnimationFrame callback, gets the time in milliseconds
+type FrameCallback = Closure<dyn FnMut(f64)>;
+
+/// Keyboard state built from the page key events
+/// Events arrive between frames, pressed/released are cleared once update has seen them.
+#[derive(Default)]
+struct WebInput {
+    held: HashSet<Key>,
+    pressed: HashSet<Key>,
+    released: HashSet<Key>,
+    text: String,
+    mouse: Option<Point2D>,
+    mouse_held: HashSet<MouseButton>,
+    mouse_pressed: HashSet<MouseButton>,
+    mouse_released: HashSet<MouseButton>,
+}
+
+impl WebInput {
+    fn key_down(&mut self, event: &KeyboardEvent) {
+        // printable keys have a single character `key`, named ones ("Enter", "Shift") more
+        let key = event.key();
+        let mut characters = key.chars();
+        if let (Some(character), None) = (characters.next(), characters.next()) {
+            if !event.ctrl_key() && !event.meta_key() {
+                self.text.push(character);
+            }
+        }
+        // auto repeat sends keydown again while held, only the first one is a press
+        if let Some(key) = Key::from_web_code(&event.code()) {
+            if self.held.insert(key) {
+                self.pressed.insert(key);
+            }
+        }
+    }
+
+    fn key_up(&mut self, event: &KeyboardEvent) {
+        if let Some(key) = Key::from_web_code(&event.code()) {
+            if self.held.remove(&key) {
+                self.released.insert(key);
+            }
+        }
+    }
+
+    fn mouse_move(&mut self, event: &MouseEvent, element: &HtmlCanvasElement) {
+        // the element may be scaled by CSS, offsets are in CSS pixels
+        let scale_x = element.width() as f32 / element.client_width().max(1) as f32;
+        let scale_y = element.height() as f32 / element.client_height().max(1) as f32;
+        self.mouse = Some(Point2D::new(
+            (event.offset_x() as f32 * scale_x) as i32,
+            (event.offset_y() as f32 * scale_y) as i32,
+        ));
+    }
+
+    fn mouse_down(&mut self, event: &MouseEvent, element: &HtmlCanvasElement) {
+        self.mouse_move(event, element);
+        if let Some(button) = mouse_button(event) {
+            if self.mouse_held.insert(button) {
+                self.mouse_pressed.insert(button);
+            }
+        }
+    }
+
+    fn mouse_up(&mut self, event: &MouseEvent, element: &HtmlCanvasElement) {
+        self.mouse_move(event, element);
+        if let Some(button) = mouse_button(event) {
+            if self.mouse_held.remove(&button) {
+                self.mouse_released.insert(button);
+            }
+        }
+    }
+
+    fn mouse_leave(&mut self, _event: &MouseEvent, _element: &HtmlCanvasElement) {
+        self.mouse = None;
+    }
+
+    /// Forget the edges once a frame has been updated with them
+    fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.text.clear();
+        self.mouse_pressed.clear();
+        self.mouse_released.clear();
+    }
+}
+
+impl Input for WebInput {
+    fn key_pressed(&self, key: Key) -> bool {
+        self.pressed.contains(&key)
+    }
+    fn key_held(&self, key: Key) -> bool {
+        self.held.contains(&key)
+    }
+    fn key_released(&self, key: Key) -> bool {
+        self.released.contains(&key)
+    }
+    fn typed_text(&self) -> String {
+        self.text.clone()
+    }
+    fn mouse_position(&self) -> Option<Point2D> {
+        self.mouse
+    }
+    fn mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_pressed.contains(&button)
+    }
+    fn mouse_held(&self, button: MouseButton) -> bool {
+        self.mouse_held.contains(&button)
+    }
+    fn mouse_released(&self, button: MouseButton) -> bool {
+        self.mouse_released.contains(&button)
+    }
+}
+
+/// Run `renderer` on the `<canvas>` element with id `canvas_id`, see Render2D::render_web
+pub(crate) fn run<R>(mut renderer: R, canvas_id: &str) -> Result<(), RenderError>
+where
+    R: Render2D + 'static,
+{
+    let window = web_sys::window()
+        .ok_or_else(|| RenderError::WindowCreation("No browser window".to_string()))?;
+    let document = window
+        .document()
+        .ok_or_else(|| RenderError::WindowCreation("No document in the window".to_string()))?;
+    let element: HtmlCanvasElement = document
+        .get_element_by_id(canvas_id)
+        .and_then(|element| element.dyn_into().ok())
+        .ok_or_else(|| {
+            RenderError::WindowCreation(format!("No <canvas> element with id '{}'", canvas_id))
+        })?;
+
+    let width = renderer.width();
+    let height = renderer.height();
+    element.set_width(width);
+    element.set_height(height);
+    document.set_title(&renderer.title());
+    let context: CanvasRenderingContext2d = element
+        .get_context("2d")
+        .ok()
+        .flatten()
+        .and_then(|context| context.dyn_into().ok())
+        .ok_or_else(|| RenderError::DisplayCreation("No 2D context for the canvas".to_string()))?;
+
+    // ImageData expects the top row first
+    let mut canvas = Canvas::new(width, height);
+    canvas.set_origin(Origin::TopLeft);
+    if !renderer.setup(&mut canvas) {
+        return Err(RenderError::SetupFailed);
+    }
+
+    let input = Rc::new(RefCell::new(WebInput::default()));
+    listen_keys(&window, "keydown", &input, WebInput::key_down)?;
+    listen_keys(&window, "keyup", &input, WebInput::key_up)?;
+    listen_mouse(&element, "mousemove", &input, WebInput::mouse_move)?;
+    listen_mouse(&element, "mousedown", &input, WebInput::mouse_down)?;
+    listen_mouse(&element, "mouseup", &input, WebInput::mouse_up)?;
+    listen_mouse(&element, "mouseleave", &input, WebInput::mouse_leave)?;
+
+    let mut stats = FrameStats::default();
+    let mut accumulator = 0.0;
+    let mut start_time = None;
+    let mut last_time = None;
+    let mut last_fps_time = 0.0;
+    let mut frame_counter = 0.0;
+
+    // the callback schedules itself again, so it holds a reference to its own slot
+    let frame: Rc<RefCell<Option<FrameCallback>>> = Rc::new(RefCell::new(None));
+    let next = Rc::clone(&frame);
+    *frame.borrow_mut() = Some(Closure::wrap(Box::new(move |time: f64| {
+        // requestAnimationFrame gives the time in milliseconds
+        let start = *start_time.get_or_insert(time);
+        let elapsed = last_time.map_or(0.0, |last| ((time - last) / 1000.0) as f32);
+        last_time = Some(time);
+        stats.elapsed = Duration::from_secs_f64((time - start) / 1000.0);
+
+        let keep_running = {
+            let input = input.borrow();
+            match renderer.fixed_timestep() {
+                Some(step) if step > 0.0 => {
+                    let mut keep_running = true;
+                    accumulator += elapsed.min(MAX_FRAME_TIME);
+                    while keep_running && accumulator >= step {
+                        keep_running =
+                            next_frame(&mut renderer, &mut canvas, &*input, step, &mut stats);
+                        accumulator -= step;
+                    }
+                    keep_running
+                }
+                _ => next_frame(&mut renderer, &mut canvas, &*input, elapsed, &mut stats),
+            }
+        };
+        input.borrow_mut().end_frame();
+        frame_counter += 1.0;
+
+        // the region stays dirty when it could not be drawn, to try again on the next frame
+        let keep_running = match blit(&context, &canvas) {
+            Ok(()) => {
+                canvas.clear_dirty();
+                keep_running
+            }
+            Err(e) => {
+                let error = RenderError::FrameDisplay(format!("{:?}", e));
+                renderer.on_error(error) && keep_running
+            }
+        };
+
+        if time - last_fps_time > 1000.0 {
+            stats.fps = (frame_counter * 1000.0 / (time - last_fps_time)) as f32;
+            frame_counter = 0.0;
+            last_fps_time = time;
+        }
+
+        // not scheduling the next frame stops the loop, the closure stays alive (leaked)
+        if keep_running {
+            if let Some(callback) = next.borrow().as_ref() {
+                request_animation_frame(callback).expect("Could not schedule the next frame");
+            }
+        }
+    }) as Box<dyn FnMut(f64)>));
+
+    let first = frame.borrow();
+    let callback = first.as_ref().expect("The frame callback was just set");
+    request_animation_frame(callback)
+        .map(|_| ())
+        .map_err(|e| RenderError::DisplayCreation(format!("{:?}", e)))
+}
+
+/// Helper: forward the page key events named `event` to `handler`
+fn listen_keys(
+    window: &web_sys::Window,
+    event: &str,
+    input: &Rc<RefCell<WebInput>>,
+    handler: fn(&mut WebInput, &KeyboardEvent),
+) -> Result<(), RenderError> {
+    let input = Rc::clone(input);
+    let callback = Closure::wrap(Box::new(move |event: KeyboardEvent| {
+        handler(&mut input.borrow_mut(), &event);
+        // keep arrows and space from scrolling the page
+        if Key::from_web_code(&event.code()).is_some() {
+            event.prevent_default();
+        }
+    }) as Box<dyn FnMut(KeyboardEvent)>);
+    window
+        .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+        .map_err(|e| RenderError::WindowCreation(format!("{:?}", e)))?;
+    // listens for as long as the page lives
+    callback.forget();
+    Ok(())
+}
+
+/// Helper: forward the mouse events named `event` over `element` to `handler`
+fn listen_mouse(
+    element: &HtmlCanvasElement,
+    event: &str,
+    input: &Rc<RefCell<WebInput>>,
+    handler: fn(&mut WebInput, &MouseEvent, &HtmlCanvasElement),
+) -> Result<(), RenderError> {
+    let input = Rc::clone(input);
+    let target = element.clone();
+    let callback = Closure::wrap(Box::new(move |event: MouseEvent| {
+        handler(&mut input.borrow_mut(), &event, &target);
+    }) as Box<dyn FnMut(MouseEvent)>);
+    element
+        .add_event_listener_with_callback(event, callback.as_ref().unchecked_ref())
+        .map_err(|e| RenderError::WindowCreation(format!("{:?}", e)))?;
+    callback.forget();
+    Ok(())
+}
+
+/// Helper: crate button of a mouse event, the DOM numbers them left, middle, right
+fn mouse_button(event: &MouseEvent) -> Option<MouseButton> {
+    match event.button() {
+        0 => Some(MouseButton::Left),
+        1 => Some(MouseButton::Middle),
+        2 => Some(MouseButton::Right),
+        _ => None,
+    }
+}
+
+/// Helper: ask the browser to call `callback` before the next repaint
+fn request_animation_frame(callback: &Closure<dyn FnMut(f64)>) -> Result<i32, JsValue> {
+    web_sys::window()
+        .ok_or_else(|| JsValue::from_str("No browser window"))?
+        .request_animation_frame(callback.as_ref().unchecked_ref())
+}
+
+/// Helper: copy the changed part of the canvas into the `<canvas>` element, if any
+/// With Origin::TopLeft the dirty rect rows are counted from the top, as the 2D context does.
+/// Only the pixels of the dirty rect are converted to RGBA.
+fn blit(context: &CanvasRenderingContext2d, canvas: &Canvas) -> Result<(), JsValue> {
+    let rect = match canvas.dirty_rect() {
+        Some(rect) => rect,
+        None => return Ok(()),
+    };
+    let pixels = canvas.pixels.borrow();
+    let mut data = Vec::with_capacity((rect.width * rect.height * 4) as usize);
+    for row in rect.bottom..rect.bottom + rect.height {
+        let start = (row * canvas.width() + rect.left) as usize;
+        for pixel in &pixels[start..start + rect.width as usize] {
+            data.extend_from_slice(&pixel.as_bytes());
+        }
+    }
+    let image =
+        ImageData::new_with_u8_clamped_array_and_sh(Clamped(&data[..]), rect.width, rect.height)?;
+    context.put_image_data(&image, rect.left as f64, rect.bottom as f64)
+}